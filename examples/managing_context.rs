@@ -84,6 +84,7 @@ fn create_best_context(
         for multisampling in &[16, 8, 4, 2, 1, 0] {
             let mut windowed_context = glutin::ContextBuilder::new()
                 .with_vsync(*vsync)
+                .with_stencil_buffer(8)
                 .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (2, 0)));
 
             if *multisampling > 1 {
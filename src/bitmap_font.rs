@@ -0,0 +1,662 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Parses the descriptor files of bitmap fonts in the BMFont (AngelCode)
+//! text, XML and binary formats.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+
+use crate::dimen::{UVec2, Vec2};
+use crate::error::{BacktraceError, ErrorMessage};
+use crate::shape::{Rect, URect};
+
+/// A glyph of a bitmap font, in pixels of the page images.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct BitmapGlyph
+{
+    /// The index of the page image containing the glyph.
+    pub page: usize,
+    /// The area of the page image covered by the glyph.
+    pub texture_area: URect,
+    /// The position of the top left of the glyph, relative to the pen
+    /// position on the baseline.
+    pub offset: Vec2,
+    pub advance_width: f32
+}
+
+/// The metrics of a bitmap font, without its page images.
+#[derive(Debug)]
+pub(crate) struct BitmapFontData
+{
+    line_height: f32,
+    base: f32,
+    page_count: usize,
+    glyphs: Vec<BitmapGlyph>,
+    glyph_indices: HashMap<char, u16>,
+    kerning: HashMap<(u16, u16), f32>
+}
+
+impl BitmapFontData
+{
+    /// Parses a font descriptor, detecting whether it uses the text, XML or
+    /// binary format.
+    pub(crate) fn parse(bytes: &[u8]) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        if bytes.starts_with(b"BMF") {
+            return parse_binary(bytes);
+        }
+
+        let text = std::str::from_utf8(bytes).map_err(|err| {
+            ErrorMessage::msg_with_cause("Font is not valid UTF-8", err)
+        })?;
+
+        let text = text.trim_start_matches('\u{FEFF}').trim_start();
+
+        if text.starts_with('<') {
+            parse_records(xml_records(text))
+        } else {
+            parse_records(text_records(text))
+        }
+    }
+
+    /// The distance between the baselines of two lines, in pixels of the
+    /// page images.
+    #[inline]
+    pub(crate) fn line_height(&self) -> f32
+    {
+        self.line_height
+    }
+
+    /// The factor by which the glyphs are scaled to draw text at `scale`,
+    /// which is the height of a line in pixels.
+    #[inline]
+    pub(crate) fn scale_factor(&self, scale: f32) -> f32
+    {
+        scale / self.line_height
+    }
+
+    pub(crate) fn v_metrics(&self, scale: f32) -> rusttype::VMetrics
+    {
+        let factor = self.scale_factor(scale);
+
+        rusttype::VMetrics {
+            ascent: self.base * factor,
+            descent: (self.base - self.line_height) * factor,
+            line_gap: 0.0
+        }
+    }
+
+    /// The number of page images referred to by the glyphs.
+    #[inline]
+    pub(crate) fn page_count(&self) -> usize
+    {
+        self.page_count
+    }
+
+    #[inline]
+    pub(crate) fn glyph_index(&self, codepoint: char) -> Option<u16>
+    {
+        self.glyph_indices.get(&codepoint).copied()
+    }
+
+    #[inline]
+    pub(crate) fn glyph(&self, index: u16) -> &BitmapGlyph
+    {
+        &self.glyphs[index as usize]
+    }
+
+    /// The adjustment to the advance width of `first` when followed by
+    /// `second`, in pixels of the page images.
+    #[inline]
+    pub(crate) fn kerning(&self, first: u16, second: u16) -> f32
+    {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0.0)
+    }
+}
+
+/// A glyph of a bitmap font which has been laid out.
+#[derive(Clone, Debug)]
+pub(crate) struct PositionedBitmapGlyph
+{
+    pub index: u16,
+    pub glyph: BitmapGlyph,
+    /// The factor by which the glyph is scaled from the size of the page
+    /// image.
+    pub scale: f32,
+    /// The pen position on the baseline.
+    pub position: Vec2
+}
+
+impl PositionedBitmapGlyph
+{
+    #[inline]
+    pub(crate) fn advance_width(&self) -> f32
+    {
+        self.glyph.advance_width * self.scale
+    }
+
+    /// The area covered by the glyph, or `None` if it is empty.
+    pub(crate) fn bounding_box(&self) -> Option<Rect>
+    {
+        let size = self.glyph.texture_area.size();
+
+        if size.x == 0 || size.y == 0 {
+            return None;
+        }
+
+        let top_left = self.position + self.glyph.offset * self.scale;
+
+        Some(Rect::new(top_left, top_left + size.into_f32() * self.scale))
+    }
+}
+
+/// Collects the parsed tags of a font descriptor. The glyph metrics are
+/// made relative to the baseline once the whole file has been read.
+#[derive(Default)]
+struct BitmapFontDataBuilder
+{
+    common: Option<(f32, f32)>,
+    page_count: usize,
+    glyphs: Vec<(u32, BitmapGlyph)>,
+    kerning: Vec<(u32, u32, f32)>
+}
+
+impl BitmapFontDataBuilder
+{
+    fn build(self) -> Result<BitmapFontData, BacktraceError<ErrorMessage>>
+    {
+        let (line_height, base) = self
+            .common
+            .ok_or_else(|| ErrorMessage::msg("Font has no 'common' tag"))?;
+
+        if !line_height.is_finite() || line_height <= 0.0 {
+            return Err(ErrorMessage::msg(format!(
+                "Invalid line height {}",
+                line_height
+            )));
+        }
+
+        let mut glyphs = Vec::new();
+        let mut glyph_indices = HashMap::new();
+        let mut ids = HashMap::new();
+
+        for (id, mut glyph) in self.glyphs {
+            // Some tools export the glyph for missing characters with an id
+            // of -1, which isn't a valid character
+            let codepoint = match char::from_u32(id) {
+                None => continue,
+                Some(codepoint) => codepoint
+            };
+
+            let index: u16 = glyphs
+                .len()
+                .try_into()
+                .map_err(|_| ErrorMessage::msg("Font has too many glyphs"))?;
+
+            glyph.offset.y -= base;
+
+            glyphs.push(glyph);
+            glyph_indices.insert(codepoint, index);
+            ids.insert(id, index);
+        }
+
+        let mut page_count = self.page_count;
+
+        for glyph in &glyphs {
+            page_count = page_count.max(page_count_including(glyph.page)?);
+        }
+
+        let kerning = self
+            .kerning
+            .into_iter()
+            .filter_map(|(first, second, amount)| {
+                Some(((*ids.get(&first)?, *ids.get(&second)?), amount))
+            })
+            .collect();
+
+        Ok(BitmapFontData {
+            line_height,
+            base,
+            page_count,
+            glyphs,
+            glyph_indices,
+            kerning
+        })
+    }
+}
+
+/// The number of pages in a font which has a page with the specified id.
+fn page_count_including(id: usize) -> Result<usize, BacktraceError<ErrorMessage>>
+{
+    id.checked_add(1)
+        .ok_or_else(|| ErrorMessage::msg(format!("Page {} is out of range", id)))
+}
+
+/// A tag from a text or XML descriptor, such as `char`, with its attributes.
+struct Record
+{
+    tag: String,
+    attributes: HashMap<String, String>
+}
+
+impl Record
+{
+    fn get<T: std::str::FromStr>(
+        &self,
+        name: &str
+    ) -> Result<T, BacktraceError<ErrorMessage>>
+    {
+        let value = self.attributes.get(name).ok_or_else(|| {
+            ErrorMessage::msg(format!("Missing '{}' in '{}' tag", name, self.tag))
+        })?;
+
+        value.trim().parse().map_err(|_| {
+            ErrorMessage::msg(format!(
+                "Invalid value '{}' for '{}' in '{}' tag",
+                value, name, self.tag
+            ))
+        })
+    }
+
+    fn get_or<T: std::str::FromStr>(
+        &self,
+        name: &str,
+        default: T
+    ) -> Result<T, BacktraceError<ErrorMessage>>
+    {
+        match self.attributes.contains_key(name) {
+            true => self.get(name),
+            false => Ok(default)
+        }
+    }
+}
+
+fn parse_records(
+    records: impl Iterator<Item = Record>
+) -> Result<BitmapFontData, BacktraceError<ErrorMessage>>
+{
+    let mut builder = BitmapFontDataBuilder::default();
+
+    for record in records {
+        match record.tag.as_str() {
+            "common" => {
+                builder.common = Some((record.get("lineHeight")?, record.get("base")?));
+            }
+
+            "page" => {
+                let id: usize = record.get("id")?;
+                builder.page_count = builder.page_count.max(page_count_including(id)?);
+            }
+
+            "char" => {
+                let id: i64 = record.get("id")?;
+
+                let x: u32 = record.get("x")?;
+                let y: u32 = record.get("y")?;
+                let width: u32 = record.get("width")?;
+                let height: u32 = record.get("height")?;
+
+                let bottom_right = x
+                    .checked_add(width)
+                    .zip(y.checked_add(height))
+                    .ok_or_else(|| {
+                        ErrorMessage::msg(format!("Glyph {} is out of range", id))
+                    })?;
+
+                builder.glyphs.push((
+                    id as u32,
+                    BitmapGlyph {
+                        page: record.get_or("page", 0)?,
+                        texture_area: URect::from_tuples((x, y), bottom_right),
+                        offset: Vec2::new(
+                            record.get_or("xoffset", 0.0)?,
+                            record.get_or("yoffset", 0.0)?
+                        ),
+                        advance_width: record.get("xadvance")?
+                    }
+                ));
+            }
+
+            "kerning" => {
+                let first: i64 = record.get("first")?;
+                let second: i64 = record.get("second")?;
+
+                builder.kerning.push((
+                    first as u32,
+                    second as u32,
+                    record.get("amount")?
+                ));
+            }
+
+            _ => {}
+        }
+    }
+
+    builder.build()
+}
+
+/// Splits `key=value` pairs separated by whitespace. Values may be quoted,
+/// in which case they can contain whitespace.
+fn parse_attributes(text: &str, decode: fn(&str) -> String) -> HashMap<String, String>
+{
+    let mut attributes = HashMap::new();
+    let mut remaining = text.trim_start();
+
+    while !remaining.is_empty() {
+        let key_end = remaining
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(remaining.len());
+
+        let key = &remaining[..key_end];
+        remaining = remaining[key_end..].trim_start();
+
+        if let Some(after_equals) = remaining.strip_prefix('=') {
+            let after_equals = after_equals.trim_start();
+
+            let (value, rest) = match after_equals.strip_prefix('"') {
+                Some(quoted) => match quoted.find('"') {
+                    Some(end) => (&quoted[..end], &quoted[end + 1..]),
+                    None => (quoted, "")
+                },
+                None => {
+                    let end = after_equals
+                        .find(char::is_whitespace)
+                        .unwrap_or(after_equals.len());
+
+                    (&after_equals[..end], &after_equals[end..])
+                }
+            };
+
+            attributes.insert(key.to_string(), decode(value));
+            remaining = rest.trim_start();
+        }
+    }
+
+    attributes
+}
+
+/// Reads one tag from each line of a descriptor in the text format.
+fn text_records(text: &str) -> impl Iterator<Item = Record> + '_
+{
+    text.lines().filter_map(|line| {
+        let line = line.trim();
+        let tag_end = line.find(char::is_whitespace).unwrap_or(line.len());
+
+        if tag_end == 0 {
+            return None;
+        }
+
+        Some(Record {
+            tag: line[..tag_end].to_string(),
+            attributes: parse_attributes(&line[tag_end..], str::to_string)
+        })
+    })
+}
+
+/// Reads the elements of a descriptor in the XML format. As the elements of
+/// interest have no content, the nesting of the elements is ignored.
+fn xml_records(text: &str) -> impl Iterator<Item = Record> + '_
+{
+    text.split('<').skip(1).filter_map(|element| {
+        let element = &element[..element.find('>')?];
+
+        if element.starts_with(['?', '!', '/']) {
+            return None;
+        }
+
+        let element = element.trim_end_matches('/');
+        let tag_end = element.find(char::is_whitespace).unwrap_or(element.len());
+
+        Some(Record {
+            tag: element[..tag_end].to_string(),
+            attributes: parse_attributes(&element[tag_end..], decode_xml_entities)
+        })
+    })
+}
+
+fn decode_xml_entities(value: &str) -> String
+{
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Reads a descriptor in the binary format, which is a sequence of blocks
+/// after the four byte header.
+fn parse_binary(bytes: &[u8]) -> Result<BitmapFontData, BacktraceError<ErrorMessage>>
+{
+    let truncated = || ErrorMessage::msg("Binary font file is truncated");
+
+    match bytes.get(3) {
+        Some(3) => {}
+        Some(version) => {
+            return Err(ErrorMessage::msg(format!(
+                "Unsupported binary font version {}",
+                version
+            )))
+        }
+        None => return Err(truncated())
+    }
+
+    let u16_at = |block: &[u8], offset: usize| {
+        block
+            .get(offset..offset + 2)
+            .map(|bytes| u16::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(truncated)
+    };
+
+    let u32_at = |block: &[u8], offset: usize| {
+        block
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or_else(truncated)
+    };
+
+    let mut builder = BitmapFontDataBuilder::default();
+    let mut remaining = &bytes[4..];
+
+    while !remaining.is_empty() {
+        let block_type = remaining[0];
+        let block_size = u32_at(remaining, 1)? as usize;
+
+        let block = remaining.get(5..5 + block_size).ok_or_else(truncated)?;
+        remaining = &remaining[5 + block_size..];
+
+        match block_type {
+            // Common
+            2 => {
+                builder.common =
+                    Some((u16_at(block, 0)? as f32, u16_at(block, 2)? as f32));
+                builder.page_count = u16_at(block, 8)? as usize;
+            }
+
+            // Characters
+            4 => {
+                for glyph in block.chunks_exact(20) {
+                    let x = u16_at(glyph, 4)? as u32;
+                    let y = u16_at(glyph, 6)? as u32;
+                    let size =
+                        UVec2::new(u16_at(glyph, 8)? as u32, u16_at(glyph, 10)? as u32);
+
+                    builder.glyphs.push((
+                        u32_at(glyph, 0)?,
+                        BitmapGlyph {
+                            page: glyph[18] as usize,
+                            texture_area: URect::from_tuples(
+                                (x, y),
+                                (x + size.x, y + size.y)
+                            ),
+                            offset: Vec2::new(
+                                u16_at(glyph, 12)? as i16 as f32,
+                                u16_at(glyph, 14)? as i16 as f32
+                            ),
+                            advance_width: u16_at(glyph, 16)? as i16 as f32
+                        }
+                    ));
+                }
+            }
+
+            // Kerning pairs
+            5 => {
+                for pair in block.chunks_exact(10) {
+                    builder.kerning.push((
+                        u32_at(pair, 0)?,
+                        u32_at(pair, 4)?,
+                        u16_at(pair, 8)? as i16 as f32
+                    ));
+                }
+            }
+
+            // Info and page names
+            _ => {}
+        }
+    }
+
+    builder.build()
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    const TEXT_FONT: &str =
+        "info face=\"Pixel Font\" size=8 bold=0\ncommon lineHeight=10 base=8 scaleW=64 \
+         scaleH=64 pages=1 packed=0\npage id=0 file=\"pixel font_0.png\"\nchars \
+         count=2\nchar id=65 x=1 y=2 width=5 height=7 xoffset=0 yoffset=1 xadvance=6 \
+         page=0 chnl=15\nchar id=86   x=7   y=2 width=5 height=7 xoffset=-1 yoffset=1 \
+         xadvance=5 page=0 chnl=15\nkernings count=1\nkerning first=65 second=86 \
+         amount=-1\n";
+
+    const XML_FONT: &str =
+        "<?xml version=\"1.0\"?>\n<font>\n<info face=\"Pixel &amp; Font\" \
+         size=\"8\"/>\n<common lineHeight=\"10\" base=\"8\" scaleW=\"64\" scaleH=\"64\" \
+         pages=\"1\"/>\n<pages>\n<page id=\"0\" file=\"pixel font_0.png\" \
+         />\n</pages>\n<chars count=\"2\">\n<char id=\"65\" x=\"1\" y=\"2\" width=\"5\" \
+         height=\"7\" xoffset=\"0\" yoffset=\"1\" xadvance=\"6\" page=\"0\" chnl=\"15\" \
+         />\n<char id=\"86\" x=\"7\" y=\"2\" width=\"5\" height=\"7\" xoffset=\"-1\" \
+         yoffset=\"1\" xadvance=\"5\" page=\"0\" chnl=\"15\" />\n</chars>\n<kernings \
+         count=\"1\">\n<kerning first=\"65\" second=\"86\" amount=\"-1\" \
+         />\n</kernings>\n</font>\n";
+
+    fn binary_font() -> Vec<u8>
+    {
+        let mut bytes = b"BMF\x03".to_vec();
+
+        let mut block = |block_type: u8, data: Vec<u8>| {
+            bytes.push(block_type);
+            bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            bytes.extend(data);
+        };
+
+        let mut common = Vec::new();
+        for value in [10u16, 8, 64, 64, 1] {
+            common.extend_from_slice(&value.to_le_bytes());
+        }
+        common.extend_from_slice(&[0, 0, 0, 0, 0]);
+
+        let mut chars = Vec::new();
+        for (id, x, offset_x, advance) in [(65u32, 1u16, 0i16, 6i16), (86, 7, -1, 5)] {
+            chars.extend_from_slice(&id.to_le_bytes());
+            for value in [x, 2, 5, 7] {
+                chars.extend_from_slice(&value.to_le_bytes());
+            }
+            for value in [offset_x, 1, advance] {
+                chars.extend_from_slice(&value.to_le_bytes());
+            }
+            chars.extend_from_slice(&[0, 15]);
+        }
+
+        let mut kerning = Vec::new();
+        kerning.extend_from_slice(&65u32.to_le_bytes());
+        kerning.extend_from_slice(&86u32.to_le_bytes());
+        kerning.extend_from_slice(&(-1i16).to_le_bytes());
+
+        block(2, common);
+        block(3, b"pixel font_0.png\0".to_vec());
+        block(4, chars);
+        block(5, kerning);
+
+        bytes
+    }
+
+    fn check_font(font: &BitmapFontData)
+    {
+        assert_eq!(10.0, font.line_height());
+        assert_eq!(16.0, font.v_metrics(20.0).ascent);
+        assert_eq!(-4.0, font.v_metrics(20.0).descent);
+        assert_eq!(1, font.page_count());
+
+        let a = font.glyph_index('A').unwrap();
+        let v = font.glyph_index('V').unwrap();
+
+        assert_eq!(None, font.glyph_index('B'));
+
+        assert_eq!(
+            &BitmapGlyph {
+                page: 0,
+                texture_area: URect::from_tuples((7, 2), (12, 9)),
+                offset: Vec2::new(-1.0, -7.0),
+                advance_width: 5.0
+            },
+            font.glyph(v)
+        );
+
+        assert_eq!(-1.0, font.kerning(a, v));
+        assert_eq!(0.0, font.kerning(v, a));
+    }
+
+    #[test]
+    pub fn test_parse_formats()
+    {
+        check_font(&BitmapFontData::parse(TEXT_FONT.as_bytes()).unwrap());
+        check_font(&BitmapFontData::parse(XML_FONT.as_bytes()).unwrap());
+        check_font(&BitmapFontData::parse(&binary_font()).unwrap());
+    }
+
+    #[test]
+    pub fn test_parse_errors()
+    {
+        assert!(BitmapFontData::parse(b"char id=65 x=1 y=2 width=5").is_err());
+        assert!(BitmapFontData::parse(b"common lineHeight=ten base=8").is_err());
+
+        let text_zero_height = TEXT_FONT.replace("lineHeight=10", "lineHeight=0");
+        assert!(BitmapFontData::parse(text_zero_height.as_bytes()).is_err());
+
+        let mut binary_zero_height = binary_font();
+        binary_zero_height[9..11].copy_from_slice(&0u16.to_le_bytes());
+        assert!(BitmapFontData::parse(&binary_zero_height).is_err());
+
+        let page_overflow =
+            TEXT_FONT.replace("page id=0", &format!("page id={}", usize::MAX));
+        assert!(BitmapFontData::parse(page_overflow.as_bytes()).is_err());
+
+        let glyph_page_overflow =
+            TEXT_FONT.replace("page=0", &format!("page={}", usize::MAX));
+        assert!(BitmapFontData::parse(glyph_page_overflow.as_bytes()).is_err());
+        assert!(BitmapFontData::parse(&binary_font()[..40]).is_err());
+
+        let text_overflow = TEXT_FONT.replace("x=1 y=2", "x=4294967295 y=2");
+        assert!(BitmapFontData::parse(text_overflow.as_bytes()).is_err());
+
+        let xml_overflow = XML_FONT.replace("height=\"7\"", "height=\"4294967295\"");
+        assert!(BitmapFontData::parse(xml_overflow.as_bytes()).is_err());
+    }
+}
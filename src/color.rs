@@ -209,6 +209,50 @@ impl Color
     }
 }
 
+/// Determines how the colors of each shape are combined with the existing
+/// contents of the screen (or image) being drawn to. Set using
+/// [crate::Graphics2D::set_blend_mode].
+///
+/// In the descriptions below, `src` is the color of the shape being drawn,
+/// and `dst` is the existing color. All modes except `Replace` combine the
+/// alpha components in the same way as `Normal`.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub enum BlendMode
+{
+    /// The shape is drawn over the existing contents, according to its
+    /// alpha: `src * src_alpha + dst * (1 - src_alpha)`. This is the default.
+    #[default]
+    Normal,
+
+    /// The colors are added together, which is useful for lights and
+    /// particle effects: `src * src_alpha + dst`.
+    Additive,
+
+    /// The colors are multiplied together, which darkens the existing
+    /// contents, for example to draw shadows: `src * dst`, weighted by the
+    /// alpha of the shape.
+    Multiply,
+
+    /// The inverse colors are multiplied together, which lightens the
+    /// existing contents: `src + dst - src * dst`, weighted by the alpha of
+    /// the shape.
+    Screen,
+
+    /// Like `Normal`, but the colors of the shape are assumed to be
+    /// premultiplied by their alpha already, for example when drawing images
+    /// whose pixel data is premultiplied: `src + dst * (1 - src_alpha)`.
+    Premultiplied,
+
+    /// The existing contents are replaced by the shape, including its alpha
+    /// component, without any blending.
+    ///
+    /// As partially covered pixels can't be blended, the edges of circles
+    /// and text are not anti-aliased in this mode: pixels which are at least
+    /// half covered are replaced, and the others are left unchanged. Edge
+    /// anti-aliasing is also disabled.
+    Replace
+}
+
 #[cfg(test)]
 mod tests
 {
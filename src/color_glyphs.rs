@@ -0,0 +1,456 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Rasterizes the color glyphs of a font, such as emoji, into RGBA bitmaps.
+//! Glyphs made of colored layers (the `COLR` and `CPAL` tables) are always
+//! supported, and embedded PNG images (the `sbix` and `CBDT` tables) are
+//! supported when the `image-loading` feature is enabled.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::dimen::{IVec2, UVec2};
+
+const PALETTE_INDEX_FOREGROUND: u16 = 0xFFFF;
+
+#[derive(Debug, Clone, PartialEq)]
+enum LayerColor
+{
+    /// The RGBA color of the layer, with each component between 0.0 and 1.0.
+    Palette([f32; 4]),
+
+    /// The layer is drawn in the color of the text.
+    Foreground
+}
+
+/// A layer of a color glyph, which is drawn using the outline of another
+/// glyph.
+#[derive(Debug, Clone, PartialEq)]
+struct ColorGlyphLayer
+{
+    glyph_id: u16,
+    color: LayerColor
+}
+
+/// A color glyph, rasterized at a particular size and subpixel offset.
+pub(crate) struct ColorGlyphImage
+{
+    pub size: UVec2,
+    /// The position of the top left of the image, relative to the origin of
+    /// the glyph.
+    pub offset: IVec2,
+    /// The pixels of the image, in RGBA format, without premultiplied alpha.
+    pub data: Vec<u8>
+}
+
+/// The color glyph tables of a font.
+pub(crate) struct ColorGlyphs
+{
+    #[cfg_attr(not(feature = "image-loading"), allow(dead_code))]
+    data: Arc<Vec<u8>>,
+    #[cfg_attr(not(feature = "image-loading"), allow(dead_code))]
+    index: u32,
+    has_images: bool,
+    layers: HashMap<u16, Vec<ColorGlyphLayer>>
+}
+
+impl Debug for ColorGlyphs
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        f.debug_struct("ColorGlyphs")
+            .field("has_images", &self.has_images)
+            .field("layers", &self.layers.len())
+            .finish()
+    }
+}
+
+impl ColorGlyphs
+{
+    /// Reads the color glyph tables of the specified face of a font file.
+    /// Returns `None` if the font has no color glyphs which can be drawn.
+    pub(crate) fn new(data: &Arc<Vec<u8>>, index: u32) -> Option<Self>
+    {
+        let face = ttf_parser::Face::from_slice(data, index).ok()?;
+
+        let has_images = cfg!(feature = "image-loading")
+            && (face.tables().sbix.is_some() || face.tables().cbdt.is_some());
+
+        let layers = match (
+            face.table_data(ttf_parser::Tag::from_bytes(b"COLR")),
+            face.table_data(ttf_parser::Tag::from_bytes(b"CPAL"))
+        ) {
+            (Some(colr), Some(cpal)) => parse_layers(colr, cpal).unwrap_or_default(),
+            _ => HashMap::new()
+        };
+
+        if !has_images && layers.is_empty() {
+            return None;
+        }
+
+        Some(ColorGlyphs {
+            data: data.clone(),
+            index,
+            has_images,
+            layers
+        })
+    }
+
+    /// Returns true if any layers of the glyph are drawn in the color of the
+    /// text, in which case it must be rasterized separately for each color.
+    pub(crate) fn uses_foreground(&self, glyph_id: u16) -> bool
+    {
+        match self.layers.get(&glyph_id) {
+            None => false,
+            Some(layers) => layers
+                .iter()
+                .any(|layer| layer.color == LayerColor::Foreground)
+        }
+    }
+
+    /// Rasterizes the glyph at its scale and position, or returns `None` if
+    /// it is not a color glyph. Layers using the foreground color are drawn
+    /// in the RGB components of `foreground`: its alpha is applied when the
+    /// glyph is drawn, along with the alpha of the other layers.
+    pub(crate) fn rasterize(
+        &self,
+        glyph: &rusttype::PositionedGlyph,
+        foreground: Color
+    ) -> Option<ColorGlyphImage>
+    {
+        if let Some(layers) = self.layers.get(&glyph.id().0) {
+            return rasterize_layers(glyph, layers, foreground);
+        }
+
+        if self.has_images {
+            return self.rasterize_image(glyph);
+        }
+
+        None
+    }
+
+    #[cfg(feature = "image-loading")]
+    fn rasterize_image(
+        &self,
+        glyph: &rusttype::PositionedGlyph
+    ) -> Option<ColorGlyphImage>
+    {
+        let face = ttf_parser::Face::from_slice(&self.data, self.index).ok()?;
+
+        // The scale of a rusttype glyph is the distance from the ascender to
+        // the descender, rather than the size of the em square
+        let pixels_per_unit =
+            glyph.scale().y / (f32::from(face.ascender()) - f32::from(face.descender()));
+        let pixels_per_em = f32::from(face.units_per_em()) * pixels_per_unit;
+
+        let image = face.glyph_raster_image(
+            ttf_parser::GlyphId(glyph.id().0),
+            pixels_per_em.round().max(1.0) as u16
+        )?;
+
+        if image.format != ttf_parser::RasterImageFormat::PNG || image.pixels_per_em == 0
+        {
+            return None;
+        }
+
+        let decoded =
+            image::load_from_memory_with_format(image.data, image::ImageFormat::Png)
+                .ok()?
+                .into_rgba8();
+
+        // The closest available size is scaled to the requested size
+        let ratio = pixels_per_em / f32::from(image.pixels_per_em);
+
+        let size = UVec2::new(
+            (decoded.width() as f32 * ratio).round().max(1.0) as u32,
+            (decoded.height() as f32 * ratio).round().max(1.0) as u32
+        );
+
+        let resized = image::imageops::resize(
+            &decoded,
+            size.x,
+            size.y,
+            image::imageops::FilterType::Triangle
+        );
+
+        // The vertical offset is to the bottom of the image, upwards from the
+        // baseline
+        let position = glyph.position();
+
+        let offset = IVec2::new(
+            (position.x + f32::from(image.x) * ratio).round() as i32,
+            (position.y - (f32::from(image.y) + decoded.height() as f32) * ratio).round()
+                as i32
+        );
+
+        Some(ColorGlyphImage {
+            size,
+            offset,
+            data: resized.into_raw()
+        })
+    }
+
+    #[cfg(not(feature = "image-loading"))]
+    fn rasterize_image(
+        &self,
+        _glyph: &rusttype::PositionedGlyph
+    ) -> Option<ColorGlyphImage>
+    {
+        None
+    }
+}
+
+/// Draws the outline of each layer in its color, from bottom to top.
+fn rasterize_layers(
+    glyph: &rusttype::PositionedGlyph,
+    layers: &[ColorGlyphLayer],
+    foreground: Color
+) -> Option<ColorGlyphImage>
+{
+    let font = glyph.font();
+
+    let foreground = [foreground.r(), foreground.g(), foreground.b(), 1.0];
+
+    let layers: Vec<_> = layers
+        .iter()
+        .filter(|layer| usize::from(layer.glyph_id) < font.glyph_count())
+        .map(|layer| {
+            let layer_glyph = font
+                .glyph(rusttype::GlyphId(layer.glyph_id))
+                .scaled(glyph.scale())
+                .positioned(glyph.position());
+
+            let color = match &layer.color {
+                LayerColor::Palette(color) => color,
+                LayerColor::Foreground => &foreground
+            };
+
+            (layer_glyph, color)
+        })
+        .collect();
+
+    let bounding_box = layers
+        .iter()
+        .filter_map(|(layer_glyph, _)| layer_glyph.pixel_bounding_box())
+        .reduce(|a, b| rusttype::Rect {
+            min: rusttype::point(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+            max: rusttype::point(a.max.x.max(b.max.x), a.max.y.max(b.max.y))
+        })?;
+
+    let size = UVec2::new(bounding_box.width() as u32, bounding_box.height() as u32);
+
+    // Premultiplied RGBA
+    let mut pixels = vec![[0.0f32; 4]; (size.x * size.y) as usize];
+
+    for (layer_glyph, color) in &layers {
+        let layer_box = match layer_glyph.pixel_bounding_box() {
+            None => continue,
+            Some(layer_box) => layer_box
+        };
+
+        let layer_offset_x = (layer_box.min.x - bounding_box.min.x) as u32;
+        let layer_offset_y = (layer_box.min.y - bounding_box.min.y) as u32;
+
+        layer_glyph.draw(|x, y, coverage| {
+            let index = ((y + layer_offset_y) * size.x + x + layer_offset_x) as usize;
+            let pixel = &mut pixels[index];
+
+            let alpha = color[3] * coverage;
+
+            for i in 0..3 {
+                pixel[i] = color[i] * alpha + pixel[i] * (1.0 - alpha);
+            }
+
+            pixel[3] = alpha + pixel[3] * (1.0 - alpha);
+        });
+    }
+
+    let data = pixels
+        .iter()
+        .flat_map(|[r, g, b, a]| {
+            let unpremultiply = if *a > 0.0 { 1.0 / a } else { 0.0 };
+
+            [r * unpremultiply, g * unpremultiply, b * unpremultiply, *a]
+                .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+        })
+        .collect();
+
+    Some(ColorGlyphImage {
+        size,
+        offset: IVec2::new(bounding_box.min.x, bounding_box.min.y),
+        data
+    })
+}
+
+#[inline]
+fn read_u16(data: &[u8], offset: usize) -> Option<u16>
+{
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?
+    ))
+}
+
+#[inline]
+fn read_u32(data: &[u8], offset: usize) -> Option<u32>
+{
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?
+    ))
+}
+
+/// Reads the layers of each base glyph from version 0 of the `COLR` table,
+/// using the first palette in the `CPAL` table.
+fn parse_layers(colr: &[u8], cpal: &[u8]) -> Option<HashMap<u16, Vec<ColorGlyphLayer>>>
+{
+    let palette_size = read_u16(cpal, 2)?;
+    let color_records_offset = read_u32(cpal, 8)? as usize;
+    let first_color_index = read_u16(cpal, 12)? as usize;
+
+    // Colors are stored as BGRA
+    let palette_color = |palette_index: u16| -> Option<LayerColor> {
+        if palette_index == PALETTE_INDEX_FOREGROUND {
+            return Some(LayerColor::Foreground);
+        }
+
+        if palette_index >= palette_size {
+            return None;
+        }
+
+        let offset =
+            color_records_offset + 4 * (first_color_index + palette_index as usize);
+        let bgra = cpal.get(offset..offset + 4)?;
+
+        Some(LayerColor::Palette(
+            [bgra[2], bgra[1], bgra[0], bgra[3]].map(|value| value as f32 / 255.0)
+        ))
+    };
+
+    let base_glyph_count = read_u16(colr, 2)? as usize;
+    let base_glyphs_offset = read_u32(colr, 4)? as usize;
+    let layers_offset = read_u32(colr, 8)? as usize;
+    let layer_count = read_u16(colr, 12)? as usize;
+
+    let mut result = HashMap::new();
+
+    for i in 0..base_glyph_count {
+        let record_offset = base_glyphs_offset + 6 * i;
+
+        let base_glyph_id = read_u16(colr, record_offset)?;
+        let first_layer = read_u16(colr, record_offset + 2)? as usize;
+        let base_layer_count = read_u16(colr, record_offset + 4)? as usize;
+
+        if first_layer + base_layer_count > layer_count {
+            continue;
+        }
+
+        let layers = (first_layer..first_layer + base_layer_count)
+            .map(|layer| {
+                let layer_offset = layers_offset + 4 * layer;
+
+                Some(ColorGlyphLayer {
+                    glyph_id: read_u16(colr, layer_offset)?,
+                    color: palette_color(read_u16(colr, layer_offset + 2)?)?
+                })
+            })
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(layers) = layers {
+            result.insert(base_glyph_id, layers);
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    pub fn test_parse_layers()
+    {
+        // Two palette entries: opaque red, and half-transparent blue
+        let cpal: Vec<u8> = [
+            &[0, 0, 0, 2, 0, 1, 0, 2][..],
+            &[0, 0, 0, 14],
+            &[0, 0],
+            &[0, 0, 255, 255, 255, 0, 0, 128]
+        ]
+        .concat();
+
+        // Glyph 5 has three layers, made from glyphs 6, 7 and 8
+        let colr: Vec<u8> = [
+            &[0, 0, 0, 1, 0, 0, 0, 14, 0, 0, 0, 20, 0, 3][..],
+            &[0, 5, 0, 0, 0, 3],
+            &[0, 6, 0, 0, 0, 7, 0, 1, 0, 8, 0xFF, 0xFF]
+        ]
+        .concat();
+
+        let layers = parse_layers(&colr, &cpal).unwrap();
+
+        assert_eq!(1, layers.len());
+
+        assert_eq!(
+            &vec![
+                ColorGlyphLayer {
+                    glyph_id: 6,
+                    color: LayerColor::Palette([1.0, 0.0, 0.0, 1.0])
+                },
+                ColorGlyphLayer {
+                    glyph_id: 7,
+                    color: LayerColor::Palette([0.0, 0.0, 1.0, 128.0 / 255.0])
+                },
+                ColorGlyphLayer {
+                    glyph_id: 8,
+                    color: LayerColor::Foreground
+                }
+            ],
+            layers.get(&5).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_uses_foreground()
+    {
+        let layers = |color| {
+            vec![
+                ColorGlyphLayer {
+                    glyph_id: 2,
+                    color: LayerColor::Palette([1.0, 0.0, 0.0, 1.0])
+                },
+                ColorGlyphLayer { glyph_id: 3, color },
+            ]
+        };
+
+        let color_glyphs = ColorGlyphs {
+            data: Arc::new(Vec::new()),
+            index: 0,
+            has_images: false,
+            layers: HashMap::from([
+                (1, layers(LayerColor::Foreground)),
+                (4, layers(LayerColor::Palette([0.0, 1.0, 0.0, 1.0])))
+            ])
+        };
+
+        assert!(color_glyphs.uses_foreground(1));
+        assert!(!color_glyphs.uses_foreground(4));
+        assert!(!color_glyphs.uses_foreground(5));
+    }
+}
@@ -435,6 +435,247 @@ impl<T> From<Point<T>> for Vector2<T>
     }
 }
 
+/// A 2D affine transformation, represented as a 2x3 matrix. This may
+/// represent any combination of translation, rotation, scaling, and
+/// shearing.
+///
+/// A point `(x, y)` is transformed as follows:
+///
+/// ```text
+/// x' = m11 * x + m12 * y + m13
+/// y' = m21 * x + m22 * y + m23
+/// ```
+///
+/// As the y axis points downwards in Speedy2D, positive rotation angles are
+/// clockwise on screen.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Affine2
+{
+    matrix: [[f32; 3]; 2]
+}
+
+impl Affine2
+{
+    /// The identity transformation, which leaves every point unchanged.
+    pub const IDENTITY: Affine2 = Affine2 {
+        matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+    };
+
+    /// Instantiates a new `Affine2` from the two rows of a 2x3 matrix.
+    #[inline]
+    #[must_use]
+    pub const fn new(matrix: [[f32; 3]; 2]) -> Self
+    {
+        Affine2 { matrix }
+    }
+
+    /// Creates a transformation which moves points by the specified offset.
+    #[inline]
+    #[must_use]
+    pub fn translation<V: Into<Vec2>>(offset: V) -> Self
+    {
+        let offset = offset.into();
+        Affine2::new([[1.0, 0.0, offset.x], [0.0, 1.0, offset.y]])
+    }
+
+    /// Creates a transformation which rotates points around the origin by the
+    /// specified angle, in radians.
+    #[inline]
+    #[must_use]
+    pub fn rotation(radians: f32) -> Self
+    {
+        let (sin, cos) = radians.sin_cos();
+        Affine2::new([[cos, -sin, 0.0], [sin, cos, 0.0]])
+    }
+
+    /// Creates a transformation which scales points relative to the origin,
+    /// using separate horizontal and vertical scale factors.
+    #[inline]
+    #[must_use]
+    pub fn scale<V: Into<Vec2>>(scale: V) -> Self
+    {
+        let scale = scale.into();
+        Affine2::new([[scale.x, 0.0, 0.0], [0.0, scale.y, 0.0]])
+    }
+
+    /// Creates a transformation which scales points relative to the origin by
+    /// the same factor in both directions.
+    #[inline]
+    #[must_use]
+    pub fn uniform_scale(scale: f32) -> Self
+    {
+        Affine2::scale((scale, scale))
+    }
+
+    /// Returns the two rows of the 2x3 matrix.
+    #[inline]
+    #[must_use]
+    pub fn matrix(&self) -> &[[f32; 3]; 2]
+    {
+        &self.matrix
+    }
+
+    /// Returns the translation component of this transformation.
+    #[inline]
+    #[must_use]
+    pub fn translation_component(&self) -> Vec2
+    {
+        Vec2::new(self.matrix[0][2], self.matrix[1][2])
+    }
+
+    /// Returns true if this transformation only translates points, without
+    /// rotating, scaling, or shearing them.
+    #[inline]
+    #[must_use]
+    pub fn is_translation_only(&self) -> bool
+    {
+        self.matrix[0][0] == 1.0
+            && self.matrix[0][1] == 0.0
+            && self.matrix[1][0] == 0.0
+            && self.matrix[1][1] == 1.0
+    }
+
+    /// Returns a transformation which first applies `self`, followed by
+    /// `other`.
+    #[inline]
+    #[must_use]
+    pub fn then(&self, other: &Affine2) -> Affine2
+    {
+        other * self
+    }
+
+    /// Applies this transformation to the specified point.
+    #[inline]
+    #[must_use]
+    pub fn transform_point<V: Into<Vec2>>(&self, point: V) -> Vec2
+    {
+        let point = point.into();
+        let [row1, row2] = &self.matrix;
+
+        Vec2::new(
+            row1[0] * point.x + row1[1] * point.y + row1[2],
+            row2[0] * point.x + row2[1] * point.y + row2[2]
+        )
+    }
+
+    /// Applies this transformation to the specified vector, ignoring the
+    /// translation component.
+    #[inline]
+    #[must_use]
+    pub fn transform_vector<V: Into<Vec2>>(&self, vector: V) -> Vec2
+    {
+        let vector = vector.into();
+        let [row1, row2] = &self.matrix;
+
+        Vec2::new(
+            row1[0] * vector.x + row1[1] * vector.y,
+            row2[0] * vector.x + row2[1] * vector.y
+        )
+    }
+
+    /// Returns the largest factor by which this transformation stretches the
+    /// length of a vector.
+    #[must_use]
+    pub(crate) fn max_scale_factor(&self) -> f32
+    {
+        let [[a, b, _], [d, e, _]] = self.matrix;
+
+        let sum_of_squares = a * a + b * b + d * d + e * e;
+        let determinant = a * e - b * d;
+
+        // The largest singular value of the 2x2 linear part
+        ((sum_of_squares
+            + (sum_of_squares * sum_of_squares - 4.0 * determinant * determinant)
+                .max(0.0)
+                .sqrt())
+            / 2.0)
+            .sqrt()
+    }
+
+    /// Returns the inverse of this transformation, or `None` if the
+    /// transformation is not invertible (for example, if it scales by zero).
+    #[must_use]
+    pub fn inverse(&self) -> Option<Affine2>
+    {
+        let [[a, b, c], [d, e, f]] = self.matrix;
+
+        let determinant = a * e - b * d;
+
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inv = 1.0 / determinant;
+
+        Some(Affine2::new([
+            [e * inv, -b * inv, (b * f - c * e) * inv],
+            [-d * inv, a * inv, (c * d - a * f) * inv]
+        ]))
+    }
+}
+
+impl Default for Affine2
+{
+    #[inline]
+    fn default() -> Self
+    {
+        Affine2::IDENTITY
+    }
+}
+
+impl std::ops::Mul<&Affine2> for &Affine2
+{
+    type Output = Affine2;
+
+    /// Combines two transformations. The resulting transformation applies
+    /// `rhs` first, followed by `self`.
+    fn mul(self, rhs: &Affine2) -> Affine2
+    {
+        let [[a1, b1, c1], [d1, e1, f1]] = self.matrix;
+        let [[a2, b2, c2], [d2, e2, f2]] = rhs.matrix;
+
+        Affine2::new([
+            [a1 * a2 + b1 * d2, a1 * b2 + b1 * e2, a1 * c2 + b1 * f2 + c1],
+            [d1 * a2 + e1 * d2, d1 * b2 + e1 * e2, d1 * c2 + e1 * f2 + f1]
+        ])
+    }
+}
+
+impl std::ops::Mul<Affine2> for Affine2
+{
+    type Output = Affine2;
+
+    /// Combines two transformations. The resulting transformation applies
+    /// `rhs` first, followed by `self`.
+    #[inline]
+    fn mul(self, rhs: Affine2) -> Affine2
+    {
+        &self * &rhs
+    }
+}
+
+impl std::ops::Mul<Vec2> for &Affine2
+{
+    type Output = Vec2;
+
+    #[inline]
+    fn mul(self, rhs: Vec2) -> Vec2
+    {
+        self.transform_point(rhs)
+    }
+}
+
+impl std::ops::Mul<Vec2> for Affine2
+{
+    type Output = Vec2;
+
+    #[inline]
+    fn mul(self, rhs: Vec2) -> Vec2
+    {
+        self.transform_point(rhs)
+    }
+}
+
 #[cfg(test)]
 mod test
 {
@@ -578,4 +819,54 @@ mod test
         }
         assert_eq!(left, Vector2::new(3, 2));
     }
+
+    fn assert_vec2_near(expected: Vec2, actual: Vec2)
+    {
+        assert!(
+            (expected - actual).magnitude() < 0.0001,
+            "Expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_affine()
+    {
+        let point = Vec2::new(3.0, 4.0);
+
+        assert_eq!(point, Affine2::IDENTITY * point);
+
+        assert_eq!(
+            Vec2::new(13.0, 2.0),
+            Affine2::translation((10.0, -2.0)) * point
+        );
+
+        assert_eq!(Vec2::new(6.0, 12.0), Affine2::scale((2.0, 3.0)) * point);
+
+        assert_vec2_near(
+            Vec2::new(-4.0, 3.0),
+            Affine2::rotation(std::f32::consts::FRAC_PI_2) * point
+        );
+
+        let combined = Affine2::translation((10.0, 0.0)) * Affine2::uniform_scale(2.0);
+
+        assert_eq!(Vec2::new(16.0, 8.0), combined * point);
+
+        assert_eq!(
+            combined,
+            Affine2::uniform_scale(2.0).then(&Affine2::translation((10.0, 0.0)))
+        );
+
+        assert_eq!(Vec2::new(6.0, 8.0), combined.transform_vector(point));
+
+        let inverse = combined.inverse().unwrap();
+
+        assert_vec2_near(point, inverse * (combined * point));
+
+        assert!(Affine2::uniform_scale(0.0).inverse().is_none());
+
+        assert!(Affine2::translation((1.0, 2.0)).is_translation_only());
+        assert!(!combined.is_translation_only());
+    }
 }
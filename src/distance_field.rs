@@ -0,0 +1,216 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Converts anti-aliased coverage bitmaps into signed distance fields, which
+//! can be scaled and rotated while keeping their edges sharp.
+
+use crate::dimen::UVec2;
+
+/// Larger than the squared distance between any two pixels of a glyph.
+const FAR_AWAY: f32 = 1.0e20;
+
+/// Coverage values this close to 0.0 or 1.0 are rounding errors from the
+/// rasterizer, and would be invisible in an 8-bit bitmap.
+const COVERAGE_EPSILON: f32 = 0.5 / 255.0;
+
+/// Converts a bitmap of coverage values between 0.0 and 1.0 into a signed
+/// distance field.
+///
+/// The value of each pixel in the output is 128 on the outline of the shape,
+/// increasing towards 255 inside the shape and decreasing towards 0 outside
+/// it. The distance at which the values reach these limits is given by
+/// `spread`, in pixels.
+pub(crate) fn coverage_to_distance_field(
+    coverage: &[f32],
+    size: UVec2,
+    spread: f32
+) -> Vec<u8>
+{
+    let width = size.x as usize;
+    let height = size.y as usize;
+
+    assert_eq!(width * height, coverage.len());
+
+    let mut to_inside: Vec<f32> = coverage
+        .iter()
+        .map(|value| if *value >= 0.5 { 0.0 } else { FAR_AWAY })
+        .collect();
+
+    let mut to_outside: Vec<f32> = coverage
+        .iter()
+        .map(|value| if *value >= 0.5 { FAR_AWAY } else { 0.0 })
+        .collect();
+
+    squared_distance_transform(&mut to_inside, width, height);
+    squared_distance_transform(&mut to_outside, width, height);
+
+    coverage
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            // Pixels on the outline are partially covered, and their coverage
+            // gives a better estimate of the distance than their neighbours.
+            let on_outline = *value > COVERAGE_EPSILON && *value < 1.0 - COVERAGE_EPSILON;
+
+            let distance = if on_outline {
+                value - 0.5
+            } else if *value >= 0.5 {
+                to_outside[i].sqrt() - 0.5
+            } else {
+                0.5 - to_inside[i].sqrt()
+            };
+
+            let normalized = (0.5 + distance / (2.0 * spread)).clamp(0.0, 1.0);
+
+            (normalized * 255.0).round() as u8
+        })
+        .collect()
+}
+
+/// Replaces each value with the squared distance to the nearest pixel with a
+/// value of zero, using the algorithm by Felzenszwalb and Huttenlocher.
+fn squared_distance_transform(values: &mut [f32], width: usize, height: usize)
+{
+    let length = width.max(height);
+
+    let mut input = vec![0.0; length];
+    let mut output = vec![0.0; length];
+    let mut parabolas = vec![0; length];
+    let mut boundaries = vec![0.0; length + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            input[y] = values[y * width + x];
+        }
+
+        squared_distance_transform_1d(
+            &input[..height],
+            &mut output[..height],
+            &mut parabolas,
+            &mut boundaries
+        );
+
+        for y in 0..height {
+            values[y * width + x] = output[y];
+        }
+    }
+
+    for y in 0..height {
+        let row = &mut values[y * width..(y + 1) * width];
+
+        input[..width].copy_from_slice(row);
+
+        squared_distance_transform_1d(
+            &input[..width],
+            row,
+            &mut parabolas,
+            &mut boundaries
+        );
+    }
+}
+
+/// Computes the lower envelope of the parabolas rooted at each input value.
+fn squared_distance_transform_1d(
+    input: &[f32],
+    output: &mut [f32],
+    parabolas: &mut [usize],
+    boundaries: &mut [f32]
+)
+{
+    if input.is_empty() {
+        return;
+    }
+
+    let intersection = |q: usize, p: usize| {
+        let (q_f, p_f) = (q as f32, p as f32);
+        ((input[q] + q_f * q_f) - (input[p] + p_f * p_f)) / (2.0 * q_f - 2.0 * p_f)
+    };
+
+    let mut count = 0;
+
+    parabolas[0] = 0;
+    boundaries[0] = f32::NEG_INFINITY;
+    boundaries[1] = f32::INFINITY;
+
+    for q in 1..input.len() {
+        let mut s = intersection(q, parabolas[count]);
+
+        while s <= boundaries[count] {
+            count -= 1;
+            s = intersection(q, parabolas[count]);
+        }
+
+        count += 1;
+        parabolas[count] = q;
+        boundaries[count] = s;
+        boundaries[count + 1] = f32::INFINITY;
+    }
+
+    count = 0;
+
+    for (q, value) in output.iter_mut().enumerate() {
+        while boundaries[count + 1] < q as f32 {
+            count += 1;
+        }
+
+        let offset = q as f32 - parabolas[count] as f32;
+        *value = offset * offset + input[parabolas[count]];
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    pub fn test_squared_distance_transform()
+    {
+        // A single zero in the middle of a 3x3 grid
+        let mut values = vec![FAR_AWAY; 9];
+        values[4] = 0.0;
+
+        squared_distance_transform(&mut values, 3, 3);
+
+        assert_eq!(vec![2.0, 1.0, 2.0, 1.0, 0.0, 1.0, 2.0, 1.0, 2.0], values);
+    }
+
+    #[test]
+    pub fn test_coverage_to_distance_field()
+    {
+        // A filled square in the middle, with a half-covered column on its
+        // right-hand edge
+        let mut coverage = vec![0.0; 8 * 8];
+
+        for y in 2..6 {
+            for x in 2..5 {
+                coverage[y * 8 + x] = 1.0;
+            }
+            coverage[y * 8 + 5] = 0.5;
+        }
+
+        let field = coverage_to_distance_field(&coverage, UVec2::new(8, 8), 2.0);
+
+        // On the outline
+        assert_eq!(128, field[3 * 8 + 5]);
+
+        // Further inside is larger, and further outside is smaller
+        assert!(field[3 * 8 + 3] > field[3 * 8 + 2]);
+        assert!(field[3 * 8 + 2] > 128);
+        assert!(field[3 * 8 + 1] < 128);
+        assert_eq!(0, field[0]);
+    }
+}
@@ -0,0 +1,350 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Adds a feathered fringe around the edges of a triangulated shape, so that
+//! the edges are anti-aliased without multisampling.
+
+use std::collections::HashMap;
+
+use crate::dimen::Vec2;
+
+/// The width of the fringe, in pixels. The opacity of the shape fades from
+/// `1.0` to `0.0` across the fringe.
+const FRINGE_WIDTH: f32 = 1.0;
+
+/// The maximum distance by which a corner of the fringe may extend from the
+/// corner of the shape, as a multiple of the fringe width.
+const MAX_MITER_LENGTH: f32 = 2.0;
+
+/// If shrinking the shape reduces the area of any triangle below this
+/// fraction of its original area, the shape is too thin to be shrunk, and the
+/// fringe is placed outside it instead.
+const MIN_SHRUNK_AREA_RATIO: f32 = 0.25;
+
+/// Triangles with a smaller area than this (in square pixels) are ignored when
+/// finding the edges of the shape.
+const DEGENERATE_AREA: f32 = 1.0e-6;
+
+/// Where the fringe is placed, relative to the edges of the shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FeatherMode
+{
+    /// The shape is shrunk by half the fringe width, and the fringe is
+    /// centered on the original edges. This preserves the apparent size of the
+    /// shape, but requires that the triangles do not overlap.
+    Centered,
+
+    /// The shape is left unchanged, and the fringe is placed outside it. This
+    /// is used for shapes with overlapping triangles, such as strokes.
+    Outside
+}
+
+/// A triangle of the fringe. Each vertex takes its color from a vertex of the
+/// original shape, multiplied by the opacity.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FringeTriangle
+{
+    pub positions: [Vec2; 3],
+    /// The index of the original triangle, and the index of the vertex within
+    /// that triangle.
+    pub sources: [(usize, usize); 3],
+    pub opacity: [f32; 3]
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FeatheredTriangles
+{
+    /// The original triangles, in the same order, with the vertices on the
+    /// edges of the shape moved inwards if required.
+    pub interior: Vec<[Vec2; 3]>,
+    pub fringe: Vec<FringeTriangle>
+}
+
+#[inline]
+fn dot(a: Vec2, b: Vec2) -> f32
+{
+    a.x * b.x + a.y * b.y
+}
+
+#[inline]
+fn signed_area(triangle: &[Vec2; 3]) -> f32
+{
+    let a = triangle[1] - triangle[0];
+    let b = triangle[2] - triangle[0];
+
+    (a.x * b.y - a.y * b.x) / 2.0
+}
+
+/// Vertices at exactly the same position are considered to be shared between
+/// triangles. Adding zero converts `-0.0` into `0.0`.
+#[inline]
+fn vertex_key(vertex: Vec2) -> (u32, u32)
+{
+    ((vertex.x + 0.0).to_bits(), (vertex.y + 0.0).to_bits())
+}
+
+/// Returns the offset from a vertex to the outer edge of the fringe, given
+/// the sum of the outward normals of the edges which meet at that vertex.
+fn miter_offset(normal_sum: Vec2, normal_count: u32) -> Vec2
+{
+    if normal_count == 0 {
+        return Vec2::ZERO;
+    }
+
+    let average = normal_sum / normal_count as f32;
+    let length_squared = average.magnitude_squared();
+
+    if length_squared < 1.0e-6 {
+        return Vec2::ZERO;
+    }
+
+    average / length_squared.max(1.0 / (MAX_MITER_LENGTH * MAX_MITER_LENGTH))
+}
+
+/// Generates the fringe for the specified triangles, which must be in pixel
+/// coordinates. Edges which are shared between two triangles are considered
+/// to be inside the shape, and do not get a fringe.
+pub(crate) fn feather(triangles: &[[Vec2; 3]], mode: FeatherMode) -> FeatheredTriangles
+{
+    let mut vertex_ids = HashMap::new();
+
+    let ids: Vec<[usize; 3]> = triangles
+        .iter()
+        .map(|triangle| {
+            triangle.map(|vertex| {
+                let next_id = vertex_ids.len();
+                *vertex_ids.entry(vertex_key(vertex)).or_insert(next_id)
+            })
+        })
+        .collect();
+
+    let areas: Vec<f32> = triangles.iter().map(signed_area).collect();
+
+    let is_edge_counted = |triangle: usize, start: usize| {
+        areas[triangle].abs() > DEGENERATE_AREA
+            && ids[triangle][start] != ids[triangle][(start + 1) % 3]
+    };
+
+    let edge_key = |triangle: usize, start: usize| {
+        let a = ids[triangle][start];
+        let b = ids[triangle][(start + 1) % 3];
+        (a.min(b), a.max(b))
+    };
+
+    let mut edge_counts: HashMap<(usize, usize), u32> = HashMap::new();
+
+    for triangle in 0..triangles.len() {
+        for start in 0..3 {
+            if is_edge_counted(triangle, start) {
+                *edge_counts.entry(edge_key(triangle, start)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    // Found in triangle order, so that the output is deterministic
+    let mut boundary_edges = Vec::new();
+
+    let mut normal_sums = vec![Vec2::ZERO; vertex_ids.len()];
+    let mut normal_counts = vec![0; vertex_ids.len()];
+
+    for (triangle_index, triangle) in triangles.iter().enumerate() {
+        for start in 0..3 {
+            if !is_edge_counted(triangle_index, start)
+                || edge_counts[&edge_key(triangle_index, start)] != 1
+            {
+                continue;
+            }
+
+            let a = triangle[start];
+            let b = triangle[(start + 1) % 3];
+            let c = triangle[(start + 2) % 3];
+
+            let normal = match (b - a).normalize() {
+                None => continue,
+                Some(direction) => direction.rotate_90_degrees_clockwise()
+            };
+
+            let outward_normal = if dot(normal, c - a) > 0.0 {
+                normal * -1.0
+            } else {
+                normal
+            };
+
+            for end in [start, (start + 1) % 3] {
+                let id = ids[triangle_index][end];
+                normal_sums[id] += outward_normal;
+                normal_counts[id] += 1;
+            }
+
+            boundary_edges.push((triangle_index, start));
+        }
+    }
+
+    let offsets: Vec<Vec2> = normal_sums
+        .iter()
+        .zip(normal_counts.iter())
+        .map(|(sum, count)| miter_offset(*sum, *count))
+        .collect();
+
+    let shrink = |inset: f32| -> Vec<[Vec2; 3]> {
+        triangles
+            .iter()
+            .zip(ids.iter())
+            .map(|(triangle, ids)| {
+                [0, 1, 2].map(|i| triangle[i] - offsets[ids[i]] * inset)
+            })
+            .collect()
+    };
+
+    let (interior, inset) = match mode {
+        FeatherMode::Outside => (triangles.to_vec(), 0.0),
+
+        FeatherMode::Centered => {
+            let inset = FRINGE_WIDTH / 2.0;
+            let interior = shrink(inset);
+
+            let too_thin = interior.iter().zip(areas.iter()).any(|(shrunk, area)| {
+                let shrunk_area = signed_area(shrunk);
+
+                area.abs() > DEGENERATE_AREA
+                    && (shrunk_area * area <= 0.0
+                        || shrunk_area.abs() < area.abs() * MIN_SHRUNK_AREA_RATIO)
+            });
+
+            if too_thin {
+                (triangles.to_vec(), 0.0)
+            } else {
+                (interior, inset)
+            }
+        }
+    };
+
+    let outer = |triangle: usize, vertex: usize| {
+        triangles[triangle][vertex]
+            + offsets[ids[triangle][vertex]] * (FRINGE_WIDTH - inset)
+    };
+
+    let mut fringe = Vec::with_capacity(boundary_edges.len() * 2);
+
+    for (triangle, start) in boundary_edges {
+        let end = (start + 1) % 3;
+
+        let start_inner = interior[triangle][start];
+        let end_inner = interior[triangle][end];
+        let start_outer = outer(triangle, start);
+        let end_outer = outer(triangle, end);
+
+        fringe.push(FringeTriangle {
+            positions: [start_inner, start_outer, end_outer],
+            sources: [(triangle, start), (triangle, start), (triangle, end)],
+            opacity: [1.0, 0.0, 0.0]
+        });
+
+        fringe.push(FringeTriangle {
+            positions: [end_outer, end_inner, start_inner],
+            sources: [(triangle, end), (triangle, end), (triangle, start)],
+            opacity: [0.0, 1.0, 1.0]
+        });
+    }
+
+    FeatheredTriangles { interior, fringe }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    fn rectangle(left: f32, top: f32, right: f32, bottom: f32) -> Vec<[Vec2; 3]>
+    {
+        let top_left = Vec2::new(left, top);
+        let top_right = Vec2::new(right, top);
+        let bottom_right = Vec2::new(right, bottom);
+        let bottom_left = Vec2::new(left, bottom);
+
+        vec![
+            [top_left, top_right, bottom_right],
+            [bottom_right, bottom_left, top_left],
+        ]
+    }
+
+    #[test]
+    pub fn test_centered_fringe()
+    {
+        let result = feather(&rectangle(10.0, 10.0, 20.0, 30.0), FeatherMode::Centered);
+
+        assert_eq!(
+            vec![
+                [
+                    Vec2::new(10.5, 10.5),
+                    Vec2::new(19.5, 10.5),
+                    Vec2::new(19.5, 29.5)
+                ],
+                [
+                    Vec2::new(19.5, 29.5),
+                    Vec2::new(10.5, 29.5),
+                    Vec2::new(10.5, 10.5)
+                ]
+            ],
+            result.interior
+        );
+
+        // The shared diagonal is not an edge of the shape
+        assert_eq!(8, result.fringe.len());
+
+        assert_eq!(
+            FringeTriangle {
+                positions: [
+                    Vec2::new(10.5, 10.5),
+                    Vec2::new(9.5, 9.5),
+                    Vec2::new(20.5, 9.5)
+                ],
+                sources: [(0, 0), (0, 0), (0, 1)],
+                opacity: [1.0, 0.0, 0.0]
+            },
+            result.fringe[0]
+        );
+    }
+
+    #[test]
+    pub fn test_outside_fringe()
+    {
+        let triangles = rectangle(10.0, 10.0, 20.0, 30.0);
+        let result = feather(&triangles, FeatherMode::Outside);
+
+        assert_eq!(triangles, result.interior);
+        assert_eq!(8, result.fringe.len());
+
+        assert_eq!(
+            [
+                Vec2::new(10.0, 10.0),
+                Vec2::new(9.0, 9.0),
+                Vec2::new(21.0, 9.0)
+            ],
+            result.fringe[0].positions
+        );
+    }
+
+    #[test]
+    pub fn test_thin_shape_not_shrunk()
+    {
+        let triangles = rectangle(10.0, 9.5, 50.0, 10.5);
+        let result = feather(&triangles, FeatherMode::Centered);
+
+        assert_eq!(triangles, result.interior);
+        assert_eq!(Vec2::new(10.0, 9.5), result.fringe[0].positions[0]);
+    }
+}
@@ -18,8 +18,8 @@ use std::collections::VecDeque;
 use std::convert::TryInto;
 use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
-use std::iter::Peekable;
-use std::ops::Deref;
+use std::ops::{Deref, RangeBounds};
+use std::rc::Rc;
 use std::slice::Iter;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -29,9 +29,16 @@ use rusttype::Scale;
 use smallvec::{smallvec, SmallVec};
 use unicode_normalization::UnicodeNormalization;
 
+use crate::bitmap_font::{BitmapFontData, PositionedBitmapGlyph};
+use crate::color::Color;
+use crate::color_glyphs::ColorGlyphs;
 use crate::dimen::{Vec2, Vector2};
 use crate::error::{BacktraceError, ErrorMessage};
+use crate::image::ImageHandle;
 use crate::shape::{Rect, Rectangle};
+#[cfg(feature = "text-shaping")]
+use crate::text_shaping::{self, ShapedGlyph};
+use crate::{text_bidi, text_line_break};
 
 static FONT_ID_GENERATOR: AtomicUsize = AtomicUsize::new(10000);
 
@@ -95,6 +102,12 @@ impl Codepoint
 struct RenderableWord
 {
     codepoints: Vec<Codepoint>,
+    /// The bidi embedding level of each codepoint.
+    bidi_levels: Vec<u8>,
+    /// The index of the span containing each codepoint.
+    spans: Vec<usize>,
+    /// The bidi embedding level of the paragraph containing this word.
+    paragraph_level: u8,
     is_whitespace: bool
 }
 
@@ -103,9 +116,14 @@ impl RenderableWord
     fn starting_from_codepoint_location(mut self, location: usize) -> Self
     {
         self.codepoints.drain(0..location);
+        self.bidi_levels.drain(0..location);
+        self.spans.drain(0..location);
 
         RenderableWord {
             codepoints: self.codepoints,
+            bidi_levels: self.bidi_levels,
+            spans: self.spans,
+            paragraph_level: self.paragraph_level,
             is_whitespace: self.is_whitespace
         }
     }
@@ -120,47 +138,73 @@ enum Word
 
 impl Word
 {
-    fn split_words(codepoints: &[Codepoint]) -> Vec<Word>
+    fn split_words(
+        codepoints: &[Codepoint],
+        span_indices: &[usize],
+        line_breaking: LineBreaking
+    ) -> Vec<Word>
     {
-        let mut reader = codepoints.iter().peekable();
+        let chars: Vec<char> = codepoints
+            .iter()
+            .map(|codepoint| codepoint.codepoint)
+            .collect();
+
+        let bidi = text_bidi::resolve_levels(&chars);
+
+        let break_before = match line_breaking {
+            LineBreaking::Whitespace => Vec::new(),
+            LineBreaking::Unicode => text_line_break::break_opportunities(&chars)
+        };
+
+        let renderable = |indices: &[usize], is_whitespace: bool| {
+            Word::Renderable(RenderableWord {
+                codepoints: indices.iter().map(|i| codepoints[*i].clone()).collect(),
+                bidi_levels: indices.iter().map(|i| bidi.levels[*i]).collect(),
+                spans: indices.iter().map(|i| span_indices[*i]).collect(),
+                paragraph_level: bidi.paragraph_levels[indices[0]],
+                is_whitespace
+            })
+        };
+
+        let mut reader = codepoints.iter().enumerate().peekable();
 
         let mut result = Vec::new();
 
-        while let Some(first_token) = reader.next() {
+        while let Some((first_index, first_token)) = reader.next() {
             match first_token.codepoint {
                 Codepoint::ZERO_WIDTH_SPACE | '\r' => {
                     // Do nothing here, just ignore it
                 }
 
+                c if text_bidi::is_formatting_character(c) => {
+                    // Only affects the bidi levels, and isn't displayed
+                }
+
                 '\n' => result.push(Word::Newline),
 
-                ' ' | '\t' => {
-                    result.push(Word::Renderable(RenderableWord {
-                        codepoints: vec![first_token.clone()],
-                        is_whitespace: true
-                    }));
-                }
+                ' ' | '\t' => result.push(renderable(&[first_index], true)),
 
                 _ => {
                     // Non-whitespace word
 
-                    let mut word_codepoints = Vec::new();
-                    word_codepoints.reserve(16);
-                    word_codepoints.push(first_token.clone());
+                    let mut word_indices = Vec::new();
+                    word_indices.reserve(16);
+                    word_indices.push(first_index);
 
-                    while let Some(next) = reader.peek() {
+                    while let Some((next_index, next)) = reader.peek() {
                         match next.codepoint {
                             ' ' | '\t' | '\r' | '\n' | Codepoint::ZERO_WIDTH_SPACE => {
                                 break
                             }
-                            _ => word_codepoints.push(reader.next().unwrap().clone())
+                            c if text_bidi::is_formatting_character(c) => {}
+                            _ if break_before.get(*next_index) == Some(&true) => break,
+                            _ => word_indices.push(*next_index)
                         }
+
+                        reader.next();
                     }
 
-                    result.push(Word::Renderable(RenderableWord {
-                        codepoints: word_codepoints,
-                        is_whitespace: false
-                    }));
+                    result.push(renderable(&word_indices, false));
                 }
             }
         }
@@ -172,14 +216,118 @@ impl Word
 /// A struct representing a glyph in a font.
 pub struct FontGlyph
 {
-    glyph: rusttype::Glyph<'static>,
-    font: Font
+    id: rusttype::GlyphId,
+    font: GlyphFont
+}
+
+/// The font containing a glyph, which provides the metrics of the glyph
+/// during layout.
+#[derive(Clone)]
+enum GlyphFont
+{
+    Outline(Font),
+    Bitmap
+    {
+        id: FontId,
+        data: Arc<BitmapFontData>
+    }
+}
+
+impl GlyphFont
+{
+    #[inline]
+    fn id(&self) -> FontId
+    {
+        match self {
+            GlyphFont::Outline(font) => font.id(),
+            GlyphFont::Bitmap { id, .. } => *id
+        }
+    }
+
+    fn v_metrics(&self, scale: &Scale) -> rusttype::VMetrics
+    {
+        match self {
+            GlyphFont::Outline(font) => font.font().v_metrics(*scale),
+            GlyphFont::Bitmap { data, .. } => data.v_metrics(scale.y)
+        }
+    }
+
+    #[cfg(not(feature = "text-shaping"))]
+    fn advance_width(&self, glyph_id: rusttype::GlyphId, scale: &Scale) -> f32
+    {
+        match self {
+            GlyphFont::Outline(font) => {
+                font.font()
+                    .glyph(glyph_id)
+                    .scaled(*scale)
+                    .h_metrics()
+                    .advance_width
+            }
+            GlyphFont::Bitmap { data, .. } => {
+                data.glyph(glyph_id.0).advance_width * data.scale_factor(scale.y)
+            }
+        }
+    }
+
+    fn pair_kerning(
+        &self,
+        scale: &Scale,
+        first: rusttype::GlyphId,
+        second: rusttype::GlyphId
+    ) -> f32
+    {
+        match self {
+            GlyphFont::Outline(font) => font.font().pair_kerning(*scale, first, second),
+            GlyphFont::Bitmap { data, .. } => {
+                data.kerning(first.0, second.0) * data.scale_factor(scale.y)
+            }
+        }
+    }
+
+    #[inline]
+    fn color_glyphs(&self) -> Option<Arc<ColorGlyphs>>
+    {
+        match self {
+            GlyphFont::Outline(font) => font.color_glyphs().clone(),
+            GlyphFont::Bitmap { .. } => None
+        }
+    }
+
+    /// Places the glyph on the baseline at the specified horizontal position.
+    fn positioned(
+        &self,
+        glyph_id: rusttype::GlyphId,
+        scale: &Scale,
+        x_pos: f32
+    ) -> PositionedFontGlyph
+    {
+        match self {
+            GlyphFont::Outline(font) => PositionedFontGlyph::Outline(
+                font.font
+                    .glyph(glyph_id)
+                    .scaled(*scale)
+                    .positioned(rusttype::point(x_pos, 0.0)),
+                font.data.clone()
+            ),
+            GlyphFont::Bitmap { data, .. } => {
+                PositionedFontGlyph::Bitmap(PositionedBitmapGlyph {
+                    index: glyph_id.0,
+                    glyph: data.glyph(glyph_id.0).clone(),
+                    scale: data.scale_factor(scale.y),
+                    position: Vec2::new(x_pos, 0.0)
+                })
+            }
+        }
+    }
 }
 
 struct WordsIterator
 {
-    words: Peekable<IntoIter<Word>>,
-    pending: VecDeque<Word>
+    words: IntoIter<Word>,
+    pending: VecDeque<Word>,
+    /// The span containing the last codepoint returned, used for the metrics
+    /// of empty lines.
+    last_span: usize
 }
 
 impl WordsIterator
@@ -187,8 +335,9 @@ impl WordsIterator
     fn from(words: Vec<Word>) -> Self
     {
         WordsIterator {
-            words: words.into_iter().peekable(),
-            pending: VecDeque::new()
+            words: words.into_iter(),
+            pending: VecDeque::new(),
+            last_span: 0
         }
     }
 
@@ -199,6 +348,20 @@ impl WordsIterator
         self.words.len() > 0 || !self.pending.is_empty()
     }
 
+    /// Returns true if any of the remaining words would be drawn, rather than
+    /// only whitespace and line breaks.
+    #[must_use]
+    fn has_visible_words(&self) -> bool
+    {
+        self.pending
+            .iter()
+            .chain(self.words.as_slice())
+            .any(|word| match word {
+                Word::Renderable(word) => !word.is_whitespace,
+                Word::Newline => false
+            })
+    }
+
     #[inline]
     #[must_use]
     fn peek(&mut self) -> Option<&Word>
@@ -207,7 +370,7 @@ impl WordsIterator
             return Some(word);
         }
 
-        if let Some(word) = self.words.peek() {
+        if let Some(word) = self.words.as_slice().first() {
             return Some(word);
         }
 
@@ -217,15 +380,15 @@ impl WordsIterator
     #[inline]
     fn next(&mut self) -> Option<Word>
     {
-        if let Some(word) = self.pending.pop_front() {
-            return Some(word);
-        }
+        let word = self.pending.pop_front().or_else(|| self.words.next());
 
-        if let Some(word) = self.words.next() {
-            return Some(word);
+        if let Some(Word::Renderable(word)) = &word {
+            if let Some(span) = word.spans.last() {
+                self.last_span = *span;
+            }
         }
 
-        None
+        word
     }
 
     #[inline]
@@ -235,6 +398,77 @@ impl WordsIterator
     }
 }
 
+/// The font, scale, and style used to lay out a span of the text.
+struct SpanStyle<'a, T: TextLayout + ?Sized>
+{
+    layout_helper: &'a T,
+    scale: Scale,
+    color: Option<Color>,
+    underline: bool,
+    strikethrough: bool,
+    overline: bool
+}
+
+impl<'a, T: TextLayout + ?Sized> SpanStyle<'a, T>
+{
+    fn new(layout_helper: &'a T, scale: f32, options: &TextOptions) -> Self
+    {
+        SpanStyle {
+            layout_helper,
+            scale: Scale::uniform(scale),
+            color: None,
+            underline: options.underline,
+            strikethrough: options.strikethrough,
+            overline: options.overline
+        }
+    }
+
+    fn has_decoration(&self, kind: TextDecorationKind) -> bool
+    {
+        match kind {
+            TextDecorationKind::Underline => self.underline,
+            TextDecorationKind::Strikethrough => self.strikethrough,
+            TextDecorationKind::Overline => self.overline
+        }
+    }
+
+    /// The position and thickness of the decoration, from the font if it
+    /// provides them, and otherwise estimated from the scale. The decoration
+    /// is always at least one pixel thick.
+    fn decoration_metrics(&self, kind: TextDecorationKind) -> TextDecorationMetrics
+    {
+        let metrics = self
+            .layout_helper
+            .text_decoration_metrics(kind, self.scale.y)
+            .unwrap_or_else(|| {
+                let thickness = self.scale.y / 16.0;
+
+                let offset_y = match kind {
+                    TextDecorationKind::Underline => self.scale.y * 0.08,
+                    TextDecorationKind::Strikethrough => {
+                        -self.scale.y * 0.25 - thickness / 2.0
+                    }
+                    TextDecorationKind::Overline => {
+                        -self
+                            .layout_helper
+                            .empty_line_vertical_metrics(self.scale.y)
+                            .ascent
+                    }
+                };
+
+                TextDecorationMetrics {
+                    offset_y,
+                    thickness
+                }
+            });
+
+        TextDecorationMetrics {
+            thickness: crate::numeric::max(1.0, metrics.thickness),
+            ..metrics
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct LineLayoutMetrics
 {
@@ -243,7 +477,8 @@ struct LineLayoutMetrics
     min_descent: f32,
     max_line_gap: f32,
     last_glyph_id: Option<rusttype::GlyphId>,
-    last_font_id: Option<FontId>
+    last_font_id: Option<FontId>,
+    last_scale: Option<Scale>
 }
 
 impl LineLayoutMetrics
@@ -256,7 +491,8 @@ impl LineLayoutMetrics
             min_descent: 0.0,
             max_line_gap: 0.0,
             last_glyph_id: None,
-            last_font_id: None
+            last_font_id: None,
+            last_scale: None
         }
     }
 
@@ -267,39 +503,47 @@ impl LineLayoutMetrics
         self.max_ascent - self.min_descent
     }
 
+    fn update_vertical_metrics(&mut self, font: &GlyphFont, scale: &Scale)
+    {
+        if self.last_font_id != Some(font.id()) || self.last_scale != Some(*scale) {
+            let v_metrics = font.v_metrics(scale);
+
+            self.max_ascent = crate::numeric::max(self.max_ascent, v_metrics.ascent);
+            self.min_descent = crate::numeric::min(self.min_descent, v_metrics.descent);
+            self.max_line_gap =
+                crate::numeric::max(self.max_line_gap, v_metrics.line_gap);
+        }
+
+        self.last_scale = Some(*scale);
+    }
+
+    #[cfg(not(feature = "text-shaping"))]
     fn update_and_get_render_pos_x(
         &mut self,
-        glyph: &rusttype::ScaledGlyph,
-        font_id: FontId,
+        glyph: &FontGlyph,
         scale: &Scale,
         options: &TextOptions
     ) -> f32
     {
+        let font_id = glyph.font.id();
+
         if let Some(last_glyph_id) = self.last_glyph_id {
-            if self.last_font_id == Some(font_id) {
-                self.x_pos +=
-                    glyph.font().pair_kerning(*scale, last_glyph_id, glyph.id());
+            if self.last_font_id == Some(font_id) && self.last_scale == Some(*scale) {
+                self.x_pos += glyph.font.pair_kerning(scale, last_glyph_id, glyph.id);
             }
 
             self.x_pos += options.tracking;
         }
 
-        if self.last_font_id != Some(font_id) {
-            let v_metrics = glyph.font().v_metrics(*scale);
-
-            self.max_ascent = crate::numeric::max(self.max_ascent, v_metrics.ascent);
-            self.min_descent = crate::numeric::min(self.min_descent, v_metrics.descent);
-            self.max_line_gap =
-                crate::numeric::max(self.max_line_gap, v_metrics.line_gap);
-        }
+        self.update_vertical_metrics(&glyph.font, scale);
 
-        let advance_width = glyph.h_metrics().advance_width;
+        let advance_width = glyph.font.advance_width(glyph.id, scale);
 
         let glyph_x_pos_start = self.x_pos;
         self.x_pos += advance_width;
 
         self.last_font_id = Some(font_id);
-        self.last_glyph_id = Some(glyph.id());
+        self.last_glyph_id = Some(glyph.id);
 
         glyph_x_pos_start
     }
@@ -333,14 +577,59 @@ impl WordLayoutResult
     }
 }
 
+/// Returns the glyph for the codepoint, or the glyph to draw in its place as
+/// set using [TextOptions::with_missing_glyph()], along with the codepoint
+/// which was used.
+fn lookup_glyph_or_replacement<T: TextLayout + ?Sized>(
+    layout_helper: &T,
+    codepoint: char,
+    options: &TextOptions
+) -> Option<(FontGlyph, char)>
+{
+    if let Some(glyph) = layout_helper.lookup_glyph_for_codepoint(codepoint) {
+        return Some((glyph, codepoint));
+    }
+
+    // Many fonts have no glyph for tabs. Without tab stops, these are skipped,
+    // but with tab stops they still need to take up space so that they can
+    // advance to the next stop.
+    if codepoint == '\t' {
+        options.tab_stops.as_ref()?;
+
+        if let Some(glyph) = layout_helper.lookup_glyph_for_codepoint(' ') {
+            return Some((glyph, ' '));
+        }
+    }
+
+    if let Some(callback) = &options.missing_glyph_callback {
+        (callback.0)(codepoint);
+    }
+
+    let replacement = || {
+        ['□', '?'].iter().find_map(|replacement| {
+            layout_helper
+                .lookup_glyph_for_codepoint(*replacement)
+                .map(|glyph| (glyph, *replacement))
+        })
+    };
+
+    match options.missing_glyph {
+        MissingGlyph::Replacement => replacement(),
+        MissingGlyph::Tofu => layout_helper
+            .missing_glyph()
+            .map(|glyph| (glyph, codepoint))
+            .or_else(replacement),
+        MissingGlyph::Skip => None
+    }
+}
+
+#[cfg(not(feature = "text-shaping"))]
 #[allow(clippy::too_many_arguments)]
 fn try_layout_word_internal<T: TextLayout + ?Sized>(
-    layout_helper: &T,
+    styles: &[SpanStyle<T>],
     word: RenderableWord,
     remaining_words: &mut WordsIterator,
-    scale: &Scale,
     options: &TextOptions,
-    pos_y_baseline: f32,
     first_word_on_line: bool,
     previous_metrics: &LineLayoutMetrics,
     output: &mut FormattedGlyphVec
@@ -362,32 +651,36 @@ fn try_layout_word_internal<T: TextLayout + ?Sized>(
         // We can't modify the actual values until we're sure we can render this glyph
         let mut new_glyph_metrics = new_word_metrics.clone();
 
-        let glyph = match layout_helper.lookup_glyph_for_codepoint(*c) {
-            None => {
-                match layout_helper
-                    .lookup_glyph_for_codepoint('□')
-                    .or_else(|| layout_helper.lookup_glyph_for_codepoint('?'))
-                {
-                    None => continue,
-                    Some(glyph) => glyph
-                }
-            }
-            Some(glyph) => glyph
+        let style = &styles[word.spans[i]];
+        let scale = &style.scale;
+
+        let glyph = match lookup_glyph_or_replacement(style.layout_helper, *c, options) {
+            None => continue,
+            Some((glyph, _)) => glyph
         };
 
-        let scaled_glyph = glyph.glyph.scaled(*scale);
+        let cell_x = new_glyph_metrics.x_pos;
 
-        let glyph_x_pos_start = new_glyph_metrics.update_and_get_render_pos_x(
-            &scaled_glyph,
-            glyph.font.id(),
-            scale,
-            options
-        );
+        let glyph_x_pos_start =
+            new_glyph_metrics.update_and_get_render_pos_x(&glyph, scale, options);
+
+        if *c == '\t' {
+            if let Some(tab_stop) = options.next_tab_stop(glyph_x_pos_start) {
+                new_glyph_metrics.x_pos = tab_stop;
+            }
+        }
 
         let formatted_glyph = FormattedGlyph {
             user_index: *user_index,
-            glyph: scaled_glyph.positioned(rusttype::point(glyph_x_pos_start, 0.0)),
-            font_id: glyph.font.id()
+            glyph: glyph.font.positioned(glyph.id, scale, glyph_x_pos_start),
+            font_id: glyph.font.id(),
+            offset_y: 0.0,
+            bidi_level: word.bidi_levels[i],
+            span_index: word.spans[i],
+            color: style.color,
+            color_glyphs: glyph.font.color_glyphs(),
+            cell_x,
+            cell_width: new_glyph_metrics.x_pos - cell_x
         };
 
         if let Some(pos_x_max) = pos_x_max {
@@ -411,10 +704,6 @@ fn try_layout_word_internal<T: TextLayout + ?Sized>(
                         ));
                     }
 
-                    glyphs.iter_mut().for_each(|glyph| {
-                        glyph.reposition_y(pos_y_baseline + new_word_metrics.max_ascent);
-                    });
-
                     output.append(&mut glyphs);
                     WordLayoutResult::PartialWord(new_word_metrics)
                 } else {
@@ -428,31 +717,423 @@ fn try_layout_word_internal<T: TextLayout + ?Sized>(
         new_word_metrics = new_glyph_metrics;
     }
 
-    glyphs.iter_mut().for_each(|glyph| {
-        glyph.reposition_y(pos_y_baseline + new_word_metrics.max_ascent);
-    });
-
     output.append(&mut glyphs);
 
     WordLayoutResult::Success(new_word_metrics)
 }
 
-fn layout_line_internal<T: TextLayout + ?Sized>(
-    layout_helper: &T,
+/// A glyph produced by shaping part of a word.
+#[cfg(feature = "text-shaping")]
+struct ShapedWordGlyph
+{
+    font: GlyphFont,
+    span: usize,
+    glyph: ShapedGlyph
+}
+
+/// Shapes the codepoints of a word, using the same font for each codepoint as
+/// would be used without shaping. Consecutive codepoints which use the same
+/// font and span, and have the same bidi level, are shaped together. The
+/// glyphs are in logical order, and the cluster of each glyph is an index into
+/// `codepoints`.
+///
+/// Bitmap fonts can't be shaped, so each of their codepoints produces one
+/// glyph, with any kerning added to its advance.
+#[cfg(feature = "text-shaping")]
+fn shape_word_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
+    codepoints: &[Codepoint],
+    bidi_levels: &[u8],
+    spans: &[usize],
+    options: &TextOptions
+) -> Vec<ShapedWordGlyph>
+{
+    let resolved: Vec<Option<(FontGlyph, char)>> = codepoints
+        .iter()
+        .zip(spans)
+        .map(|(codepoint, span)| {
+            lookup_glyph_or_replacement(
+                styles[*span].layout_helper,
+                codepoint.codepoint,
+                options
+            )
+        })
+        .collect();
+
+    let mut result = Vec::new();
+    let mut index = 0;
+
+    while index < resolved.len() {
+        let font = match &resolved[index] {
+            None => {
+                index += 1;
+                continue;
+            }
+            Some((glyph, _)) => glyph.font.clone()
+        };
+
+        let run_start = index;
+        let run_level = bidi_levels[index];
+        let run_span = spans[index];
+        let mut run = Vec::new();
+        let mut run_glyph_ids = Vec::new();
+
+        while let Some(Some((glyph, codepoint))) = resolved.get(index) {
+            if glyph.font.id() != font.id()
+                || bidi_levels[index] != run_level
+                || spans[index] != run_span
+            {
+                break;
+            }
+
+            run.push(*codepoint);
+            run_glyph_ids.push(glyph.id);
+            index += 1;
+        }
+
+        let right_to_left = text_bidi::is_right_to_left(run_level);
+        let scale = &styles[run_span].scale;
+
+        let glyphs = match &font {
+            GlyphFont::Outline(font) => {
+                text_shaping::shape(font, &run, scale.y, right_to_left)
+            }
+            GlyphFont::Bitmap { data, .. } => run_glyph_ids
+                .iter()
+                .enumerate()
+                .map(|(cluster, glyph_id)| {
+                    let kerning = match run_glyph_ids.get(cluster + 1) {
+                        None => 0.0,
+                        Some(next_id) => font.pair_kerning(scale, *glyph_id, *next_id)
+                    };
+
+                    ShapedGlyph {
+                        glyph_id: glyph_id.0,
+                        cluster,
+                        x_advance: data.glyph(glyph_id.0).advance_width
+                            * data.scale_factor(scale.y)
+                            + kerning,
+                        x_offset: 0.0,
+                        y_offset: 0.0
+                    }
+                })
+                .collect()
+        };
+
+        for glyph in glyphs {
+            result.push(ShapedWordGlyph {
+                font: font.clone(),
+                span: run_span,
+                glyph: ShapedGlyph {
+                    cluster: glyph.cluster + run_start,
+                    ..glyph
+                }
+            });
+        }
+    }
+
+    result
+}
+
+/// Positions the shaped glyphs after the glyphs already on the line. Tracking
+/// is added between clusters, rather than between glyphs, so that marks stay
+/// attached to their base.
+#[cfg(feature = "text-shaping")]
+fn place_shaped_word_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
+    shaped: &[ShapedWordGlyph],
+    codepoints: &[Codepoint],
+    bidi_levels: &[u8],
+    options: &TextOptions,
+    previous_metrics: &LineLayoutMetrics
+) -> (LineLayoutMetrics, FormattedGlyphVec)
+{
+    let mut metrics = previous_metrics.clone();
+    let mut glyphs = FormattedGlyphVec::new();
+    let mut previous_cluster = None;
+
+    for ShapedWordGlyph { font, span, glyph } in shaped {
+        let style = &styles[*span];
+        let scale = &style.scale;
+        let cell_x = metrics.x_pos;
+
+        if previous_cluster != Some(glyph.cluster) && metrics.last_glyph_id.is_some() {
+            metrics.x_pos += options.tracking;
+        }
+
+        previous_cluster = Some(glyph.cluster);
+
+        metrics.update_vertical_metrics(font, scale);
+
+        let glyph_x_pos_start = metrics.x_pos + glyph.x_offset;
+        let glyph_id = rusttype::GlyphId(glyph.glyph_id);
+
+        metrics.x_pos += glyph.x_advance;
+
+        if codepoints[glyph.cluster].codepoint == '\t' {
+            if let Some(tab_stop) = options.next_tab_stop(glyph_x_pos_start) {
+                metrics.x_pos = tab_stop;
+            }
+        }
+
+        metrics.last_font_id = Some(font.id());
+        metrics.last_glyph_id = Some(glyph_id);
+
+        glyphs.push(FormattedGlyph {
+            user_index: codepoints[glyph.cluster].user_index,
+            glyph: font.positioned(glyph_id, scale, glyph_x_pos_start),
+            font_id: font.id(),
+            offset_y: glyph.y_offset,
+            bidi_level: bidi_levels[glyph.cluster],
+            span_index: *span,
+            color: style.color,
+            color_glyphs: font.color_glyphs(),
+            cell_x,
+            cell_width: metrics.x_pos - cell_x
+        });
+    }
+
+    (metrics, glyphs)
+}
+
+#[cfg(feature = "text-shaping")]
+#[allow(clippy::too_many_arguments)]
+fn try_layout_word_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
+    word: RenderableWord,
+    remaining_words: &mut WordsIterator,
+    options: &TextOptions,
+    first_word_on_line: bool,
+    previous_metrics: &LineLayoutMetrics,
+    output: &mut FormattedGlyphVec
+) -> WordLayoutResult
+{
+    let place = |length: usize| {
+        let codepoints = &word.codepoints[..length];
+        let bidi_levels = &word.bidi_levels[..length];
+
+        place_shaped_word_internal(
+            styles,
+            &shape_word_internal(
+                styles,
+                codepoints,
+                bidi_levels,
+                &word.spans[..length],
+                options
+            ),
+            codepoints,
+            bidi_levels,
+            options,
+            previous_metrics
+        )
+    };
+
+    let fits = |metrics: &LineLayoutMetrics| match options.wrap_words_after_width {
+        None => true,
+        Some(pos_x_max) => metrics.x_pos <= pos_x_max
+    };
+
+    let shaped = shape_word_internal(
+        styles,
+        &word.codepoints,
+        &word.bidi_levels,
+        &word.spans,
+        options
+    );
+
+    let (mut new_word_metrics, mut glyphs) = place_shaped_word_internal(
+        styles,
+        &shaped,
+        &word.codepoints,
+        &word.bidi_levels,
+        options,
+        previous_metrics
+    );
+
+    let result = if fits(&new_word_metrics) {
+        WordLayoutResult::Success(new_word_metrics.clone())
+    } else if !first_word_on_line {
+        remaining_words.add_pending(Word::Renderable(word));
+        return WordLayoutResult::NotEnoughSpace;
+    } else {
+        // The word doesn't fit on a line by itself, so split it after the
+        // longest prefix which fits. Clusters are never split, and at least
+        // one cluster is always placed, even though it goes over the boundary.
+        let mut split_points: Vec<usize> = shaped
+            .iter()
+            .map(|shaped_glyph| shaped_glyph.glyph.cluster)
+            .filter(|location| *location > 0)
+            .collect();
+
+        split_points.sort_unstable();
+        split_points.dedup();
+
+        if !split_points.is_empty() {
+            let fitting_count =
+                split_points.partition_point(|location| fits(&place(*location).0));
+
+            let split_location = split_points[fitting_count.max(1) - 1];
+
+            let (prefix_metrics, prefix_glyphs) = place(split_location);
+
+            new_word_metrics = prefix_metrics;
+            glyphs = prefix_glyphs;
+
+            remaining_words.add_pending(Word::Renderable(
+                word.starting_from_codepoint_location(split_location)
+            ));
+        }
+
+        WordLayoutResult::PartialWord(new_word_metrics.clone())
+    };
+
+    output.append(&mut glyphs);
+
+    result
+}
+
+/// Reorders the glyphs of a line, which were laid out in logical order, into
+/// visual order, and moves each glyph to its new position. Trailing whitespace
+/// takes the direction of the paragraph (rule L1).
+fn reorder_line_glyphs_internal(
+    glyphs: &mut FormattedGlyphVec,
+    paragraph_level: u8,
+    trailing_whitespace_start: usize,
+    start_x: f32
+)
+{
+    for glyph in glyphs[trailing_whitespace_start..].iter_mut() {
+        glyph.bidi_level = paragraph_level;
+    }
+
+    if glyphs.iter().all(|glyph| glyph.bidi_level == 0) {
+        return;
+    }
+
+    let levels: Vec<u8> = glyphs.iter().map(|glyph| glyph.bidi_level).collect();
+
+    let mut reordered = FormattedGlyphVec::with_capacity(glyphs.len());
+    let mut pos_x = start_x;
+
+    for index in text_bidi::visual_order(&levels) {
+        let mut glyph = glyphs[index].clone();
+        glyph.add_offset_x(pos_x - glyph.cell_x);
+        pos_x += glyph.cell_width;
+        reordered.push(glyph);
+    }
+
+    *glyphs = reordered;
+}
+
+/// Finds the decorations of each span on the line. Adjacent glyphs from the
+/// same span share a single decoration.
+fn line_decorations_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
+    glyphs: &FormattedGlyphVec,
+    baseline_y: f32
+) -> Vec<TextDecoration>
+{
+    let mut result: Vec<TextDecoration> = Vec::new();
+
+    for kind in [
+        TextDecorationKind::Underline,
+        TextDecorationKind::Strikethrough,
+        TextDecorationKind::Overline
+    ] {
+        let mut previous_span = None;
+
+        for glyph in glyphs.iter() {
+            let style = &styles[glyph.span_index];
+
+            if !style.has_decoration(kind) {
+                previous_span = None;
+                continue;
+            }
+
+            let right = glyph.cell_x + glyph.cell_width;
+
+            match result.last_mut() {
+                Some(decoration) if previous_span == Some(glyph.span_index) => {
+                    decoration.rect = Rect::new(
+                        *decoration.rect.top_left(),
+                        Vec2::new(right, decoration.rect.bottom_right().y)
+                    );
+                }
+
+                _ => {
+                    let metrics = style.decoration_metrics(kind);
+                    let top = baseline_y + metrics.offset_y;
+
+                    result.push(TextDecoration {
+                        kind,
+                        rect: Rect::from_tuples(
+                            (glyph.cell_x, top),
+                            (right, top + metrics.thickness)
+                        ),
+                        color: style.color
+                    });
+                }
+            }
+
+            previous_span = Some(glyph.span_index);
+        }
+    }
+
+    result.retain(|decoration| decoration.rect.is_positive_area());
+    result
+}
+
+/// The glyphs of a line in logical order, before they are reordered and
+/// aligned.
+struct LineGlyphs
+{
+    glyphs: FormattedGlyphVec,
+    metrics: LineLayoutMetrics,
+    paragraph_level: u8,
+    trailing_whitespace_start: usize,
+    /// The indices of the glyphs of whitespace words.
+    whitespace_glyphs: Vec<usize>,
+    /// False if the line was wrapped before the end of its paragraph.
+    ends_paragraph: bool,
+    /// The width of the indent before the first glyph.
+    indent: f32
+}
+
+fn layout_line_glyphs_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
     words: &mut WordsIterator,
-    scale: &Scale,
     options: &TextOptions,
-    pos_y_baseline: f32
-) -> FormattedTextLine
+    starts_paragraph: bool
+) -> LineGlyphs
 {
+    let indent = if starts_paragraph {
+        options.first_line_indent
+    } else {
+        0.0
+    };
+
     let mut line_metrics = LineLayoutMetrics::new();
+    line_metrics.x_pos = indent;
+
     let mut glyphs = SmallVec::new();
 
     let mut first_word_on_line = true;
+    let mut paragraph_level = 0;
+    let mut trailing_whitespace_start = 0;
+    let mut whitespace_glyphs = Vec::new();
+    let mut ends_paragraph = true;
 
-    // Skip whitespace
+    // Skip whitespace, apart from tabs at the start of a paragraph when tab
+    // stops are set
     while let Some(Word::Renderable(word)) = words.peek() {
-        if word.is_whitespace {
+        let is_leading_tab = starts_paragraph
+            && options.tab_stops.is_some()
+            && word
+                .codepoints
+                .iter()
+                .all(|codepoint| codepoint.codepoint == '\t');
+
+        if word.is_whitespace && !is_leading_tab {
             words.next().unwrap();
         } else {
             break;
@@ -460,13 +1141,18 @@ fn layout_line_internal<T: TextLayout + ?Sized>(
     }
 
     while let Some(Word::Renderable(word)) = words.next() {
+        if first_word_on_line {
+            paragraph_level = word.paragraph_level;
+        }
+
+        let is_whitespace = word.is_whitespace;
+        let word_start = glyphs.len();
+
         let result = try_layout_word_internal(
-            layout_helper,
+            styles,
             word,
             words,
-            scale,
             options,
-            pos_y_baseline,
             first_word_on_line,
             &line_metrics,
             &mut glyphs
@@ -476,7 +1162,14 @@ fn layout_line_internal<T: TextLayout + ?Sized>(
             line_metrics = metrics.clone();
         }
 
+        if is_whitespace {
+            whitespace_glyphs.extend(word_start..glyphs.len());
+        } else if glyphs.len() > word_start {
+            trailing_whitespace_start = glyphs.len();
+        }
+
         if result.end_of_line() {
+            ends_paragraph = false;
             break;
         }
 
@@ -484,17 +1177,221 @@ fn layout_line_internal<T: TextLayout + ?Sized>(
     }
 
     if glyphs.is_empty() {
-        let empty_metrics = layout_helper.empty_line_vertical_metrics(scale.y);
+        let style = &styles[words.last_span];
+        let empty_metrics = style
+            .layout_helper
+            .empty_line_vertical_metrics(style.scale.y);
         line_metrics.max_ascent = empty_metrics.ascent;
         line_metrics.min_descent = empty_metrics.descent;
         line_metrics.max_line_gap = empty_metrics.line_gap;
     }
 
-    if let Some(max_width) = options.wrap_words_after_width {
-        let offset_x = match options.alignment {
-            TextAlignment::Left => None,
-            TextAlignment::Center => Some((max_width - line_metrics.x_pos) / 2.0),
-            TextAlignment::Right => Some(max_width - line_metrics.x_pos)
+    LineGlyphs {
+        glyphs,
+        metrics: line_metrics,
+        paragraph_level,
+        trailing_whitespace_start,
+        whitespace_glyphs,
+        ends_paragraph,
+        indent
+    }
+}
+
+/// Removes glyphs from the end of the last visible line until the ellipsis
+/// fits within the wrapping width, then appends the ellipsis. The glyphs of
+/// the ellipsis are given the `user_index` of the first hidden codepoint.
+fn truncate_line_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
+    line: &mut LineGlyphs,
+    remaining_words: &mut WordsIterator,
+    options: &TextOptions
+)
+{
+    let span = line
+        .glyphs
+        .last()
+        .map(|glyph| glyph.span_index)
+        .unwrap_or(remaining_words.last_span);
+
+    let paragraph_level = line.paragraph_level;
+    let mut hidden_user_index = None;
+
+    while let Some(word) = remaining_words.next() {
+        if let Word::Renderable(word) = word {
+            if let Some(codepoint) = word.codepoints.first() {
+                hidden_user_index = Some(codepoint.user_index);
+                break;
+            }
+        }
+    }
+
+    let ellipsis_word = |metrics: &LineLayoutMetrics, user_index: UserGlyphIndex| {
+        let mut glyphs = FormattedGlyphVec::new();
+        let codepoints: Vec<Codepoint> = options
+            .ellipsis
+            .chars()
+            .map(|codepoint| Codepoint::new(user_index, codepoint))
+            .collect();
+
+        let result = try_layout_word_internal(
+            styles,
+            RenderableWord {
+                bidi_levels: vec![paragraph_level; codepoints.len()],
+                spans: vec![span; codepoints.len()],
+                codepoints,
+                paragraph_level,
+                is_whitespace: false
+            },
+            &mut WordsIterator::from(Vec::new()),
+            &TextOptions {
+                wrap_words_after_width: None,
+                ..options.clone()
+            },
+            false,
+            metrics,
+            &mut glyphs
+        );
+
+        (result.get_metrics().unwrap().clone(), glyphs)
+    };
+
+    if let Some(max_width) = options.wrap_words_after_width {
+        let ellipsis_width = ellipsis_word(&LineLayoutMetrics::new(), 0).0.x_pos;
+
+        // Trailing whitespace is always removed. Every glyph of a cluster has
+        // the same user index, and they are removed together.
+        while let Some(last) = line.glyphs.last() {
+            let is_whitespace = line.glyphs.len() > line.trailing_whitespace_start;
+
+            let fits =
+                line.metrics.x_pos + options.tracking + ellipsis_width <= max_width;
+
+            if fits && !is_whitespace {
+                break;
+            }
+
+            let user_index = last.user_index;
+
+            line.glyphs.retain(|glyph| glyph.user_index != user_index);
+
+            hidden_user_index = Some(user_index);
+
+            line.metrics.x_pos = line
+                .glyphs
+                .last()
+                .map_or(line.indent, |glyph| glyph.cell_x + glyph.cell_width);
+            line.metrics.last_glyph_id = line.glyphs.last().map(|glyph| glyph.glyph.id());
+            line.metrics.last_font_id = line.glyphs.last().map(|glyph| glyph.font_id);
+            line.trailing_whitespace_start =
+                line.trailing_whitespace_start.min(line.glyphs.len());
+        }
+
+        let glyph_count = line.glyphs.len();
+        line.whitespace_glyphs.retain(|index| *index < glyph_count);
+    }
+
+    let user_index = hidden_user_index.unwrap_or_else(|| {
+        line.glyphs
+            .iter()
+            .map(|glyph| glyph.user_index + 1)
+            .max()
+            .unwrap_or(0)
+    });
+
+    let (metrics, mut glyphs) = ellipsis_word(&line.metrics, user_index);
+
+    line.metrics = metrics;
+    line.glyphs.append(&mut glyphs);
+    line.trailing_whitespace_start = line.glyphs.len();
+}
+
+/// Widens the whitespace between the words of the line, so that the line
+/// fills the wrapping width. Trailing whitespace is not widened.
+fn justify_line_internal(line: &mut LineGlyphs, max_width: f32)
+{
+    let content_width = line
+        .glyphs
+        .get(line.trailing_whitespace_start)
+        .map_or(line.metrics.x_pos, |glyph| glyph.cell_x);
+
+    let gaps: Vec<usize> = line
+        .whitespace_glyphs
+        .iter()
+        .copied()
+        .filter(|index| *index < line.trailing_whitespace_start)
+        .collect();
+
+    if gaps.is_empty() || content_width >= max_width {
+        return;
+    }
+
+    let extra_width = (max_width - content_width) / gaps.len() as f32;
+    let mut gaps = gaps.iter().peekable();
+    let mut offset_x = 0.0;
+
+    for (index, glyph) in line.glyphs.iter_mut().enumerate() {
+        glyph.add_offset_x(offset_x);
+
+        if gaps.peek() == Some(&&index) {
+            gaps.next();
+            glyph.cell_width += extra_width;
+            offset_x += extra_width;
+        }
+    }
+
+    line.metrics.x_pos += offset_x;
+}
+
+fn finish_line_internal<T: TextLayout + ?Sized>(
+    styles: &[SpanStyle<T>],
+    mut line: LineGlyphs,
+    options: &TextOptions,
+    pos_y_baseline: f32
+) -> FormattedTextLine
+{
+    if let Some(max_width) = options.wrap_words_after_width {
+        if options.alignment == TextAlignment::Justify && !line.ends_paragraph {
+            justify_line_internal(&mut line, max_width);
+        }
+    }
+
+    let LineGlyphs {
+        mut glyphs,
+        metrics: line_metrics,
+        paragraph_level,
+        trailing_whitespace_start,
+        indent,
+        ..
+    } = line;
+
+    // The indent of right-to-left paragraphs is on the right, so their glyphs
+    // start from zero
+    let start_x = if text_bidi::is_right_to_left(paragraph_level) {
+        0.0
+    } else {
+        indent
+    };
+
+    reorder_line_glyphs_internal(
+        &mut glyphs,
+        paragraph_level,
+        trailing_whitespace_start,
+        start_x
+    );
+
+    if let Some(max_width) = options.wrap_words_after_width {
+        let right_to_left = text_bidi::is_right_to_left(paragraph_level);
+
+        let offset_x = match (&options.alignment, right_to_left) {
+            (TextAlignment::Left, _)
+            | (TextAlignment::Start, false)
+            | (TextAlignment::End, true)
+            | (TextAlignment::Justify, false) => None,
+            (TextAlignment::Center, _) => Some((max_width - line_metrics.x_pos) / 2.0),
+            (TextAlignment::Right, _)
+            | (TextAlignment::Start, true)
+            | (TextAlignment::End, false)
+            | (TextAlignment::Justify, true) => Some(max_width - line_metrics.x_pos)
         };
 
         if let Some(offset_x) = offset_x {
@@ -504,8 +1401,19 @@ fn layout_line_internal<T: TextLayout + ?Sized>(
         }
     }
 
+    // Spans with a larger ascent may have been added after the earlier glyphs
+    // were placed
+    let baseline_y = pos_y_baseline + line_metrics.max_ascent;
+
+    for glyph in glyphs.iter_mut() {
+        glyph.reposition_y(baseline_y);
+    }
+
+    let decorations = line_decorations_internal(styles, &glyphs, baseline_y);
+
     FormattedTextLine {
         glyphs: Arc::new(glyphs),
+        decorations: Arc::new(decorations),
         baseline_vertical_position: pos_y_baseline,
         width: line_metrics.x_pos,
         height: line_metrics.height(),
@@ -515,30 +1423,59 @@ fn layout_line_internal<T: TextLayout + ?Sized>(
     }
 }
 
+/// Lays out the codepoints, where the codepoint at each index is in the span
+/// with the style `styles[span_indices[index]]`.
 fn layout_multiple_lines_internal<T: TextLayout + ?Sized>(
-    layout_helper: &T,
+    styles: &[SpanStyle<T>],
     codepoints: &[Codepoint],
-    scale: f32,
+    span_indices: &[usize],
     options: TextOptions
 ) -> FormattedTextBlock
 {
-    let scale = Scale::uniform(scale);
-
-    let mut iterator = WordsIterator::from(Word::split_words(codepoints));
+    let mut iterator = WordsIterator::from(Word::split_words(
+        codepoints,
+        span_indices,
+        options.line_breaking
+    ));
 
     let mut pos_y = 0.0;
     let mut lines = SmallVec::new();
 
     let mut width = 0.0;
+    let mut truncated = false;
+    let mut starts_paragraph = true;
 
     while iterator.has_next() {
-        let line =
-            layout_line_internal(layout_helper, &mut iterator, &scale, &options, pos_y);
+        // Only lines containing glyphs count as omitted text
+        if options.max_lines == Some(lines.len()) {
+            truncated = iterator.has_visible_words();
+            break;
+        }
+
+        let mut line_glyphs = layout_line_glyphs_internal(
+            styles,
+            &mut iterator,
+            &options,
+            starts_paragraph
+        );
+
+        if options.max_lines == Some(lines.len() + 1) && iterator.has_visible_words() {
+            truncate_line_internal(styles, &mut line_glyphs, &mut iterator, &options);
+            truncated = true;
+        }
+
+        starts_paragraph = line_glyphs.ends_paragraph;
+
+        let line = finish_line_internal(styles, line_glyphs, &options, pos_y);
 
         pos_y += line.height * options.line_spacing_multiplier;
 
         if iterator.has_next() {
             pos_y += line.line_gap * options.line_spacing_multiplier;
+
+            if starts_paragraph {
+                pos_y += options.paragraph_spacing;
+            }
         }
 
         width = crate::numeric::max(width, line.width);
@@ -549,7 +1486,8 @@ fn layout_multiple_lines_internal<T: TextLayout + ?Sized>(
     FormattedTextBlock {
         lines: Arc::new(lines),
         width,
-        height: pos_y
+        height: pos_y,
+        truncated
     }
 }
 
@@ -574,14 +1512,60 @@ impl LineVerticalMetrics
     }
 }
 
+/// The position and thickness of a [TextDecoration], as returned by
+/// [TextLayout::text_decoration_metrics()].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextDecorationMetrics
+{
+    offset_y: f32,
+    thickness: f32
+}
+
+impl TextDecorationMetrics
+{
+    /// The position of the top of the line in pixels, relative to the
+    /// baseline. Positive values are below the baseline.
+    #[inline]
+    #[must_use]
+    pub fn offset_y(&self) -> f32
+    {
+        self.offset_y
+    }
+
+    /// The thickness of the line in pixels.
+    #[inline]
+    #[must_use]
+    pub fn thickness(&self) -> f32
+    {
+        self.thickness
+    }
+}
+
 /// Objects implementing this trait are able to lay out text, ready for
 /// rendering.
+///
+/// If the `text-shaping` feature is enabled, each word is shaped using the
+/// fonts returned by `lookup_glyph_for_codepoint()`, so that ligatures,
+/// contextual forms, and mark positioning are applied.
 pub trait TextLayout
 {
     /// Returns the glyph corresponding to the provided codepoint. If the glyph
     /// cannot be found, `None` is returned.
     fn lookup_glyph_for_codepoint(&self, codepoint: char) -> Option<FontGlyph>;
 
+    /// Returns the glyph which is drawn in place of codepoints that can't be
+    /// found, when using [MissingGlyph::Tofu]. For outline fonts, this is the
+    /// `.notdef` glyph, which is usually an empty box.
+    ///
+    /// The default implementation returns `None`, in which case the
+    /// replacement characters of [MissingGlyph::Replacement] are used
+    /// instead.
+    #[inline]
+    fn missing_glyph(&self) -> Option<FontGlyph>
+    {
+        None
+    }
+
     /// Lays out a block of text with the specified scale and options. The
     /// result may be passed to `Graphics2D::draw_text`.
     ///
@@ -638,12 +1622,118 @@ pub trait TextLayout
         options: TextOptions
     ) -> FormattedTextBlock
     {
-        layout_multiple_lines_internal(self, codepoints, scale, options)
+        layout_multiple_lines_internal(
+            &[SpanStyle::new(self, scale, &options)],
+            codepoints,
+            &vec![0; codepoints.len()],
+            options
+        )
     }
 
     /// The default metrics of a line which contains no characters.
     #[must_use]
     fn empty_line_vertical_metrics(&self, scale: f32) -> LineVerticalMetrics;
+
+    /// Returns the position and thickness of the specified kind of
+    /// decoration at the specified scale, as given by the font.
+    ///
+    /// The default implementation returns `None`, in which case they are
+    /// estimated from the scale.
+    #[inline]
+    #[must_use]
+    fn text_decoration_metrics(
+        &self,
+        _kind: TextDecorationKind,
+        _scale: f32
+    ) -> Option<TextDecorationMetrics>
+    {
+        None
+    }
+}
+
+/// The value of one of the variation axes of a variable font, such as its
+/// weight or width.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontVariation
+{
+    tag: [u8; 4],
+    value: f32
+}
+
+impl FontVariation
+{
+    /// Creates a variation which sets the axis with the specified tag to
+    /// `value`. Registered axes include `b"wght"` (weight), `b"wdth"`
+    /// (width), `b"ital"` (italic), `b"slnt"` (slant), and `b"opsz"`
+    /// (optical size).
+    #[inline]
+    #[must_use]
+    pub fn new(tag: &[u8; 4], value: f32) -> Self
+    {
+        FontVariation { tag: *tag, value }
+    }
+
+    /// The four character tag of the axis.
+    #[inline]
+    #[must_use]
+    pub fn tag(&self) -> &[u8; 4]
+    {
+        &self.tag
+    }
+
+    /// The value of the axis.
+    #[inline]
+    #[must_use]
+    pub fn value(&self) -> f32
+    {
+        self.value
+    }
+}
+
+/// One of the variation axes of a variable font, returned by
+/// [Font::variation_axes].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontVariationAxis
+{
+    tag: [u8; 4],
+    min_value: f32,
+    default_value: f32,
+    max_value: f32
+}
+
+impl FontVariationAxis
+{
+    /// The four character tag of the axis, such as `b"wght"`.
+    #[inline]
+    #[must_use]
+    pub fn tag(&self) -> &[u8; 4]
+    {
+        &self.tag
+    }
+
+    /// The minimum value of the axis.
+    #[inline]
+    #[must_use]
+    pub fn min_value(&self) -> f32
+    {
+        self.min_value
+    }
+
+    /// The value of the axis when no variation is applied.
+    #[inline]
+    #[must_use]
+    pub fn default_value(&self) -> f32
+    {
+        self.default_value
+    }
+
+    /// The maximum value of the axis.
+    #[inline]
+    #[must_use]
+    pub fn max_value(&self) -> f32
+    {
+        self.max_value
+    }
 }
 
 /// A struct representing a font.
@@ -651,7 +1741,72 @@ pub trait TextLayout
 pub struct Font
 {
     id: usize,
-    font: Arc<rusttype::Font<'static>>
+    font: Arc<rusttype::Font<'static>>,
+    color_glyphs: Option<Arc<ColorGlyphs>>,
+    data: Arc<Vec<u8>>,
+    index: u32,
+    // The outlines already include the variations, but the shaper needs them
+    #[cfg_attr(not(feature = "text-shaping"), allow(dead_code))]
+    variations: Arc<[FontVariation]>,
+    decoration_units: FontDecorationUnits
+}
+
+/// The decoration positions from the font's `post` and `OS/2` tables, in font
+/// units. Positions are relative to the baseline, positive upwards.
+#[derive(Debug, Clone, Copy)]
+struct FontDecorationUnits
+{
+    underline: Option<ttf_parser::LineMetrics>,
+    strikeout: Option<ttf_parser::LineMetrics>,
+    ascender: i16,
+    descender: i16
+}
+
+impl FontDecorationUnits
+{
+    fn from_face(face: &ttf_parser::Face) -> Self
+    {
+        let valid = |metrics: ttf_parser::LineMetrics| metrics.thickness > 0;
+
+        FontDecorationUnits {
+            underline: face.underline_metrics().filter(|m| valid(*m)),
+            strikeout: face.strikeout_metrics().filter(|m| valid(*m)),
+            ascender: face.ascender(),
+            descender: face.descender()
+        }
+    }
+
+    fn metrics(
+        &self,
+        kind: TextDecorationKind,
+        scale: f32
+    ) -> Option<TextDecorationMetrics>
+    {
+        let units_height = f32::from(self.ascender) - f32::from(self.descender);
+
+        if units_height <= 0.0 {
+            return None;
+        }
+
+        let factor = scale / units_height;
+
+        let (position, thickness) = match kind {
+            TextDecorationKind::Underline => {
+                let metrics = self.underline?;
+                (metrics.position, metrics.thickness)
+            }
+            TextDecorationKind::Strikethrough => {
+                let metrics = self.strikeout?;
+                (metrics.position, metrics.thickness)
+            }
+            TextDecorationKind::Overline => (self.ascender, self.underline?.thickness)
+        };
+
+        Some(TextDecorationMetrics {
+            offset_y: -f32::from(position) * factor,
+            thickness: f32::from(thickness) * factor
+        })
+    }
 }
 
 impl Font
@@ -659,15 +1814,126 @@ impl Font
     /// Constructs a new font from the specified bytes.
     ///
     /// The font may be in TrueType or OpenType format. Support for OpenType
-    /// fonts may be limited.
+    /// fonts may be limited. If the bytes contain a font collection, the
+    /// first font is used.
     pub fn new(bytes: &[u8]) -> Result<Font, BacktraceError<ErrorMessage>>
     {
-        let font = rusttype::Font::try_from_vec(bytes.to_vec())
-            .ok_or_else(|| ErrorMessage::msg("Failed to load font"))?;
+        Font::from_data(Arc::new(bytes.to_vec()), 0, &[])
+    }
+
+    /// Constructs a new font from the font at the specified index of a
+    /// TrueType or OpenType font collection, such as a `.ttc` file. The
+    /// number of fonts in the collection can be found using
+    /// [Font::collection_size].
+    ///
+    /// A file containing a single font is treated as a collection of one font.
+    pub fn new_from_collection(
+        bytes: &[u8],
+        index: u32
+    ) -> Result<Font, BacktraceError<ErrorMessage>>
+    {
+        let size = Font::collection_size(bytes);
+
+        if index >= size {
+            return Err(ErrorMessage::msg(format!(
+                "Font index {} is out of range, collection contains {} fonts",
+                index, size
+            )));
+        }
+
+        Font::from_data(Arc::new(bytes.to_vec()), index, &[])
+    }
+
+    /// The number of fonts in the specified font collection. If the bytes
+    /// contain a single font rather than a collection, this is `1`.
+    #[must_use]
+    pub fn collection_size(bytes: &[u8]) -> u32
+    {
+        ttf_parser::fonts_in_collection(bytes).unwrap_or(1)
+    }
+
+    /// Creates an instance of this variable font with the specified values for
+    /// its variation axes. Axes which aren't specified take their default
+    /// values, and values outside the range of an axis are clamped.
+    ///
+    /// An error is returned if the font doesn't have one of the axes. The
+    /// available axes are returned by [Font::variation_axes].
+    ///
+    /// The returned font is separate from this one, so text laid out using
+    /// each of them is cached separately.
+    pub fn with_variations(
+        &self,
+        variations: &[FontVariation]
+    ) -> Result<Font, BacktraceError<ErrorMessage>>
+    {
+        Font::from_data(self.data.clone(), self.index, variations)
+    }
+
+    /// The variation axes of this font, or an empty list if it isn't a
+    /// variable font.
+    #[must_use]
+    pub fn variation_axes(&self) -> Vec<FontVariationAxis>
+    {
+        ttf_parser::Face::from_slice(&self.data, self.index)
+            .map(|face| {
+                face.variation_axes()
+                    .into_iter()
+                    .filter(|axis| !axis.hidden)
+                    .map(|axis| FontVariationAxis {
+                        tag: axis.tag.to_bytes(),
+                        min_value: axis.min_value,
+                        default_value: axis.def_value,
+                        max_value: axis.max_value
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn from_data(
+        data: Arc<Vec<u8>>,
+        index: u32,
+        variations: &[FontVariation]
+    ) -> Result<Font, BacktraceError<ErrorMessage>>
+    {
+        let mut face = ttf_parser::Face::from_slice(&data, index)
+            .map_err(|err| ErrorMessage::msg_with_cause("Failed to load font", err))?;
+
+        for variation in variations {
+            if face
+                .set_variation(
+                    ttf_parser::Tag::from_bytes(variation.tag()),
+                    variation.value()
+                )
+                .is_none()
+            {
+                return Err(ErrorMessage::msg(format!(
+                    "Font has no variation axis '{}'",
+                    String::from_utf8_lossy(variation.tag())
+                )));
+            }
+        }
+
+        let decoration_units = FontDecorationUnits::from_face(&face);
+
+        // SAFETY: The face borrows from `data`, which is shared with every
+        // instance of this font rather than copied. The face is only lent out
+        // for the lifetime of this `Font` (see `Font::font`), apart from the
+        // glyphs stored in `PositionedFontGlyph::Outline`, which each hold a
+        // reference to `data`. The bytes therefore outlive every copy of the
+        // face.
+        let face = unsafe {
+            std::mem::transmute::<ttf_parser::Face<'_>, ttf_parser::Face<'static>>(face)
+        };
 
         Ok(Font {
             id: FONT_ID_GENERATOR.fetch_add(1, Ordering::SeqCst),
-            font: Arc::new(font)
+            font: Arc::new(rusttype::Font::Ref(Arc::new(face))),
+            color_glyphs: ColorGlyphs::new(&data, index).map(Arc::new),
+            data,
+            index,
+            variations: variations.into(),
+            decoration_units
         })
     }
 
@@ -678,10 +1944,39 @@ impl Font
     }
 
     #[inline]
-    fn font(&self) -> &rusttype::Font<'static>
+    pub(crate) fn font(&self) -> &rusttype::Font<'_>
     {
         &self.font
     }
+
+    #[inline]
+    pub(crate) fn color_glyphs(&self) -> &Option<Arc<ColorGlyphs>>
+    {
+        &self.color_glyphs
+    }
+
+    /// The original bytes of the font file, used by the shaper.
+    #[cfg(feature = "text-shaping")]
+    #[inline]
+    pub(crate) fn data(&self) -> &[u8]
+    {
+        &self.data
+    }
+
+    /// The index of the font within the font file.
+    #[cfg(feature = "text-shaping")]
+    #[inline]
+    pub(crate) fn index(&self) -> u32
+    {
+        self.index
+    }
+
+    #[cfg(feature = "text-shaping")]
+    #[inline]
+    pub(crate) fn variations(&self) -> &[FontVariation]
+    {
+        &self.variations
+    }
 }
 
 impl TextLayout for FontFamily
@@ -697,6 +1992,20 @@ impl TextLayout for FontFamily
         None
     }
 
+    fn missing_glyph(&self) -> Option<FontGlyph>
+    {
+        self.fonts.first()?.missing_glyph()
+    }
+
+    fn text_decoration_metrics(
+        &self,
+        kind: TextDecorationKind,
+        scale: f32
+    ) -> Option<TextDecorationMetrics>
+    {
+        self.fonts.first()?.text_decoration_metrics(kind, scale)
+    }
+
     fn empty_line_vertical_metrics(&self, scale: f32) -> LineVerticalMetrics
     {
         match Arc::deref(&self.fonts).first() {
@@ -721,18 +2030,35 @@ impl TextLayout for Font
 {
     fn lookup_glyph_for_codepoint(&self, codepoint: char) -> Option<FontGlyph>
     {
-        let glyph = self.font().glyph(codepoint);
+        let glyph_id = self.font().glyph(codepoint).id();
 
-        if glyph.id().0 == 0 {
+        if glyph_id.0 == 0 {
             None
         } else {
             Some(FontGlyph {
-                glyph,
-                font: self.clone()
+                id: glyph_id,
+                font: GlyphFont::Outline(self.clone())
             })
         }
     }
 
+    fn missing_glyph(&self) -> Option<FontGlyph>
+    {
+        Some(FontGlyph {
+            id: rusttype::GlyphId(0),
+            font: GlyphFont::Outline(self.clone())
+        })
+    }
+
+    fn text_decoration_metrics(
+        &self,
+        kind: TextDecorationKind,
+        scale: f32
+    ) -> Option<TextDecorationMetrics>
+    {
+        self.decoration_units.metrics(kind, scale)
+    }
+
     fn empty_line_vertical_metrics(&self, scale: f32) -> LineVerticalMetrics
     {
         let metrics = self.font.v_metrics(Scale::uniform(scale));
@@ -772,31 +2098,186 @@ impl Debug for Font
     }
 }
 
-/// A collection of fonts, in decreasing order of priority. When laying out
-/// text, if a codepoint cannot be found in the first font in the list, the
-/// subsequent fonts will also be searched.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct FontFamily
+/// A font whose glyphs are drawn from images, rather than being rasterized
+/// from outlines. The glyph metrics are loaded from a descriptor file in the
+/// BMFont (AngelCode) text, XML or binary format, and the glyphs are drawn
+/// from the page images which accompany it.
+///
+/// Bitmap fonts are created using [crate::Graphics2D::create_bitmap_font], and
+/// can only be drawn in the graphics context in which they were created. The
+/// page images are released when the font and all its clones are dropped, so
+/// text laid out using the font is no longer drawn after this point, and an
+/// error is logged instead.
+///
+/// The glyphs are drawn at the size of the page images when the text is laid
+/// out with a scale equal to [BitmapFont::line_height]. For crisp pixel art,
+/// use a multiple of this, and load the page images using
+/// [crate::image::ImageSmoothingMode::NearestNeighbor]. The glyphs are tinted
+/// with the color of the text, so white glyphs take on the color of the text,
+/// and colored glyphs should be drawn using [Color::WHITE].
+///
+/// Bitmap fonts are not shaped. Outlines, glows and blurred shadows from a
+/// [TextStyle] are not drawn around their glyphs.
+#[derive(Clone)]
+pub struct BitmapFont
 {
-    fonts: Arc<Vec<Font>>
+    id: FontId,
+    data: Arc<BitmapFontData>,
+    pages: Rc<Vec<ImageHandle>>
 }
 
-impl FontFamily
+impl BitmapFont
 {
-    /// Instantiates a new font family, containing the specified fonts in
-    /// decreasing order of priority.
-    #[must_use]
-    pub fn new(fonts: Vec<Font>) -> Self
+    pub(crate) fn new(
+        bytes: &[u8],
+        pages: Vec<ImageHandle>
+    ) -> Result<BitmapFont, BacktraceError<ErrorMessage>>
     {
-        FontFamily {
-            fonts: Arc::new(fonts)
+        let data = BitmapFontData::parse(bytes)?;
+
+        if pages.len() < data.page_count() {
+            return Err(ErrorMessage::msg(format!(
+                "Font uses {} page images, but only {} were provided",
+                data.page_count(),
+                pages.len()
+            )));
         }
+
+        Ok(BitmapFont {
+            id: FONT_ID_GENERATOR.fetch_add(1, Ordering::SeqCst),
+            data: Arc::new(data),
+            pages: Rc::new(pages)
+        })
     }
-}
 
-/// The horizontal alignment of a block of text. This can be set when calling
+    #[inline]
+    pub(crate) fn id(&self) -> FontId
+    {
+        self.id
+    }
+
+    #[inline]
+    pub(crate) fn pages(&self) -> &Rc<Vec<ImageHandle>>
+    {
+        &self.pages
+    }
+
+    /// The distance between the baselines of two lines of text, in pixels of
+    /// the page images.
+    #[inline]
+    #[must_use]
+    pub fn line_height(&self) -> f32
+    {
+        self.data.line_height()
+    }
+}
+
+impl TextLayout for BitmapFont
+{
+    fn lookup_glyph_for_codepoint(&self, codepoint: char) -> Option<FontGlyph>
+    {
+        self.data.glyph_index(codepoint).map(|index| FontGlyph {
+            id: rusttype::GlyphId(index),
+            font: GlyphFont::Bitmap {
+                id: self.id,
+                data: self.data.clone()
+            }
+        })
+    }
+
+    fn empty_line_vertical_metrics(&self, scale: f32) -> LineVerticalMetrics
+    {
+        let metrics = self.data.v_metrics(scale);
+        LineVerticalMetrics {
+            ascent: metrics.ascent,
+            descent: metrics.descent,
+            line_gap: metrics.line_gap
+        }
+    }
+}
+
+impl PartialEq for BitmapFont
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool
+    {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for BitmapFont {}
+
+impl Hash for BitmapFont
+{
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H)
+    {
+        self.id().hash(state);
+    }
+}
+
+impl Debug for BitmapFont
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result
+    {
+        f.debug_struct("BitmapFont")
+            .field("id", &self.id())
+            .finish()
+    }
+}
+
+/// A collection of fonts, in decreasing order of priority. When laying out
+/// text, if a codepoint cannot be found in the first font in the list, the
+/// subsequent fonts will also be searched.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FontFamily
+{
+    fonts: Arc<Vec<Font>>
+}
+
+impl From<Font> for FontFamily
+{
+    #[inline]
+    fn from(font: Font) -> Self
+    {
+        FontFamily::new(vec![font])
+    }
+}
+
+impl From<&Font> for FontFamily
+{
+    #[inline]
+    fn from(font: &Font) -> Self
+    {
+        FontFamily::new(vec![font.clone()])
+    }
+}
+
+impl From<&FontFamily> for FontFamily
+{
+    #[inline]
+    fn from(family: &FontFamily) -> Self
+    {
+        family.clone()
+    }
+}
+
+impl FontFamily
+{
+    /// Instantiates a new font family, containing the specified fonts in
+    /// decreasing order of priority.
+    #[must_use]
+    pub fn new(fonts: Vec<Font>) -> Self
+    {
+        FontFamily {
+            fonts: Arc::new(fonts)
+        }
+    }
+}
+
+/// The horizontal alignment of a block of text. This can be set when calling
 /// `TextOptions::with_wrap_words_after_width`.
-#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
 pub enum TextAlignment
 {
     /// Align the text to the left.
@@ -804,78 +2285,240 @@ pub enum TextAlignment
     /// Center the text in the maximum width.
     Center,
     /// Align the text to the rightmost point within the maximum width.
-    Right
+    Right,
+    /// Align each line to the start of its paragraph: the left for
+    /// left-to-right paragraphs, and the right for right-to-left paragraphs.
+    Start,
+    /// Align each line to the end of its paragraph: the right for
+    /// left-to-right paragraphs, and the left for right-to-left paragraphs.
+    End,
+    /// Widen the spaces between words, so that each line fills the maximum
+    /// width. The last line of each paragraph is aligned to the start of the
+    /// paragraph instead.
+    Justify
 }
 
-/// A series of options for specifying how text should be laid out.
-pub struct TextOptions
+/// The vertical alignment of a block of text within a rectangle, as used by
+/// `Graphics2D::draw_text_in_rect`.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum VerticalAlignment
 {
-    tracking: f32,
-    wrap_words_after_width: Option<f32>,
-    alignment: TextAlignment,
-    line_spacing_multiplier: f32
+    /// Align the top of the text to the top of the rectangle.
+    Top,
+    /// Center the text vertically in the rectangle.
+    Middle,
+    /// Align the baseline of the first line of text to the bottom of the
+    /// rectangle, so that only the descenders extend below it.
+    Baseline,
+    /// Align the bottom of the text to the bottom of the rectangle.
+    Bottom
 }
 
-impl TextOptions
+/// What is drawn in place of a codepoint which can't be found in the font.
+/// This can be set using [TextOptions::with_missing_glyph()].
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum MissingGlyph
 {
-    /// Instantiates a new `TextOptions` with the default settings.
+    /// The replacement character `□` is drawn, or `?` if the font doesn't
+    /// contain it either.
+    Replacement,
+    /// The font's glyph for missing characters is drawn, which is usually an
+    /// empty box known as "tofu". For a [FontFamily], the glyph of the first
+    /// font is used. Fonts without such a glyph (see
+    /// [TextLayout::missing_glyph()]) use the replacement character instead.
+    Tofu,
+    /// Nothing is drawn, and the codepoint takes up no space.
+    Skip
+}
+
+/// The positions which tab characters advance to, set using
+/// [TextOptions::with_tab_stops()]. Positions are in pixels, measured from
+/// the start of the line.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TabStops
+{
+    /// Tab stops are placed at every multiple of the specified width.
+    Interval(f32),
+    /// Tab stops are placed at each of the specified positions. Tabs after
+    /// the last position just take up the width of the tab glyph.
+    Positions(Vec<f32>)
+}
+
+impl TabStops
+{
+    /// Returns the first tab stop after the specified position.
+    fn next_stop(&self, pos_x: f32) -> Option<f32>
+    {
+        match self {
+            TabStops::Interval(interval) => {
+                if *interval > 0.0 {
+                    Some(((pos_x / interval).floor() + 1.0) * interval)
+                } else {
+                    None
+                }
+            }
+            TabStops::Positions(positions) => positions
+                .iter()
+                .copied()
+                .filter(|position| *position > pos_x)
+                .reduce(crate::numeric::min)
+        }
+    }
+}
+
+/// A callback which is invoked with each codepoint that can't be found in the
+/// font.
+#[derive(Clone)]
+struct MissingGlyphCallback(Arc<dyn Fn(char) + Send + Sync>);
+
+impl Debug for MissingGlyphCallback
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result
+    {
+        f.write_str("MissingGlyphCallback")
+    }
+}
+
+/// The places at which a line of text may be wrapped. This can be set using
+/// [TextOptions::with_line_breaking()].
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum LineBreaking
+{
+    /// Lines are only wrapped at spaces and tabs. Words which don't fit on a
+    /// line by themselves are split wherever they reach the maximum width.
+    Whitespace,
+    /// Lines may also be wrapped at the break opportunities defined by the
+    /// Unicode Line Breaking Algorithm (UAX #14), for example between
+    /// Chinese or Japanese characters, after hyphens, or after the slashes in
+    /// a URL.
+    Unicode
+}
+
+/// The way in which glyphs are rasterized and drawn. This can be set using
+/// [crate::Graphics2D::set_text_rendering_mode()].
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum TextRenderingMode
+{
+    /// Each glyph is rasterized separately for every size and subpixel offset
+    /// at which it is drawn, and is aligned to the pixel grid. This gives the
+    /// crispest results for text drawn without scaling or rotation.
+    Bitmap,
+    /// Each glyph is rasterized once into a signed distance field, which is
+    /// stretched to the size at which the glyph is drawn. Text remains sharp
+    /// at any size and when scaled or rotated using a transform, and changing
+    /// the size of text does not require the glyphs to be rasterized again.
+    ///
+    /// Very small text may appear slightly softer than in `Bitmap` mode.
+    SignedDistanceField
+}
+
+/// Options for the cache in which rasterized glyphs are stored between
+/// frames. This can be set using
+/// [crate::Graphics2D::set_glyph_cache_options()].
+///
+/// Glyphs are packed into textures, called pages. Glyphs rasterized as bitmaps
+/// and as signed distance fields (see [TextRenderingMode]) are stored in
+/// separate sets of pages, and each set is limited to the maximum number of
+/// pages.
+///
+/// When all the pages are full, the glyphs which were drawn least recently are
+/// evicted to make space. If the glyphs drawn in a single frame don't fit into
+/// the maximum number of pages, extra pages are created until they are no
+/// longer needed.
+///
+/// ```rust,no_run
+/// use speedy2d::font::GlyphCacheOptions;
+///
+/// let options = GlyphCacheOptions::new()
+///     .with_page_size(2048)
+///     .with_max_pages(2)
+///     .with_eviction_frames(600);
+/// ```
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct GlyphCacheOptions
+{
+    page_size: u32,
+    max_pages: usize,
+    eviction_frames: Option<u32>
+}
+
+impl GlyphCacheOptions
+{
+    /// Instantiates a new `GlyphCacheOptions` with the default settings.
     #[inline]
     #[must_use]
     pub fn new() -> Self
     {
-        TextOptions {
-            tracking: 0.0,
-            wrap_words_after_width: None,
-            alignment: TextAlignment::Left,
-            line_spacing_multiplier: 1.0
+        GlyphCacheOptions {
+            page_size: 1024,
+            max_pages: 4,
+            eviction_frames: None
         }
     }
 
-    /// Sets the tracking of the font. This is the amount of extra space (in
-    /// pixels) to put between each character.
+    /// Sets the width and height of each page, in pixels. Glyphs larger than
+    /// this can't be drawn.
     ///
-    /// The default is `0.0`.
+    /// The default is `1024`.
     #[inline]
     #[must_use]
-    pub fn with_tracking(mut self, tracking: f32) -> Self
+    pub fn with_page_size(mut self, page_size: u32) -> Self
     {
-        self.tracking = tracking;
+        self.page_size = page_size.max(1);
         self
     }
 
-    /// Limits the width of the text block to the specified pixel value,
-    /// wrapping words to a new line if they exceed that limit.
-    ///
-    /// This function also sets the alignment, within the specified width.
+    /// Sets the maximum number of pages in each set of pages.
     ///
-    /// The default is to not wrap text.
+    /// The default is `4`.
     #[inline]
     #[must_use]
-    pub fn with_wrap_to_width(
-        mut self,
-        wrap_words_after_width_px: f32,
-        alignment: TextAlignment
-    ) -> Self
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self
     {
-        self.wrap_words_after_width = Some(wrap_words_after_width_px);
-        self.alignment = alignment;
+        self.max_pages = max_pages.max(1);
         self
     }
 
-    /// Sets the amount of space between each line of text. The gap between the
-    /// baseline of each line of text is multiplied by this value.
+    /// Evicts glyphs which haven't been drawn during the specified number of
+    /// frames, even if there is space for them. The space they used is reused
+    /// when their page is empty, or when the pages are next rearranged.
     ///
-    /// The default is `1.0`.
+    /// The default is to only evict glyphs when the pages are full.
     #[inline]
     #[must_use]
-    pub fn with_line_spacing_multiplier(mut self, line_spacing_multiplier: f32) -> Self
+    pub fn with_eviction_frames(mut self, frames: u32) -> Self
     {
-        self.line_spacing_multiplier = line_spacing_multiplier;
+        self.eviction_frames = Some(frames);
         self
     }
+
+    /// Returns the width and height of each page, in pixels.
+    #[inline]
+    #[must_use]
+    pub fn page_size(&self) -> u32
+    {
+        self.page_size
+    }
+
+    /// Returns the maximum number of pages in each set of pages.
+    #[inline]
+    #[must_use]
+    pub fn max_pages(&self) -> usize
+    {
+        self.max_pages
+    }
+
+    /// Returns the number of frames after which unused glyphs are evicted, if
+    /// set.
+    #[inline]
+    #[must_use]
+    pub fn eviction_frames(&self) -> Option<u32>
+    {
+        self.eviction_frames
+    }
 }
 
-impl Default for TextOptions
+impl Default for GlyphCacheOptions
 {
     fn default() -> Self
     {
@@ -883,309 +2526,2242 @@ impl Default for TextOptions
     }
 }
 
-/// Represents a glyph which has been laid out as part of a line of text.
-#[derive(Clone)]
-pub struct FormattedGlyph
+/// Statistics about the glyph cache, which can be used to tune its
+/// [GlyphCacheOptions]. These are returned by
+/// [crate::Graphics2D::glyph_cache_stats()].
+///
+/// The counts are totals since the cache was created, or since its options
+/// were last set. To find the counts for a single frame, subtract the values
+/// returned at the start of the frame.
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq)]
+pub struct GlyphCacheStats
 {
-    glyph: rusttype::PositionedGlyph<'static>,
-    font_id: FontId,
-    user_index: UserGlyphIndex
+    pub(crate) hits: u64,
+    pub(crate) misses: u64,
+    pub(crate) uploads: u64,
+    pub(crate) evictions: u64,
+    pub(crate) pages_in_use: usize,
+    pub(crate) cached_glyphs: usize
 }
 
-impl FormattedGlyph
+impl GlyphCacheStats
 {
+    /// The number of times a glyph was drawn which was already in the cache.
     #[inline]
     #[must_use]
-    pub(crate) fn glyph(&self) -> &rusttype::PositionedGlyph<'static>
+    pub fn hits(&self) -> u64
     {
-        &self.glyph
+        self.hits
     }
 
-    /// The identifier of the font which was used to render this glyph.
+    /// The number of times a glyph had to be rasterized and added to the
+    /// cache. Glyphs with no outline, such as spaces, are not counted.
     #[inline]
     #[must_use]
-    pub fn font_id(&self) -> FontId
+    pub fn misses(&self) -> u64
     {
-        self.font_id
+        self.misses
     }
 
-    /// The `user_index` of the corresponding `Codepoint`. This allows you to
-    /// identify which input `Codepoint` corresponds to the output
-    /// `FormattedGlyph`.
+    /// The number of times a page was uploaded to the GPU after glyphs were
+    /// added to it.
     #[inline]
     #[must_use]
-    pub fn user_index(&self) -> UserGlyphIndex
+    pub fn uploads(&self) -> u64
     {
-        self.user_index
+        self.uploads
     }
 
-    /// The `x` coordinate of this glyph, relative to the start of the line
+    /// The number of glyphs which were removed from the cache, either to make
+    /// space or because they hadn't been drawn recently.
     #[inline]
     #[must_use]
-    pub fn position_x(&self) -> f32
+    pub fn evictions(&self) -> u64
     {
-        self.glyph.position().x
+        self.evictions
     }
 
-    /// The character's advance width. In the absence of any kerning
-    /// information, this would represent the horizontal distance between
-    /// the position of this character, and the position of the next
-    /// character.
+    /// The number of pages which currently contain glyphs.
     #[inline]
     #[must_use]
-    pub fn advance_width(&self) -> f32
+    pub fn pages_in_use(&self) -> usize
     {
-        self.glyph.unpositioned().h_metrics().advance_width
+        self.pages_in_use
     }
 
-    /// The bounding box of this glyph in pixels. This encloses the
-    /// total renderable area of the glyph.
-    ///
-    /// Some glyphs, such as a space, might not render anything at all -- in
-    /// this case, this function will return `None`.
+    /// The number of glyphs currently in the cache.
     #[inline]
     #[must_use]
-    pub fn pixel_bounding_box(&self) -> Option<Rect>
-    {
-        self.glyph.pixel_bounding_box().map(|r| {
-            Rect::from_tuples(
-                (r.min.x as f32, r.min.y as f32),
-                (r.max.x as f32, r.max.y as f32)
-            )
-        })
-    }
-
-    #[inline]
-    fn reposition_y(&mut self, y_pos: f32)
-    {
-        let existing_pos = self.glyph.position();
-        self.glyph
-            .set_position(rusttype::point(existing_pos.x, y_pos));
-    }
-
-    #[inline]
-    fn add_offset_x(&mut self, offset_x: f32)
+    pub fn cached_glyphs(&self) -> usize
     {
-        let existing_pos = self.glyph.position();
-        self.glyph
-            .set_position(rusttype::point(existing_pos.x + offset_x, existing_pos.y));
+        self.cached_glyphs
     }
 }
 
-/// Represents a block of text which has been laid out.
-#[derive(Clone)]
-pub struct FormattedTextBlock
+/// Effects which are drawn behind text, such as outlines and drop shadows,
+/// to keep it readable over a busy background. This can be passed to
+/// [crate::Graphics2D::draw_text_with_style()].
+///
+/// The effects are drawn using signed distance fields (see
+/// [TextRenderingMode::SignedDistanceField]), so they are cheap to draw at any
+/// size. Each effect can extend at most a quarter of the font size beyond the
+/// outline of the glyphs.
+///
+/// ```rust,no_run
+/// use speedy2d::color::Color;
+/// use speedy2d::font::TextStyle;
+///
+/// let style = TextStyle::new()
+///     .with_outline(2.0, Color::BLACK)
+///     .with_shadow((3.0, 3.0), 2.0, Color::from_rgba(0.0, 0.0, 0.0, 0.5));
+/// ```
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct TextStyle
 {
-    lines: Arc<FormattedTextLineVec>,
-    width: f32,
-    height: f32
+    outline: Option<(f32, Color)>,
+    shadow: Option<(Vec2, f32, Color)>,
+    glow: Option<(f32, Color)>
 }
 
-impl FormattedTextBlock
+impl TextStyle
 {
-    /// Iterate over the lines of text in this block.
+    /// Instantiates a new `TextStyle` with no effects.
     #[inline]
-    pub fn iter_lines(&self) -> Iter<'_, FormattedTextLine>
+    #[must_use]
+    pub fn new() -> Self
     {
-        self.lines.iter()
+        Self::default()
     }
 
-    /// The width (in pixels) of this text block.
+    /// Draws an outline of the specified width in pixels around each glyph.
     #[inline]
     #[must_use]
-    pub fn width(&self) -> f32
+    pub fn with_outline(mut self, width: f32, color: Color) -> Self
     {
-        self.width
+        self.outline = Some((width.max(0.0), color));
+        self
     }
 
-    /// The height (in pixels) of this text block.
+    /// Draws a shadow behind the text (and its outline, if any), moved by
+    /// `offset` pixels. The edges of the shadow fade out over `blur` pixels.
     #[inline]
     #[must_use]
-    pub fn height(&self) -> f32
+    pub fn with_shadow<V: Into<Vec2>>(
+        mut self,
+        offset: V,
+        blur: f32,
+        color: Color
+    ) -> Self
     {
-        self.height
+        self.shadow = Some((offset.into(), blur.max(0.0), color));
+        self
     }
 
-    /// The size (in pixels) of this text block.
+    /// Draws a glow around the text (and its outline, if any), which fades out
+    /// over `radius` pixels.
     #[inline]
     #[must_use]
-    pub fn size(&self) -> Vec2
+    pub fn with_glow(mut self, radius: f32, color: Color) -> Self
     {
-        Vec2::new(self.width, self.height)
+        self.glow = Some((radius.max(0.0), color));
+        self
+    }
+
+    #[inline]
+    pub(crate) fn outline(&self) -> Option<(f32, Color)>
+    {
+        self.outline
+    }
+
+    #[inline]
+    pub(crate) fn shadow(&self) -> Option<(Vec2, f32, Color)>
+    {
+        self.shadow
+    }
+
+    #[inline]
+    pub(crate) fn glow(&self) -> Option<(f32, Color)>
+    {
+        self.glow
     }
 }
 
-/// Represents a line of text which has been laid out as part of a block.
-#[derive(Clone)]
-pub struct FormattedTextLine
+/// A series of options for specifying how text should be laid out.
+#[derive(Clone, Debug)]
+pub struct TextOptions
 {
-    glyphs: Arc<FormattedGlyphVec>,
-    baseline_vertical_position: f32,
-    width: f32,
-    height: f32,
-    ascent: f32,
-    descent: f32,
-    line_gap: f32
+    tracking: f32,
+    wrap_words_after_width: Option<f32>,
+    alignment: TextAlignment,
+    line_spacing_multiplier: f32,
+    line_breaking: LineBreaking,
+    max_lines: Option<usize>,
+    ellipsis: String,
+    missing_glyph: MissingGlyph,
+    missing_glyph_callback: Option<MissingGlyphCallback>,
+    underline: bool,
+    strikethrough: bool,
+    overline: bool,
+    tab_stops: Option<TabStops>,
+    paragraph_spacing: f32,
+    first_line_indent: f32
 }
 
-impl FormattedTextLine
+impl TextOptions
 {
-    /// Iterate over the glyphs in this line.
+    /// Instantiates a new `TextOptions` with the default settings.
     #[inline]
-    pub fn iter_glyphs(&self) -> Iter<'_, FormattedGlyph>
+    #[must_use]
+    pub fn new() -> Self
     {
-        self.glyphs.iter()
+        TextOptions {
+            tracking: 0.0,
+            wrap_words_after_width: None,
+            alignment: TextAlignment::Left,
+            line_spacing_multiplier: 1.0,
+            line_breaking: LineBreaking::Whitespace,
+            max_lines: None,
+            ellipsis: "\u{2026}".to_string(),
+            missing_glyph: MissingGlyph::Replacement,
+            missing_glyph_callback: None,
+            underline: false,
+            strikethrough: false,
+            overline: false,
+            tab_stops: None,
+            paragraph_spacing: 0.0,
+            first_line_indent: 0.0
+        }
     }
 
-    /// Convert this line of text into an individually-renderable block (while
-    /// maintaining the same vertical offset).
+    /// Sets the tracking of the font. This is the amount of extra space (in
+    /// pixels) to put between each character.
+    ///
+    /// The default is `0.0`.
     #[inline]
     #[must_use]
-    pub fn as_block(&self) -> FormattedTextBlock
+    pub fn with_tracking(mut self, tracking: f32) -> Self
     {
-        FormattedTextBlock {
-            lines: Arc::new(smallvec![self.clone()]),
-            width: self.width,
-            height: self.height
-        }
+        self.tracking = tracking;
+        self
     }
 
-    /// The width (in pixels) of this text line.
+    /// Limits the width of the text block to the specified pixel value,
+    /// wrapping words to a new line if they exceed that limit.
+    ///
+    /// This function also sets the alignment, within the specified width.
+    ///
+    /// The default is to not wrap text.
     #[inline]
     #[must_use]
-    pub fn width(&self) -> f32
+    pub fn with_wrap_to_width(
+        mut self,
+        wrap_words_after_width_px: f32,
+        alignment: TextAlignment
+    ) -> Self
     {
-        self.width
+        self.wrap_words_after_width = Some(wrap_words_after_width_px);
+        self.alignment = alignment;
+        self
     }
 
-    /// The height (in pixels) of this text line. This is equal to the
-    /// `ascent()` minus the `descent()`.
+    /// Sets the amount of space between each line of text. The gap between the
+    /// baseline of each line of text is multiplied by this value.
+    ///
+    /// The default is `1.0`.
     #[inline]
     #[must_use]
-    pub fn height(&self) -> f32
+    pub fn with_line_spacing_multiplier(mut self, line_spacing_multiplier: f32) -> Self
     {
-        self.height
+        self.line_spacing_multiplier = line_spacing_multiplier;
+        self
     }
 
-    /// The ascent (in pixels) of this text line. This is the maximum height of
-    /// each glyph above the text baseline.
+    /// Sets the amount of extra space, in pixels, between the last line of
+    /// each paragraph and the first line of the next. Paragraphs are
+    /// separated by newline characters. This is added in addition to the
+    /// spacing set using [TextOptions::with_line_spacing_multiplier()].
+    ///
+    /// The default is `0.0`.
     #[inline]
     #[must_use]
-    pub fn ascent(&self) -> f32
+    pub fn with_paragraph_spacing(mut self, paragraph_spacing: f32) -> Self
     {
-        self.ascent
+        self.paragraph_spacing = paragraph_spacing;
+        self
     }
 
-    /// The descent (in pixels) of this text line. This is the furthest distance
-    /// of each glyph below the text baseline.
+    /// Sets the indent, in pixels, of the first line of each paragraph. For
+    /// right-to-left paragraphs, the line is indented from the right.
     ///
-    /// This is negative: a value of `-10.0` means the font can descend `10`
-    /// pixels below the baseline.
+    /// The default is `0.0`.
     #[inline]
     #[must_use]
-    pub fn descent(&self) -> f32
+    pub fn with_first_line_indent(mut self, first_line_indent: f32) -> Self
     {
-        self.descent
+        self.first_line_indent = first_line_indent;
+        self
     }
 
-    /// The recommended gap to put between each line of text, as encoded by the
-    /// font authors.
+    /// Sets the positions which tab characters advance to. Tabs at the start
+    /// of a paragraph are kept, rather than being removed like other leading
+    /// whitespace. If the font has no glyph for tabs, the space glyph is used
+    /// in its place.
+    ///
+    /// ```rust
+    /// use speedy2d::font::{TabStops, TextOptions};
+    ///
+    /// let every_80_px = TextOptions::new().with_tab_stops(TabStops::Interval(80.0));
+    ///
+    /// let columns =
+    ///     TextOptions::new().with_tab_stops(TabStops::Positions(vec![60.0, 200.0]));
+    /// ```
+    ///
+    /// The default is no tab stops, in which case each tab takes up the width
+    /// of the tab glyph, or no space if the font doesn't have one.
     #[inline]
     #[must_use]
-    pub fn line_gap(&self) -> f32
+    pub fn with_tab_stops(mut self, tab_stops: TabStops) -> Self
     {
-        self.line_gap
+        self.tab_stops = Some(tab_stops);
+        self
     }
 
-    /// The vertical position of this line's baseline within the block of text.
+    /// Returns the first tab stop after the specified position, if any.
+    fn next_tab_stop(&self, pos_x: f32) -> Option<f32>
+    {
+        self.tab_stops.as_ref()?.next_stop(pos_x)
+    }
+
+    /// Sets where lines may be wrapped, when a maximum width is set using
+    /// [TextOptions::with_wrap_to_width()].
+    ///
+    /// The default is [LineBreaking::Whitespace].
     #[inline]
     #[must_use]
-    pub fn baseline_position(&self) -> f32
+    pub fn with_line_breaking(mut self, line_breaking: LineBreaking) -> Self
     {
-        self.baseline_vertical_position
+        self.line_breaking = line_breaking;
+        self
     }
-}
 
-impl<T: Copy> From<&rusttype::Rect<T>> for Rectangle<T>
-{
+    /// Limits the number of lines in the text block. If the text doesn't fit,
+    /// the remaining text is omitted, and the ellipsis is added to the end of
+    /// the last line. If a wrapping width is set, glyphs are removed from the
+    /// end of the last line as needed for the ellipsis to fit.
+    ///
+    /// Use [FormattedTextBlock::is_truncated()] to find out whether any text
+    /// was omitted.
+    ///
+    /// The default is no limit.
     #[inline]
     #[must_use]
-    fn from(rect: &rusttype::Rect<T>) -> Self
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self
     {
-        Rectangle::new(
-            Vector2::new(rect.min.x, rect.min.y),
-            Vector2::new(rect.max.x, rect.max.y)
-        )
+        self.max_lines = Some(max_lines);
+        self
+    }
+
+    /// Sets the string which is added to the end of the last line when the
+    /// text is truncated by [TextOptions::with_max_lines()]. This may be
+    /// empty.
+    ///
+    /// The default is `"…"`.
+    #[inline]
+    #[must_use]
+    pub fn with_ellipsis<S: Into<String>>(mut self, ellipsis: S) -> Self
+    {
+        self.ellipsis = ellipsis.into();
+        self
+    }
+
+    /// Sets whether a line is drawn below the text. The position and
+    /// thickness of the line are taken from the font if it specifies them.
+    /// When laying out [RichText], this applies to all the spans.
+    ///
+    /// The default is `false`.
+    #[inline]
+    #[must_use]
+    pub fn with_underline(mut self, underline: bool) -> Self
+    {
+        self.underline = underline;
+        self
+    }
+
+    /// Sets whether a line is drawn through the middle of the text. The
+    /// position and thickness of the line are taken from the font if it
+    /// specifies them. When laying out [RichText], this applies to all the
+    /// spans.
+    ///
+    /// The default is `false`.
+    #[inline]
+    #[must_use]
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self
+    {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    /// Sets whether a line is drawn above the text, at the height of the
+    /// font's ascent. When laying out [RichText], this applies to all the
+    /// spans.
+    ///
+    /// The default is `false`.
+    #[inline]
+    #[must_use]
+    pub fn with_overline(mut self, overline: bool) -> Self
+    {
+        self.overline = overline;
+        self
+    }
+
+    /// Sets what is drawn in place of codepoints which can't be found in the
+    /// font.
+    ///
+    /// The default is [MissingGlyph::Replacement].
+    #[inline]
+    #[must_use]
+    pub fn with_missing_glyph(mut self, missing_glyph: MissingGlyph) -> Self
+    {
+        self.missing_glyph = missing_glyph;
+        self
+    }
+
+    /// Sets a callback which is invoked during layout with each codepoint
+    /// that can't be found in the font, for example to log it or to load a
+    /// fallback font. The callback may be invoked more than once for the same
+    /// codepoint.
+    ///
+    /// ```rust,no_run
+    /// use speedy2d::font::TextOptions;
+    ///
+    /// let options = TextOptions::new().with_missing_glyph_callback(|codepoint| {
+    ///     eprintln!("No glyph for U+{:04X}", codepoint as u32);
+    /// });
+    /// ```
+    ///
+    /// The default is no callback.
+    #[inline]
+    #[must_use]
+    pub fn with_missing_glyph_callback<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(char) + Send + Sync + 'static
+    {
+        self.missing_glyph_callback = Some(MissingGlyphCallback(Arc::new(callback)));
+        self
     }
 }
 
-#[cfg(test)]
-mod test
+impl Default for TextOptions
 {
-    use super::*;
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
 
-    #[test]
-    fn test_word_split_1()
+/// A run of text with its own font, scale, and style. Spans are combined into
+/// a single block of text using [RichText].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextSpan
+{
+    text: String,
+    font: FontFamily,
+    scale: f32,
+    color: Option<Color>,
+    underline: bool,
+    strikethrough: bool,
+    overline: bool
+}
+
+impl TextSpan
+{
+    /// Instantiates a new span containing the specified text, which will be
+    /// laid out using the specified font and scale. To fall back to another
+    /// font if a glyph isn't found, pass a [FontFamily].
+    #[inline]
+    #[must_use]
+    pub fn new<S: Into<String>, F: Into<FontFamily>>(text: S, font: F, scale: f32)
+        -> Self
     {
-        let codepoints = Codepoint::from_unindexed_codepoints(&['a', 'b', ' ', 'c', 'd']);
+        TextSpan {
+            text: text.into(),
+            font: font.into(),
+            scale,
+            color: None,
+            underline: false,
+            strikethrough: false,
+            overline: false
+        }
+    }
 
-        let words = Word::split_words(&codepoints);
+    /// Sets the color of this span. If no color is set, the span is drawn
+    /// using the color passed to `Graphics2D::draw_text`.
+    #[inline]
+    #[must_use]
+    pub fn with_color(mut self, color: Color) -> Self
+    {
+        self.color = Some(color);
+        self
+    }
 
-        assert_eq!(
-            vec![
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(0, 'a'), Codepoint::new(1, 'b')],
-                    is_whitespace: false
-                }),
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(2, ' ')],
-                    is_whitespace: true
-                }),
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(3, 'c'), Codepoint::new(4, 'd')],
-                    is_whitespace: false
-                })
-            ],
-            words
-        )
+    /// Sets whether a line is drawn below this span.
+    ///
+    /// The default is `false`.
+    #[inline]
+    #[must_use]
+    pub fn with_underline(mut self, underline: bool) -> Self
+    {
+        self.underline = underline;
+        self
     }
 
-    #[test]
-    fn test_word_split_2()
+    /// Sets whether a line is drawn through the middle of this span.
+    ///
+    /// The default is `false`.
+    #[inline]
+    #[must_use]
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self
     {
-        let codepoints = Codepoint::from_unindexed_codepoints(&[
-            'a', 'b', '\t', ' ', '\n', 'c', 'd', '\n', '\n', ' '
-        ]);
+        self.strikethrough = strikethrough;
+        self
+    }
 
-        let words = Word::split_words(&codepoints);
+    /// Sets whether a line is drawn above this span.
+    ///
+    /// The default is `false`.
+    #[inline]
+    #[must_use]
+    pub fn with_overline(mut self, overline: bool) -> Self
+    {
+        self.overline = overline;
+        self
+    }
 
-        assert_eq!(
-            vec![
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(0, 'a'), Codepoint::new(1, 'b')],
-                    is_whitespace: false
-                }),
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(2, '\t'),],
-                    is_whitespace: true
-                }),
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(3, ' '),],
-                    is_whitespace: true
-                }),
-                Word::Newline,
-                Word::Renderable(RenderableWord {
-                    codepoints: vec![Codepoint::new(5, 'c'), Codepoint::new(6, 'd')],
-                    is_whitespace: false
+    /// The text of this span.
+    #[inline]
+    #[must_use]
+    pub fn text(&self) -> &str
+    {
+        &self.text
+    }
+}
+
+/// A block of text made up of several [TextSpan] objects, each of which may
+/// have a different font, scale, and style. The spans are wrapped and aligned
+/// together, as if they were a single string.
+///
+/// ```rust
+/// # use speedy2d::color::Color;
+/// # use speedy2d::font::{Font, RichText, TextOptions, TextSpan};
+/// # let bytes = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+/// # let font = Font::new(bytes).unwrap();
+/// let block = RichText::new()
+///     .with_span(TextSpan::new("Hello, ", &font, 32.0))
+///     .with_span(
+///         TextSpan::new("world", &font, 48.0)
+///             .with_color(Color::RED)
+///             .with_underline(true)
+///     )
+///     .layout(TextOptions::new());
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RichText
+{
+    spans: Vec<TextSpan>
+}
+
+impl RichText
+{
+    /// Instantiates a new `RichText` with no spans.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self
+    {
+        RichText { spans: Vec::new() }
+    }
+
+    /// Appends the specified span to the end of the text.
+    #[inline]
+    #[must_use]
+    pub fn with_span(mut self, span: TextSpan) -> Self
+    {
+        self.spans.push(span);
+        self
+    }
+
+    /// The spans of this text, in order.
+    #[inline]
+    #[must_use]
+    pub fn spans(&self) -> &[TextSpan]
+    {
+        &self.spans
+    }
+
+    /// Lays out the spans as a single block of text. The result may be passed
+    /// to `Graphics2D::draw_text`.
+    ///
+    /// The text of each span undergoes normalization, and the `user_index` of
+    /// each `FormattedGlyph` is the index of the normalized codepoint within
+    /// the text of all the spans.
+    #[must_use]
+    pub fn layout(&self, options: TextOptions) -> FormattedTextBlock
+    {
+        let styles: Vec<SpanStyle<FontFamily>> = self
+            .spans
+            .iter()
+            .map(|span| SpanStyle {
+                layout_helper: &span.font,
+                scale: Scale::uniform(span.scale),
+                color: span.color,
+                underline: span.underline || options.underline,
+                strikethrough: span.strikethrough || options.strikethrough,
+                overline: span.overline || options.overline
+            })
+            .collect();
+
+        let mut codepoints = Vec::new();
+        let mut span_indices = Vec::new();
+
+        for (span_index, span) in self.spans.iter().enumerate() {
+            for codepoint in span.text.nfc() {
+                codepoints.push(Codepoint::new(
+                    codepoints.len().try_into().unwrap(),
+                    codepoint
+                ));
+                span_indices.push(span_index);
+            }
+        }
+
+        layout_multiple_lines_internal(&styles, &codepoints, &span_indices, options)
+    }
+}
+
+/// The outline or bitmap of a glyph which has been laid out.
+#[derive(Clone)]
+pub(crate) enum PositionedFontGlyph
+{
+    /// The glyph borrows from the bytes of its font file, so it holds a
+    /// reference to them to keep them alive.
+    Outline(
+        rusttype::PositionedGlyph<'static>,
+        #[allow(dead_code)] Arc<Vec<u8>>
+    ),
+    Bitmap(PositionedBitmapGlyph)
+}
+
+impl PositionedFontGlyph
+{
+    #[inline]
+    fn id(&self) -> rusttype::GlyphId
+    {
+        match self {
+            PositionedFontGlyph::Outline(glyph, _) => glyph.id(),
+            PositionedFontGlyph::Bitmap(glyph) => rusttype::GlyphId(glyph.index)
+        }
+    }
+
+    /// The pen position on the baseline.
+    #[inline]
+    fn position(&self) -> Vec2
+    {
+        match self {
+            PositionedFontGlyph::Outline(glyph, _) => {
+                Vec2::new(glyph.position().x, glyph.position().y)
+            }
+            PositionedFontGlyph::Bitmap(glyph) => glyph.position
+        }
+    }
+
+    #[inline]
+    fn set_position(&mut self, position: Vec2)
+    {
+        match self {
+            PositionedFontGlyph::Outline(glyph, _) => {
+                glyph.set_position(rusttype::point(position.x, position.y))
+            }
+            PositionedFontGlyph::Bitmap(glyph) => glyph.position = position
+        }
+    }
+}
+
+/// Represents a glyph which has been laid out as part of a line of text.
+#[derive(Clone)]
+pub struct FormattedGlyph
+{
+    glyph: PositionedFontGlyph,
+    font_id: FontId,
+    user_index: UserGlyphIndex,
+    offset_y: f32,
+    bidi_level: u8,
+    span_index: usize,
+    color: Option<Color>,
+    color_glyphs: Option<Arc<ColorGlyphs>>,
+    /// The start of the horizontal span occupied by this glyph on the line,
+    /// including any kerning and tracking before it.
+    cell_x: f32,
+    cell_width: f32
+}
+
+impl FormattedGlyph
+{
+    #[inline]
+    #[must_use]
+    pub(crate) fn glyph(&self) -> &PositionedFontGlyph
+    {
+        &self.glyph
+    }
+
+    /// The color glyph tables of the font, if it has any.
+    #[inline]
+    #[must_use]
+    pub(crate) fn color_glyphs(&self) -> Option<&ColorGlyphs>
+    {
+        self.color_glyphs.as_deref()
+    }
+
+    /// The identifier of the font which was used to render this glyph.
+    #[inline]
+    #[must_use]
+    pub fn font_id(&self) -> FontId
+    {
+        self.font_id
+    }
+
+    /// The `user_index` of the corresponding `Codepoint`. This allows you to
+    /// identify which input `Codepoint` corresponds to the output
+    /// `FormattedGlyph`.
+    #[inline]
+    #[must_use]
+    pub fn user_index(&self) -> UserGlyphIndex
+    {
+        self.user_index
+    }
+
+    /// The color of the [TextSpan] containing this glyph, if one was set.
+    /// Otherwise, the glyph is drawn using the color passed to
+    /// `Graphics2D::draw_text`.
+    #[inline]
+    #[must_use]
+    pub fn color(&self) -> Option<Color>
+    {
+        self.color
+    }
+
+    /// The `x` coordinate of this glyph, relative to the start of the line
+    #[inline]
+    #[must_use]
+    pub fn position_x(&self) -> f32
+    {
+        self.glyph.position().x
+    }
+
+    /// The character's advance width. In the absence of any kerning
+    /// information, this would represent the horizontal distance between
+    /// the position of this character, and the position of the next
+    /// character.
+    #[inline]
+    #[must_use]
+    pub fn advance_width(&self) -> f32
+    {
+        match &self.glyph {
+            PositionedFontGlyph::Outline(glyph, _) => {
+                glyph.unpositioned().h_metrics().advance_width
+            }
+            PositionedFontGlyph::Bitmap(glyph) => glyph.advance_width()
+        }
+    }
+
+    /// The bounding box of this glyph in pixels. This encloses the
+    /// total renderable area of the glyph.
+    ///
+    /// Some glyphs, such as a space, might not render anything at all -- in
+    /// this case, this function will return `None`.
+    #[inline]
+    #[must_use]
+    pub fn pixel_bounding_box(&self) -> Option<Rect>
+    {
+        match &self.glyph {
+            PositionedFontGlyph::Outline(glyph, _) => {
+                glyph.pixel_bounding_box().map(|r| {
+                    Rect::from_tuples(
+                        (r.min.x as f32, r.min.y as f32),
+                        (r.max.x as f32, r.max.y as f32)
+                    )
+                })
+            }
+            PositionedFontGlyph::Bitmap(glyph) => glyph.bounding_box()
+        }
+    }
+
+    /// Moves the glyph to the specified baseline, keeping any vertical offset
+    /// applied during shaping.
+    #[inline]
+    fn reposition_y(&mut self, y_pos: f32)
+    {
+        let existing_pos = self.glyph.position();
+        self.glyph
+            .set_position(Vec2::new(existing_pos.x, y_pos + self.offset_y));
+    }
+
+    #[inline]
+    fn add_offset_x(&mut self, offset_x: f32)
+    {
+        let existing_pos = self.glyph.position();
+        self.glyph
+            .set_position(Vec2::new(existing_pos.x + offset_x, existing_pos.y));
+        self.cell_x += offset_x;
+    }
+
+    /// The horizontal position of the edge of this glyph which comes first in
+    /// reading order: the left for left-to-right text, and the right for
+    /// right-to-left text.
+    #[inline]
+    fn leading_edge_x(&self) -> f32
+    {
+        if text_bidi::is_right_to_left(self.bidi_level) {
+            self.cell_x + self.cell_width
+        } else {
+            self.cell_x
+        }
+    }
+
+    /// The horizontal position of the edge of this glyph which comes last in
+    /// reading order.
+    #[inline]
+    fn trailing_edge_x(&self) -> f32
+    {
+        if text_bidi::is_right_to_left(self.bidi_level) {
+            self.cell_x
+        } else {
+            self.cell_x + self.cell_width
+        }
+    }
+}
+
+/// The glyph found at a position within some laid out text, returned by
+/// [FormattedTextBlock::hit_test] and [FormattedTextLine::hit_test].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextHit
+{
+    user_index: UserGlyphIndex,
+    is_trailing: bool
+}
+
+impl TextHit
+{
+    /// The `user_index` of the glyph at the position.
+    #[inline]
+    #[must_use]
+    pub fn user_index(&self) -> UserGlyphIndex
+    {
+        self.user_index
+    }
+
+    /// True if the position is closer to the edge of the glyph which comes
+    /// last in reading order. When placing a caret, this means the caret
+    /// should go after the glyph rather than before it.
+    #[inline]
+    #[must_use]
+    pub fn is_trailing(&self) -> bool
+    {
+        self.is_trailing
+    }
+}
+
+/// The position of a caret within a block of text, returned by
+/// [FormattedTextBlock::caret_position].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextCaretPosition
+{
+    line_index: usize,
+    position: Vec2,
+    height: f32
+}
+
+impl TextCaretPosition
+{
+    /// The index of the line containing the caret.
+    #[inline]
+    #[must_use]
+    pub fn line_index(&self) -> usize
+    {
+        self.line_index
+    }
+
+    /// The position of the top of the caret, relative to the top left of the
+    /// block.
+    #[inline]
+    #[must_use]
+    pub fn position(&self) -> Vec2
+    {
+        self.position
+    }
+
+    /// The height of the caret, which is equal to the height of the line.
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> f32
+    {
+        self.height
+    }
+}
+
+/// The type of a [TextDecoration].
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum TextDecorationKind
+{
+    /// A line below the baseline.
+    Underline,
+    /// A line through the middle of the text.
+    Strikethrough,
+    /// A line above the text.
+    Overline
+}
+
+/// A line drawn under, through or over part of a line of text, as requested
+/// using [TextOptions::with_underline], [TextSpan::with_underline], or the
+/// equivalent methods for strikethroughs and overlines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextDecoration
+{
+    kind: TextDecorationKind,
+    rect: Rect,
+    color: Option<Color>
+}
+
+impl TextDecoration
+{
+    /// The type of this decoration.
+    #[inline]
+    #[must_use]
+    pub fn kind(&self) -> TextDecorationKind
+    {
+        self.kind
+    }
+
+    /// The area covered by this decoration, relative to the top left of the
+    /// block of text.
+    #[inline]
+    #[must_use]
+    pub fn rect(&self) -> &Rect
+    {
+        &self.rect
+    }
+
+    /// The color of the span containing this decoration, if one was set.
+    #[inline]
+    #[must_use]
+    pub fn color(&self) -> Option<Color>
+    {
+        self.color
+    }
+}
+
+/// Represents a block of text which has been laid out.
+#[derive(Clone)]
+pub struct FormattedTextBlock
+{
+    lines: Arc<FormattedTextLineVec>,
+    width: f32,
+    height: f32,
+    truncated: bool
+}
+
+impl FormattedTextBlock
+{
+    /// Iterate over the lines of text in this block.
+    #[inline]
+    pub fn iter_lines(&self) -> Iter<'_, FormattedTextLine>
+    {
+        self.lines.iter()
+    }
+
+    /// The width (in pixels) of this text block.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> f32
+    {
+        self.width
+    }
+
+    /// The height (in pixels) of this text block.
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> f32
+    {
+        self.height
+    }
+
+    /// The size (in pixels) of this text block.
+    #[inline]
+    #[must_use]
+    pub fn size(&self) -> Vec2
+    {
+        Vec2::new(self.width, self.height)
+    }
+
+    /// Returns true if some of the text was omitted, because it didn't fit
+    /// within the limit set by [TextOptions::with_max_lines()].
+    #[inline]
+    #[must_use]
+    pub fn is_truncated(&self) -> bool
+    {
+        self.truncated
+    }
+
+    /// Returns the position of the top left of this block when it is aligned
+    /// within the specified rectangle, as drawn by
+    /// `Graphics2D::draw_text_in_rect`.
+    ///
+    /// The block is aligned as a whole, using its [width()](Self::width), so
+    /// [TextAlignment::Start] and [TextAlignment::Justify] are treated as
+    /// [TextAlignment::Left], and [TextAlignment::End] as
+    /// [TextAlignment::Right]. To align the lines of the block relative to
+    /// each other, use [TextOptions::with_wrap_to_width()].
+    #[must_use]
+    pub fn position_in_rect(
+        &self,
+        rect: &Rect,
+        h_align: TextAlignment,
+        v_align: VerticalAlignment
+    ) -> Vec2
+    {
+        let x = match h_align {
+            TextAlignment::Left | TextAlignment::Start | TextAlignment::Justify => {
+                rect.top_left().x
+            }
+            TextAlignment::Center => {
+                rect.top_left().x + (rect.width() - self.width) / 2.0
+            }
+            TextAlignment::Right | TextAlignment::End => {
+                rect.bottom_right().x - self.width
+            }
+        };
+
+        let y = match v_align {
+            VerticalAlignment::Top => rect.top_left().y,
+            VerticalAlignment::Middle => {
+                rect.top_left().y + (rect.height() - self.height) / 2.0
+            }
+            VerticalAlignment::Baseline => {
+                let ascent = self.lines.first().map_or(0.0, |line| line.ascent);
+                rect.bottom_right().y - ascent
+            }
+            VerticalAlignment::Bottom => rect.bottom_right().y - self.height
+        };
+
+        Vec2::new(x, y)
+    }
+
+    /// Returns true if this block, aligned within the specified rectangle
+    /// using [position_in_rect()](Self::position_in_rect), extends outside
+    /// it.
+    ///
+    /// With [VerticalAlignment::Baseline], the descent of the first line is
+    /// expected to extend below the rectangle, and isn't counted as
+    /// overflowing.
+    #[must_use]
+    pub fn overflows_rect(
+        &self,
+        rect: &Rect,
+        h_align: TextAlignment,
+        v_align: VerticalAlignment
+    ) -> bool
+    {
+        let position = self.position_in_rect(rect, h_align, v_align);
+        let area = self.area_in_rect(rect, v_align);
+
+        position.x < area.top_left().x
+            || position.y < area.top_left().y
+            || position.x + self.width > area.bottom_right().x
+            || position.y + self.height > area.bottom_right().y
+    }
+
+    /// The area which this block may occupy when it is aligned within the
+    /// specified rectangle. This is the rectangle itself, extended downwards
+    /// by the descent of the first line for [VerticalAlignment::Baseline].
+    pub(crate) fn area_in_rect(&self, rect: &Rect, v_align: VerticalAlignment) -> Rect
+    {
+        match (v_align, self.lines.first()) {
+            (VerticalAlignment::Baseline, Some(line)) => Rect::new(
+                *rect.top_left(),
+                *rect.bottom_right() - Vec2::new(0.0, line.descent)
+            ),
+            _ => rect.clone()
+        }
+    }
+
+    /// Finds the glyph at the specified position, relative to the top left of
+    /// the block. This can be used to place a caret where the user clicks.
+    ///
+    /// Each line extends down to the top of the next line, and positions
+    /// beyond the edges of the block return the nearest glyph. Returns `None`
+    /// if the line at the position contains no glyphs.
+    #[must_use]
+    pub fn hit_test<V: Into<Vec2>>(&self, point: V) -> Option<TextHit>
+    {
+        let point = point.into();
+
+        self.lines
+            .iter()
+            .rev()
+            .find(|line| line.baseline_vertical_position <= point.y)
+            .or_else(|| self.lines.first())?
+            .hit_test(point.x)
+    }
+
+    /// Returns the position of a caret placed before the glyph with the
+    /// specified `user_index`, relative to the top left of the block.
+    ///
+    /// If no glyph has this `user_index` (for example, at the end of the
+    /// text, or for whitespace skipped when wrapping), the caret is placed
+    /// after the glyph with the closest lower `user_index`. Returns `None` if
+    /// there is no such glyph.
+    #[must_use]
+    pub fn caret_position(&self, user_index: UserGlyphIndex)
+        -> Option<TextCaretPosition>
+    {
+        let caret = |line_index: usize, x: f32| {
+            let line: &FormattedTextLine = &self.lines[line_index];
+
+            TextCaretPosition {
+                line_index,
+                position: Vec2::new(x, line.baseline_vertical_position),
+                height: line.height
+            }
+        };
+
+        for (line_index, line) in self.lines.iter().enumerate() {
+            if let Some(x) = line.caret_position_x(user_index) {
+                return Some(caret(line_index, x));
+            }
+        }
+
+        let mut preceding: Option<(usize, &FormattedGlyph)> = None;
+
+        for (line_index, line) in self.lines.iter().enumerate() {
+            for glyph in line.glyphs.iter() {
+                if glyph.user_index >= user_index {
+                    continue;
+                }
+
+                let is_closer = match preceding {
+                    None => true,
+                    Some((_, preceding)) => glyph.user_index > preceding.user_index
+                };
+
+                if is_closer {
+                    preceding = Some((line_index, glyph));
+                }
+            }
+        }
+
+        preceding.map(|(line_index, glyph)| caret(line_index, glyph.trailing_edge_x()))
+    }
+
+    /// Returns the rectangles which should be highlighted to show that the
+    /// glyphs with a `user_index` in the specified range are selected. The
+    /// rectangles are relative to the top left of the block, and cover the
+    /// full height of each line.
+    ///
+    /// Where the text contains both left-to-right and right-to-left runs, the
+    /// selection on a single line may be split into several rectangles.
+    #[must_use]
+    pub fn selection_rects<R: RangeBounds<UserGlyphIndex>>(&self, range: R) -> Vec<Rect>
+    {
+        self.lines
+            .iter()
+            .flat_map(|line| {
+                line.selection_rects((range.start_bound(), range.end_bound()))
+            })
+            .collect()
+    }
+}
+
+/// Represents a line of text which has been laid out as part of a block.
+#[derive(Clone)]
+pub struct FormattedTextLine
+{
+    glyphs: Arc<FormattedGlyphVec>,
+    decorations: Arc<Vec<TextDecoration>>,
+    baseline_vertical_position: f32,
+    width: f32,
+    height: f32,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32
+}
+
+impl FormattedTextLine
+{
+    /// Iterate over the glyphs in this line.
+    #[inline]
+    pub fn iter_glyphs(&self) -> Iter<'_, FormattedGlyph>
+    {
+        self.glyphs.iter()
+    }
+
+    /// Iterate over the underlines, strikethroughs and overlines in this
+    /// line. These are drawn along with the glyphs by `Graphics2D::draw_text`.
+    #[inline]
+    pub fn iter_decorations(&self) -> Iter<'_, TextDecoration>
+    {
+        self.decorations.iter()
+    }
+
+    /// Convert this line of text into an individually-renderable block (while
+    /// maintaining the same vertical offset).
+    #[inline]
+    #[must_use]
+    pub fn as_block(&self) -> FormattedTextBlock
+    {
+        FormattedTextBlock {
+            lines: Arc::new(smallvec![self.clone()]),
+            width: self.width,
+            height: self.height,
+            truncated: false
+        }
+    }
+
+    /// The width (in pixels) of this text line.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> f32
+    {
+        self.width
+    }
+
+    /// The height (in pixels) of this text line. This is equal to the
+    /// `ascent()` minus the `descent()`.
+    #[inline]
+    #[must_use]
+    pub fn height(&self) -> f32
+    {
+        self.height
+    }
+
+    /// The ascent (in pixels) of this text line. This is the maximum height of
+    /// each glyph above the text baseline.
+    #[inline]
+    #[must_use]
+    pub fn ascent(&self) -> f32
+    {
+        self.ascent
+    }
+
+    /// The descent (in pixels) of this text line. This is the furthest distance
+    /// of each glyph below the text baseline.
+    ///
+    /// This is negative: a value of `-10.0` means the font can descend `10`
+    /// pixels below the baseline.
+    #[inline]
+    #[must_use]
+    pub fn descent(&self) -> f32
+    {
+        self.descent
+    }
+
+    /// The recommended gap to put between each line of text, as encoded by the
+    /// font authors.
+    #[inline]
+    #[must_use]
+    pub fn line_gap(&self) -> f32
+    {
+        self.line_gap
+    }
+
+    /// The vertical position of this line's baseline within the block of text.
+    #[inline]
+    #[must_use]
+    pub fn baseline_position(&self) -> f32
+    {
+        self.baseline_vertical_position
+    }
+
+    /// Finds the glyph at the specified horizontal position, relative to the
+    /// left of the block. Positions beyond either end of the line return the
+    /// glyph at that end. Returns `None` if the line contains no glyphs.
+    #[must_use]
+    pub fn hit_test(&self, x: f32) -> Option<TextHit>
+    {
+        let glyph = self
+            .glyphs
+            .iter()
+            .find(|glyph| x < glyph.cell_x + glyph.cell_width)
+            .or_else(|| self.glyphs.last())?;
+
+        let is_right_half = x >= glyph.cell_x + glyph.cell_width / 2.0;
+
+        Some(TextHit {
+            user_index: glyph.user_index,
+            is_trailing: is_right_half != text_bidi::is_right_to_left(glyph.bidi_level)
+        })
+    }
+
+    /// Returns the horizontal position of a caret placed before the glyph
+    /// with the specified `user_index`, relative to the left of the block, or
+    /// `None` if no glyph on this line has this `user_index`.
+    #[must_use]
+    pub fn caret_position_x(&self, user_index: UserGlyphIndex) -> Option<f32>
+    {
+        self.glyphs
+            .iter()
+            .find(|glyph| glyph.user_index == user_index)
+            .map(FormattedGlyph::leading_edge_x)
+    }
+
+    /// Returns the rectangles which should be highlighted to show that the
+    /// glyphs on this line with a `user_index` in the specified range are
+    /// selected. See [FormattedTextBlock::selection_rects].
+    #[must_use]
+    pub fn selection_rects<R: RangeBounds<UserGlyphIndex>>(&self, range: R) -> Vec<Rect>
+    {
+        let top = self.baseline_vertical_position;
+        let bottom = top + self.height;
+
+        let mut result: Vec<Rect> = Vec::new();
+        let mut previous_selected = false;
+
+        // The glyphs are in visual order, so adjacent selected glyphs can be
+        // merged into one rectangle
+        for glyph in self.glyphs.iter() {
+            let selected = range.contains(&glyph.user_index);
+            let right = glyph.cell_x + glyph.cell_width;
+
+            match result.last_mut() {
+                Some(rect) if selected && previous_selected => {
+                    *rect = Rect::from_tuples((rect.top_left().x, top), (right, bottom));
+                }
+                _ if selected => {
+                    result.push(Rect::from_tuples((glyph.cell_x, top), (right, bottom)))
+                }
+                _ => {}
+            }
+
+            previous_selected = selected;
+        }
+
+        result.retain(Rect::is_positive_area);
+        result
+    }
+}
+
+impl<T: Copy> From<&rusttype::Rect<T>> for Rectangle<T>
+{
+    #[inline]
+    #[must_use]
+    fn from(rect: &rusttype::Rect<T>) -> Self
+    {
+        Rectangle::new(
+            Vector2::new(rect.min.x, rect.min.y),
+            Vector2::new(rect.max.x, rect.max.y)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    fn test_word_split_1()
+    {
+        let codepoints = Codepoint::from_unindexed_codepoints(&['a', 'b', ' ', 'c', 'd']);
+
+        let words = Word::split_words(
+            &codepoints,
+            &vec![0; codepoints.len()],
+            LineBreaking::Whitespace
+        );
+
+        assert_eq!(
+            vec![
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(0, 'a'), Codepoint::new(1, 'b')],
+                    bidi_levels: vec![0, 0],
+                    spans: vec![0, 0],
+                    paragraph_level: 0,
+                    is_whitespace: false
+                }),
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(2, ' ')],
+                    bidi_levels: vec![0],
+                    spans: vec![0],
+                    paragraph_level: 0,
+                    is_whitespace: true
+                }),
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(3, 'c'), Codepoint::new(4, 'd')],
+                    bidi_levels: vec![0, 0],
+                    spans: vec![0, 0],
+                    paragraph_level: 0,
+                    is_whitespace: false
+                })
+            ],
+            words
+        )
+    }
+
+    #[test]
+    fn test_word_split_2()
+    {
+        let codepoints = Codepoint::from_unindexed_codepoints(&[
+            'a', 'b', '\t', ' ', '\n', 'c', 'd', '\n', '\n', ' '
+        ]);
+
+        let words = Word::split_words(
+            &codepoints,
+            &vec![0; codepoints.len()],
+            LineBreaking::Whitespace
+        );
+
+        assert_eq!(
+            vec![
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(0, 'a'), Codepoint::new(1, 'b')],
+                    bidi_levels: vec![0, 0],
+                    spans: vec![0, 0],
+                    paragraph_level: 0,
+                    is_whitespace: false
+                }),
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(2, '\t'),],
+                    bidi_levels: vec![0],
+                    spans: vec![0],
+                    paragraph_level: 0,
+                    is_whitespace: true
+                }),
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(3, ' '),],
+                    bidi_levels: vec![0],
+                    spans: vec![0],
+                    paragraph_level: 0,
+                    is_whitespace: true
+                }),
+                Word::Newline,
+                Word::Renderable(RenderableWord {
+                    codepoints: vec![Codepoint::new(5, 'c'), Codepoint::new(6, 'd')],
+                    bidi_levels: vec![0, 0],
+                    spans: vec![0, 0],
+                    paragraph_level: 0,
+                    is_whitespace: false
                 }),
                 Word::Newline,
                 Word::Newline,
                 Word::Renderable(RenderableWord {
                     codepoints: vec![Codepoint::new(9, ' ')],
+                    bidi_levels: vec![0],
+                    spans: vec![0],
+                    paragraph_level: 0,
                     is_whitespace: true
                 })
             ],
-            words
-        )
+            words
+        )
+    }
+
+    fn noto_sans() -> Font
+    {
+        Font::new(include_bytes!("../assets/fonts/NotoSans-Regular.ttf")).unwrap()
+    }
+
+    fn user_indices(block: &FormattedTextBlock) -> Vec<Vec<UserGlyphIndex>>
+    {
+        block
+            .iter_lines()
+            .map(|line| line.iter_glyphs().map(|glyph| glyph.user_index()).collect())
+            .collect()
+    }
+
+    fn positions_x(block: &FormattedTextBlock) -> Vec<f32>
+    {
+        block
+            .iter_lines()
+            .flat_map(|line| line.iter_glyphs().map(|glyph| glyph.position_x()))
+            .collect()
+    }
+
+    #[test]
+    fn test_bidi_reordering()
+    {
+        let font = noto_sans();
+
+        let ltr_paragraph = font.layout_text("abc אבג", 20.0, TextOptions::new());
+        assert_eq!(
+            vec![vec![0, 1, 2, 3, 6, 5, 4]],
+            user_indices(&ltr_paragraph)
+        );
+
+        let rtl_paragraph = font.layout_text("אבג abc", 20.0, TextOptions::new());
+        assert_eq!(
+            vec![vec![4, 5, 6, 3, 2, 1, 0]],
+            user_indices(&rtl_paragraph)
+        );
+
+        // Each paragraph has its own direction
+        let mixed = font.layout_text("אב 12\nab", 20.0, TextOptions::new());
+        assert_eq!(vec![vec![3, 4, 2, 1, 0], vec![6, 7]], user_indices(&mixed));
+
+        for block in [ltr_paragraph, rtl_paragraph, mixed] {
+            for line in block.iter_lines() {
+                let positions: Vec<f32> =
+                    line.iter_glyphs().map(|glyph| glyph.position_x()).collect();
+
+                assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_bidi_alignment()
+    {
+        let font = noto_sans();
+
+        let layout = |text: &str, alignment: TextAlignment| {
+            positions_x(&font.layout_text(
+                text,
+                20.0,
+                TextOptions::new().with_wrap_to_width(200.0, alignment)
+            ))
+        };
+
+        assert_eq!(
+            layout("אבג", TextAlignment::Right),
+            layout("אבג", TextAlignment::Start)
+        );
+        assert_eq!(
+            layout("אבג", TextAlignment::Left),
+            layout("אבג", TextAlignment::End)
+        );
+        assert_eq!(
+            layout("abc", TextAlignment::Left),
+            layout("abc", TextAlignment::Start)
+        );
+        assert_eq!(
+            layout("abc", TextAlignment::Right),
+            layout("abc", TextAlignment::End)
+        );
+    }
+
+    #[test]
+    fn test_hit_test()
+    {
+        let block = noto_sans().layout_text("ab\ncd", 20.0, TextOptions::new());
+        let first_line = block.iter_lines().next().unwrap();
+        let glyph_b = first_line.iter_glyphs().nth(1).unwrap();
+
+        let hit = |x: f32, y: f32| {
+            block
+                .hit_test((x, y))
+                .map(|hit| (hit.user_index(), hit.is_trailing()))
+        };
+
+        let b_x = glyph_b.position_x();
+        let b_width = glyph_b.advance_width();
+
+        assert_eq!(Some((1, false)), hit(b_x + 1.0, 5.0));
+        assert_eq!(Some((1, true)), hit(b_x + b_width - 1.0, 5.0));
+        assert_eq!(Some((0, false)), hit(-100.0, -100.0));
+        assert_eq!(Some((1, true)), hit(1000.0, 5.0));
+        assert_eq!(Some((4, true)), hit(1000.0, 1000.0));
+
+        // Right-to-left glyphs are trailing on their left side
+        let rtl = noto_sans().layout_text("אב", 20.0, TextOptions::new());
+        assert_eq!(
+            Some(1),
+            rtl.hit_test((1.0, 5.0)).map(|hit| hit.user_index())
+        );
+        assert_eq!(
+            Some(true),
+            rtl.hit_test((1.0, 5.0)).map(|hit| hit.is_trailing())
+        );
+    }
+
+    #[test]
+    fn test_caret_position()
+    {
+        let block = noto_sans().layout_text("ab\ncd", 20.0, TextOptions::new());
+        let second_line = block.iter_lines().nth(1).unwrap();
+
+        let caret = block.caret_position(3).unwrap();
+        assert_eq!(1, caret.line_index());
+        assert_eq!(
+            Vec2::new(0.0, second_line.baseline_position()),
+            caret.position()
+        );
+        assert_eq!(second_line.height(), caret.height());
+
+        // The end of the text
+        let caret = block.caret_position(5).unwrap();
+        assert_eq!(1, caret.line_index());
+        assert_eq!(second_line.width(), caret.position().x);
+
+        // The start of right-to-left text is on the right
+        let rtl = noto_sans().layout_text("אב", 20.0, TextOptions::new());
+        assert_eq!(
+            Some(rtl.width()),
+            rtl.caret_position(0).map(|c| c.position().x)
+        );
+        assert_eq!(Some(0.0), rtl.caret_position(2).map(|c| c.position().x));
+    }
+
+    #[test]
+    fn test_selection_rects()
+    {
+        let font = noto_sans();
+
+        let block = font.layout_text("ab cd", 20.0, TextOptions::new());
+        let rects = block.selection_rects(1..4);
+        let glyphs: Vec<&FormattedGlyph> =
+            block.iter_lines().next().unwrap().iter_glyphs().collect();
+
+        assert_eq!(1, rects.len());
+        assert_eq!(glyphs[1].position_x(), rects[0].top_left().x);
+        assert_eq!(glyphs[4].position_x(), rects[0].bottom_right().x);
+        assert_eq!(block.height(), rects[0].height());
+
+        // Visually, the selection is split by the right-to-left word
+        let mixed = font.layout_text("abc אבג", 20.0, TextOptions::new());
+        assert_eq!(2, mixed.selection_rects(2..5).len());
+        assert_eq!(1, mixed.selection_rects(4..).len());
+        assert!(mixed.selection_rects(..0).is_empty());
+    }
+
+    #[test]
+    fn test_rich_text_spans()
+    {
+        let font = noto_sans();
+
+        let block = RichText::new()
+            .with_span(TextSpan::new("ab ", &font, 20.0))
+            .with_span(TextSpan::new("cd", &font, 40.0).with_color(Color::RED))
+            .with_span(TextSpan::new("", &font, 80.0))
+            .with_span(TextSpan::new("\ne", &font, 20.0))
+            .layout(TextOptions::new());
+
+        assert_eq!(vec![vec![0, 1, 2, 3, 4], vec![6]], user_indices(&block));
+
+        let lines: Vec<&FormattedTextLine> = block.iter_lines().collect();
+        let glyphs: Vec<&FormattedGlyph> = lines[0].iter_glyphs().collect();
+
+        assert_eq!(None, glyphs[0].color());
+        assert_eq!(Some(Color::RED), glyphs[3].color());
+
+        // The line is as tall as its largest span, with a shared baseline
+        assert_eq!(
+            font.layout_text("cd", 40.0, TextOptions::new()).height(),
+            lines[0].height()
+        );
+        assert_eq!(
+            glyphs[0].glyph().position().y,
+            glyphs[3].glyph().position().y
+        );
+        assert!(lines[1].height() < lines[0].height());
+    }
+
+    #[test]
+    fn test_rich_text_decorations()
+    {
+        let font = noto_sans();
+
+        let block = RichText::new()
+            .with_span(TextSpan::new("ab ", &font, 20.0).with_underline(true))
+            .with_span(
+                TextSpan::new("cd", &font, 20.0)
+                    .with_underline(true)
+                    .with_strikethrough(true)
+                    .with_color(Color::BLUE)
+            )
+            .layout(TextOptions::new());
+
+        let line = block.iter_lines().next().unwrap();
+        let glyphs: Vec<&FormattedGlyph> = line.iter_glyphs().collect();
+
+        let decorations: Vec<(TextDecorationKind, Option<Color>)> = line
+            .iter_decorations()
+            .map(|decoration| (decoration.kind(), decoration.color()))
+            .collect();
+
+        // Each span has its own underline
+        assert_eq!(
+            vec![
+                (TextDecorationKind::Underline, None),
+                (TextDecorationKind::Underline, Some(Color::BLUE)),
+                (TextDecorationKind::Strikethrough, Some(Color::BLUE))
+            ],
+            decorations
+        );
+
+        let underline = line.iter_decorations().next().unwrap().rect();
+        let strikethrough = line.iter_decorations().nth(2).unwrap().rect();
+        let baseline_y = glyphs[0].glyph().position().y;
+
+        assert_eq!(0.0, underline.top_left().x);
+        assert_eq!(glyphs[3].position_x(), underline.bottom_right().x);
+        assert!(underline.top_left().y > baseline_y);
+        assert!(strikethrough.bottom_right().y < baseline_y);
+    }
+
+    #[test]
+    fn test_text_decoration_metrics_from_font()
+    {
+        let font = noto_sans();
+
+        let data = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+        let face = ttf_parser::Face::from_slice(data, 0).unwrap();
+        let underline = face.underline_metrics().unwrap();
+        let factor = 40.0 / (f32::from(face.ascender()) - f32::from(face.descender()));
+
+        let metrics = font
+            .text_decoration_metrics(TextDecorationKind::Underline, 40.0)
+            .unwrap();
+
+        assert_eq!(-f32::from(underline.position) * factor, metrics.offset_y());
+        assert_eq!(f32::from(underline.thickness) * factor, metrics.thickness());
+
+        let block = font.layout_text(
+            "ab cd ef gh",
+            40.0,
+            TextOptions::new()
+                .with_wrap_to_width(100.0, TextAlignment::Left)
+                .with_underline(true)
+                .with_overline(true)
+        );
+
+        assert!(block.iter_lines().count() > 1);
+
+        // Every wrapped line is decorated, relative to its own baseline
+        for line in block.iter_lines() {
+            let baseline_y = line.iter_glyphs().next().unwrap().glyph().position().y;
+
+            let decorations: Vec<&TextDecoration> = line.iter_decorations().collect();
+            assert_eq!(2, decorations.len());
+
+            assert_eq!(TextDecorationKind::Underline, decorations[0].kind());
+            assert_eq!(
+                baseline_y + metrics.offset_y(),
+                decorations[0].rect().top_left().y
+            );
+
+            assert_eq!(TextDecorationKind::Overline, decorations[1].kind());
+            assert!(decorations[1].rect().bottom_right().y < baseline_y);
+        }
+    }
+
+    #[test]
+    fn test_justify()
+    {
+        let font = noto_sans();
+
+        let width = font
+            .layout_text("ab cd ef", 20.0, TextOptions::new())
+            .width();
+
+        let block = font.layout_text(
+            "ab cd ef gh\nij kl",
+            20.0,
+            TextOptions::new().with_wrap_to_width(width + 15.0, TextAlignment::Justify)
+        );
+
+        let lines: Vec<&FormattedTextLine> = block.iter_lines().collect();
+        let unjustified =
+            positions_x(&font.layout_text("ab cd ef", 20.0, TextOptions::new()));
+        let shifts: Vec<f32> = positions_x(&lines[0].as_block())
+            .iter()
+            .zip(unjustified)
+            .map(|(justified, unjustified)| {
+                ((justified - unjustified) * 100.0).round() / 100.0
+            })
+            .collect();
+
+        // The extra space is shared between the two gaps
+        assert_eq!(3, lines.len());
+        assert_eq!(vec![0.0, 0.0, 0.0, 7.5, 7.5, 7.5, 15.0, 15.0], shifts);
+
+        // The last line of each paragraph isn't justified
+        assert_eq!(
+            positions_x(&font.layout_text("ij kl", 20.0, TextOptions::new())),
+            positions_x(&lines[2].as_block())
+        );
+    }
+
+    #[test]
+    fn test_tab_stops()
+    {
+        let font = noto_sans();
+
+        let layout = |tab_stops: TabStops| {
+            positions_x(&font.layout_text(
+                "\ta\tbc\td\te",
+                20.0,
+                TextOptions::new().with_tab_stops(tab_stops)
+            ))
+        };
+
+        // Leading tabs are kept, and each tab advances to the next stop
+        let interval = layout(TabStops::Interval(50.0));
+        assert_eq!(50.0, interval[1]);
+        assert_eq!(100.0, interval[3]);
+        assert_eq!(150.0, interval[6]);
+        assert_eq!(200.0, interval[8]);
+
+        // After the last explicit stop, tabs take up their own width
+        let positions = layout(TabStops::Positions(vec![120.0, 30.0]));
+        assert_eq!(30.0, positions[1]);
+        assert_eq!(120.0, positions[3]);
+        assert!(positions[6] < 150.0);
+        assert!(positions[6] > positions[4]);
+
+        // Without tab stops, leading tabs are removed as before
+        let default = positions_x(&font.layout_text("\ta", 20.0, TextOptions::new()));
+        assert_eq!(vec![0.0], default);
+
+        // Without tab stops, tabs which the font has no glyph for are skipped
+        assert_eq!(
+            positions_x(&font.layout_text("ab", 20.0, TextOptions::new())),
+            positions_x(&font.layout_text("a\tb", 20.0, TextOptions::new()))
+        );
+    }
+
+    #[test]
+    fn test_paragraph_spacing_and_indent()
+    {
+        let font = noto_sans();
+
+        let width = font
+            .layout_text("ab ab ab", 20.0, TextOptions::new())
+            .width();
+
+        let layout = |options: TextOptions| {
+            font.layout_text(
+                "ab ab ab ab\ngh",
+                20.0,
+                options.with_wrap_to_width(width + 1.0, TextAlignment::Left)
+            )
+        };
+
+        let plain = layout(TextOptions::new());
+
+        let block = layout(
+            TextOptions::new()
+                .with_paragraph_spacing(10.0)
+                .with_first_line_indent(15.0)
+        );
+
+        let baselines = |block: &FormattedTextBlock| -> Vec<f32> {
+            block
+                .iter_lines()
+                .map(|line| line.baseline_position())
+                .collect()
+        };
+
+        let first_x = |block: &FormattedTextBlock| -> Vec<f32> {
+            block
+                .iter_lines()
+                .map(|line| line.iter_glyphs().next().unwrap().position_x())
+                .collect()
+        };
+
+        // The indent makes the first line wrap earlier
+        assert_eq!(
+            vec![vec![0, 1, 2, 3, 4, 5, 6, 7], vec![9, 10], vec![12, 13]],
+            user_indices(&plain)
+        );
+        assert_eq!(
+            vec![vec![0, 1, 2, 3, 4, 5], vec![6, 7, 8, 9, 10], vec![12, 13]],
+            user_indices(&block)
+        );
+
+        assert_eq!(vec![15.0, 0.0, 15.0], first_x(&block));
+
+        let plain_baselines = baselines(&plain);
+        let spaced_baselines = baselines(&block);
+        let line_advance = plain_baselines[1] - plain_baselines[0];
+
+        assert_eq!(line_advance, spaced_baselines[1] - spaced_baselines[0]);
+        assert_eq!(
+            line_advance + 10.0,
+            spaced_baselines[2] - spaced_baselines[1]
+        );
+        assert_eq!(plain.height() + 10.0, block.height());
+    }
+
+    #[test]
+    fn test_position_in_rect()
+    {
+        let font = noto_sans();
+
+        let block = font.layout_text("ab\ncd", 20.0, TextOptions::new());
+        let ascent = block.iter_lines().next().unwrap().ascent();
+        let size = block.size();
+
+        let rect = Rect::from_tuples((10.0, 20.0), (210.0, 120.0));
+
+        let position = |h_align: TextAlignment, v_align: VerticalAlignment| {
+            block.position_in_rect(&rect, h_align, v_align)
+        };
+
+        assert_eq!(
+            Vec2::new(10.0, 20.0),
+            position(TextAlignment::Left, VerticalAlignment::Top)
+        );
+        assert_eq!(
+            Vec2::new(110.0 - size.x / 2.0, 70.0 - size.y / 2.0),
+            position(TextAlignment::Center, VerticalAlignment::Middle)
+        );
+        assert_eq!(
+            Vec2::new(210.0 - size.x, 120.0 - size.y),
+            position(TextAlignment::Right, VerticalAlignment::Bottom)
+        );
+        assert_eq!(
+            Vec2::new(10.0, 120.0 - ascent),
+            position(TextAlignment::Start, VerticalAlignment::Baseline)
+        );
+
+        assert!(!block.overflows_rect(
+            &rect,
+            TextAlignment::Center,
+            VerticalAlignment::Middle
+        ));
+
+        // Only the descent of the first line may extend below the rectangle
+        let label = font.layout_text("ab", 20.0, TextOptions::new());
+        assert!(!label.overflows_rect(
+            &rect,
+            TextAlignment::Left,
+            VerticalAlignment::Baseline
+        ));
+        assert!(block.overflows_rect(
+            &rect,
+            TextAlignment::Left,
+            VerticalAlignment::Baseline
+        ));
+
+        let small = Rect::from_tuples((10.0, 20.0), (20.0, 120.0));
+        assert!(block.overflows_rect(
+            &small,
+            TextAlignment::Center,
+            VerticalAlignment::Middle
+        ));
+    }
+
+    #[test]
+    fn test_unicode_line_breaking()
+    {
+        let font = noto_sans();
+
+        let width = font.layout_text("ab cd-", 20.0, TextOptions::new()).width();
+
+        let layout = |line_breaking: LineBreaking| {
+            user_indices(
+                &font.layout_text(
+                    "ab cd-ef",
+                    20.0,
+                    TextOptions::new()
+                        .with_wrap_to_width(width + 1.0, TextAlignment::Left)
+                        .with_line_breaking(line_breaking)
+                )
+            )
+        };
+
+        assert_eq!(
+            vec![vec![0, 1, 2], vec![3, 4, 5, 6, 7]],
+            layout(LineBreaking::Whitespace)
+        );
+        assert_eq!(
+            vec![vec![0, 1, 2, 3, 4, 5], vec![6, 7]],
+            layout(LineBreaking::Unicode)
+        );
+    }
+
+    #[test]
+    fn test_max_lines()
+    {
+        let font = noto_sans();
+
+        let block =
+            font.layout_text("ab\ncd\nef", 20.0, TextOptions::new().with_max_lines(2));
+
+        assert!(block.is_truncated());
+        assert_eq!(vec![vec![0, 1], vec![3, 4, 6]], user_indices(&block));
+        assert_eq!(
+            font.layout_text("ab\ncd…", 20.0, TextOptions::new()).size(),
+            block.size()
+        );
+
+        let complete =
+            font.layout_text("ab\ncd", 20.0, TextOptions::new().with_max_lines(2));
+
+        assert!(!complete.is_truncated());
+        assert_eq!(vec![vec![0, 1], vec![3, 4]], user_indices(&complete));
+
+        let no_lines = font.layout_text("ab", 20.0, TextOptions::new().with_max_lines(0));
+
+        assert!(no_lines.is_truncated());
+        assert_eq!(0, no_lines.iter_lines().len());
+
+        // Empty lines at the end are omitted without an ellipsis
+        for text in ["abc\n", "abc\n\n", "abc\n \n"] {
+            let block =
+                font.layout_text(text, 20.0, TextOptions::new().with_max_lines(1));
+
+            assert!(!block.is_truncated());
+            assert_eq!(vec![vec![0, 1, 2]], user_indices(&block));
+        }
+
+        let hidden_text =
+            font.layout_text("abc\n\nd", 20.0, TextOptions::new().with_max_lines(1));
+
+        assert!(hidden_text.is_truncated());
+        assert_eq!(vec![vec![0, 1, 2, 5]], user_indices(&hidden_text));
+    }
+
+    #[test]
+    fn test_max_lines_ellipsis_fits_width()
+    {
+        let font = noto_sans();
+
+        let width = font
+            .layout_text("abc def", 20.0, TextOptions::new())
+            .width();
+
+        let block = font.layout_text(
+            "abc def ghi",
+            20.0,
+            TextOptions::new()
+                .with_wrap_to_width(width, TextAlignment::Left)
+                .with_max_lines(1)
+                .with_ellipsis("...")
+        );
+
+        // Glyphs are removed to make room for the ellipsis, which has the
+        // index of the first hidden codepoint
+        assert!(block.is_truncated());
+        assert_eq!(vec![vec![0, 1, 2, 3, 4, 5, 5, 5]], user_indices(&block));
+        assert!(block.width() <= width);
+
+        let no_ellipsis = font.layout_text(
+            "abc def ghi",
+            20.0,
+            TextOptions::new()
+                .with_wrap_to_width(width, TextAlignment::Left)
+                .with_max_lines(1)
+                .with_ellipsis("")
+        );
+
+        assert_eq!(vec![vec![0, 1, 2, 3, 4, 5, 6]], user_indices(&no_ellipsis));
+    }
+
+    /// Wraps the font in a collection containing it `count` times, with each
+    /// table directory pointing at the same copy of the original file.
+    fn font_collection(font: &[u8], count: u32) -> Vec<u8>
+    {
+        let table_count = u16::from_be_bytes([font[4], font[5]]) as usize;
+        let directory_size = 12 + 16 * table_count;
+        let font_offset = 12 + 4 * count as usize + count as usize * directory_size;
+
+        let mut result = b"ttcf\x00\x01\x00\x00".to_vec();
+        result.extend_from_slice(&count.to_be_bytes());
+
+        for i in 0..count as usize {
+            let offset = 12 + 4 * count as usize + i * directory_size;
+            result.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+
+        for _ in 0..count {
+            result.extend_from_slice(&font[..12]);
+
+            for table in font[12..directory_size].chunks_exact(16) {
+                let offset = u32::from_be_bytes(table[8..12].try_into().unwrap());
+                result.extend_from_slice(&table[..8]);
+                result.extend_from_slice(&(offset + font_offset as u32).to_be_bytes());
+                result.extend_from_slice(&table[12..]);
+            }
+        }
+
+        result.extend_from_slice(font);
+        result
+    }
+
+    #[test]
+    fn test_missing_glyph()
+    {
+        let font = noto_sans();
+
+        let glyph_ids = |options: TextOptions| -> Vec<u16> {
+            font.layout_text("a\u{65E5}b", 20.0, options)
+                .iter_lines()
+                .flat_map(|line| line.iter_glyphs())
+                .map(|glyph| glyph.glyph().id().0)
+                .collect()
+        };
+
+        let replacement = glyph_ids(TextOptions::new());
+        let tofu = glyph_ids(TextOptions::new().with_missing_glyph(MissingGlyph::Tofu));
+        let skip = glyph_ids(TextOptions::new().with_missing_glyph(MissingGlyph::Skip));
+
+        assert_eq!(3, replacement.len());
+        assert_ne!(0, replacement[1]);
+        assert_eq!(vec![replacement[0], 0, replacement[2]], tofu);
+        assert_eq!(vec![replacement[0], replacement[2]], skip);
+
+        let missing = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let missing_clone = missing.clone();
+
+        let _ = glyph_ids(TextOptions::new().with_missing_glyph_callback(
+            move |codepoint| missing_clone.lock().unwrap().push(codepoint)
+        ));
+
+        assert_eq!(vec!['\u{65E5}'], *missing.lock().unwrap());
+    }
+
+    #[test]
+    fn test_font_collection()
+    {
+        let bytes = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+        let collection = font_collection(bytes, 2);
+
+        assert_eq!(1, Font::collection_size(bytes));
+        assert_eq!(2, Font::collection_size(&collection));
+
+        assert!(Font::new_from_collection(bytes, 0).is_ok());
+        assert!(Font::new_from_collection(bytes, 1).is_err());
+        assert!(Font::new_from_collection(&collection, 2).is_err());
+
+        let width =
+            |font: &Font| font.layout_text("abc", 20.0, TextOptions::new()).width();
+
+        assert_eq!(
+            width(&noto_sans()),
+            width(&Font::new_from_collection(&collection, 1).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_font_variations()
+    {
+        // Noto Sans Regular is not a variable font
+        let font = noto_sans();
+
+        assert_eq!(Vec::<FontVariationAxis>::new(), font.variation_axes());
+        assert!(font
+            .with_variations(&[FontVariation::new(b"wght", 700.0)])
+            .is_err());
+
+        let instance = font.with_variations(&[]).unwrap();
+
+        assert_ne!(font, instance);
+        assert_eq!(
+            font.layout_text("abc", 20.0, TextOptions::new()).width(),
+            instance
+                .layout_text("abc", 20.0, TextOptions::new())
+                .width()
+        );
+
+        let font =
+            Font::new(include_bytes!("../test/assets/test_fonts/variable.ttf")).unwrap();
+
+        assert_eq!(
+            vec![FontVariationAxis {
+                tag: *b"wght",
+                min_value: 100.0,
+                default_value: 400.0,
+                max_value: 900.0
+            }],
+            font.variation_axes()
+        );
+
+        let bold = font
+            .with_variations(&[FontVariation::new(b"wght", 900.0)])
+            .unwrap();
+
+        // The instance shares the bytes of the font file
+        assert!(Arc::ptr_eq(&font.data, &bold.data));
+
+        // The glyph is widened at the maximum weight
+        let horizontal_bounds = |font: &Font| {
+            let bounds = font
+                .font()
+                .glyph('A')
+                .scaled(Scale::uniform(100.0))
+                .positioned(rusttype::point(0.0, 0.0))
+                .pixel_bounding_box()
+                .unwrap();
+
+            (bounds.min.x, bounds.max.x)
+        };
+
+        assert_eq!((10, 50), horizontal_bounds(&font));
+        assert_eq!((10, 90), horizontal_bounds(&bold));
+    }
+
+    #[test]
+    fn test_bitmap_font_layout()
+    {
+        // The page images are only needed for drawing
+        let font = BitmapFont {
+            id: FONT_ID_GENERATOR.fetch_add(1, Ordering::SeqCst),
+            data: Arc::new(
+                BitmapFontData::parse(include_bytes!(
+                    "../test/assets/test_images/pixel_font.fnt"
+                ))
+                .unwrap()
+            ),
+            pages: Rc::new(Vec::new())
+        };
+
+        // Twice the size of the page image, with kerning between "A" and "V"
+        let block = font.layout_text("AV A", 18.0, TextOptions::new());
+
+        assert_eq!(vec![0.0, 10.0, 22.0, 30.0], positions_x(&block));
+        assert_eq!(18.0, block.height());
+        assert_eq!(42.0, block.width());
+
+        let glyph = block
+            .iter_lines()
+            .next()
+            .unwrap()
+            .iter_glyphs()
+            .next()
+            .unwrap();
+
+        assert_eq!(12.0, glyph.advance_width());
+        assert_eq!(
+            Some(Rect::from_tuples((0.0, 2.0), (10.0, 16.0))),
+            glyph.pixel_bounding_box()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "text-shaping")]
+    fn test_shaping_combining_mark()
+    {
+        let block = noto_sans().layout_text_from_unindexed_codepoints(
+            &['e', '\u{0301}', 'x'],
+            20.0,
+            TextOptions::new()
+        );
+
+        // The shaper composes the mark with its base
+        assert_eq!(vec![vec![0, 2]], user_indices(&block));
+    }
+
+    #[test]
+    #[cfg(feature = "text-shaping")]
+    fn test_shaping_long_word_split()
+    {
+        let font = noto_sans();
+
+        let unwrapped = font.layout_text("xxxxxx", 20.0, TextOptions::new());
+
+        let block = font.layout_text(
+            "xxxxxx",
+            20.0,
+            TextOptions::new()
+                .with_wrap_to_width(unwrapped.width() * 0.55, TextAlignment::Left)
+        );
+
+        assert_eq!(vec![vec![0, 1, 2], vec![3, 4, 5]], user_indices(&block));
     }
 }
@@ -15,26 +15,44 @@
  */
 
 use std::collections::hash_map::Entry;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::convert::TryInto;
 use std::fmt::{Display, Formatter};
 use std::ops::Div;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 
+use crate::bitmap_font::PositionedBitmapGlyph;
 use crate::color::Color;
 use crate::dimen::{IVec2, UVec2, Vec2};
 use crate::error::{BacktraceError, Context, ErrorMessage};
+use crate::font::{
+    BitmapFont,
+    FontId,
+    GlyphCacheOptions,
+    GlyphCacheStats,
+    PositionedFontGlyph,
+    TextRenderingMode
+};
 use crate::glwrapper::{
     GLContextManager,
     GLTexture,
     GLTextureImageFormatU8,
     GLTextureSmoothing
 };
+use crate::image::ImageHandle;
 use crate::numeric::RoundFloat;
 use crate::renderer2d::{Renderer2DAction, Renderer2DVertex};
 use crate::shape::Rectangle;
 use crate::texture_packer::{TexturePacker, TexturePackerError};
-use crate::{font, Rect};
+use crate::{distance_field, font, Rect};
+
+/// The scale at which glyphs are rasterized into signed distance fields.
+const SDF_GLYPH_SCALE: f32 = 64.0;
+
+/// The distance from the outline of a glyph, in pixels at `SDF_GLYPH_SCALE`,
+/// over which the values of its signed distance field vary. This limits the
+/// size of outlines, shadows and glows to a quarter of the font size.
+const SDF_SPREAD: f32 = 16.0;
 
 #[repr(transparent)]
 #[derive(Debug, Hash, Eq, PartialEq, Clone)]
@@ -69,7 +87,11 @@ struct GlyphCacheKey
     subpixel_offset: (QuantizedDimension, QuantizedDimension),
 
     scale: QuantizedDimension,
-    glyph_id: rusttype::GlyphId
+    glyph_id: rusttype::GlyphId,
+
+    /// The RGB components of the text color, for color glyphs with layers
+    /// drawn in that color.
+    foreground: Option<[u8; 3]>
 }
 
 impl GlyphCacheKey
@@ -98,18 +120,196 @@ impl GlyphCacheKey
             font_id,
             subpixel_offset,
             scale,
-            glyph_id: positioned_glyph.id()
+            glyph_id: positioned_glyph.id(),
+            foreground: None
+        }
+    }
+
+    /// Distance fields are independent of the size and position at which
+    /// the glyph is drawn, so only one is needed for each glyph.
+    #[inline]
+    fn for_distance_field(
+        font_id: usize,
+        positioned_glyph: &rusttype::PositionedGlyph
+    ) -> Self
+    {
+        GlyphCacheKey {
+            font_id,
+            subpixel_offset: (
+                QuantizedDimension::from_pixels(0.0),
+                QuantizedDimension::from_pixels(0.0)
+            ),
+            scale: QuantizedDimension::from_pixels(SDF_GLYPH_SCALE),
+            glyph_id: positioned_glyph.id(),
+            foreground: None
+        }
+    }
+
+    /// Color glyphs with layers drawn in the text color are cached separately
+    /// for each color.
+    #[inline]
+    fn with_foreground(
+        mut self,
+        formatted_glyph: &font::FormattedGlyph,
+        color: Color
+    ) -> Self
+    {
+        let uses_foreground = match formatted_glyph.color_glyphs() {
+            None => false,
+            Some(color_glyphs) => color_glyphs.uses_foreground(self.glyph_id.0)
+        };
+
+        if uses_foreground {
+            self.foreground = Some(
+                [color.r(), color.g(), color.b()]
+                    .map(|value| (value.clamp(0.0, 1.0) * 255.0).round() as u8)
+            );
+        }
+
+        self
+    }
+
+    /// The color in which layers using the foreground color are rasterized.
+    #[inline]
+    fn foreground_color(&self) -> Color
+    {
+        match self.foreground {
+            None => Color::BLACK,
+            Some([r, g, b]) => Color::from_int_rgb(r, g, b)
+        }
+    }
+}
+
+/// How a glyph is drawn using the cache.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum GlyphRendering
+{
+    Bitmap,
+
+    /// All distances are in pixels at the scale of the text.
+    DistanceField
+    {
+        /// The factor by which the glyph will be scaled by the transform after
+        /// the actions are generated.
+        pixel_scale: f32,
+
+        /// The distance by which the outline of the glyph is moved outwards.
+        dilation: f32,
+
+        /// The width over which the edge of the glyph fades out, in addition
+        /// to the width of one pixel needed for anti-aliasing.
+        blur: f32
+    }
+}
+
+impl GlyphRendering
+{
+    /// Returns the start and end of the range of distance field values over
+    /// which the glyph fades in.
+    fn distance_field_edge(&self, glyph_scale: f32) -> Vec2
+    {
+        match self {
+            GlyphRendering::Bitmap => Vec2::ZERO,
+
+            GlyphRendering::DistanceField {
+                pixel_scale,
+                dilation,
+                blur
+            } => {
+                let value_per_pixel = SDF_GLYPH_SCALE / (2.0 * SDF_SPREAD * glyph_scale);
+
+                let center = 0.5 - dilation * value_per_pixel;
+
+                let half_width =
+                    (0.5 * value_per_pixel * (blur + 1.0 / pixel_scale)).clamp(0.0, 0.5);
+
+                Vec2::new(center - half_width, center + half_width)
+            }
         }
     }
 }
 
 pub(crate) struct GlyphCache
 {
-    last_frame: HashSet<GlyphCacheKey>,
-    this_frame: HashSet<GlyphCacheKey>,
+    bitmaps: GlyphAtlas,
+    distance_fields: GlyphAtlas,
+    /// The page images of the bitmap fonts created using this renderer. These
+    /// are released when the font is dropped.
+    bitmap_font_pages: HashMap<FontId, Weak<Vec<ImageHandle>>>
+}
 
-    cache_entries: HashMap<GlyphCacheKey, GlyphCacheEntry>,
-    textures: Vec<GlyphCacheTexture>
+/// Generates the actions to draw the specified area of the texture, cropped to
+/// the crop window if one is given. The vertices are created by `vertex`
+/// from their screen positions and texture coordinates.
+fn textured_quad_actions(
+    texture: &GLTexture,
+    mut screen_region: Rect,
+    mut texture_region: Rect,
+    crop_window: Option<&Rect>,
+    vertex: impl Fn(Vec2, Vec2) -> Renderer2DVertex,
+    runner: &mut impl FnMut(Renderer2DAction)
+)
+{
+    if let Some(crop_window) = crop_window {
+        if let Some(screen_intersection) = screen_region.intersect(crop_window) {
+            let left_diff = (screen_intersection.top_left().x
+                - screen_region.top_left().x)
+                / screen_region.width();
+            let right_diff = (screen_region.bottom_right().x
+                - screen_intersection.bottom_right().x)
+                / screen_region.width();
+
+            let top_diff = (screen_intersection.top_left().y
+                - screen_region.top_left().y)
+                / screen_region.height();
+            let bottom_diff = (screen_region.bottom_right().y
+                - screen_intersection.bottom_right().y)
+                / screen_region.height();
+
+            texture_region = Rectangle::new(
+                texture_region.top_left()
+                    + Vec2::new(
+                        texture_region.width() * left_diff,
+                        texture_region.height() * top_diff
+                    ),
+                texture_region.bottom_right()
+                    - Vec2::new(
+                        texture_region.width() * right_diff,
+                        texture_region.height() * bottom_diff
+                    )
+            );
+
+            screen_region = screen_intersection;
+        } else {
+            return;
+        }
+    }
+
+    runner(Renderer2DAction {
+        texture: Some(texture.clone()),
+        shader: None,
+        vertices_clockwise: [
+            vertex(*screen_region.top_left(), *texture_region.top_left()),
+            vertex(screen_region.top_right(), texture_region.top_right()),
+            vertex(
+                *screen_region.bottom_right(),
+                *texture_region.bottom_right()
+            )
+        ]
+    });
+
+    runner(Renderer2DAction {
+        texture: Some(texture.clone()),
+        shader: None,
+        vertices_clockwise: [
+            vertex(
+                *screen_region.bottom_right(),
+                *texture_region.bottom_right()
+            ),
+            vertex(screen_region.bottom_left(), texture_region.bottom_left()),
+            vertex(*screen_region.top_left(), *texture_region.top_left())
+        ]
+    });
 }
 
 impl GlyphCache
@@ -121,25 +321,56 @@ impl GlyphCache
         position: Vec2,
         color: Color,
         crop_window: Option<&Rect>,
+        rendering: &GlyphRendering,
         runner: &mut impl FnMut(Renderer2DAction)
     )
     {
-        let positioned_glyph = glyph.glyph();
+        let positioned_glyph = match glyph.glyph() {
+            PositionedFontGlyph::Outline(positioned_glyph, _) => positioned_glyph,
+            PositionedFontGlyph::Bitmap(bitmap_glyph) => {
+                return self.get_bitmap_glyph_actions(
+                    glyph.font_id(),
+                    bitmap_glyph,
+                    position,
+                    color,
+                    crop_window,
+                    rendering,
+                    runner
+                );
+            }
+        };
+
+        let (atlas, key) = match rendering {
+            GlyphRendering::Bitmap => (
+                &self.bitmaps,
+                GlyphCacheKey::from(glyph.font_id(), positioned_glyph, position)
+            ),
+            GlyphRendering::DistanceField { .. } => (
+                &self.distance_fields,
+                GlyphCacheKey::for_distance_field(glyph.font_id(), positioned_glyph)
+            )
+        };
 
-        let key = GlyphCacheKey::from(glyph.font_id(), positioned_glyph, position);
+        let key = key.with_foreground(glyph, color);
 
-        let entry = match self.cache_entries.get(&key) {
+        let entry = match atlas.cache_entries.get(&key) {
             None => return, // This is valid for many glyphs, e.g. space
             Some(entry) => entry
         };
 
-        let texture_cache = self.textures.get(entry.texture_id.unwrap()).unwrap();
+        let texture_cache = atlas.textures.get(entry.texture_id.unwrap()).unwrap();
+
+        // Color glyphs are drawn in their own colors, with the specified opacity
+        let color = match entry.is_color {
+            false => color,
+            true => Color::from_rgba(1.0, 1.0, 1.0, color.a())
+        };
 
         let texture_entry = texture_cache.entries.get(&key).unwrap();
 
-        let texture_size = GlyphCacheTexture::SIZE as f32;
+        let texture_size = texture_cache.size as f32;
 
-        let mut texture_region = Rectangle::new(
+        let texture_region = Rectangle::new(
             texture_entry
                 .texture_area
                 .top_left()
@@ -154,171 +385,202 @@ impl GlyphCache
 
         let position = position + Vec2::from(positioned_glyph.position());
 
-        // We round the position here as the offset is between -0.5 and 0.5
-        let screen_region_start = position.round().into_i32() + entry.bounding_box_offset;
+        let (screen_region, sdf_mix, sdf_edge) = match rendering {
+            GlyphRendering::Bitmap => {
+                // We round the position here as the offset is between -0.5 and 0.5
+                let screen_region_start =
+                    position.round().into_i32() + entry.bounding_box_offset;
 
-        let mut screen_region = Rectangle::new(
-            screen_region_start,
-            screen_region_start + texture_entry.texture_area.size().into_i32()
-        )
-        .into_f32();
-
-        if let Some(crop_window) = crop_window {
-            if let Some(screen_intersection) = screen_region.intersect(crop_window) {
-                let left_diff = (screen_intersection.top_left().x
-                    - screen_region.top_left().x)
-                    / screen_region.width();
-                let right_diff = (screen_region.bottom_right().x
-                    - screen_intersection.bottom_right().x)
-                    / screen_region.width();
-
-                let top_diff = (screen_intersection.top_left().y
-                    - screen_region.top_left().y)
-                    / screen_region.height();
-                let bottom_diff = (screen_region.bottom_right().y
-                    - screen_intersection.bottom_right().y)
-                    / screen_region.height();
-
-                texture_region = Rectangle::new(
-                    texture_region.top_left()
-                        + Vec2::new(
-                            texture_region.width() * left_diff,
-                            texture_region.height() * top_diff
-                        ),
-                    texture_region.bottom_right()
-                        - Vec2::new(
-                            texture_region.width() * right_diff,
-                            texture_region.height() * bottom_diff
-                        )
+                let screen_region = Rectangle::new(
+                    screen_region_start,
+                    screen_region_start + texture_entry.texture_area.size().into_i32()
+                )
+                .into_f32();
+
+                (screen_region, 0.0, Vec2::ZERO)
+            }
+
+            GlyphRendering::DistanceField { .. } => {
+                let ratio = positioned_glyph.scale().y / SDF_GLYPH_SCALE;
+
+                let screen_region_start =
+                    position + entry.bounding_box_offset.into_f32() * ratio;
+
+                let screen_region = Rectangle::new(
+                    screen_region_start,
+                    screen_region_start
+                        + texture_entry.texture_area.size().into_f32() * ratio
                 );
 
-                screen_region = screen_intersection;
-            } else {
+                if entry.is_color {
+                    (screen_region, 0.0, Vec2::ZERO)
+                } else {
+                    (
+                        screen_region,
+                        1.0,
+                        rendering.distance_field_edge(positioned_glyph.scale().y)
+                    )
+                }
+            }
+        };
+
+        textured_quad_actions(
+            &texture_cache.texture,
+            screen_region,
+            texture_region,
+            crop_window,
+            |position, texture_coord| Renderer2DVertex {
+                position,
+                texture_coord,
+                color,
+                texture_mix: 1.0,
+                circle_mix: 0.0,
+                gradient_coord: Vec2::ZERO,
+                linear_gradient_mix: 0.0,
+                radial_gradient_mix: 0.0,
+                sdf_mix,
+                sdf_edge,
+                coverage_mix: 1.0
+            },
+            runner
+        );
+    }
+
+    /// Draws a glyph of a bitmap font directly from its page image.
+    #[allow(clippy::too_many_arguments)]
+    fn get_bitmap_glyph_actions(
+        &self,
+        font_id: FontId,
+        glyph: &PositionedBitmapGlyph,
+        position: Vec2,
+        color: Color,
+        crop_window: Option<&Rect>,
+        rendering: &GlyphRendering,
+        runner: &mut impl FnMut(Renderer2DAction)
+    )
+    {
+        // Bitmap fonts have no distance fields to dilate or blur
+        if let GlyphRendering::DistanceField { dilation, blur, .. } = rendering {
+            if *dilation != 0.0 || *blur != 0.0 {
                 return;
             }
         }
 
-        runner(Renderer2DAction {
-            texture: Some(texture_cache.texture.clone()),
-            vertices_clockwise: [
-                Renderer2DVertex {
-                    position: *screen_region.top_left(),
-                    texture_coord: *texture_region.top_left(),
-                    color,
-                    texture_mix: 1.0,
-                    circle_mix: 0.0
-                },
-                Renderer2DVertex {
-                    position: screen_region.top_right(),
-                    texture_coord: texture_region.top_right(),
-                    color,
-                    texture_mix: 1.0,
-                    circle_mix: 0.0
-                },
-                Renderer2DVertex {
-                    position: *screen_region.bottom_right(),
-                    texture_coord: *texture_region.bottom_right(),
-                    color,
-                    texture_mix: 1.0,
-                    circle_mix: 0.0
-                }
-            ]
-        });
+        let pages = match self.bitmap_font_pages.get(&font_id).and_then(Weak::upgrade) {
+            None => {
+                log::error!(
+                    "Not drawing glyph: its bitmap font has been dropped, or was \
+                     created in a different context"
+                );
+                return;
+            }
+            Some(pages) => pages
+        };
 
-        runner(Renderer2DAction {
-            texture: Some(texture_cache.texture.clone()),
-            vertices_clockwise: [
-                Renderer2DVertex {
-                    position: *screen_region.bottom_right(),
-                    texture_coord: *texture_region.bottom_right(),
-                    color,
-                    texture_mix: 1.0,
-                    circle_mix: 0.0
-                },
-                Renderer2DVertex {
-                    position: screen_region.bottom_left(),
-                    texture_coord: texture_region.bottom_left(),
-                    color,
-                    texture_mix: 1.0,
-                    circle_mix: 0.0
-                },
-                Renderer2DVertex {
-                    position: *screen_region.top_left(),
-                    texture_coord: *texture_region.top_left(),
-                    color,
-                    texture_mix: 1.0,
-                    circle_mix: 0.0
-                }
-            ]
-        });
+        let page = &pages[glyph.glyph.page];
+
+        let mut screen_region = match glyph.bounding_box() {
+            None => return, // This is valid for many glyphs, e.g. space
+            Some(bounding_box) => bounding_box.with_offset(position)
+        };
+
+        if let GlyphRendering::Bitmap = rendering {
+            // Keep the pixels of the font aligned with the pixels of the screen
+            let top_left = screen_region.top_left().round();
+            screen_region = Rectangle::new(top_left, top_left + screen_region.size());
+        }
+
+        let page_size = page.size().into_f32();
+        let texture_area = glyph.glyph.texture_area.clone().into_f32();
+
+        let texture_region = Rectangle::new(
+            Vec2::new(
+                texture_area.top_left().x / page_size.x,
+                texture_area.top_left().y / page_size.y
+            ),
+            Vec2::new(
+                texture_area.bottom_right().x / page_size.x,
+                texture_area.bottom_right().y / page_size.y
+            )
+        );
+
+        textured_quad_actions(
+            &page.texture,
+            screen_region,
+            texture_region,
+            crop_window,
+            |position, texture_coord| Renderer2DVertex {
+                position,
+                texture_coord,
+                color,
+                texture_mix: 1.0,
+                circle_mix: 0.0,
+                gradient_coord: Vec2::ZERO,
+                linear_gradient_mix: 0.0,
+                radial_gradient_mix: 0.0,
+                sdf_mix: 0.0,
+                sdf_edge: Vec2::ZERO,
+                coverage_mix: 1.0
+            },
+            runner
+        );
+    }
+
+    pub(crate) fn add_bitmap_font(&mut self, font: &BitmapFont)
+    {
+        self.bitmap_font_pages
+            .retain(|_, pages| pages.strong_count() > 0);
+
+        self.bitmap_font_pages
+            .insert(font.id(), Rc::downgrade(font.pages()));
     }
 
     pub(crate) fn add_to_cache(
         &mut self,
         _context: &GLContextManager,
         formatted_glyph: &font::FormattedGlyph,
-        position: Vec2
+        position: Vec2,
+        color: Color,
+        rendering_mode: TextRenderingMode
     )
     {
-        let key = GlyphCacheKey::from(
-            formatted_glyph.font_id(),
-            formatted_glyph.glyph(),
-            position
-        );
-
-        self.this_frame.insert(key.clone());
-
-        let cache_entries = &mut self.cache_entries;
+        // The glyphs of bitmap fonts are drawn directly from their page images
+        let positioned_glyph = match formatted_glyph.glyph() {
+            PositionedFontGlyph::Outline(positioned_glyph, _) => positioned_glyph,
+            PositionedFontGlyph::Bitmap(_) => return
+        };
 
-        match cache_entries.entry(key.clone()) {
-            Entry::Occupied(_) => {
-                // Already in the cache, nothing to do
+        match rendering_mode {
+            TextRenderingMode::Bitmap => {
+                let key = GlyphCacheKey::from(
+                    formatted_glyph.font_id(),
+                    positioned_glyph,
+                    position
+                )
+                .with_foreground(formatted_glyph, color);
+
+                self.bitmaps.add_if_missing(key, |key| {
+                    GlyphCacheEntry::rasterize_bitmap(
+                        formatted_glyph,
+                        positioned_glyph,
+                        key
+                    )
+                });
             }
 
-            Entry::Vacant(entry) => {
-                let glyph = formatted_glyph
-                    .glyph()
-                    .unpositioned()
-                    .unscaled()
-                    .clone()
-                    .scaled(rusttype::Scale::uniform(key.scale.to_pixels()))
-                    .positioned(rusttype::point(
-                        key.subpixel_offset.0.to_pixels(),
-                        key.subpixel_offset.1.to_pixels()
-                    ));
-
-                let bounding_box = match glyph.pixel_bounding_box() {
-                    None => return, // This is valid for some glyphs, e.g. space
-                    Some(bounding_box) => bounding_box
-                };
-
-                let bounding_box_size =
-                    UVec2::new(bounding_box.width() as u32, bounding_box.height() as u32);
-
-                if bounding_box_size.x > GlyphCacheTexture::SIZE
-                    || bounding_box_size.y > GlyphCacheTexture::SIZE
-                {
-                    log::error!(
-                        "Glyph too big to render ({}x{}). Limit is {} px.",
-                        bounding_box_size.x,
-                        bounding_box_size.y,
-                        GlyphCacheTexture::SIZE
-                    );
-
-                    return;
-                }
-
-                let mut bitmap = BitmapRGBA::new(bounding_box_size);
-
-                bitmap.draw_glyph(&glyph);
-
-                entry.insert(GlyphCacheEntry {
-                    glyph_bitmap: Rc::new(bitmap),
-                    bounding_box_offset: IVec2::new(
-                        bounding_box.min.x,
-                        bounding_box.min.y
-                    ),
-                    texture_id: None
+            TextRenderingMode::SignedDistanceField => {
+                let key = GlyphCacheKey::for_distance_field(
+                    formatted_glyph.font_id(),
+                    positioned_glyph
+                )
+                .with_foreground(formatted_glyph, color);
+
+                self.distance_fields.add_if_missing(key, |key| {
+                    GlyphCacheEntry::rasterize_distance_field(
+                        formatted_glyph,
+                        positioned_glyph,
+                        key
+                    )
                 });
             }
         }
@@ -326,8 +588,11 @@ impl GlyphCache
 
     pub(crate) fn on_new_frame_start(&mut self)
     {
-        self.last_frame.clear();
-        std::mem::swap(&mut self.last_frame, &mut self.this_frame);
+        self.bitmaps.on_new_frame_start();
+        self.distance_fields.on_new_frame_start();
+
+        self.bitmap_font_pages
+            .retain(|_, pages| pages.strong_count() > 0);
     }
 
     pub(crate) fn prepare_for_draw(
@@ -335,163 +600,332 @@ impl GlyphCache
         context: &GLContextManager
     ) -> Result<(), BacktraceError<ErrorMessage>>
     {
-        if self.try_insert_pending().is_err() {
-            // Not enough space. Rearrange everything!
+        self.bitmaps.prepare_for_draw(context)?;
+        self.distance_fields.prepare_for_draw(context)
+    }
 
-            self.textures.iter_mut().for_each(|texture| texture.clear());
+    /// Replaces the options, which empties the cache and resets the
+    /// statistics.
+    pub(crate) fn set_options(&mut self, options: GlyphCacheOptions)
+    {
+        self.bitmaps =
+            GlyphAtlas::new(GLTextureSmoothing::NearestNeighbour, options.clone());
+        // Distance fields are interpolated when scaled
+        self.distance_fields = GlyphAtlas::new(GLTextureSmoothing::Linear, options);
+    }
 
-            let cache_entries = &mut self.cache_entries;
-            let last_frame = &self.last_frame;
-            let this_frame = &self.this_frame;
+    #[inline]
+    pub(crate) fn options(&self) -> &GlyphCacheOptions
+    {
+        &self.bitmaps.options
+    }
 
-            cache_entries
-                .iter_mut()
-                .for_each(|(_, entry)| entry.texture_id = None);
+    pub(crate) fn stats(&self) -> GlyphCacheStats
+    {
+        let mut stats = GlyphCacheStats::default();
+
+        for atlas in [&self.bitmaps, &self.distance_fields] {
+            stats.hits += atlas.stats.hits;
+            stats.misses += atlas.stats.misses;
+            stats.uploads += atlas.stats.uploads;
+            stats.evictions += atlas.stats.evictions;
+            stats.pages_in_use += atlas
+                .textures
+                .iter()
+                .filter(|texture| !texture.entries.is_empty())
+                .count();
+            stats.cached_glyphs += atlas.cache_entries.len();
+        }
 
-            cache_entries
-                .retain(|key, _| last_frame.contains(key) || this_frame.contains(key));
+        stats
+    }
 
-            // Sort entries by height
+    pub(crate) fn new() -> Self
+    {
+        let options = GlyphCacheOptions::new();
 
-            let mut all_entries: Vec<_> = cache_entries.iter_mut().collect();
+        Self {
+            bitmaps: GlyphAtlas::new(
+                GLTextureSmoothing::NearestNeighbour,
+                options.clone()
+            ),
+            // Distance fields are interpolated when scaled
+            distance_fields: GlyphAtlas::new(GLTextureSmoothing::Linear, options),
+            bitmap_font_pages: HashMap::new()
+        }
+    }
+}
 
-            all_entries.sort_unstable_by(|(_, a), (_, b)| {
-                b.glyph_bitmap.size.y.cmp(&a.glyph_bitmap.size.y)
-            });
+/// A set of rasterized glyphs, packed into one or more textures.
+struct GlyphAtlas
+{
+    cache_entries: HashMap<GlyphCacheKey, GlyphCacheEntry>,
+    textures: Vec<GlyphCacheTexture>,
 
-            // Insert in height order
+    smoothing: GLTextureSmoothing,
+    options: GlyphCacheOptions,
 
-            let mut cleared_textures = Vec::new();
-            std::mem::swap(&mut self.textures, &mut cleared_textures);
+    /// Incremented at the start of each frame, to find the glyphs which were
+    /// used least recently.
+    frame: u64,
 
-            cleared_textures
-                .iter_mut()
-                .for_each(|texture| texture.clear());
+    stats: GlyphCacheStats
+}
 
-            for (key, entry) in &mut all_entries {
-                let texture_id = GlyphCache::internal_rearrange_append_glyph(
-                    context,
-                    &mut self.textures,
-                    &mut cleared_textures,
-                    key,
-                    &entry.glyph_bitmap
-                )
-                .map_err(|err| {
-                    ErrorMessage::msg_with_cause("Glyph rearrangement failed", err)
-                })?;
+impl GlyphAtlas
+{
+    fn new(smoothing: GLTextureSmoothing, options: GlyphCacheOptions) -> Self
+    {
+        Self {
+            cache_entries: HashMap::new(),
+            textures: Vec::new(),
+            smoothing,
+            options,
+            frame: 0,
+            stats: GlyphCacheStats::default()
+        }
+    }
 
-                entry.texture_id = Some(texture_id);
+    fn add_if_missing<F>(&mut self, key: GlyphCacheKey, rasterize: F)
+    where
+        F: FnOnce(&GlyphCacheKey) -> Option<GlyphCacheEntry>
+    {
+        match self.cache_entries.entry(key) {
+            Entry::Occupied(mut entry) => {
+                entry.get_mut().last_used_frame = self.frame;
+                self.stats.hits += 1;
             }
 
-            // Delete all but one spare texture
-            if let Some(texture) = cleared_textures.pop() {
-                self.textures.push(texture);
+            Entry::Vacant(entry) => {
+                // This is None for some glyphs, e.g. space
+                if let Some(mut cache_entry) = rasterize(entry.key()) {
+                    let size = cache_entry.glyph_bitmap.size;
+                    let page_size = self.options.page_size();
+
+                    if size.x > page_size || size.y > page_size {
+                        log::error!(
+                            "Glyph too big to render ({}x{}). Limit is {} px.",
+                            size.x,
+                            size.y,
+                            page_size
+                        );
+
+                        return;
+                    }
+
+                    cache_entry.last_used_frame = self.frame;
+                    entry.insert(cache_entry);
+                    self.stats.misses += 1;
+                }
             }
         }
+    }
 
-        for texture in &mut self.textures {
-            texture
-                .revalidate(context)
-                .map_err(|err| err.context("Failed to revalidate texture"))?;
+    fn on_new_frame_start(&mut self)
+    {
+        self.frame += 1;
+
+        let eviction_frames = match self.options.eviction_frames() {
+            None => return,
+            Some(eviction_frames) => eviction_frames as u64
+        };
+
+        let frame = self.frame;
+        let count_before = self.cache_entries.len();
+
+        self.cache_entries
+            .retain(|_, entry| frame - entry.last_used_frame <= eviction_frames);
+
+        let evicted = count_before - self.cache_entries.len();
+
+        if evicted == 0 {
+            return;
         }
 
-        Ok(())
-    }
+        self.stats.evictions += evicted as u64;
 
-    pub(crate) fn new() -> Self
-    {
-        Self {
-            last_frame: HashSet::new(),
-            this_frame: HashSet::new(),
-            cache_entries: HashMap::new(),
-            textures: Vec::new()
+        let cache_entries = &self.cache_entries;
+
+        for texture in &mut self.textures {
+            if texture.entries.is_empty() {
+                continue;
+            }
+
+            texture
+                .entries
+                .retain(|key, _| cache_entries.contains_key(key));
+
+            // The page can only be reused once all its glyphs are gone
+            if texture.entries.is_empty() {
+                texture.clear();
+            }
         }
     }
 
-    fn try_insert_pending(&mut self) -> Result<(), GlyphCacheTextureAppendError>
+    fn prepare_for_draw(
+        &mut self,
+        context: &GLContextManager
+    ) -> Result<(), BacktraceError<ErrorMessage>>
     {
-        for (key, entry) in &mut self.cache_entries {
+        let mut pending: Vec<_> = self
+            .cache_entries
+            .iter_mut()
+            .filter(|(_, entry)| entry.texture_id.is_none())
+            .collect();
+
+        // Insert in height order
+        pending.sort_unstable_by(|(_, a), (_, b)| {
+            b.glyph_bitmap.size.y.cmp(&a.glyph_bitmap.size.y)
+        });
+
+        let mut out_of_space = false;
+
+        for (key, entry) in pending {
+            entry.texture_id = GlyphAtlas::append_glyph(
+                context,
+                &mut self.textures,
+                &self.smoothing,
+                self.options.page_size(),
+                self.options.max_pages(),
+                key,
+                &entry.glyph_bitmap
+            )?;
+
             if entry.texture_id.is_none() {
-                let texture_id = Self::try_append_to_existing_texture(
-                    &mut self.textures,
-                    key,
-                    &entry.glyph_bitmap
-                )?;
+                out_of_space = true;
+                break;
+            }
+        }
+
+        if out_of_space {
+            self.rearrange(context).map_err(|err| {
+                ErrorMessage::msg_with_cause("Glyph rearrangement failed", err)
+            })?;
+        }
 
-                entry.texture_id = Some(texture_id);
+        for texture in &mut self.textures {
+            if texture.invalidated {
+                self.stats.uploads += 1;
             }
+
+            texture
+                .revalidate(context)
+                .map_err(|err| err.context("Failed to revalidate texture"))?;
         }
 
         Ok(())
     }
 
-    fn try_append_to_existing_texture(
-        all_textures: &mut [GlyphCacheTexture],
-        key: &GlyphCacheKey,
-        glyph_bitmap: &Rc<BitmapRGBA>
-    ) -> Result<usize, GlyphCacheTextureAppendError>
+    /// Packs all the glyphs into the pages again, evicting the least recently
+    /// used glyphs which no longer fit. Glyphs which were used in the current
+    /// frame are never evicted, and extra pages are created for them if
+    /// needed.
+    fn rearrange(
+        &mut self,
+        context: &GLContextManager
+    ) -> Result<(), BacktraceError<ErrorMessage>>
     {
-        let mut last_error: GlyphCacheTextureAppendError =
-            GlyphCacheTextureAppendError::NotEnoughSpace;
+        self.textures.iter_mut().for_each(|texture| texture.clear());
+
+        let frame = self.frame;
+
+        let mut all_entries: Vec<_> = self.cache_entries.iter_mut().collect();
+
+        // Most recently used first, and then in height order
+        all_entries.sort_unstable_by(|(_, a), (_, b)| {
+            b.last_used_frame
+                .cmp(&a.last_used_frame)
+                .then(b.glyph_bitmap.size.y.cmp(&a.glyph_bitmap.size.y))
+        });
 
-        for (i, texture) in all_textures.iter_mut().enumerate() {
-            match texture.try_append_glyph(key, glyph_bitmap) {
-                Ok(_) => return Ok(i),
-                Err(err) => last_error = err
+        // Once a glyph doesn't fit, all the glyphs used less recently than it
+        // are evicted, even if they are small enough to fit
+        let mut evict_before = 0;
+
+        for (key, entry) in all_entries {
+            if entry.last_used_frame < evict_before {
+                entry.texture_id = None;
+                continue;
             }
+
+            let max_pages = match entry.last_used_frame == frame {
+                true => usize::MAX,
+                false => self.options.max_pages()
+            };
+
+            entry.texture_id = GlyphAtlas::append_glyph(
+                context,
+                &mut self.textures,
+                &self.smoothing,
+                self.options.page_size(),
+                max_pages,
+                key,
+                &entry.glyph_bitmap
+            )?;
+
+            if entry.texture_id.is_none() {
+                evict_before = entry.last_used_frame;
+            }
+        }
+
+        let count_before = self.cache_entries.len();
+
+        self.cache_entries
+            .retain(|_, entry| entry.texture_id.is_some());
+
+        self.stats.evictions += (count_before - self.cache_entries.len()) as u64;
+
+        // Delete the extra pages once they are no longer needed
+        while self.textures.len() > self.options.max_pages()
+            && self.textures.last().unwrap().entries.is_empty()
+        {
+            self.textures.pop();
         }
 
-        Err(last_error)
+        Ok(())
     }
 
-    fn internal_rearrange_append_glyph(
+    /// Appends the glyph to the first of the `max_pages` pages with enough
+    /// space, creating a new page if needed. Returns `None` if the glyph
+    /// doesn't fit.
+    #[allow(clippy::too_many_arguments)]
+    fn append_glyph(
         context: &GLContextManager,
-        current_textures: &mut Vec<GlyphCacheTexture>,
-        previous_textures: &mut Vec<GlyphCacheTexture>,
+        textures: &mut Vec<GlyphCacheTexture>,
+        smoothing: &GLTextureSmoothing,
+        page_size: u32,
+        max_pages: usize,
         key: &GlyphCacheKey,
         glyph_bitmap: &Rc<BitmapRGBA>
-    ) -> Result<usize, BacktraceError<ErrorMessage>>
+    ) -> Result<Option<usize>, BacktraceError<ErrorMessage>>
     {
-        for (i, texture) in current_textures.iter_mut().enumerate() {
+        for (i, texture) in textures.iter_mut().take(max_pages).enumerate() {
             if texture.try_append_glyph(key, glyph_bitmap).is_ok() {
-                return Ok(i);
+                return Ok(Some(i));
             }
         }
 
-        if !previous_textures.is_empty() {
-            current_textures.push(previous_textures.pop().unwrap());
-
-            if current_textures
-                .last_mut()
-                .unwrap()
-                .try_append_glyph(key, glyph_bitmap)
-                .is_ok()
-            {
-                return Ok(current_textures.len() - 1);
-            }
+        if textures.len() >= max_pages {
+            return Ok(None);
         }
 
         log::info!(
             "No more space in existing textures ({}). Creating new.",
-            current_textures.len()
+            textures.len()
         );
 
-        current_textures.push(match GlyphCacheTexture::new(context) {
-            Ok(texture) => texture,
-            Err(err) => {
-                return Err(ErrorMessage::msg_with_cause(
-                    "Failed to create new texture",
-                    err
-                ))
-            }
-        });
+        textures.push(
+            GlyphCacheTexture::new(context, smoothing.clone(), page_size).map_err(
+                |err| ErrorMessage::msg_with_cause("Failed to create new texture", err)
+            )?
+        );
 
-        match current_textures
+        match textures
             .last_mut()
             .unwrap()
             .try_append_glyph(key, glyph_bitmap)
         {
-            Ok(_) => Ok(current_textures.len() - 1),
+            Ok(_) => Ok(Some(textures.len() - 1)),
             Err(err) => Err(ErrorMessage::msg_with_cause(
                 "Internal bug: Could not append to new texture",
                 err
@@ -515,6 +949,18 @@ impl BitmapRGBA
         BitmapRGBA { data, size }
     }
 
+    /// Creates a white bitmap with the specified alpha value for each pixel.
+    fn from_alpha(size: UVec2, alpha: &[u8]) -> Self
+    {
+        let mut bitmap = BitmapRGBA::new(size);
+
+        for (pixel, alpha) in bitmap.data.chunks_exact_mut(4).zip(alpha) {
+            pixel.copy_from_slice(&[255, 255, 255, *alpha]);
+        }
+
+        bitmap
+    }
+
     fn clear(&mut self)
     {
         self.data.fill(0);
@@ -570,13 +1016,14 @@ impl BitmapRGBA
     fn upload_to_texture(
         &self,
         context: &GLContextManager,
-        texture: &GLTexture
+        texture: &GLTexture,
+        smoothing: GLTextureSmoothing
     ) -> Result<(), BacktraceError<ErrorMessage>>
     {
         texture.set_image_data(
             context,
             GLTextureImageFormatU8::RGBA,
-            GLTextureSmoothing::NearestNeighbour,
+            smoothing,
             &self.size,
             self.data.as_slice()
         )
@@ -588,7 +1035,130 @@ struct GlyphCacheEntry
 {
     glyph_bitmap: Rc<BitmapRGBA>,
     bounding_box_offset: IVec2,
-    texture_id: Option<usize>
+    texture_id: Option<usize>,
+    /// Color glyphs are stored as RGBA images rather than distance fields,
+    /// and are not tinted with the color of the text.
+    is_color: bool,
+    last_used_frame: u64
+}
+
+impl GlyphCacheEntry
+{
+    fn rasterize_bitmap(
+        formatted_glyph: &font::FormattedGlyph,
+        positioned_glyph: &rusttype::PositionedGlyph,
+        key: &GlyphCacheKey
+    ) -> Option<Self>
+    {
+        let glyph = positioned_glyph
+            .unpositioned()
+            .unscaled()
+            .clone()
+            .scaled(rusttype::Scale::uniform(key.scale.to_pixels()))
+            .positioned(rusttype::point(
+                key.subpixel_offset.0.to_pixels(),
+                key.subpixel_offset.1.to_pixels()
+            ));
+
+        if let Some(entry) =
+            GlyphCacheEntry::rasterize_color(formatted_glyph, &glyph, key)
+        {
+            return Some(entry);
+        }
+
+        // This is None for some glyphs, e.g. space
+        let bounding_box = glyph.pixel_bounding_box()?;
+
+        let bounding_box_size =
+            UVec2::new(bounding_box.width() as u32, bounding_box.height() as u32);
+
+        let mut bitmap = BitmapRGBA::new(bounding_box_size);
+
+        bitmap.draw_glyph(&glyph);
+
+        Some(GlyphCacheEntry {
+            glyph_bitmap: Rc::new(bitmap),
+            bounding_box_offset: IVec2::new(bounding_box.min.x, bounding_box.min.y),
+            texture_id: None,
+            is_color: false,
+            last_used_frame: 0
+        })
+    }
+
+    fn rasterize_color(
+        formatted_glyph: &font::FormattedGlyph,
+        glyph: &rusttype::PositionedGlyph,
+        key: &GlyphCacheKey
+    ) -> Option<Self>
+    {
+        let image = formatted_glyph
+            .color_glyphs()?
+            .rasterize(glyph, key.foreground_color())?;
+
+        Some(GlyphCacheEntry {
+            glyph_bitmap: Rc::new(BitmapRGBA {
+                data: image.data,
+                size: image.size
+            }),
+            bounding_box_offset: image.offset,
+            texture_id: None,
+            is_color: true,
+            last_used_frame: 0
+        })
+    }
+
+    /// Rasterizes the glyph at `SDF_GLYPH_SCALE`, with enough padding around
+    /// the outline to hold the full spread of the distance field.
+    fn rasterize_distance_field(
+        formatted_glyph: &font::FormattedGlyph,
+        positioned_glyph: &rusttype::PositionedGlyph,
+        key: &GlyphCacheKey
+    ) -> Option<Self>
+    {
+        let glyph = positioned_glyph
+            .unpositioned()
+            .unscaled()
+            .clone()
+            .scaled(rusttype::Scale::uniform(SDF_GLYPH_SCALE))
+            .positioned(rusttype::point(0.0, 0.0));
+
+        // Color glyphs are scaled without using a distance field
+        if let Some(entry) =
+            GlyphCacheEntry::rasterize_color(formatted_glyph, &glyph, key)
+        {
+            return Some(entry);
+        }
+
+        // This is None for some glyphs, e.g. space
+        let bounding_box = glyph.pixel_bounding_box()?;
+
+        let padding = SDF_SPREAD.ceil() as u32;
+
+        let size = UVec2::new(
+            bounding_box.width() as u32 + 2 * padding,
+            bounding_box.height() as u32 + 2 * padding
+        );
+
+        let mut coverage = vec![0.0; (size.x * size.y) as usize];
+
+        glyph.draw(|x, y, alpha| {
+            coverage[((y + padding) * size.x + x + padding) as usize] = alpha;
+        });
+
+        let field =
+            distance_field::coverage_to_distance_field(&coverage, size, SDF_SPREAD);
+
+        Some(GlyphCacheEntry {
+            glyph_bitmap: Rc::new(BitmapRGBA::from_alpha(size, &field)),
+            bounding_box_offset: IVec2::new(
+                bounding_box.min.x - padding as i32,
+                bounding_box.min.y - padding as i32
+            ),
+            texture_id: None,
+            is_color: false,
+            last_used_frame: 0
+        })
+    }
 }
 
 struct GlyphTextureCacheEntry
@@ -601,6 +1171,8 @@ struct GlyphCacheTexture
     bitmap: BitmapRGBA,
     texture: GLTexture,
     invalidated: bool,
+    smoothing: GLTextureSmoothing,
+    size: u32,
 
     packer: TexturePacker,
 
@@ -641,23 +1213,24 @@ impl From<TexturePackerError> for GlyphCacheTextureAppendError
 
 impl GlyphCacheTexture
 {
-    const SIZE: u32 = 1024;
-
-    fn new(context: &GLContextManager) -> Result<Self, BacktraceError<ErrorMessage>>
+    fn new(
+        context: &GLContextManager,
+        smoothing: GLTextureSmoothing,
+        size: u32
+    ) -> Result<Self, BacktraceError<ErrorMessage>>
     {
         Ok(GlyphCacheTexture {
-            bitmap: BitmapRGBA::new(UVec2::new(
-                GlyphCacheTexture::SIZE,
-                GlyphCacheTexture::SIZE
-            )),
+            bitmap: BitmapRGBA::new(UVec2::new(size, size)),
 
             texture: context
                 .new_texture()
                 .context("GPU texture creation failed")?,
 
             invalidated: false,
+            smoothing,
+            size,
 
-            packer: TexturePacker::new(GlyphCacheTexture::SIZE, GlyphCacheTexture::SIZE),
+            packer: TexturePacker::new(size, size),
 
             entries: HashMap::new()
         })
@@ -667,8 +1240,7 @@ impl GlyphCacheTexture
     {
         self.invalidated = false;
 
-        self.packer =
-            TexturePacker::new(GlyphCacheTexture::SIZE, GlyphCacheTexture::SIZE);
+        self.packer = TexturePacker::new(self.size, self.size);
 
         self.entries.clear();
 
@@ -701,7 +1273,8 @@ impl GlyphCacheTexture
     {
         if self.invalidated {
             self.invalidated = false;
-            self.bitmap.upload_to_texture(context, &self.texture)
+            self.bitmap
+                .upload_to_texture(context, &self.texture, self.smoothing.clone())
         } else {
             Ok(())
         }
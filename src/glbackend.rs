@@ -34,6 +34,8 @@ pub mod types
     pub type GLTypeProgram = glow::Program;
     pub type GLTypeBuffer = glow::Buffer;
     pub type GLTypeTexture = glow::Texture;
+    pub type GLTypeFramebuffer = glow::Framebuffer;
+    pub type GLTypeRenderbuffer = glow::Renderbuffer;
     pub type GLTypeUniformLocation = glow::UniformLocation;
 }
 
@@ -52,9 +54,45 @@ pub mod constants
 
     pub const GL_SCISSOR_TEST: GLenum = glow::SCISSOR_TEST;
 
+    pub const GL_STENCIL_TEST: GLenum = glow::STENCIL_TEST;
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_NEVER: GLenum = glow::NEVER;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_LESS: GLenum = glow::LESS;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_LEQUAL: GLenum = glow::LEQUAL;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_GREATER: GLenum = glow::GREATER;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_GEQUAL: GLenum = glow::GEQUAL;
+    pub const GL_EQUAL: GLenum = glow::EQUAL;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_NOTEQUAL: GLenum = glow::NOTEQUAL;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_ALWAYS: GLenum = glow::ALWAYS;
+
+    pub const GL_KEEP: GLenum = glow::KEEP;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_REPLACE: GLenum = glow::REPLACE;
+    pub const GL_INCR: GLenum = glow::INCR;
+    pub const GL_DECR: GLenum = glow::DECR;
+
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_ZERO: GLenum = glow::ZERO;
     pub const GL_ONE: GLenum = glow::ONE;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_SRC_COLOR: GLenum = glow::SRC_COLOR;
+    pub const GL_ONE_MINUS_SRC_COLOR: GLenum = glow::ONE_MINUS_SRC_COLOR;
+    pub const GL_DST_COLOR: GLenum = glow::DST_COLOR;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_ONE_MINUS_DST_COLOR: GLenum = glow::ONE_MINUS_DST_COLOR;
     pub const GL_SRC_ALPHA: GLenum = glow::SRC_ALPHA;
     pub const GL_ONE_MINUS_SRC_ALPHA: GLenum = glow::ONE_MINUS_SRC_ALPHA;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_DST_ALPHA: GLenum = glow::DST_ALPHA;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_ONE_MINUS_DST_ALPHA: GLenum = glow::ONE_MINUS_DST_ALPHA;
 
     pub const GL_NEAREST: GLenum = glow::NEAREST;
     pub const GL_LINEAR: GLenum = glow::LINEAR;
@@ -84,6 +122,7 @@ pub mod constants
     pub const GL_TRIANGLES: GLenum = glow::TRIANGLES;
 
     pub const GL_COLOR_BUFFER_BIT: GLenum = glow::COLOR_BUFFER_BIT;
+    pub const GL_STENCIL_BUFFER_BIT: GLenum = glow::STENCIL_BUFFER_BIT;
 
     pub const GL_NO_ERROR: GLenum = glow::NO_ERROR;
     pub const GL_INVALID_ENUM: GLenum = glow::INVALID_ENUM;
@@ -108,6 +147,16 @@ pub mod constants
     pub const GL_DEBUG_OUTPUT_SYNCHRONOUS: GLenum = glow::DEBUG_OUTPUT_SYNCHRONOUS;
 
     pub const GL_UNPACK_ALIGNMENT: GLenum = glow::UNPACK_ALIGNMENT;
+
+    pub const GL_FRAMEBUFFER: GLenum = glow::FRAMEBUFFER;
+    pub const GL_COLOR_ATTACHMENT0: GLenum = glow::COLOR_ATTACHMENT0;
+    pub const GL_DEPTH_STENCIL_ATTACHMENT: GLenum = glow::DEPTH_STENCIL_ATTACHMENT;
+    pub const GL_RENDERBUFFER: GLenum = glow::RENDERBUFFER;
+    pub const GL_DEPTH24_STENCIL8: GLenum = glow::DEPTH24_STENCIL8;
+    pub const GL_FRAMEBUFFER_COMPLETE: GLenum = glow::FRAMEBUFFER_COMPLETE;
+    #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+    pub const GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT: GLenum =
+        glow::FRAMEBUFFER_INCOMPLETE_ATTACHMENT;
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
@@ -154,8 +203,42 @@ pub trait GLBackend
     unsafe fn gl_delete_shader(&self, handle: GLTypeShader);
     unsafe fn gl_delete_buffer(&self, handle: GLTypeBuffer);
     unsafe fn gl_delete_texture(&self, handle: GLTypeTexture);
+    unsafe fn gl_delete_framebuffer(&self, handle: GLTypeFramebuffer);
+    unsafe fn gl_delete_renderbuffer(&self, handle: GLTypeRenderbuffer);
     unsafe fn gl_active_texture(&self, unit: GLenum);
-    unsafe fn gl_bind_texture(&self, target: GLenum, handle: GLTypeTexture);
+    unsafe fn gl_bind_texture(&self, target: GLenum, handle: Option<GLTypeTexture>);
+    unsafe fn gl_bind_framebuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeFramebuffer>
+    );
+    unsafe fn gl_framebuffer_texture_2d(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        texture_target: GLenum,
+        texture: GLTypeTexture,
+        level: GLint
+    );
+    unsafe fn gl_bind_renderbuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeRenderbuffer>
+    );
+    unsafe fn gl_renderbuffer_storage(
+        &self,
+        target: GLenum,
+        internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei
+    );
+    unsafe fn gl_framebuffer_renderbuffer(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: Option<GLTypeRenderbuffer>
+    );
     unsafe fn gl_enable(&self, cap: GLenum);
     unsafe fn gl_disable(&self, cap: GLenum);
     unsafe fn gl_blend_func(&self, sfactor: GLenum, dfactor: GLenum);
@@ -166,11 +249,41 @@ pub trait GLBackend
         sfactor_alpha: GLenum,
         dfactor_alpha: GLenum
     );
+    unsafe fn gl_stencil_func(&self, func: GLenum, reference: GLint, mask: GLuint);
+    unsafe fn gl_stencil_op(
+        &self,
+        stencil_fail: GLenum,
+        depth_fail: GLenum,
+        pass: GLenum
+    );
+    unsafe fn gl_color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool);
     unsafe fn gl_use_program(&self, handle: GLTypeProgram);
     unsafe fn gl_enable_vertex_attrib_array(&self, handle: GLuint);
     unsafe fn gl_disable_vertex_attrib_array(&self, handle: GLuint);
     unsafe fn gl_uniform_1f(&self, handle: &GLTypeUniformLocation, value: f32);
     unsafe fn gl_uniform_1i(&self, handle: &GLTypeUniformLocation, value: GLint);
+    unsafe fn gl_uniform_2f(&self, handle: &GLTypeUniformLocation, x: f32, y: f32);
+    unsafe fn gl_uniform_3f(
+        &self,
+        handle: &GLTypeUniformLocation,
+        x: f32,
+        y: f32,
+        z: f32
+    );
+    unsafe fn gl_uniform_4f(
+        &self,
+        handle: &GLTypeUniformLocation,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32
+    );
+    unsafe fn gl_bind_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint,
+        name: &str
+    );
     unsafe fn gl_attach_shader(&self, program: GLTypeProgram, shader: GLTypeShader);
     unsafe fn gl_link_program(&self, program: GLTypeProgram);
     unsafe fn gl_shader_source(&self, handle: GLTypeShader, source: &str);
@@ -180,6 +293,7 @@ pub trait GLBackend
     unsafe fn gl_buffer_data(&self, target: GLenum, data: &[u8], usage: GLenum);
     unsafe fn gl_draw_arrays(&self, mode: GLenum, first: GLint, count: GLsizei);
     unsafe fn gl_clear_color(&self, r: f32, g: f32, b: f32, a: f32);
+    unsafe fn gl_clear_stencil(&self, value: GLint);
     unsafe fn gl_clear(&self, mask: GLenum);
     unsafe fn gl_enable_debug_message_callback(&self);
     unsafe fn gl_get_string(&self, parameter: GLenum) -> String;
@@ -240,9 +354,20 @@ pub trait GLBackend
         &self
     ) -> Result<GLTypeTexture, BacktraceError<ErrorMessage>>;
 
+    unsafe fn gl_gen_framebuffer(
+        &self
+    ) -> Result<GLTypeFramebuffer, BacktraceError<ErrorMessage>>;
+
+    unsafe fn gl_gen_renderbuffer(
+        &self
+    ) -> Result<GLTypeRenderbuffer, BacktraceError<ErrorMessage>>;
+
     #[must_use]
     unsafe fn gl_get_error(&self) -> GLenum;
 
+    #[must_use]
+    unsafe fn gl_check_framebuffer_status(&self, target: GLenum) -> GLenum;
+
     #[must_use]
     unsafe fn gl_get_attrib_location(
         &self,
@@ -361,14 +486,88 @@ impl GLBackend for GLBackendGlow
         self.context.delete_texture(handle)
     }
 
+    unsafe fn gl_delete_framebuffer(&self, handle: GLTypeFramebuffer)
+    {
+        self.context.delete_framebuffer(handle)
+    }
+
+    unsafe fn gl_delete_renderbuffer(&self, handle: GLTypeRenderbuffer)
+    {
+        self.context.delete_renderbuffer(handle)
+    }
+
     unsafe fn gl_active_texture(&self, unit: GLenum)
     {
         self.context.active_texture(unit)
     }
 
-    unsafe fn gl_bind_texture(&self, target: GLenum, handle: GLTypeTexture)
+    unsafe fn gl_bind_texture(&self, target: GLenum, handle: Option<GLTypeTexture>)
+    {
+        self.context.bind_texture(target, handle)
+    }
+
+    unsafe fn gl_bind_framebuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeFramebuffer>
+    )
     {
-        self.context.bind_texture(target, Some(handle))
+        self.context.bind_framebuffer(target, handle)
+    }
+
+    unsafe fn gl_framebuffer_texture_2d(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        texture_target: GLenum,
+        texture: GLTypeTexture,
+        level: GLint
+    )
+    {
+        self.context.framebuffer_texture_2d(
+            target,
+            attachment,
+            texture_target,
+            Some(texture),
+            level
+        )
+    }
+
+    unsafe fn gl_bind_renderbuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeRenderbuffer>
+    )
+    {
+        self.context.bind_renderbuffer(target, handle)
+    }
+
+    unsafe fn gl_renderbuffer_storage(
+        &self,
+        target: GLenum,
+        internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei
+    )
+    {
+        self.context
+            .renderbuffer_storage(target, internal_format, width, height)
+    }
+
+    unsafe fn gl_framebuffer_renderbuffer(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: Option<GLTypeRenderbuffer>
+    )
+    {
+        self.context.framebuffer_renderbuffer(
+            target,
+            attachment,
+            renderbuffer_target,
+            renderbuffer
+        )
     }
 
     unsafe fn gl_enable(&self, cap: GLenum)
@@ -398,6 +597,21 @@ impl GLBackend for GLBackendGlow
             .blend_func_separate(sfactor, dfactor, sfactor_alpha, dfactor_alpha)
     }
 
+    unsafe fn gl_stencil_func(&self, func: GLenum, reference: GLint, mask: GLuint)
+    {
+        self.context.stencil_func(func, reference, mask)
+    }
+
+    unsafe fn gl_stencil_op(&self, stencil_fail: GLenum, depth_fail: GLenum, pass: GLenum)
+    {
+        self.context.stencil_op(stencil_fail, depth_fail, pass)
+    }
+
+    unsafe fn gl_color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        self.context.color_mask(red, green, blue, alpha)
+    }
+
     unsafe fn gl_use_program(&self, handle: GLTypeProgram)
     {
         self.context.use_program(Some(handle))
@@ -423,6 +637,38 @@ impl GLBackend for GLBackendGlow
         self.context.uniform_1_i32(Some(handle), value)
     }
 
+    unsafe fn gl_uniform_2f(&self, handle: &GLTypeUniformLocation, x: f32, y: f32)
+    {
+        self.context.uniform_2_f32(Some(handle), x, y)
+    }
+
+    unsafe fn gl_uniform_3f(&self, handle: &GLTypeUniformLocation, x: f32, y: f32, z: f32)
+    {
+        self.context.uniform_3_f32(Some(handle), x, y, z)
+    }
+
+    unsafe fn gl_uniform_4f(
+        &self,
+        handle: &GLTypeUniformLocation,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32
+    )
+    {
+        self.context.uniform_4_f32(Some(handle), x, y, z, w)
+    }
+
+    unsafe fn gl_bind_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        index: GLuint,
+        name: &str
+    )
+    {
+        self.context.bind_attrib_location(program, index, name)
+    }
+
     unsafe fn gl_attach_shader(&self, program: GLTypeProgram, shader: GLTypeShader)
     {
         self.context.attach_shader(program, shader)
@@ -468,6 +714,11 @@ impl GLBackend for GLBackendGlow
         self.context.clear_color(r, g, b, a)
     }
 
+    unsafe fn gl_clear_stencil(&self, value: GLint)
+    {
+        self.context.clear_stencil(value)
+    }
+
     unsafe fn gl_clear(&self, mask: u32)
     {
         self.context.clear(mask)
@@ -631,11 +882,38 @@ impl GLBackend for GLBackendGlow
         Ok(handle)
     }
 
+    unsafe fn gl_gen_framebuffer(
+        &self
+    ) -> Result<GLTypeFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        let handle = self.context.create_framebuffer().map_err(|err| {
+            ErrorMessage::msg(format!("Failed to create framebuffer: {err}"))
+        })?;
+
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_renderbuffer(
+        &self
+    ) -> Result<GLTypeRenderbuffer, BacktraceError<ErrorMessage>>
+    {
+        let handle = self.context.create_renderbuffer().map_err(|err| {
+            ErrorMessage::msg(format!("Failed to create renderbuffer: {err}"))
+        })?;
+
+        Ok(handle)
+    }
+
     unsafe fn gl_get_error(&self) -> GLenum
     {
         self.context.get_error()
     }
 
+    unsafe fn gl_check_framebuffer_status(&self, target: GLenum) -> GLenum
+    {
+        self.context.check_framebuffer_status(target)
+    }
+
     unsafe fn gl_get_attrib_location(
         &self,
         program: GLTypeProgram,
@@ -0,0 +1,2228 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! A `GLBackend` which rasterizes in software into an in-memory RGBA buffer.
+//!
+//! Only the subset of GL used by Speedy2D is implemented. Instead of
+//! interpreting GLSL, the built-in `r2d` vertex and fragment shaders are
+//! emulated directly in Rust.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::mem::MaybeUninit;
+
+use crate::error::{BacktraceError, ErrorMessage};
+use crate::glbackend::constants::*;
+use crate::glbackend::types::*;
+use crate::glbackend::GLBackend;
+
+/// The maximum number of floats interpolated across a triangle, excluding
+/// the position.
+const MAX_VARYINGS: usize = 16;
+
+/// The vertex attributes of the built-in shader, in the order they are
+/// stored in the varyings array, along with their component counts.
+const R2D_ATTRIBUTES: &[(&str, usize)] = &[
+    ("in_Color", 4),
+    ("in_TextureCoord", 2),
+    ("in_TextureMix", 1),
+    ("in_CircleMix", 1),
+    ("in_GradientCoord", 2),
+    ("in_LinearGradientMix", 1),
+    ("in_RadialGradientMix", 1),
+    ("in_SdfMix", 1),
+    ("in_SdfEdge", 2),
+    ("in_CoverageMix", 1)
+];
+
+const R2D_ATTRIBUTE_POSITION: &str = "in_Position";
+
+const VARYING_COLOR: usize = 0;
+const VARYING_TEXTURE_COORD: usize = 4;
+const VARYING_TEXTURE_MIX: usize = 6;
+const VARYING_CIRCLE_MIX: usize = 7;
+const VARYING_GRADIENT_COORD: usize = 8;
+const VARYING_LINEAR_GRADIENT_MIX: usize = 10;
+const VARYING_RADIAL_GRADIENT_MIX: usize = 11;
+const VARYING_SDF_MIX: usize = 12;
+const VARYING_SDF_EDGE: usize = 13;
+const VARYING_COVERAGE_MIX: usize = 15;
+
+const R2D_UNIFORMS: &[&str] = &[
+    "in_ScaleX",
+    "in_ScaleY",
+    "in_Texture",
+    "in_PremultiplyAlpha",
+    "in_PremultiplyCoverage",
+    "in_CoverageThreshold"
+];
+
+/// The rasterizer implements the built-in shaders directly, so these are the
+/// only shader sources which can be compiled.
+const R2D_SHADER_SOURCES: &[&str] = &[
+    include_str!("shaders/r2d_vertex_v110.glsl"),
+    include_str!("shaders/r2d_fragment_v110.glsl")
+];
+
+type Varyings = [f32; MAX_VARYINGS];
+
+const SUBPIXEL_STEPS: i64 = 256;
+
+#[derive(Debug, Clone, Copy)]
+struct AttributePointer
+{
+    buffer: GLTypeBuffer,
+    size: usize,
+    stride: usize,
+    offset: usize
+}
+
+struct SoftwareShader
+{
+    supported: bool,
+    compiled: bool
+}
+
+#[derive(Debug, Clone, Copy)]
+enum UniformValue
+{
+    Float(f32),
+    Int(GLint),
+    Vector([f32; 4])
+}
+
+impl UniformValue
+{
+    fn as_f32(&self) -> f32
+    {
+        match self {
+            UniformValue::Float(value) => *value,
+            UniformValue::Int(value) => *value as f32,
+            UniformValue::Vector(value) => value[0]
+        }
+    }
+
+    fn as_int(&self) -> GLint
+    {
+        match self {
+            UniformValue::Float(value) => *value as GLint,
+            UniformValue::Int(value) => *value,
+            UniformValue::Vector(value) => value[0] as GLint
+        }
+    }
+}
+
+struct SoftwareProgram
+{
+    shaders: Vec<GLTypeShader>,
+    linked: bool,
+    attribute_locations: HashMap<&'static str, GLuint>,
+    uniform_locations: HashMap<&'static str, GLTypeUniformLocation>,
+    uniform_values: HashMap<GLTypeUniformLocation, UniformValue>
+}
+
+impl SoftwareProgram
+{
+    fn uniform(&self, name: &str) -> Option<UniformValue>
+    {
+        self.uniform_locations
+            .get(name)
+            .and_then(|location| self.uniform_values.get(location))
+            .copied()
+    }
+}
+
+struct SoftwareTexture
+{
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+    mag_filter: GLenum
+}
+
+impl SoftwareTexture
+{
+    fn texel(&self, x: isize, y: isize) -> [f32; 4]
+    {
+        let x = x.clamp(0, self.width as isize - 1) as usize;
+        let y = y.clamp(0, self.height as isize - 1) as usize;
+
+        let pos = (y * self.width + x) * 4;
+        let texel = &self.data[pos..pos + 4];
+
+        [
+            texel[0] as f32 / 255.0,
+            texel[1] as f32 / 255.0,
+            texel[2] as f32 / 255.0,
+            texel[3] as f32 / 255.0
+        ]
+    }
+
+    fn sample(&self, u: f32, v: f32) -> [f32; 4]
+    {
+        if self.width == 0 || self.height == 0 {
+            return [0.0, 0.0, 0.0, 1.0];
+        }
+
+        let x = u * self.width as f32;
+        let y = v * self.height as f32;
+
+        if self.mag_filter == GL_NEAREST {
+            return self.texel(x.floor() as isize, y.floor() as isize);
+        }
+
+        let x = x - 0.5;
+        let y = y - 0.5;
+
+        let x0 = x.floor();
+        let y0 = y.floor();
+
+        let fx = x - x0;
+        let fy = y - y0;
+
+        let x0 = x0 as isize;
+        let y0 = y0 as isize;
+
+        let tl = self.texel(x0, y0);
+        let tr = self.texel(x0 + 1, y0);
+        let bl = self.texel(x0, y0 + 1);
+        let br = self.texel(x0 + 1, y0 + 1);
+
+        let mut result = [0.0; 4];
+
+        for i in 0..4 {
+            let top = tl[i] + (tr[i] - tl[i]) * fx;
+            let bottom = bl[i] + (br[i] - bl[i]) * fx;
+            result[i] = top + (bottom - top) * fy;
+        }
+
+        result
+    }
+}
+
+/// Framebuffer pixels are stored bottom row first, as in GL window
+/// coordinates. The stencil buffer has one byte per pixel, and is empty if
+/// the render target has no stencil attachment.
+#[derive(Default)]
+struct SoftwareFramebuffer
+{
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+    stencil: Vec<u8>
+}
+
+impl SoftwareFramebuffer
+{
+    fn resize(&mut self, width: usize, height: usize)
+    {
+        if self.width == width && self.height == height {
+            return;
+        }
+
+        self.data = resize_pixels(&self.data, 4, self.width, self.height, width, height);
+        self.stencil =
+            resize_pixels(&self.stencil, 1, self.width, self.height, width, height);
+
+        self.width = width;
+        self.height = height;
+    }
+}
+
+/// Copies the overlapping area of the pixels into a buffer of the new size.
+fn resize_pixels(
+    data: &[u8],
+    bytes_per_pixel: usize,
+    old_width: usize,
+    old_height: usize,
+    width: usize,
+    height: usize
+) -> Vec<u8>
+{
+    let mut result = vec![0; width * height * bytes_per_pixel];
+
+    for y in 0..height.min(old_height) {
+        let row_bytes = width.min(old_width) * bytes_per_pixel;
+        let src = y * old_width * bytes_per_pixel;
+        let dst = y * width * bytes_per_pixel;
+        result[dst..dst + row_bytes].copy_from_slice(&data[src..src + row_bytes]);
+    }
+
+    result
+}
+
+/// Only stencil renderbuffers are supported, with one byte per pixel.
+#[derive(Default)]
+struct SoftwareRenderbuffer
+{
+    width: usize,
+    height: usize,
+    data: Vec<u8>
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SoftwareAttachments
+{
+    color: Option<GLTypeTexture>,
+    stencil: Option<GLTypeRenderbuffer>
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StencilState
+{
+    func: GLenum,
+    reference: GLint,
+    mask: GLuint,
+    fail_op: GLenum,
+    pass_op: GLenum
+}
+
+impl StencilState
+{
+    /// Performs the stencil test on the specified value, and updates it
+    /// according to the stencil operations. Returns true if the test passed.
+    fn test_and_update(&self, value: &mut u8) -> bool
+    {
+        let reference = (self.reference.clamp(0, 255) as GLuint & self.mask) as u8;
+        let masked = (*value as GLuint & self.mask) as u8;
+
+        let passed = match self.func {
+            GL_NEVER => false,
+            GL_LESS => reference < masked,
+            GL_LEQUAL => reference <= masked,
+            GL_GREATER => reference > masked,
+            GL_GEQUAL => reference >= masked,
+            GL_EQUAL => reference == masked,
+            GL_NOTEQUAL => reference != masked,
+            _ => true
+        };
+
+        let op = if passed { self.pass_op } else { self.fail_op };
+
+        *value = match op {
+            GL_KEEP => *value,
+            GL_ZERO => 0,
+            GL_REPLACE => self.reference.clamp(0, 255) as u8,
+            GL_INCR => value.saturating_add(1),
+            GL_DECR => value.saturating_sub(1),
+            _ => {
+                log::error!("Unsupported stencil operation {}", op);
+                *value
+            }
+        };
+
+        passed
+    }
+}
+
+/// A vertex in window coordinates. The position is snapped to a fixed-point
+/// grid of `SUBPIXEL_STEPS` steps per pixel, so that edges shared between
+/// triangles are evaluated exactly.
+#[derive(Debug, Clone, Copy)]
+struct WindowVertex
+{
+    x: i64,
+    y: i64,
+    varyings: Varyings
+}
+
+struct SoftwareState
+{
+    next_handle: u32,
+    error: GLenum,
+
+    shaders: HashMap<GLTypeShader, SoftwareShader>,
+    programs: HashMap<GLTypeProgram, SoftwareProgram>,
+    buffers: HashMap<GLTypeBuffer, Vec<u8>>,
+    textures: HashMap<GLTypeTexture, SoftwareTexture>,
+    framebuffers: HashMap<GLTypeFramebuffer, SoftwareAttachments>,
+    renderbuffers: HashMap<GLTypeRenderbuffer, SoftwareRenderbuffer>,
+
+    active_texture_unit: GLenum,
+    bound_textures: HashMap<GLenum, GLTypeTexture>,
+    bound_array_buffer: Option<GLTypeBuffer>,
+    bound_framebuffer: Option<GLTypeFramebuffer>,
+    bound_renderbuffer: Option<GLTypeRenderbuffer>,
+    current_program: Option<GLTypeProgram>,
+    attribute_pointers: HashMap<GLuint, AttributePointer>,
+
+    blend_enabled: bool,
+    blend_func: [GLenum; 4],
+    scissor_enabled: bool,
+    scissor: [GLint; 4],
+    stencil_enabled: bool,
+    stencil: StencilState,
+    color_mask: [bool; 4],
+    viewport: [GLint; 4],
+    clear_color: [f32; 4],
+    clear_stencil: u8,
+    unpack_alignment: usize,
+
+    framebuffer: SoftwareFramebuffer
+}
+
+impl SoftwareState
+{
+    fn new_handle(&mut self) -> u32
+    {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        handle
+    }
+
+    fn set_error(&mut self, error: GLenum)
+    {
+        if self.error == GL_NO_ERROR {
+            self.error = error;
+        }
+    }
+
+    fn bound_texture_mut(&mut self) -> Option<&mut SoftwareTexture>
+    {
+        match self.bound_textures.get(&self.active_texture_unit) {
+            None => None,
+            Some(handle) => self.textures.get_mut(handle)
+        }
+    }
+
+    /// Returns the attachments of the bound framebuffer, or `None` when
+    /// rendering to the default framebuffer.
+    fn render_target_attachments(&self) -> Result<Option<SoftwareAttachments>, GLenum>
+    {
+        match self.bound_framebuffer {
+            None => Ok(None),
+            Some(framebuffer) => match self.framebuffers.get(&framebuffer) {
+                Some(
+                    attachments @ SoftwareAttachments {
+                        color: Some(texture),
+                        ..
+                    }
+                ) if self.textures.contains_key(texture) => Ok(Some(*attachments)),
+                _ => Err(GL_INVALID_FRAMEBUFFER_OPERATION)
+            }
+        }
+    }
+
+    /// Moves the pixels of the current render target out of the state, so
+    /// that they can be written while textures are being sampled. They must
+    /// be returned using `restore_render_target`.
+    fn take_render_target(&mut self) -> Option<SoftwareFramebuffer>
+    {
+        match self.render_target_attachments() {
+            Err(error) => {
+                self.set_error(error);
+                None
+            }
+            Ok(None) => Some(std::mem::take(&mut self.framebuffer)),
+            Ok(Some(attachments)) => {
+                let texture = self.textures.get_mut(&attachments.color.unwrap()).unwrap();
+
+                let mut target = SoftwareFramebuffer {
+                    width: std::mem::take(&mut texture.width),
+                    height: std::mem::take(&mut texture.height),
+                    data: std::mem::take(&mut texture.data),
+                    stencil: Vec::new()
+                };
+
+                // A stencil buffer of a different size is ignored
+                if let Some(renderbuffer) = attachments
+                    .stencil
+                    .and_then(|handle| self.renderbuffers.get_mut(&handle))
+                {
+                    if renderbuffer.width == target.width
+                        && renderbuffer.height == target.height
+                    {
+                        target.stencil = std::mem::take(&mut renderbuffer.data);
+                    }
+                }
+
+                Some(target)
+            }
+        }
+    }
+
+    fn restore_render_target(&mut self, target: SoftwareFramebuffer)
+    {
+        match self.render_target_attachments() {
+            Ok(Some(attachments)) => {
+                let texture = self.textures.get_mut(&attachments.color.unwrap()).unwrap();
+                texture.width = target.width;
+                texture.height = target.height;
+                texture.data = target.data;
+
+                if let Some(renderbuffer) = attachments
+                    .stencil
+                    .and_then(|handle| self.renderbuffers.get_mut(&handle))
+                {
+                    if !target.stencil.is_empty() {
+                        renderbuffer.data = target.stencil;
+                    }
+                }
+            }
+            _ => self.framebuffer = target
+        }
+    }
+
+    fn read_attribute(&self, location: Option<GLuint>, vertex: usize, out: &mut [f32])
+    {
+        let pointer = match location.and_then(|loc| self.attribute_pointers.get(&loc)) {
+            None => return,
+            Some(pointer) => pointer
+        };
+
+        let buffer = match self.buffers.get(&pointer.buffer) {
+            None => return,
+            Some(buffer) => buffer
+        };
+
+        let stride = if pointer.stride == 0 {
+            pointer.size * 4
+        } else {
+            pointer.stride
+        };
+
+        let start = pointer.offset + vertex * stride;
+
+        for (i, value) in out.iter_mut().enumerate().take(pointer.size) {
+            let pos = start + i * 4;
+            if let Some(bytes) = buffer.get(pos..pos + 4) {
+                *value = f32::from_ne_bytes(bytes.try_into().unwrap());
+            }
+        }
+    }
+
+    fn draw_triangles(&mut self, first: usize, count: usize)
+    {
+        let program = match self.current_program.and_then(|p| self.programs.get(&p)) {
+            Some(program) if program.linked => program,
+            _ => {
+                self.set_error(GL_INVALID_OPERATION);
+                return;
+            }
+        };
+
+        let scale_x = program.uniform("in_ScaleX").map_or(0.0, |v| v.as_f32());
+        let scale_y = program.uniform("in_ScaleY").map_or(0.0, |v| v.as_f32());
+        let texture_unit = program.uniform("in_Texture").map_or(0, |v| v.as_int());
+        let premultiply_alpha = program
+            .uniform("in_PremultiplyAlpha")
+            .map_or(0.0, |v| v.as_f32());
+        let premultiply_coverage = program
+            .uniform("in_PremultiplyCoverage")
+            .map_or(0.0, |v| v.as_f32());
+        let coverage_threshold = program
+            .uniform("in_CoverageThreshold")
+            .map_or(0.0, |v| v.as_f32());
+
+        let position_location = program
+            .attribute_locations
+            .get(R2D_ATTRIBUTE_POSITION)
+            .copied();
+
+        let attribute_locations: Vec<Option<GLuint>> = R2D_ATTRIBUTES
+            .iter()
+            .map(|(name, _)| program.attribute_locations.get(name).copied())
+            .collect();
+
+        let [vx, vy, vw, vh] = self.viewport;
+
+        let mut target = match self.take_render_target() {
+            None => return,
+            Some(target) => target
+        };
+
+        let mut vertices = Vec::with_capacity(count);
+
+        for vertex in first..(first + count) {
+            let mut position = [0.0; 2];
+            self.read_attribute(position_location, vertex, &mut position);
+
+            let mut varyings = [0.0; MAX_VARYINGS];
+            let mut offset = 0;
+
+            for ((_, size), location) in R2D_ATTRIBUTES.iter().zip(&attribute_locations) {
+                self.read_attribute(
+                    *location,
+                    vertex,
+                    &mut varyings[offset..offset + size]
+                );
+                offset += size;
+            }
+
+            let ndc_x = position[0] * scale_x - 1.0;
+            let ndc_y = position[1] * scale_y + 1.0;
+
+            let x = (ndc_x + 1.0) * 0.5 * vw as f32 + vx as f32;
+            let y = (ndc_y + 1.0) * 0.5 * vh as f32 + vy as f32;
+
+            vertices.push(WindowVertex {
+                x: snap_to_subpixel(x),
+                y: snap_to_subpixel(y),
+                varyings
+            });
+        }
+
+        let texture_unit = GL_TEXTURE0 + texture_unit.max(0) as GLenum;
+
+        let textures = &self.textures;
+
+        let texture = self
+            .bound_textures
+            .get(&texture_unit)
+            .and_then(|handle| textures.get(handle));
+
+        let mut clip = [0, 0, target.width as GLint, target.height as GLint];
+
+        if self.scissor_enabled {
+            let [sx, sy, sw, sh] = self.scissor;
+            clip = [
+                clip[0].max(sx),
+                clip[1].max(sy),
+                clip[2].min(sx.saturating_add(sw)),
+                clip[3].min(sy.saturating_add(sh))
+            ];
+        }
+
+        let raster = Rasterizer {
+            texture,
+            clip,
+            premultiply_alpha,
+            premultiply_coverage,
+            coverage_threshold,
+            stencil: if self.stencil_enabled {
+                Some(self.stencil)
+            } else {
+                None
+            },
+            color_mask: self.color_mask,
+            blend: if self.blend_enabled {
+                Some(self.blend_func)
+            } else {
+                None
+            }
+        };
+
+        for triangle in vertices.chunks_exact(3) {
+            raster.draw_triangle(&mut target, triangle);
+        }
+
+        self.restore_render_target(target);
+    }
+
+    fn clear(&mut self, mask: GLenum)
+    {
+        if mask & (GL_COLOR_BUFFER_BIT | GL_STENCIL_BUFFER_BIT) == 0 {
+            return;
+        }
+
+        let color = self.clear_color.map(to_u8);
+        let clear_color = mask & GL_COLOR_BUFFER_BIT != 0;
+        let clear_stencil = mask & GL_STENCIL_BUFFER_BIT != 0;
+
+        let mut target = match self.take_render_target() {
+            None => return,
+            Some(target) => target
+        };
+
+        let (mut x0, mut y0, mut x1, mut y1) = (0, 0, target.width, target.height);
+
+        if self.scissor_enabled {
+            let [sx, sy, sw, sh] = self.scissor;
+            x0 = x0.max(sx.max(0) as usize);
+            y0 = y0.max(sy.max(0) as usize);
+            x1 = x1.min(sx.saturating_add(sw).max(0) as usize);
+            y1 = y1.min(sy.saturating_add(sh).max(0) as usize);
+        }
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let index = y * target.width + x;
+
+                if clear_color {
+                    let pixel = &mut target.data[index * 4..index * 4 + 4];
+
+                    for i in 0..4 {
+                        if self.color_mask[i] {
+                            pixel[i] = color[i];
+                        }
+                    }
+                }
+
+                if clear_stencil {
+                    if let Some(value) = target.stencil.get_mut(index) {
+                        *value = self.clear_stencil;
+                    }
+                }
+            }
+        }
+
+        self.restore_render_target(target);
+    }
+}
+
+struct Rasterizer<'a>
+{
+    texture: Option<&'a SoftwareTexture>,
+    clip: [GLint; 4],
+    premultiply_alpha: f32,
+    premultiply_coverage: f32,
+    coverage_threshold: f32,
+    stencil: Option<StencilState>,
+    color_mask: [bool; 4],
+    blend: Option<[GLenum; 4]>
+}
+
+impl<'a> Rasterizer<'a>
+{
+    fn draw_triangle(&self, target: &mut SoftwareFramebuffer, triangle: &[WindowVertex])
+    {
+        let mut v0 = &triangle[0];
+        let mut v1 = &triangle[1];
+        let v2 = &triangle[2];
+
+        let mut area = edge(v0, v1, v2.x, v2.y);
+
+        if area == 0 {
+            return;
+        }
+
+        if area < 0 {
+            std::mem::swap(&mut v0, &mut v1);
+            area = -area;
+        }
+
+        let to_pixel = |value: i64| value.div_euclid(SUBPIXEL_STEPS);
+
+        let min_x = to_pixel(v0.x.min(v1.x).min(v2.x)).max(self.clip[0] as i64);
+        let min_y = to_pixel(v0.y.min(v1.y).min(v2.y)).max(self.clip[1] as i64);
+        let max_x = (to_pixel(v0.x.max(v1.x).max(v2.x)) + 1).min(self.clip[2] as i64);
+        let max_y = (to_pixel(v0.y.max(v1.y).max(v2.y)) + 1).min(self.clip[3] as i64);
+
+        if min_x >= max_x || min_y >= max_y {
+            return;
+        }
+
+        let edges = [(v1, v2), (v2, v0), (v0, v1)];
+        let area = area as f32;
+
+        for py in min_y..max_y {
+            for px in min_x..max_x {
+                let sx = px * SUBPIXEL_STEPS + SUBPIXEL_STEPS / 2;
+                let sy = py * SUBPIXEL_STEPS + SUBPIXEL_STEPS / 2;
+
+                let mut weights = [0.0; 3];
+                let mut inside = true;
+
+                for (weight, (a, b)) in weights.iter_mut().zip(edges.iter()) {
+                    let w = edge(a, b, sx, sy);
+
+                    if w < 0 || (w == 0 && !is_top_left(a, b)) {
+                        inside = false;
+                        break;
+                    }
+
+                    *weight = w as f32 / area;
+                }
+
+                if !inside {
+                    continue;
+                }
+
+                let index = py as usize * target.width + px as usize;
+
+                let mut varyings = [0.0; MAX_VARYINGS];
+
+                for (i, value) in varyings.iter_mut().enumerate() {
+                    *value = v0.varyings[i] * weights[0]
+                        + v1.varyings[i] * weights[1]
+                        + v2.varyings[i] * weights[2];
+                }
+
+                // Discarded fragments don't update the stencil buffer
+                let color = match self.shade_fragment(&varyings) {
+                    None => continue,
+                    Some(color) => color
+                };
+
+                // Without a stencil buffer, the stencil test always passes
+                if let (Some(stencil), Some(value)) =
+                    (&self.stencil, target.stencil.get_mut(index))
+                {
+                    if !stencil.test_and_update(value) {
+                        continue;
+                    }
+                }
+
+                if self.color_mask == [false; 4] {
+                    continue;
+                }
+
+                let pos = index * 4;
+                self.write_pixel(&mut target.data[pos..pos + 4], color);
+            }
+        }
+    }
+
+    /// Returns `None` if the fragment is discarded.
+    fn shade_fragment(&self, varyings: &Varyings) -> Option<[f32; 4]>
+    {
+        let color = &varyings[VARYING_COLOR..VARYING_COLOR + 4];
+        let u = varyings[VARYING_TEXTURE_COORD];
+        let v = varyings[VARYING_TEXTURE_COORD + 1];
+        let texture_mix = varyings[VARYING_TEXTURE_MIX];
+        let circle_mix = varyings[VARYING_CIRCLE_MIX];
+        let gradient_u = varyings[VARYING_GRADIENT_COORD];
+        let gradient_v = varyings[VARYING_GRADIENT_COORD + 1];
+        let linear_gradient_mix = varyings[VARYING_LINEAR_GRADIENT_MIX];
+        let radial_gradient_mix = varyings[VARYING_RADIAL_GRADIENT_MIX];
+        let sdf_mix = varyings[VARYING_SDF_MIX];
+        let sdf_edge_start = varyings[VARYING_SDF_EDGE];
+        let sdf_edge_end = varyings[VARYING_SDF_EDGE + 1];
+        let coverage_mix = varyings[VARYING_COVERAGE_MIX];
+
+        let sample = |u: f32, v: f32| match self.texture {
+            None => [0.0, 0.0, 0.0, 1.0],
+            Some(texture) => texture.sample(u, v)
+        };
+
+        let mut texture_color = sample(u, v);
+
+        let sdf_alpha = ((texture_color[3] - sdf_edge_start)
+            / (sdf_edge_end - sdf_edge_start).max(0.0001))
+        .clamp(0.0, 1.0);
+
+        texture_color[3] += (sdf_alpha - texture_color[3]) * sdf_mix;
+
+        let circle_alpha = if u * u + v * v < 1.0 { 1.0 } else { 0.0 };
+
+        let coverage = (1.0 + (texture_color[3] - 1.0) * coverage_mix)
+            * (1.0 + (circle_alpha - 1.0) * circle_mix);
+
+        if coverage < self.coverage_threshold {
+            return None;
+        }
+
+        let gradient_offset = (gradient_u * linear_gradient_mix
+            + (gradient_u * gradient_u + gradient_v * gradient_v).sqrt()
+                * radial_gradient_mix)
+            .clamp(0.0, 1.0);
+
+        let gradient_color = sample((gradient_offset * 255.0 + 0.5) / 256.0, 0.5);
+        let gradient_mix = linear_gradient_mix + radial_gradient_mix;
+
+        let mut result = [0.0; 4];
+
+        for i in 0..4 {
+            let circle_color = if i == 3 { circle_alpha } else { 1.0 };
+
+            result[i] = color[i]
+                * ((1.0 - texture_mix - circle_mix)
+                    + texture_color[i] * texture_mix
+                    + circle_color * circle_mix)
+                * ((1.0 - gradient_mix) + gradient_color[i] * gradient_mix);
+        }
+
+        let premultiply = (1.0 - self.premultiply_alpha
+            + result[3] * self.premultiply_alpha)
+            * (1.0 - self.premultiply_coverage + coverage * self.premultiply_coverage);
+
+        for value in result.iter_mut().take(3) {
+            *value *= premultiply;
+        }
+
+        Some(result.map(|value| value.clamp(0.0, 1.0)))
+    }
+
+    fn write_pixel(&self, pixel: &mut [u8], src: [f32; 4])
+    {
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha] = match self.blend {
+            None => {
+                for i in 0..4 {
+                    if self.color_mask[i] {
+                        pixel[i] = to_u8(src[i]);
+                    }
+                }
+                return;
+            }
+            Some(blend) => blend
+        };
+
+        let dst = [
+            pixel[0] as f32 / 255.0,
+            pixel[1] as f32 / 255.0,
+            pixel[2] as f32 / 255.0,
+            pixel[3] as f32 / 255.0
+        ];
+
+        for i in 0..4 {
+            let (src_factor, dst_factor) = if i == 3 {
+                (src_alpha, dst_alpha)
+            } else {
+                (src_rgb, dst_rgb)
+            };
+
+            let value = src[i] * blend_factor(src_factor, &src, &dst, i)
+                + dst[i] * blend_factor(dst_factor, &src, &dst, i);
+
+            if self.color_mask[i] {
+                pixel[i] = to_u8(value);
+            }
+        }
+    }
+}
+
+fn edge(a: &WindowVertex, b: &WindowVertex, x: i64, y: i64) -> i64
+{
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+/// Coordinates are clamped to a guard band, so that edge function products
+/// cannot overflow.
+fn snap_to_subpixel(value: f32) -> i64
+{
+    const GUARD_BAND_PIXELS: f32 = (1 << 20) as f32;
+
+    let value = value.clamp(-GUARD_BAND_PIXELS, GUARD_BAND_PIXELS);
+    (value * SUBPIXEL_STEPS as f32).round() as i64
+}
+
+/// Decides ownership of pixels lying exactly on an edge, so that pixels on
+/// an edge shared by two triangles are only drawn once.
+fn is_top_left(a: &WindowVertex, b: &WindowVertex) -> bool
+{
+    let dy = b.y - a.y;
+    dy < 0 || (dy == 0 && b.x > a.x)
+}
+
+fn blend_factor(factor: GLenum, src: &[f32; 4], dst: &[f32; 4], channel: usize) -> f32
+{
+    match factor {
+        GL_ZERO => 0.0,
+        GL_ONE => 1.0,
+        GL_SRC_COLOR => src[channel],
+        GL_ONE_MINUS_SRC_COLOR => 1.0 - src[channel],
+        GL_DST_COLOR => dst[channel],
+        GL_ONE_MINUS_DST_COLOR => 1.0 - dst[channel],
+        GL_SRC_ALPHA => src[3],
+        GL_ONE_MINUS_SRC_ALPHA => 1.0 - src[3],
+        GL_DST_ALPHA => dst[3],
+        GL_ONE_MINUS_DST_ALPHA => 1.0 - dst[3],
+        _ => {
+            log::error!("Unsupported blend factor {}", factor);
+            0.0
+        }
+    }
+}
+
+#[inline]
+fn to_u8(value: f32) -> u8
+{
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts the specified pixel data to tightly packed RGBA.
+fn convert_to_rgba(
+    format: GLenum,
+    width: usize,
+    height: usize,
+    unpack_alignment: usize,
+    pixels: &[u8]
+) -> Option<Vec<u8>>
+{
+    let bytes_per_pixel = match format {
+        GL_RED => 1,
+        GL_RGB => 3,
+        GL_RGBA => 4,
+        _ => return None
+    };
+
+    let row_bytes = width * bytes_per_pixel;
+    let stride = row_bytes.div_ceil(unpack_alignment) * unpack_alignment;
+
+    let mut result = Vec::with_capacity(width * height * 4);
+
+    for y in 0..height {
+        let row = pixels.get((y * stride)..(y * stride + row_bytes))?;
+
+        for pixel in row.chunks_exact(bytes_per_pixel) {
+            match bytes_per_pixel {
+                1 => result.extend_from_slice(&[pixel[0], 0, 0, 255]),
+                3 => result.extend_from_slice(&[pixel[0], pixel[1], pixel[2], 255]),
+                _ => result.extend_from_slice(pixel)
+            }
+        }
+    }
+
+    Some(result)
+}
+
+/// A software implementation of the GL calls used by Speedy2D. The output
+/// is rendered into an in-memory buffer, which can be retrieved using
+/// `gl_read_pixels`.
+pub struct GLBackendSoftware
+{
+    state: RefCell<SoftwareState>
+}
+
+impl GLBackendSoftware
+{
+    #[must_use]
+    pub fn new() -> Self
+    {
+        GLBackendSoftware {
+            state: RefCell::new(SoftwareState {
+                next_handle: 1,
+                error: GL_NO_ERROR,
+                shaders: HashMap::new(),
+                programs: HashMap::new(),
+                buffers: HashMap::new(),
+                textures: HashMap::new(),
+                framebuffers: HashMap::new(),
+                renderbuffers: HashMap::new(),
+                active_texture_unit: GL_TEXTURE0,
+                bound_textures: HashMap::new(),
+                bound_array_buffer: None,
+                bound_framebuffer: None,
+                bound_renderbuffer: None,
+                current_program: None,
+                attribute_pointers: HashMap::new(),
+                blend_enabled: false,
+                blend_func: [GL_ONE, GL_ZERO, GL_ONE, GL_ZERO],
+                scissor_enabled: false,
+                scissor: [0, 0, 0, 0],
+                stencil_enabled: false,
+                stencil: StencilState {
+                    func: GL_ALWAYS,
+                    reference: 0,
+                    mask: GLuint::MAX,
+                    fail_op: GL_KEEP,
+                    pass_op: GL_KEEP
+                },
+                color_mask: [true; 4],
+                viewport: [0, 0, 0, 0],
+                clear_color: [0.0, 0.0, 0.0, 0.0],
+                clear_stencil: 0,
+                unpack_alignment: 4,
+                framebuffer: SoftwareFramebuffer::default()
+            })
+        }
+    }
+}
+
+impl GLBackend for GLBackendSoftware
+{
+    unsafe fn gl_delete_program(&self, handle: GLTypeProgram)
+    {
+        let mut state = self.state.borrow_mut();
+        state.programs.remove(&handle);
+        if state.current_program == Some(handle) {
+            state.current_program = None;
+        }
+    }
+
+    unsafe fn gl_delete_shader(&self, handle: GLTypeShader)
+    {
+        self.state.borrow_mut().shaders.remove(&handle);
+    }
+
+    unsafe fn gl_delete_buffer(&self, handle: GLTypeBuffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.buffers.remove(&handle);
+        if state.bound_array_buffer == Some(handle) {
+            state.bound_array_buffer = None;
+        }
+    }
+
+    unsafe fn gl_delete_texture(&self, handle: GLTypeTexture)
+    {
+        let mut state = self.state.borrow_mut();
+        state.textures.remove(&handle);
+        state.bound_textures.retain(|_, bound| *bound != handle);
+    }
+
+    unsafe fn gl_delete_framebuffer(&self, handle: GLTypeFramebuffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.framebuffers.remove(&handle);
+        if state.bound_framebuffer == Some(handle) {
+            state.bound_framebuffer = None;
+        }
+    }
+
+    unsafe fn gl_delete_renderbuffer(&self, handle: GLTypeRenderbuffer)
+    {
+        let mut state = self.state.borrow_mut();
+        state.renderbuffers.remove(&handle);
+        if state.bound_renderbuffer == Some(handle) {
+            state.bound_renderbuffer = None;
+        }
+    }
+
+    unsafe fn gl_active_texture(&self, unit: GLenum)
+    {
+        self.state.borrow_mut().active_texture_unit = unit;
+    }
+
+    unsafe fn gl_bind_texture(&self, target: GLenum, handle: Option<GLTypeTexture>)
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_TEXTURE_2D {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        let unit = state.active_texture_unit;
+
+        let handle = match handle {
+            None | Some(0) => {
+                state.bound_textures.remove(&unit);
+                return;
+            }
+            Some(handle) => handle
+        };
+
+        state
+            .textures
+            .entry(handle)
+            .or_insert_with(|| SoftwareTexture {
+                width: 0,
+                height: 0,
+                data: Vec::new(),
+                mag_filter: GL_LINEAR
+            });
+
+        state.bound_textures.insert(unit, handle);
+    }
+
+    unsafe fn gl_bind_framebuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeFramebuffer>
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_FRAMEBUFFER {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        match handle {
+            None | Some(0) => state.bound_framebuffer = None,
+            Some(handle) => {
+                state.framebuffers.entry(handle).or_default();
+                state.bound_framebuffer = Some(handle);
+            }
+        }
+    }
+
+    unsafe fn gl_framebuffer_texture_2d(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        texture_target: GLenum,
+        texture: GLTypeTexture,
+        _level: GLint
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_FRAMEBUFFER
+            || attachment != GL_COLOR_ATTACHMENT0
+            || texture_target != GL_TEXTURE_2D
+        {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        match state.bound_framebuffer {
+            None => state.set_error(GL_INVALID_OPERATION),
+            Some(framebuffer) => {
+                state.framebuffers.entry(framebuffer).or_default().color = Some(texture);
+            }
+        }
+    }
+
+    unsafe fn gl_bind_renderbuffer(
+        &self,
+        target: GLenum,
+        handle: Option<GLTypeRenderbuffer>
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_RENDERBUFFER {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        match handle {
+            None | Some(0) => state.bound_renderbuffer = None,
+            Some(handle) => {
+                state.renderbuffers.entry(handle).or_default();
+                state.bound_renderbuffer = Some(handle);
+            }
+        }
+    }
+
+    unsafe fn gl_renderbuffer_storage(
+        &self,
+        target: GLenum,
+        internal_format: GLenum,
+        width: GLsizei,
+        height: GLsizei
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_RENDERBUFFER || internal_format != GL_DEPTH24_STENCIL8 {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        if width < 0 || height < 0 {
+            state.set_error(GL_INVALID_VALUE);
+            return;
+        }
+
+        let (width, height) = (width as usize, height as usize);
+
+        match state
+            .bound_renderbuffer
+            .and_then(|handle| state.renderbuffers.get_mut(&handle))
+        {
+            None => state.set_error(GL_INVALID_OPERATION),
+            Some(renderbuffer) => {
+                *renderbuffer = SoftwareRenderbuffer {
+                    width,
+                    height,
+                    data: vec![0; width * height]
+                }
+            }
+        }
+    }
+
+    unsafe fn gl_framebuffer_renderbuffer(
+        &self,
+        target: GLenum,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: Option<GLTypeRenderbuffer>
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_FRAMEBUFFER
+            || attachment != GL_DEPTH_STENCIL_ATTACHMENT
+            || renderbuffer_target != GL_RENDERBUFFER
+        {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        match state.bound_framebuffer {
+            None => state.set_error(GL_INVALID_OPERATION),
+            Some(framebuffer) => {
+                state.framebuffers.entry(framebuffer).or_default().stencil = renderbuffer;
+            }
+        }
+    }
+
+    unsafe fn gl_enable(&self, cap: GLenum)
+    {
+        let mut state = self.state.borrow_mut();
+        match cap {
+            GL_BLEND => state.blend_enabled = true,
+            GL_SCISSOR_TEST => state.scissor_enabled = true,
+            GL_STENCIL_TEST => state.stencil_enabled = true,
+            _ => {}
+        }
+    }
+
+    unsafe fn gl_disable(&self, cap: GLenum)
+    {
+        let mut state = self.state.borrow_mut();
+        match cap {
+            GL_BLEND => state.blend_enabled = false,
+            GL_SCISSOR_TEST => state.scissor_enabled = false,
+            GL_STENCIL_TEST => state.stencil_enabled = false,
+            _ => {}
+        }
+    }
+
+    unsafe fn gl_blend_func(&self, sfactor: GLenum, dfactor: GLenum)
+    {
+        self.state.borrow_mut().blend_func = [sfactor, dfactor, sfactor, dfactor];
+    }
+
+    unsafe fn gl_blend_func_separate(
+        &self,
+        sfactor: GLenum,
+        dfactor: GLenum,
+        sfactor_alpha: GLenum,
+        dfactor_alpha: GLenum
+    )
+    {
+        self.state.borrow_mut().blend_func =
+            [sfactor, dfactor, sfactor_alpha, dfactor_alpha];
+    }
+
+    unsafe fn gl_stencil_func(&self, func: GLenum, reference: GLint, mask: GLuint)
+    {
+        let mut state = self.state.borrow_mut();
+        state.stencil.func = func;
+        state.stencil.reference = reference;
+        state.stencil.mask = mask;
+    }
+
+    unsafe fn gl_stencil_op(
+        &self,
+        stencil_fail: GLenum,
+        _depth_fail: GLenum,
+        pass: GLenum
+    )
+    {
+        // There is no depth buffer, so the depth test always passes
+        let mut state = self.state.borrow_mut();
+        state.stencil.fail_op = stencil_fail;
+        state.stencil.pass_op = pass;
+    }
+
+    unsafe fn gl_color_mask(&self, red: bool, green: bool, blue: bool, alpha: bool)
+    {
+        self.state.borrow_mut().color_mask = [red, green, blue, alpha];
+    }
+
+    unsafe fn gl_use_program(&self, handle: GLTypeProgram)
+    {
+        let mut state = self.state.borrow_mut();
+
+        if !state.programs.contains_key(&handle) {
+            state.set_error(GL_INVALID_VALUE);
+            return;
+        }
+
+        state.current_program = Some(handle);
+    }
+
+    unsafe fn gl_enable_vertex_attrib_array(&self, _handle: GLuint) {}
+
+    unsafe fn gl_disable_vertex_attrib_array(&self, _handle: GLuint) {}
+
+    unsafe fn gl_uniform_1f(&self, handle: &GLTypeUniformLocation, value: f32)
+    {
+        let mut state = self.state.borrow_mut();
+        if let Some(program) = state.current_program {
+            if let Some(program) = state.programs.get_mut(&program) {
+                program
+                    .uniform_values
+                    .insert(*handle, UniformValue::Float(value));
+                return;
+            }
+        }
+        state.set_error(GL_INVALID_OPERATION);
+    }
+
+    unsafe fn gl_uniform_1i(&self, handle: &GLTypeUniformLocation, value: GLint)
+    {
+        let mut state = self.state.borrow_mut();
+        if let Some(program) = state.current_program {
+            if let Some(program) = state.programs.get_mut(&program) {
+                program
+                    .uniform_values
+                    .insert(*handle, UniformValue::Int(value));
+                return;
+            }
+        }
+        state.set_error(GL_INVALID_OPERATION);
+    }
+
+    unsafe fn gl_uniform_2f(&self, handle: &GLTypeUniformLocation, x: f32, y: f32)
+    {
+        self.gl_uniform_4f(handle, x, y, 0.0, 0.0)
+    }
+
+    unsafe fn gl_uniform_3f(&self, handle: &GLTypeUniformLocation, x: f32, y: f32, z: f32)
+    {
+        self.gl_uniform_4f(handle, x, y, z, 0.0)
+    }
+
+    unsafe fn gl_uniform_4f(
+        &self,
+        handle: &GLTypeUniformLocation,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32
+    )
+    {
+        let mut state = self.state.borrow_mut();
+        if let Some(program) = state.current_program {
+            if let Some(program) = state.programs.get_mut(&program) {
+                program
+                    .uniform_values
+                    .insert(*handle, UniformValue::Vector([x, y, z, w]));
+                return;
+            }
+        }
+        state.set_error(GL_INVALID_OPERATION);
+    }
+
+    unsafe fn gl_bind_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        _index: GLuint,
+        _name: &str
+    )
+    {
+        // Attribute locations are fixed when the program is created, in the
+        // same order in which Renderer2D binds them.
+        let mut state = self.state.borrow_mut();
+        if !state.programs.contains_key(&program) {
+            state.set_error(GL_INVALID_VALUE);
+        }
+    }
+
+    unsafe fn gl_attach_shader(&self, program: GLTypeProgram, shader: GLTypeShader)
+    {
+        let mut state = self.state.borrow_mut();
+        match state.programs.get_mut(&program) {
+            None => state.set_error(GL_INVALID_VALUE),
+            Some(program) => program.shaders.push(shader)
+        }
+    }
+
+    unsafe fn gl_link_program(&self, program: GLTypeProgram)
+    {
+        let mut state = self.state.borrow_mut();
+
+        let linked = match state.programs.get(&program) {
+            None => {
+                state.set_error(GL_INVALID_VALUE);
+                return;
+            }
+            Some(program) => {
+                program.shaders.len() == 2
+                    && program.shaders.iter().all(|shader| {
+                        state.shaders.get(shader).is_some_and(|s| s.compiled)
+                    })
+            }
+        };
+
+        let mut uniform_locations = HashMap::new();
+
+        for name in R2D_UNIFORMS {
+            uniform_locations.insert(*name, state.new_handle());
+        }
+
+        let program = state.programs.get_mut(&program).unwrap();
+
+        program.linked = linked;
+        program.uniform_locations = uniform_locations;
+        program.uniform_values.clear();
+        program.attribute_locations = std::iter::once(R2D_ATTRIBUTE_POSITION)
+            .chain(R2D_ATTRIBUTES.iter().map(|(name, _)| *name))
+            .enumerate()
+            .map(|(location, name)| (name, location as GLuint))
+            .collect();
+    }
+
+    unsafe fn gl_shader_source(&self, handle: GLTypeShader, source: &str)
+    {
+        let mut state = self.state.borrow_mut();
+        match state.shaders.get_mut(&handle) {
+            None => state.set_error(GL_INVALID_VALUE),
+            Some(shader) => shader.supported = R2D_SHADER_SOURCES.contains(&source)
+        }
+    }
+
+    unsafe fn gl_compile_shader(&self, handle: GLTypeShader)
+    {
+        let mut state = self.state.borrow_mut();
+        match state.shaders.get_mut(&handle) {
+            None => state.set_error(GL_INVALID_VALUE),
+            Some(shader) => shader.compiled = shader.supported
+        }
+    }
+
+    unsafe fn gl_tex_parameter_i(&self, target: GLenum, parameter: GLenum, value: GLint)
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_TEXTURE_2D {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        if parameter == GL_TEXTURE_MAG_FILTER {
+            if let Some(texture) = state.bound_texture_mut() {
+                texture.mag_filter = value as GLenum;
+            }
+        }
+    }
+
+    unsafe fn gl_bind_buffer(&self, target: GLenum, handle: GLTypeBuffer)
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_ARRAY_BUFFER {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        state.bound_array_buffer = Some(handle);
+    }
+
+    unsafe fn gl_buffer_data(&self, target: GLenum, data: &[u8], _usage: GLenum)
+    {
+        let mut state = self.state.borrow_mut();
+
+        match state.bound_array_buffer {
+            Some(buffer) if target == GL_ARRAY_BUFFER => {
+                state.buffers.insert(buffer, data.to_vec());
+            }
+            _ => state.set_error(GL_INVALID_OPERATION)
+        }
+    }
+
+    unsafe fn gl_draw_arrays(&self, mode: GLenum, first: GLint, count: GLsizei)
+    {
+        let mut state = self.state.borrow_mut();
+
+        if mode != GL_TRIANGLES {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        if first < 0 || count < 0 {
+            state.set_error(GL_INVALID_VALUE);
+            return;
+        }
+
+        state.draw_triangles(first as usize, count as usize);
+    }
+
+    unsafe fn gl_clear_color(&self, r: f32, g: f32, b: f32, a: f32)
+    {
+        self.state.borrow_mut().clear_color = [r, g, b, a];
+    }
+
+    unsafe fn gl_clear_stencil(&self, value: GLint)
+    {
+        self.state.borrow_mut().clear_stencil = value.clamp(0, 255) as u8;
+    }
+
+    unsafe fn gl_clear(&self, mask: GLenum)
+    {
+        self.state.borrow_mut().clear(mask);
+    }
+
+    unsafe fn gl_enable_debug_message_callback(&self)
+    {
+        log::info!("Software renderer does not support debug message callbacks");
+    }
+
+    unsafe fn gl_get_string(&self, parameter: GLenum) -> String
+    {
+        match parameter {
+            GL_VERSION => "2.0 Speedy2D software renderer".to_string(),
+            _ => String::new()
+        }
+    }
+
+    unsafe fn gl_viewport(&self, x: i32, y: i32, width: i32, height: i32)
+    {
+        let mut state = self.state.borrow_mut();
+
+        if width < 0 || height < 0 {
+            state.set_error(GL_INVALID_VALUE);
+            return;
+        }
+
+        state.viewport = [x, y, width, height];
+
+        // The default framebuffer stands in for a window, so it follows the
+        // viewport size
+        if state.bound_framebuffer.is_none() {
+            state
+                .framebuffer
+                .resize((x.max(0) + width) as usize, (y.max(0) + height) as usize);
+        }
+    }
+
+    unsafe fn gl_scissor(&self, x: GLint, y: GLint, width: GLsizei, height: GLsizei)
+    {
+        self.state.borrow_mut().scissor = [x, y, width, height];
+    }
+
+    unsafe fn gl_pixel_store_i(&self, param: GLenum, value: GLint)
+    {
+        let mut state = self.state.borrow_mut();
+
+        match (param, value) {
+            (GL_UNPACK_ALIGNMENT, 1 | 2 | 4 | 8) => {
+                state.unpack_alignment = value as usize
+            }
+            _ => state.set_error(GL_INVALID_VALUE)
+        }
+    }
+
+    unsafe fn gl_vertex_attrib_pointer_f32(
+        &self,
+        index: GLuint,
+        size: GLsizei,
+        data_type: GLenum,
+        _normalized: bool,
+        stride: GLsizei,
+        offset: GLsizei
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if data_type != GL_FLOAT {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        let buffer = match state.bound_array_buffer {
+            None => {
+                state.set_error(GL_INVALID_OPERATION);
+                return;
+            }
+            Some(buffer) => buffer
+        };
+
+        state.attribute_pointers.insert(
+            index,
+            AttributePointer {
+                buffer,
+                size: size as usize,
+                stride: stride as usize,
+                offset: offset as usize
+            }
+        );
+    }
+
+    unsafe fn gl_tex_image_2d(
+        &self,
+        target: GLenum,
+        _level: GLint,
+        _internal_format: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        _border: GLint,
+        format: GLenum,
+        data_type: GLenum,
+        pixels: Option<&[u8]>
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_TEXTURE_2D || data_type != GL_UNSIGNED_BYTE {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        let width = width as usize;
+        let height = height as usize;
+
+        let data = match pixels {
+            None => Some(vec![0; width * height * 4]),
+            Some(pixels) => {
+                convert_to_rgba(format, width, height, state.unpack_alignment, pixels)
+            }
+        };
+
+        let data = match data {
+            None => {
+                state.set_error(GL_INVALID_VALUE);
+                return;
+            }
+            Some(data) => data
+        };
+
+        match state.bound_texture_mut() {
+            None => state.set_error(GL_INVALID_OPERATION),
+            Some(texture) => {
+                texture.width = width;
+                texture.height = height;
+                texture.data = data;
+            }
+        }
+    }
+
+    unsafe fn gl_tex_sub_image_2d(
+        &self,
+        target: GLenum,
+        _level: GLint,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        data_type: GLenum,
+        pixels: &[u8]
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_TEXTURE_2D || data_type != GL_UNSIGNED_BYTE {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        let (width, height) = (width as usize, height as usize);
+
+        let data = match convert_to_rgba(
+            format,
+            width,
+            height,
+            state.unpack_alignment,
+            pixels
+        ) {
+            None => {
+                state.set_error(GL_INVALID_VALUE);
+                return;
+            }
+            Some(data) => data
+        };
+
+        let texture = match state.bound_texture_mut() {
+            Some(texture)
+                if x + width <= texture.width && y + height <= texture.height =>
+            {
+                texture
+            }
+            _ => {
+                state.set_error(GL_INVALID_VALUE);
+                return;
+            }
+        };
+
+        for row in 0..height {
+            let src = row * width * 4;
+            let dst = ((y + row) * texture.width + x) * 4;
+            texture.data[dst..dst + width * 4]
+                .copy_from_slice(&data[src..src + width * 4]);
+        }
+    }
+
+    unsafe fn gl_create_program(
+        &self
+    ) -> Result<GLTypeProgram, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+
+        state.programs.insert(
+            handle,
+            SoftwareProgram {
+                shaders: Vec::new(),
+                linked: false,
+                attribute_locations: HashMap::new(),
+                uniform_locations: HashMap::new(),
+                uniform_values: HashMap::new()
+            }
+        );
+
+        Ok(handle)
+    }
+
+    unsafe fn gl_create_shader(
+        &self,
+        shader_type: GLenum
+    ) -> Result<GLTypeShader, BacktraceError<ErrorMessage>>
+    {
+        if shader_type != GL_VERTEX_SHADER && shader_type != GL_FRAGMENT_SHADER {
+            return Err(ErrorMessage::msg(format!(
+                "Failed to create shader: invalid type {shader_type}"
+            )));
+        }
+
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+
+        state.shaders.insert(
+            handle,
+            SoftwareShader {
+                supported: false,
+                compiled: false
+            }
+        );
+
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_buffer(&self) -> Result<GLTypeBuffer, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state.buffers.insert(handle, Vec::new());
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_texture(&self)
+        -> Result<GLTypeTexture, BacktraceError<ErrorMessage>>
+    {
+        Ok(self.state.borrow_mut().new_handle())
+    }
+
+    unsafe fn gl_gen_framebuffer(
+        &self
+    ) -> Result<GLTypeFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state
+            .framebuffers
+            .insert(handle, SoftwareAttachments::default());
+        Ok(handle)
+    }
+
+    unsafe fn gl_gen_renderbuffer(
+        &self
+    ) -> Result<GLTypeRenderbuffer, BacktraceError<ErrorMessage>>
+    {
+        let mut state = self.state.borrow_mut();
+        let handle = state.new_handle();
+        state
+            .renderbuffers
+            .insert(handle, SoftwareRenderbuffer::default());
+        Ok(handle)
+    }
+
+    unsafe fn gl_get_error(&self) -> GLenum
+    {
+        std::mem::replace(&mut self.state.borrow_mut().error, GL_NO_ERROR)
+    }
+
+    unsafe fn gl_check_framebuffer_status(&self, target: GLenum) -> GLenum
+    {
+        let mut state = self.state.borrow_mut();
+
+        if target != GL_FRAMEBUFFER {
+            state.set_error(GL_INVALID_ENUM);
+            return 0;
+        }
+
+        match state.render_target_attachments() {
+            Ok(None) => GL_FRAMEBUFFER_COMPLETE,
+            Ok(Some(attachments)) => {
+                let texture = &state.textures[&attachments.color.unwrap()];
+
+                let stencil_complete = match attachments.stencil {
+                    None => true,
+                    Some(handle) => {
+                        state
+                            .renderbuffers
+                            .get(&handle)
+                            .is_some_and(|renderbuffer| {
+                                renderbuffer.width == texture.width
+                                    && renderbuffer.height == texture.height
+                            })
+                    }
+                };
+
+                if texture.width > 0 && stencil_complete {
+                    GL_FRAMEBUFFER_COMPLETE
+                } else {
+                    GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT
+                }
+            }
+            Err(_) => GL_FRAMEBUFFER_INCOMPLETE_ATTACHMENT
+        }
+    }
+
+    unsafe fn gl_get_attrib_location(
+        &self,
+        program: GLTypeProgram,
+        name: &str
+    ) -> Option<GLuint>
+    {
+        self.state
+            .borrow()
+            .programs
+            .get(&program)
+            .and_then(|program| program.attribute_locations.get(name))
+            .copied()
+    }
+
+    unsafe fn gl_get_uniform_location(
+        &self,
+        program: GLTypeProgram,
+        name: &str
+    ) -> Option<GLTypeUniformLocation>
+    {
+        self.state
+            .borrow()
+            .programs
+            .get(&program)
+            .and_then(|program| program.uniform_locations.get(name))
+            .copied()
+    }
+
+    unsafe fn gl_get_program_link_status(&self, program: GLTypeProgram) -> bool
+    {
+        self.state
+            .borrow()
+            .programs
+            .get(&program)
+            .is_some_and(|program| program.linked)
+    }
+
+    unsafe fn gl_get_shader_compile_status(&self, shader: GLTypeShader) -> bool
+    {
+        self.state
+            .borrow()
+            .shaders
+            .get(&shader)
+            .is_some_and(|shader| shader.compiled)
+    }
+
+    unsafe fn gl_get_program_info_log(
+        &self,
+        _program: GLTypeProgram
+    ) -> Result<String, BacktraceError<ErrorMessage>>
+    {
+        Ok(String::new())
+    }
+
+    unsafe fn gl_get_shader_info_log(
+        &self,
+        shader: GLTypeShader
+    ) -> Result<String, BacktraceError<ErrorMessage>>
+    {
+        match self.state.borrow().shaders.get(&shader) {
+            Some(shader) if !shader.compiled => Ok(String::from(
+                "The software renderer only supports the built-in shaders"
+            )),
+            _ => Ok(String::new())
+        }
+    }
+
+    unsafe fn gl_read_pixels(
+        &self,
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        data_type: GLenum,
+        data: &mut [MaybeUninit<u8>]
+    )
+    {
+        let mut state = self.state.borrow_mut();
+
+        let bytes_per_pixel = match format {
+            GL_RGB => 3,
+            GL_RGBA => 4,
+            _ => {
+                state.set_error(GL_INVALID_ENUM);
+                return;
+            }
+        };
+
+        if data_type != GL_UNSIGNED_BYTE {
+            state.set_error(GL_INVALID_ENUM);
+            return;
+        }
+
+        let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+
+        if data.len() < width * height * bytes_per_pixel {
+            state.set_error(GL_INVALID_OPERATION);
+            return;
+        }
+
+        let framebuffer = match state.take_render_target() {
+            None => return,
+            Some(target) => target
+        };
+
+        for row in 0..height {
+            for col in 0..width {
+                let fx = x + col as GLint;
+                let fy = y + row as GLint;
+
+                let pixel = if fx >= 0
+                    && fy >= 0
+                    && (fx as usize) < framebuffer.width
+                    && (fy as usize) < framebuffer.height
+                {
+                    let pos = (fy as usize * framebuffer.width + fx as usize) * 4;
+                    &framebuffer.data[pos..pos + 4]
+                } else {
+                    &[0, 0, 0, 0]
+                };
+
+                let out = (row * width + col) * bytes_per_pixel;
+
+                for i in 0..bytes_per_pixel {
+                    data[out + i] = MaybeUninit::new(pixel[i]);
+                }
+            }
+        }
+
+        state.restore_render_target(framebuffer);
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::color::Color;
+    use crate::image::{ImageDataType, ImageSmoothingMode, RawBitmapData};
+    use crate::shape::{Polygon, Rectangle};
+    use crate::GLRenderer;
+
+    fn pixel_at(image: &RawBitmapData, x: u32, y: u32) -> [u8; 4]
+    {
+        let pos = ((y * image.size().x + x) * 4) as usize;
+        image.data()[pos..pos + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn test_draw_rectangles()
+    {
+        let mut renderer = GLRenderer::new_for_software_rendering((50, 50)).unwrap();
+
+        let image = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::BLUE);
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((10.0, 20.0), (30.0, 40.0)),
+                Color::MAGENTA
+            );
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 0.0), (5.0, 5.0)),
+                Color::from_rgba(1.0, 1.0, 1.0, 0.5)
+            );
+            graphics.capture(ImageDataType::RGBA)
+        });
+
+        assert_eq!(image.size(), (50, 50).into());
+
+        assert_eq!(pixel_at(&image, 10, 20), [255, 0, 255, 255]);
+        assert_eq!(pixel_at(&image, 29, 39), [255, 0, 255, 255]);
+        assert_eq!(pixel_at(&image, 30, 39), [0, 0, 255, 255]);
+        assert_eq!(pixel_at(&image, 29, 40), [0, 0, 255, 255]);
+        assert_eq!(pixel_at(&image, 9, 20), [0, 0, 255, 255]);
+
+        assert_eq!(pixel_at(&image, 2, 2), [128, 128, 255, 255]);
+    }
+
+    #[test]
+    fn test_draw_circle_and_clip()
+    {
+        let mut renderer = GLRenderer::new_for_software_rendering((40, 40)).unwrap();
+
+        let image = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::WHITE);
+            graphics.set_clip(Some(Rectangle::from_tuples((0, 0), (20, 40))));
+            graphics.draw_circle((20.0, 20.0), 10.0, Color::RED);
+            graphics.capture(ImageDataType::RGBA)
+        });
+
+        assert_eq!(pixel_at(&image, 15, 20), [255, 0, 0, 255]);
+        assert_eq!(pixel_at(&image, 11, 11), [255, 255, 255, 255]);
+        assert_eq!(pixel_at(&image, 25, 20), [255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_clip_stack()
+    {
+        let mut renderer = GLRenderer::new_for_software_rendering((40, 40)).unwrap();
+
+        let full = Rectangle::from_tuples((0.0, 0.0), (40.0, 40.0));
+
+        renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::WHITE);
+
+            graphics.push_clip(Rectangle::from_tuples((0, 0), (20, 20)));
+            graphics.push_clip(Rectangle::from_tuples((10, 10), (40, 40)));
+            graphics.draw_rectangle(full.clone(), Color::RED);
+            graphics.pop_clip();
+            graphics.pop_clip();
+            graphics.pop_clip();
+
+            graphics.push_clip_circle((30.0, 30.0), 8.0);
+            graphics.push_clip_polygon(
+                &Polygon::new(&[(0.0, 0.0), (40.0, 0.0), (40.0, 30.0), (0.0, 30.0)]),
+                (0.0, 0.0)
+            );
+            graphics.draw_rectangle(full.clone(), Color::BLUE);
+            graphics.pop_clip();
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 34.0), (40.0, 40.0)),
+                Color::GREEN
+            );
+
+            // Not popped, but reset at the start of the next frame
+            graphics.push_clip_circle((0.0, 0.0), 1.0);
+        });
+
+        // The framebuffer contents are preserved between frames
+        let image = renderer.draw_frame(|graphics| {
+            graphics.draw_rectangle(
+                Rectangle::from_tuples((0.0, 0.0), (40.0, 2.0)),
+                Color::BLACK
+            );
+            graphics.capture(ImageDataType::RGBA)
+        });
+
+        assert_eq!(pixel_at(&image, 15, 15), [255, 0, 0, 255]);
+        assert_eq!(pixel_at(&image, 5, 15), [255, 255, 255, 255]);
+        assert_eq!(pixel_at(&image, 25, 15), [255, 255, 255, 255]);
+
+        assert_eq!(pixel_at(&image, 30, 26), [0, 0, 255, 255]);
+        assert_eq!(pixel_at(&image, 30, 31), [255, 255, 255, 255]);
+        assert_eq!(pixel_at(&image, 30, 35), [0, 255, 0, 255]);
+        assert_eq!(pixel_at(&image, 10, 35), [255, 255, 255, 255]);
+
+        assert_eq!(pixel_at(&image, 39, 1), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_draw_image()
+    {
+        let mut renderer = GLRenderer::new_for_software_rendering((4, 2)).unwrap();
+
+        let pixels = [255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255];
+
+        let handle = renderer
+            .create_image_from_raw_pixels(
+                ImageDataType::RGB,
+                ImageSmoothingMode::NearestNeighbor,
+                (2, 2).into(),
+                &pixels
+            )
+            .unwrap();
+
+        let image = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::BLACK);
+            graphics.draw_image((2.0, 0.0), &handle);
+            graphics.capture(ImageDataType::RGB)
+        });
+
+        assert_eq!(
+            image.data(),
+            &vec![
+                0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 255, 255,
+                255, 255,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_shader_unsupported()
+    {
+        let mut renderer = GLRenderer::new_for_software_rendering((10, 10)).unwrap();
+
+        let error = renderer
+            .create_fragment_shader("#version 110\nvoid main(void) {}")
+            .unwrap_err();
+
+        assert!(format!("{error:?}").contains("only supports the built-in shaders"));
+    }
+
+    #[test]
+    fn test_draw_to_image()
+    {
+        let mut renderer = GLRenderer::new_for_software_rendering((8, 8)).unwrap();
+
+        let (inner, outer) = renderer.draw_frame(|graphics| {
+            graphics.clear_screen(Color::BLACK);
+            graphics.set_clip(Some(Rectangle::from_tuples((0, 0), (6, 8))));
+
+            let mut inner = None;
+
+            let image = graphics
+                .draw_to_image((4, 4), ImageSmoothingMode::NearestNeighbor, |graphics| {
+                    graphics.draw_rectangle(
+                        Rectangle::from_tuples((0.0, 0.0), (4.0, 2.0)),
+                        Color::RED
+                    );
+                    graphics.draw_rectangle(
+                        Rectangle::from_tuples((0.0, 2.0), (4.0, 4.0)),
+                        Color::BLUE
+                    );
+
+                    graphics.set_clip(Some(Rectangle::from_tuples((0, 0), (1, 1))));
+                    graphics.clear_screen(Color::GREEN);
+
+                    inner = Some(graphics.capture(ImageDataType::RGBA));
+                })
+                .unwrap();
+
+            assert_eq!(image.size(), &(4, 4).into());
+
+            graphics.draw_image((4.0, 2.0), &image);
+
+            (inner.unwrap(), graphics.capture(ImageDataType::RGBA))
+        });
+
+        assert_eq!(inner.size(), (4, 4).into());
+        assert_eq!(pixel_at(&inner, 0, 0), [0, 255, 0, 255]);
+        assert_eq!(pixel_at(&inner, 1, 0), [255, 0, 0, 255]);
+        assert_eq!(pixel_at(&inner, 0, 3), [0, 0, 255, 255]);
+
+        assert_eq!(outer.size(), (8, 8).into());
+        assert_eq!(pixel_at(&outer, 4, 2), [0, 255, 0, 255]);
+        assert_eq!(pixel_at(&outer, 5, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel_at(&outer, 5, 5), [0, 0, 255, 255]);
+        assert_eq!(pixel_at(&outer, 6, 2), [0, 0, 0, 255]);
+        assert_eq!(pixel_at(&outer, 3, 2), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_convert_to_rgba()
+    {
+        assert_eq!(
+            convert_to_rgba(GL_RGB, 1, 2, 4, &[1, 2, 3, 0, 4, 5, 6, 0]),
+            Some(vec![1, 2, 3, 255, 4, 5, 6, 255])
+        );
+
+        assert_eq!(
+            convert_to_rgba(GL_RED, 2, 1, 1, &[7, 8]),
+            Some(vec![7, 0, 0, 255, 8, 0, 0, 255])
+        );
+
+        assert_eq!(convert_to_rgba(GL_RGBA, 2, 2, 4, &[0; 8]), None);
+    }
+
+    #[test]
+    fn test_texture_sample()
+    {
+        let mut texture = SoftwareTexture {
+            width: 2,
+            height: 1,
+            data: vec![0, 0, 0, 255, 255, 255, 255, 255],
+            mag_filter: GL_NEAREST
+        };
+
+        assert_eq!(texture.sample(0.25, 0.5), [0.0, 0.0, 0.0, 1.0]);
+        assert_eq!(texture.sample(0.75, 0.5), [1.0, 1.0, 1.0, 1.0]);
+
+        texture.mag_filter = GL_LINEAR;
+
+        assert_eq!(texture.sample(0.5, 0.5), [0.5, 0.5, 0.5, 1.0]);
+        assert_eq!(texture.sample(0.0, 0.5), [0.0, 0.0, 0.0, 1.0]);
+    }
+}
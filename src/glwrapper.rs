@@ -23,13 +23,15 @@ use std::num::TryFromIntError;
 use std::ptr;
 use std::rc::{Rc, Weak};
 
-use crate::color::Color;
+use crate::color::{BlendMode, Color};
 use crate::dimen::UVec2;
 use crate::error::{BacktraceError, Context, ErrorMessage};
 use crate::glbackend::constants::*;
 use crate::glbackend::types::{
     GLTypeBuffer,
+    GLTypeFramebuffer,
     GLTypeProgram,
+    GLTypeRenderbuffer,
     GLTypeShader,
     GLTypeTexture,
     GLTypeUniformLocation,
@@ -79,7 +81,9 @@ enum GLHandleType
     Program,
     Shader,
     Buffer,
-    Texture
+    Texture,
+    Framebuffer,
+    Renderbuffer
 }
 
 trait GLHandleId: Debug + Hash + PartialEq + Eq
@@ -112,6 +116,18 @@ struct GLHandleTypeTexture
     handle: GLTypeTexture
 }
 
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct GLHandleTypeFramebuffer
+{
+    handle: GLTypeFramebuffer
+}
+
+#[derive(Debug, Hash, PartialEq, Eq)]
+struct GLHandleTypeRenderbuffer
+{
+    handle: GLTypeRenderbuffer
+}
+
 struct GLHandle<HandleType: GLHandleId>
 {
     context: Weak<RefCell<GLContextManagerState>>,
@@ -177,6 +193,8 @@ impl<HandleType: GLHandleId> GLHandle<HandleType>
             GLHandleType::Shader => gl_clear_and_log_old_error(context),
             GLHandleType::Buffer => {}
             GLHandleType::Texture => {}
+            GLHandleType::Framebuffer => {}
+            GLHandleType::Renderbuffer => {}
         }
 
         let handle = handle_creator().context("Handle creation failed")?;
@@ -186,6 +204,8 @@ impl<HandleType: GLHandleId> GLHandle<HandleType>
             GLHandleType::Shader => gl_check_error_always(context)?,
             GLHandleType::Buffer => {}
             GLHandleType::Texture => {}
+            GLHandleType::Framebuffer => {}
+            GLHandleType::Renderbuffer => {}
         }
 
         Ok(GLHandle {
@@ -257,6 +277,30 @@ impl GLHandleId for GLHandleTypeTexture
     }
 }
 
+impl GLHandleId for GLHandleTypeFramebuffer
+{
+    type HandleRawType = GLTypeFramebuffer;
+
+    fn delete(&self, context: &GLContextManager)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_delete_framebuffer(self.handle)
+        });
+    }
+}
+
+impl GLHandleId for GLHandleTypeRenderbuffer
+{
+    type HandleRawType = GLTypeRenderbuffer;
+
+    fn delete(&self, context: &GLContextManager)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_delete_renderbuffer(self.handle)
+        });
+    }
+}
+
 #[derive(Debug)]
 pub struct GLProgram
 {
@@ -335,7 +379,20 @@ impl GLProgram
         program.attach_shader(context, vertex_shader)?;
         program.attach_shader(context, fragment_shader)?;
 
+        let attribute_names: Vec<&'static str> =
+            attribute_names.into_iter().copied().collect();
+
+        // Bind the attributes to the same locations in every program, so that
+        // programs sharing a vertex shader can share vertex buffers.
         context.with_gl_backend(|backend| unsafe {
+            for (index, attribute_name) in attribute_names.iter().enumerate() {
+                backend.gl_bind_attrib_location(
+                    program.get_handle(),
+                    index as GLuint,
+                    attribute_name
+                );
+            }
+
             backend.gl_link_program(program.get_handle());
         });
 
@@ -354,11 +411,15 @@ impl GLProgram
 
         gl_check_error_always(context)?;
 
-        for attribute_name in attribute_names.into_iter() {
-            program.attribute_handles.insert(
-                attribute_name.as_ref(),
-                program.get_attribute_handle(attribute_name.as_ref())?
-            );
+        for attribute_name in attribute_names {
+            // Attributes which are not used by the shaders may be removed
+            // during linking.
+            match program.get_attribute_handle(attribute_name) {
+                Ok(handle) => {
+                    program.attribute_handles.insert(attribute_name, handle);
+                }
+                Err(_) => log::info!("Attribute {attribute_name} is unused")
+            }
         }
 
         Ok(program)
@@ -522,7 +583,7 @@ pub struct GLAttributeHandle
     handle: GLuint
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GLUniformHandle
 {
     handle: GLTypeUniformLocation
@@ -543,6 +604,34 @@ impl GLUniformHandle
             backend.gl_uniform_1i(&self.handle, value)
         })
     }
+
+    pub fn set_value_vec2(&self, context: &GLContextManager, x: f32, y: f32)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_2f(&self.handle, x, y)
+        })
+    }
+
+    pub fn set_value_vec3(&self, context: &GLContextManager, x: f32, y: f32, z: f32)
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_3f(&self.handle, x, y, z)
+        })
+    }
+
+    pub fn set_value_vec4(
+        &self,
+        context: &GLContextManager,
+        x: f32,
+        y: f32,
+        z: f32,
+        w: f32
+    )
+    {
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_uniform_4f(&self.handle, x, y, z, w)
+        })
+    }
 }
 
 pub enum GLBufferTarget
@@ -727,6 +816,31 @@ impl GLTexture
         size: &UVec2,
         data: &[u8]
     ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.set_image_data_internal(context, format, smoothing, size, Some(data))
+    }
+
+    /// Allocates storage for the image without uploading any pixel data. The
+    /// initial contents of the texture are undefined.
+    pub fn allocate_image_data(
+        &self,
+        context: &GLContextManager,
+        format: GLTextureImageFormatU8,
+        smoothing: GLTextureSmoothing,
+        size: &UVec2
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        self.set_image_data_internal(context, format, smoothing, size, None)
+    }
+
+    fn set_image_data_internal(
+        &self,
+        context: &GLContextManager,
+        format: GLTextureImageFormatU8,
+        smoothing: GLTextureSmoothing,
+        size: &UVec2,
+        data: Option<&[u8]>
+    ) -> Result<(), BacktraceError<ErrorMessage>>
     {
         if !context.is_valid() {
             log::warn!("Ignoring texture set_image_data: invalid GL context");
@@ -788,7 +902,7 @@ impl GLTexture
                     0,
                     format.get_format(),
                     GL_UNSIGNED_BYTE,
-                    Some(data)
+                    data
                 );
 
                 Ok(())
@@ -797,6 +911,181 @@ impl GLTexture
     }
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct GLFramebuffer
+{
+    handle: Rc<GLHandle<GLHandleTypeFramebuffer>>
+}
+
+impl GLHandleOwner<GLHandleTypeFramebuffer> for GLFramebuffer
+{
+    fn get_handle(&self) -> <GLHandleTypeFramebuffer as GLHandleId>::HandleRawType
+    {
+        self.handle.handle.handle
+    }
+}
+
+impl GLFramebuffer
+{
+    fn new(context: &GLContextManager) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let handle = GLHandle::wrap(context, GLHandleType::Framebuffer, || {
+            context.with_gl_backend(|backend| unsafe {
+                Ok(GLHandleTypeFramebuffer {
+                    handle: backend.gl_gen_framebuffer()?
+                })
+            })
+        })?;
+
+        Ok(GLFramebuffer {
+            handle: Rc::new(handle)
+        })
+    }
+
+    /// Attaches the texture as the color buffer of this framebuffer. The
+    /// framebuffer must currently be bound.
+    pub fn attach_texture(
+        &self,
+        context: &GLContextManager,
+        texture: &GLTexture
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        context.ensure_valid()?;
+
+        gl_clear_and_log_old_error(context);
+
+        let status = context.with_gl_backend(|backend| unsafe {
+            backend.gl_framebuffer_texture_2d(
+                GL_FRAMEBUFFER,
+                GL_COLOR_ATTACHMENT0,
+                GL_TEXTURE_2D,
+                texture.get_handle(),
+                0
+            );
+
+            backend.gl_check_framebuffer_status(GL_FRAMEBUFFER)
+        });
+
+        gl_check_error_always(context)?;
+
+        if status != GL_FRAMEBUFFER_COMPLETE {
+            return Err(ErrorMessage::msg(format!(
+                "Framebuffer is incomplete (status 0x{status:x})"
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Attaches the renderbuffer as the depth and stencil buffer of this
+    /// framebuffer. The framebuffer must currently be bound. If the
+    /// framebuffer is incomplete as a result, the renderbuffer is detached
+    /// again and an error is returned.
+    pub fn attach_stencil_renderbuffer(
+        &self,
+        context: &GLContextManager,
+        renderbuffer: &GLRenderbuffer
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        context.ensure_valid()?;
+
+        gl_clear_and_log_old_error(context);
+
+        let status = context.with_gl_backend(|backend| unsafe {
+            backend.gl_framebuffer_renderbuffer(
+                GL_FRAMEBUFFER,
+                GL_DEPTH_STENCIL_ATTACHMENT,
+                GL_RENDERBUFFER,
+                Some(renderbuffer.get_handle())
+            );
+
+            backend.gl_check_framebuffer_status(GL_FRAMEBUFFER)
+        });
+
+        let result = gl_check_error_always(context);
+
+        if result.is_err() || status != GL_FRAMEBUFFER_COMPLETE {
+            context.with_gl_backend(|backend| unsafe {
+                backend.gl_framebuffer_renderbuffer(
+                    GL_FRAMEBUFFER,
+                    GL_DEPTH_STENCIL_ATTACHMENT,
+                    GL_RENDERBUFFER,
+                    None
+                );
+            });
+
+            result?;
+
+            return Err(ErrorMessage::msg(format!(
+                "Framebuffer is incomplete with stencil buffer (status 0x{status:x})"
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct GLRenderbuffer
+{
+    handle: Rc<GLHandle<GLHandleTypeRenderbuffer>>
+}
+
+impl GLHandleOwner<GLHandleTypeRenderbuffer> for GLRenderbuffer
+{
+    fn get_handle(&self) -> <GLHandleTypeRenderbuffer as GLHandleId>::HandleRawType
+    {
+        self.handle.handle.handle
+    }
+}
+
+impl GLRenderbuffer
+{
+    fn new(context: &GLContextManager) -> Result<Self, BacktraceError<ErrorMessage>>
+    {
+        let handle = GLHandle::wrap(context, GLHandleType::Renderbuffer, || {
+            context.with_gl_backend(|backend| unsafe {
+                Ok(GLHandleTypeRenderbuffer {
+                    handle: backend.gl_gen_renderbuffer()?
+                })
+            })
+        })?;
+
+        Ok(GLRenderbuffer {
+            handle: Rc::new(handle)
+        })
+    }
+
+    /// Allocates storage for a combined depth and stencil buffer of the
+    /// specified size. The combined format is used because it is the most
+    /// widely supported way of obtaining a stencil buffer.
+    pub fn allocate_stencil_storage(
+        &self,
+        context: &GLContextManager,
+        size: &UVec2
+    ) -> Result<(), BacktraceError<ErrorMessage>>
+    {
+        context.ensure_valid()?;
+
+        gl_clear_and_log_old_error(context);
+
+        context.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_renderbuffer(GL_RENDERBUFFER, Some(self.get_handle()));
+            backend.gl_renderbuffer_storage(
+                GL_RENDERBUFFER,
+                GL_DEPTH24_STENCIL8,
+                size.x.try_into()?,
+                size.y.try_into()?
+            );
+            backend.gl_bind_renderbuffer(GL_RENDERBUFFER, None);
+
+            Ok::<(), BacktraceError<ErrorMessage>>(())
+        })?;
+
+        gl_check_error_always(context)
+    }
+}
+
 #[must_use]
 fn obtain_context_if_valid(
     state: &RefCell<GLContextManagerState>
@@ -829,8 +1118,10 @@ struct GLContextManagerState
 {
     is_valid: bool,
     active_texture: Option<GLTexture>,
+    active_framebuffer: Option<GLFramebuffer>,
     active_program: Option<Rc<GLProgram>>,
     active_blend_mode: Option<GLBlendEnabled>,
+    active_stencil_mode: Option<GLStencilMode>,
     viewport_size: Option<UVec2>,
     scissor_enabled: bool,
     gl_backend: Rc<dyn GLBackend + 'static>,
@@ -863,8 +1154,10 @@ impl GLContextManager
             state: Rc::new(RefCell::new(GLContextManagerState {
                 is_valid: true,
                 active_texture: None,
+                active_framebuffer: None,
                 active_program: None,
                 active_blend_mode: None,
+                active_stencil_mode: None,
                 viewport_size: None,
                 scissor_enabled: false,
                 gl_backend,
@@ -931,6 +1224,19 @@ impl GLContextManager
         GLTexture::new(self)
     }
 
+    pub fn new_framebuffer(&self) -> Result<GLFramebuffer, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+        GLFramebuffer::new(self)
+    }
+
+    pub fn new_renderbuffer(&self)
+        -> Result<GLRenderbuffer, BacktraceError<ErrorMessage>>
+    {
+        self.ensure_valid()?;
+        GLRenderbuffer::new(self)
+    }
+
     pub fn set_viewport_size(&self, size: UVec2)
     {
         if !self.is_valid() {
@@ -967,37 +1273,96 @@ impl GLContextManager
 
         self.with_gl_backend(|backend| unsafe {
             backend.gl_active_texture(GL_TEXTURE0);
-            backend.gl_bind_texture(GL_TEXTURE_2D, texture.get_handle());
+            backend.gl_bind_texture(GL_TEXTURE_2D, Some(texture.get_handle()));
         });
     }
 
     pub fn unbind_texture(&self)
     {
-        #[cfg(not(target_arch = "wasm32"))]
+        if !self.is_valid() {
+            log::warn!("Ignoring unbind_texture: invalid GL context");
+            return;
+        }
+
+        if RefCell::borrow(&self.state)
+            .active_texture
+            .as_ref()
+            .is_none()
         {
-            if !self.is_valid() {
-                log::warn!("Ignoring unbind_texture: invalid GL context");
-                return;
-            }
+            // Already unbound
+            return;
+        }
 
-            if RefCell::borrow(&self.state)
-                .active_texture
-                .as_ref()
-                .is_none()
-            {
-                // Already unbound
-                return;
-            }
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_active_texture(GL_TEXTURE0);
+            backend.gl_bind_texture(GL_TEXTURE_2D, None);
+        });
 
-            self.with_gl_backend(|backend| unsafe {
-                backend.gl_active_texture(GL_TEXTURE0);
-                backend.gl_bind_texture(GL_TEXTURE_2D, 0);
-            });
+        // Drop separately to avoid a duplicate borrow of `state`.
+        let old_texture = RefCell::borrow_mut(&self.state).active_texture.take();
+        drop(old_texture);
+    }
 
-            // Drop separately to avoid a duplicate borrow of `state`.
-            let old_texture = RefCell::borrow_mut(&self.state).active_texture.take();
-            drop(old_texture);
+    /// Binds a texture to a texture unit other than unit 0, for sampling in
+    /// custom shaders. Unlike `bind_texture`, the binding is not cached, and
+    /// texture unit 0 remains the active unit afterwards.
+    pub fn bind_texture_to_unit(&self, unit: u32, texture: &GLTexture)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring bind_texture_to_unit: invalid GL context");
+            return;
         }
+
+        if unit == 0 {
+            self.bind_texture(texture);
+            return;
+        }
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_active_texture(GL_TEXTURE0 + unit);
+            backend.gl_bind_texture(GL_TEXTURE_2D, Some(texture.get_handle()));
+            backend.gl_active_texture(GL_TEXTURE0);
+        });
+    }
+
+    /// Binds the specified framebuffer as the render target, or the default
+    /// framebuffer if `None` is passed.
+    pub fn bind_framebuffer(&self, framebuffer: Option<&GLFramebuffer>)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring bind_framebuffer: invalid GL context");
+            return;
+        }
+
+        if RefCell::borrow(&self.state).active_framebuffer.as_ref() == framebuffer {
+            // Already bound
+            return;
+        }
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_bind_framebuffer(
+                GL_FRAMEBUFFER,
+                framebuffer.map(|framebuffer| framebuffer.get_handle())
+            );
+        });
+
+        // Drop separately to avoid a duplicate borrow of `state`.
+        let old_framebuffer = std::mem::replace(
+            &mut RefCell::borrow_mut(&self.state).active_framebuffer,
+            framebuffer.cloned()
+        );
+        drop(old_framebuffer);
+    }
+
+    pub fn active_framebuffer(&self) -> Option<GLFramebuffer>
+    {
+        RefCell::borrow(&self.state).active_framebuffer.clone()
+    }
+
+    /// Returns true if rendering currently goes to the default framebuffer.
+    fn is_default_framebuffer_bound(&self) -> bool
+    {
+        RefCell::borrow(&self.state).active_framebuffer.is_none()
     }
 
     pub fn use_program(&self, program: &Rc<GLProgram>)
@@ -1012,7 +1377,10 @@ impl GLContextManager
             return;
         }
 
-        if let Some(existing_program) = &RefCell::borrow_mut(&self.state).active_program {
+        // Take the existing program first, as disabling it borrows `state`
+        let existing_program = RefCell::borrow_mut(&self.state).active_program.take();
+
+        if let Some(existing_program) = existing_program {
             existing_program.disable(self);
         }
 
@@ -1029,17 +1397,31 @@ impl GLContextManager
         RefCell::borrow_mut(&self.state).active_blend_mode = Some(blend_mode.clone());
 
         match blend_mode {
-            GLBlendEnabled::Enabled(mode) => match mode {
-                GLBlendMode::OneMinusSrcAlpha => self.with_gl_backend(|backend| unsafe {
+            GLBlendEnabled::Enabled(mode) => {
+                // The Multiply and Screen modes expect the shader output to be
+                // premultiplied by alpha
+                let (src_rgb, dst_rgb) = match mode {
+                    GLBlendMode::OneMinusSrcAlpha => {
+                        (GL_SRC_ALPHA, GL_ONE_MINUS_SRC_ALPHA)
+                    }
+                    GLBlendMode::Additive => (GL_SRC_ALPHA, GL_ONE),
+                    GLBlendMode::Multiply => (GL_DST_COLOR, GL_ONE_MINUS_SRC_ALPHA),
+                    GLBlendMode::Screen => (GL_ONE, GL_ONE_MINUS_SRC_COLOR),
+                    GLBlendMode::PremultipliedOneMinusSrcAlpha => {
+                        (GL_ONE, GL_ONE_MINUS_SRC_ALPHA)
+                    }
+                };
+
+                self.with_gl_backend(|backend| unsafe {
                     backend.gl_enable(GL_BLEND);
                     backend.gl_blend_func_separate(
-                        GL_SRC_ALPHA,
-                        GL_ONE_MINUS_SRC_ALPHA,
+                        src_rgb,
+                        dst_rgb,
                         GL_ONE,
                         GL_ONE_MINUS_SRC_ALPHA
                     );
                 })
-            },
+            }
 
             GLBlendEnabled::Disabled => self.with_gl_backend(|backend| unsafe {
                 backend.gl_disable(GL_BLEND);
@@ -1047,6 +1429,60 @@ impl GLContextManager
         }
     }
 
+    pub fn set_stencil_mode(&self, stencil_mode: GLStencilMode)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring set_stencil_mode: invalid GL context");
+            return;
+        }
+
+        if RefCell::borrow(&self.state).active_stencil_mode == Some(stencil_mode) {
+            return;
+        }
+
+        RefCell::borrow_mut(&self.state).active_stencil_mode = Some(stencil_mode);
+
+        let (reference, pass_op, write_color) = match stencil_mode {
+            GLStencilMode::Disabled => {
+                self.with_gl_backend(|backend| unsafe {
+                    backend.gl_disable(GL_STENCIL_TEST);
+                    backend.gl_color_mask(true, true, true, true);
+                });
+                return;
+            }
+            GLStencilMode::Test { reference } => (reference, GL_KEEP, true),
+            GLStencilMode::Increment { reference } => (reference, GL_INCR, false),
+            GLStencilMode::Decrement { reference } => (reference, GL_DECR, false)
+        };
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_enable(GL_STENCIL_TEST);
+            backend.gl_stencil_func(GL_EQUAL, reference.into(), 0xFF);
+            backend.gl_stencil_op(GL_KEEP, GL_KEEP, pass_op);
+            backend.gl_color_mask(write_color, write_color, write_color, write_color);
+        });
+    }
+
+    /// Sets the stencil value of every pixel to zero, ignoring the clip area.
+    pub fn clear_stencil(&self)
+    {
+        if !self.is_valid() {
+            log::warn!("Ignoring clear_stencil: invalid GL context");
+            return;
+        }
+
+        let scissor_enabled = self.state.borrow().scissor_enabled;
+
+        self.set_enable_scissor(false);
+
+        self.with_gl_backend(|backend| unsafe {
+            backend.gl_clear_stencil(0);
+            backend.gl_clear(GL_STENCIL_BUFFER_BIT);
+        });
+
+        self.set_enable_scissor(scissor_enabled);
+    }
+
     pub fn set_enable_scissor(&self, enabled: bool)
     {
         if enabled != self.state.borrow().scissor_enabled {
@@ -1060,14 +1496,24 @@ impl GLContextManager
         }
     }
 
+    /// Offscreen framebuffers are rendered upside down (see
+    /// `Renderer2D::begin_render_to_image`), so the clip rectangle is only
+    /// flipped for the default framebuffer.
     pub fn set_clip(&self, x: i32, y: i32, width: i32, height: i32)
     {
         let vp_height = match self.state.borrow().viewport_size {
             None => panic!("Call to set_clip before viewport size set"),
             Some(viewport_size) => viewport_size.y as i32
         };
+
+        let y = if self.is_default_framebuffer_bound() {
+            vp_height - y - height
+        } else {
+            y
+        };
+
         self.with_gl_backend(|backend| unsafe {
-            backend.gl_scissor(x, vp_height - y - height, width, height);
+            backend.gl_scissor(x, y, width, height);
         });
     }
 
@@ -1160,6 +1606,11 @@ impl GLContextManager
             buf.set_len(bytes);
         }
 
+        if !self.is_default_framebuffer_bound() {
+            // Offscreen framebuffers are already stored top row first
+            return RawBitmapData::new(buf, viewport_size, format);
+        }
+
         let row_bytes = width * bpp;
 
         let buf_ptr = buf.as_mut_ptr();
@@ -1186,13 +1637,59 @@ impl GLContextManager
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub enum GLBlendMode
 {
-    OneMinusSrcAlpha
+    OneMinusSrcAlpha,
+    Additive,
+    Multiply,
+    Screen,
+    PremultipliedOneMinusSrcAlpha
 }
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub enum GLBlendEnabled
 {
     Enabled(GLBlendMode),
-    #[allow(dead_code)]
     Disabled
 }
+
+/// Configures the stencil buffer, which is used to clip drawing operations
+/// to arbitrary shapes. Each nested shape increments the stencil value of
+/// the pixels inside it.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum GLStencilMode
+{
+    Disabled,
+    /// Only pixels whose stencil value equals `reference` are drawn.
+    Test
+    {
+        reference: u8
+    },
+    /// The stencil value of pixels equal to `reference` is incremented, and
+    /// no colors are written.
+    Increment
+    {
+        reference: u8
+    },
+    /// The stencil value of pixels equal to `reference` is decremented, and
+    /// no colors are written.
+    Decrement
+    {
+        reference: u8
+    }
+}
+
+impl From<BlendMode> for GLBlendEnabled
+{
+    fn from(blend_mode: BlendMode) -> Self
+    {
+        match blend_mode {
+            BlendMode::Normal => GLBlendEnabled::Enabled(GLBlendMode::OneMinusSrcAlpha),
+            BlendMode::Additive => GLBlendEnabled::Enabled(GLBlendMode::Additive),
+            BlendMode::Multiply => GLBlendEnabled::Enabled(GLBlendMode::Multiply),
+            BlendMode::Screen => GLBlendEnabled::Enabled(GLBlendMode::Screen),
+            BlendMode::Premultiplied => {
+                GLBlendEnabled::Enabled(GLBlendMode::PremultipliedOneMinusSrcAlpha)
+            }
+            BlendMode::Replace => GLBlendEnabled::Disabled
+        }
+    }
+}
@@ -32,6 +32,10 @@
 //! context. If you'd like to handle this yourself, and use Speedy2D only for
 //! rendering, you can disable the `windowing` feature.
 //!
+//! To display text in scripts which require complex shaping, such as Arabic
+//! or the Indic scripts, or to use the ligatures in a font, enable the
+//! optional `text-shaping` feature.
+//!
 //! # Useful Links
 //!
 //! * [Source repository](https://github.com/QuantumBadger/Speedy2D)
@@ -182,6 +186,27 @@
 //! });
 //! ```
 //!
+//! ## Alternative: Rendering without a GPU
+//!
+//! On machines without a GPU or OpenGL driver, such as servers and CI
+//! environments, [GLRenderer::new_for_software_rendering()] creates a
+//! renderer which rasterizes on the CPU into an in-memory buffer. The
+//! rendered pixels can be retrieved using [Graphics2D::capture()].
+//!
+//! ```rust
+//! use speedy2d::color::Color;
+//! use speedy2d::image::ImageDataType;
+//! use speedy2d::GLRenderer;
+//!
+//! let mut renderer = GLRenderer::new_for_software_rendering((640, 480)).unwrap();
+//!
+//! let image = renderer.draw_frame(|graphics| {
+//!     graphics.clear_screen(Color::WHITE);
+//!     graphics.draw_circle((100.0, 100.0), 75.0, Color::BLUE);
+//!     graphics.capture(ImageDataType::RGBA)
+//! });
+//! ```
+//!
 //! # Laying out text
 //!
 //! To render text, a font must be created. Call [font::Font::new()] with the
@@ -242,6 +267,10 @@
 //!     TextOptions::new().with_wrap_to_width(300.0, TextAlignment::Left));
 //! ```
 //!
+//! By default, lines are only wrapped at whitespace. To also wrap text such as
+//! Chinese, Japanese, or long URLs, pass [font::LineBreaking::Unicode] to
+//! [font::TextOptions::with_line_breaking()].
+//!
 //! # Loading images
 //!
 //! Image files (in formats such as PNG, JPG, and BMP) can be loaded using the
@@ -307,17 +336,32 @@ use {
     std::path::Path
 };
 
-use crate::color::Color;
-use crate::dimen::{UVec2, Vec2};
+use crate::color::{BlendMode, Color};
+use crate::dimen::{Affine2, UVec2, Vec2};
 use crate::error::{BacktraceError, ErrorMessage};
-use crate::font::FormattedTextBlock;
+use crate::font::{
+    BitmapFont,
+    FormattedTextBlock,
+    GlyphCacheOptions,
+    GlyphCacheStats,
+    TextAlignment,
+    TextLayout,
+    TextOptions,
+    TextRenderingMode,
+    TextStyle,
+    VerticalAlignment
+};
 use crate::glbackend::GLBackend;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::glbackend::GLBackendGlow;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::glbackend_software::GLBackendSoftware;
 use crate::glwrapper::{GLContextManager, GLVersion};
 use crate::image::{ImageDataType, ImageHandle, ImageSmoothingMode, RawBitmapData};
+use crate::paint::Paint;
 use crate::renderer2d::Renderer2D;
-use crate::shape::{Polygon, Rect, Rectangle};
+use crate::shader::{ShaderHandle, ShaderUniforms};
+use crate::shape::{Polygon, Rect, Rectangle, StrokeStyle};
 #[cfg(target_arch = "wasm32")]
 use crate::web::WebCanvasElement;
 #[cfg(any(doc, doctest, feature = "windowing"))]
@@ -350,6 +394,10 @@ pub mod shape;
 /// Components for loading fonts and laying out text.
 pub mod font;
 
+/// Finding and loading the fonts installed on the system.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod system_fonts;
+
 /// Types representing sizes and positions.
 pub mod dimen;
 
@@ -362,6 +410,12 @@ pub mod error;
 /// Types relating to images.
 pub mod image;
 
+/// Types describing how shapes are filled, including gradients.
+pub mod paint;
+
+/// Types relating to custom fragment shaders.
+pub mod shader;
+
 /// Utilities for accessing the system clock on all platforms.
 pub mod time;
 
@@ -385,10 +439,21 @@ mod window_internal_doctest;
 #[cfg(any(target_arch = "wasm32"))]
 mod web;
 
+mod bitmap_font;
+mod color_glyphs;
+mod distance_field;
+mod edge_antialiasing;
 mod font_cache;
 mod glbackend;
+#[cfg(not(target_arch = "wasm32"))]
+mod glbackend_software;
 mod glwrapper;
+mod path_tessellation;
 mod renderer2d;
+mod text_bidi;
+mod text_line_break;
+#[cfg(feature = "text-shaping")]
+mod text_shaping;
 mod texture_packer;
 mod utils;
 
@@ -481,6 +546,28 @@ impl GLRenderer
         )
     }
 
+    /// Creates a `GLRenderer` which renders into an in-memory buffer using a
+    /// software rasterizer, without requiring a GPU or an OpenGL driver.
+    ///
+    /// This is useful on headless machines, for example when rendering
+    /// images on a server, or in automated tests. Frames are drawn using
+    /// [GLRenderer::draw_frame()] as usual, and the result can be retrieved
+    /// using [Graphics2D::capture()].
+    ///
+    /// The software renderer is much slower than a hardware GL context, and
+    /// is not intended for interactive use.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new_for_software_rendering<V: Into<UVec2>>(
+        viewport_size_pixels: V
+    ) -> Result<Self, BacktraceError<GLRendererCreationError>>
+    {
+        Self::new_with_gl_backend(
+            viewport_size_pixels,
+            Rc::new(GLBackendSoftware::new()),
+            GLVersion::OpenGL2_0
+        )
+    }
+
     /// Creates a `GLRenderer` for the specified HTML canvas. The canvas
     /// will be found based on the specified ID.
     ///
@@ -556,6 +643,24 @@ impl GLRenderer
             .create_image_from_raw_pixels(data_type, smoothing_mode, size, data)
     }
 
+    /// Compiles a custom fragment shader, which can be used to draw
+    /// rectangles and images using methods such as
+    /// [Graphics2D::draw_rectangle_with_shader()].
+    ///
+    /// See [Graphics2D::create_fragment_shader()] for details of the inputs
+    /// available to the shader. If the shader fails to compile, the error
+    /// contains the compiler output.
+    ///
+    /// The returned [ShaderHandle] is valid only for the current graphics
+    /// context.
+    pub fn create_fragment_shader(
+        &mut self,
+        fragment_shader_source: &str
+    ) -> Result<ShaderHandle, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_fragment_shader(fragment_shader_source)
+    }
+
     /// Loads an image from the specified file path.
     ///
     /// If no `data_type` is provided, an attempt will be made to guess the file
@@ -621,6 +726,40 @@ impl GLRenderer
             .create_image_from_file_bytes(data_type, smoothing_mode, file_bytes)
     }
 
+    /// Creates a [BitmapFont] from a descriptor file in the BMFont (AngelCode)
+    /// text, XML or binary format, and the page images which it refers to.
+    ///
+    /// The page images must be given in the order of their page IDs, and may
+    /// be loaded using methods such as
+    /// [GLRenderer::create_image_from_file_path].
+    ///
+    /// The returned [BitmapFont] is valid only for the current graphics
+    /// context.
+    pub fn create_bitmap_font(
+        &mut self,
+        descriptor_bytes: &[u8],
+        pages: Vec<ImageHandle>
+    ) -> Result<BitmapFont, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_bitmap_font(descriptor_bytes, pages)
+    }
+
+    /// Sets the options for the cache in which rasterized glyphs are stored.
+    /// See [Graphics2D::set_glyph_cache_options()].
+    pub fn set_glyph_cache_options(&mut self, options: GlyphCacheOptions)
+    {
+        self.renderer.set_glyph_cache_options(options);
+    }
+
+    /// Returns statistics about the glyph cache. See
+    /// [Graphics2D::glyph_cache_stats()].
+    #[inline]
+    #[must_use]
+    pub fn glyph_cache_stats(&self) -> GlyphCacheStats
+    {
+        self.renderer.glyph_cache_stats()
+    }
+
     /// Starts the process of drawing a frame. A `Graphics2D` object will be
     /// provided to the callback. When the callback returns, the internal
     /// render queue will be flushed.
@@ -630,7 +769,12 @@ impl GLRenderer
     #[inline]
     pub fn draw_frame<F: FnOnce(&mut Graphics2D) -> R, R>(&mut self, callback: F) -> R
     {
-        self.renderer.set_clip(None);
+        self.renderer.renderer.reset_clip();
+        self.renderer.set_blend_mode(BlendMode::Normal);
+        self.renderer.set_edge_antialiasing(false);
+        self.renderer
+            .set_text_rendering_mode(TextRenderingMode::Bitmap);
+        self.renderer.renderer.reset_transform();
         let result = callback(&mut self.renderer);
         self.renderer.renderer.finish_frame();
         result
@@ -682,6 +826,88 @@ impl Graphics2D
         )
     }
 
+    /// Compiles a custom fragment shader, which can be used to draw
+    /// rectangles and images using [Graphics2D::draw_rectangle_with_shader()]
+    /// and [Graphics2D::draw_rectangle_image_with_shader()].
+    ///
+    /// The source must be written for the GL version of the context: GLSL
+    /// 1.10 (`#version 110`) for OpenGL 2.0, which is used on desktop
+    /// platforms, or GLSL ES 3.00 (`#version 300 es`) for WebGL 2.0.
+    ///
+    /// The shader may declare the following inputs, which are provided by the
+    /// built-in vertex shader (using `in` rather than `varying` for GLSL ES
+    /// 3.00):
+    ///
+    ///  * `varying vec2 pass_TextureCoord`: ranges from `(0.0, 0.0)` at the top
+    ///    left of the rectangle to `(1.0, 1.0)` at the bottom right.
+    ///  * `uniform sampler2D in_Texture`: the image being drawn, if any.
+    ///
+    /// Any other uniforms are set for each draw call using [ShaderUniforms].
+    /// The output color is blended with the existing contents of the
+    /// screen according to the current [BlendMode]. The
+    /// [BlendMode::Multiply] and [BlendMode::Screen] modes expect the output
+    /// to be premultiplied by alpha: if the shader declares
+    /// `uniform float in_PremultiplyAlpha`, it is set to `1.0` when this is
+    /// required, and `0.0` otherwise.
+    ///
+    /// Note: custom shaders are not supported by the software renderer
+    /// created using [GLRenderer::new_for_software_rendering()].
+    ///
+    /// The returned [ShaderHandle] is valid only for the current graphics
+    /// context.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::shape::Rectangle;
+    /// # use speedy2d::shader::ShaderUniforms;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # use speedy2d::image::{ImageDataType, ImageSmoothingMode};
+    /// # let image = renderer.create_image_from_raw_pixels(
+    /// #     ImageDataType::RGBA,
+    /// #     ImageSmoothingMode::Linear,
+    /// #     (1, 1).into(),
+    /// #     &[255, 255, 255, 255]
+    /// # ).unwrap();
+    /// let grayscale = renderer
+    ///     .create_fragment_shader(
+    ///         r#"
+    ///         #version 110
+    ///
+    ///         uniform sampler2D in_Texture;
+    ///         uniform float u_Amount;
+    ///
+    ///         varying vec2 pass_TextureCoord;
+    ///
+    ///         void main(void) {
+    ///             vec4 color = texture2D(in_Texture, pass_TextureCoord);
+    ///             float gray = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+    ///             gl_FragColor = vec4(mix(color.rgb, vec3(gray), u_Amount), color.a);
+    ///         }
+    ///         "#
+    ///     )
+    ///     .unwrap();
+    ///
+    /// renderer.draw_frame(|graphics| {
+    ///     graphics.draw_rectangle_image_with_shader(
+    ///         Rectangle::from_tuples((100.0, 100.0), (300.0, 300.0)),
+    ///         &image,
+    ///         &grayscale,
+    ///         &ShaderUniforms::new().with_float("u_Amount", 1.0)
+    ///     );
+    /// });
+    /// ```
+    pub fn create_fragment_shader(
+        &mut self,
+        fragment_shader_source: &str
+    ) -> Result<ShaderHandle, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_fragment_shader(fragment_shader_source)
+    }
+
     /// Loads an image from the specified file path.
     ///
     /// If no `data_type` is provided, an attempt will be made to guess the file
@@ -749,6 +975,79 @@ impl Graphics2D
             .create_image_from_file_bytes(data_type, smoothing_mode, file_bytes)
     }
 
+    /// Creates a [BitmapFont] from a descriptor file in the BMFont (AngelCode)
+    /// text, XML or binary format, and the page images which it refers to.
+    ///
+    /// The page images must be given in the order of their page IDs, and may
+    /// be loaded using methods such as
+    /// [Graphics2D::create_image_from_file_path].
+    ///
+    /// The returned [BitmapFont] is valid only for the current graphics
+    /// context.
+    pub fn create_bitmap_font(
+        &mut self,
+        descriptor_bytes: &[u8],
+        pages: Vec<ImageHandle>
+    ) -> Result<BitmapFont, BacktraceError<ErrorMessage>>
+    {
+        self.renderer.create_bitmap_font(descriptor_bytes, pages)
+    }
+
+    /// Creates a new [ImageHandle] of the specified size, and renders into it
+    /// using the provided callback. The image starts out fully transparent.
+    ///
+    /// While the callback is running, all drawing operations on the
+    /// [Graphics2D] object passed to it go to the image rather than the
+    /// screen. The coordinates `(0, 0)` refer to the top left of the image.
+    /// The transform stack and clip stack start out empty, and the previous
+    /// transforms and clip areas are restored afterwards. The blend mode is
+    /// reset to [BlendMode::Normal] in the same way. Calls may be nested.
+    ///
+    /// Note: the color of partially transparent pixels is multiplied by
+    /// their alpha value, as the image is blended onto a transparent
+    /// background.
+    ///
+    /// The returned [ImageHandle] is valid only for the current graphics
+    /// context.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::image::ImageSmoothingMode;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// let image = graphics
+    ///     .draw_to_image((100, 100), ImageSmoothingMode::Linear, |graphics| {
+    ///         graphics.draw_circle((50.0, 50.0), 40.0, Color::RED);
+    ///     })
+    ///     .unwrap();
+    ///
+    /// graphics.draw_image((200.0, 200.0), &image);
+    /// # });
+    /// ```
+    pub fn draw_to_image<S, F>(
+        &mut self,
+        size: S,
+        smoothing_mode: ImageSmoothingMode,
+        callback: F
+    ) -> Result<ImageHandle, BacktraceError<ErrorMessage>>
+    where
+        S: Into<UVec2>,
+        F: FnOnce(&mut Graphics2D)
+    {
+        let state = self
+            .renderer
+            .begin_render_to_image(size.into(), smoothing_mode)?;
+
+        callback(self);
+
+        Ok(self.renderer.end_render_to_image(state))
+    }
+
     /// Fills the screen with the specified color.
     pub fn clear_screen(&mut self, color: Color)
     {
@@ -764,6 +1063,10 @@ impl Graphics2D
     /// To fall back to another font if a glyph isn't found, see
     /// [crate::font::FontFamily].
     ///
+    /// To combine several fonts, sizes, and colors in one block of text, see
+    /// [crate::font::RichText]. Glyphs from spans with their own color are
+    /// drawn in that color, rather than `color`.
+    ///
     /// To achieve good performance, it's possible to layout a line of text
     /// once, and then re-use the same [crate::font::FormattedTextLine]
     /// object whenever you need to draw that text to the screen.
@@ -772,7 +1075,9 @@ impl Graphics2D
     /// position changes between frames, performance may be degraded, as the
     /// text will need to be re-rendered and re-uploaded. To avoid this,
     /// call `round()` on the position coordinates, to ensure that
-    /// the text is always located at an integer pixel position.
+    /// the text is always located at an integer pixel position. Alternatively,
+    /// text which is frequently moved, scaled, or rotated can be drawn using
+    /// [Graphics2D::set_text_rendering_mode()].
     pub fn draw_text<V: Into<Vec2>>(
         &mut self,
         position: V,
@@ -783,6 +1088,44 @@ impl Graphics2D
         self.renderer.draw_text(position, color, text);
     }
 
+    /// Draws the provided block of text at the specified position, along with
+    /// the outline, shadow, and glow effects of the specified style. The
+    /// effects are drawn behind the text, in their own colors.
+    ///
+    /// The text itself is drawn in the same way as [Graphics2D::draw_text],
+    /// using the current [Graphics2D::set_text_rendering_mode()]. Underlines
+    /// and strikethroughs are drawn without effects.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::font::{Font, TextLayout, TextOptions, TextStyle};
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # let bytes = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+    /// # let font = Font::new(bytes).unwrap();
+    /// let text = font.layout_text("Game over", 48.0, TextOptions::new());
+    /// let style = TextStyle::new().with_outline(3.0, Color::BLACK);
+    ///
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.draw_text_with_style((100.0, 100.0), Color::WHITE, &style, &text);
+    /// # });
+    /// ```
+    pub fn draw_text_with_style<V: Into<Vec2>>(
+        &mut self,
+        position: V,
+        color: Color,
+        style: &TextStyle,
+        text: &FormattedTextBlock
+    )
+    {
+        self.renderer
+            .draw_text_with_style(position, color, style, text);
+    }
+
     /// Draws the provided block of text at the specified position, cropped to
     /// the specified window. Characters outside this window will not be
     /// rendered. Characters partially inside the window will be cropped.
@@ -803,6 +1146,72 @@ impl Graphics2D
             .draw_text_cropped(position, crop_window, color, text);
     }
 
+    /// Draws the provided block of text aligned within the specified
+    /// rectangle, for example to center a label in a button. The position of
+    /// the text is given by [FormattedTextBlock::position_in_rect()].
+    ///
+    /// If the text extends outside the rectangle, it is cropped to the
+    /// rectangle in the same way as [Graphics2D::draw_text_cropped], and
+    /// `true` is returned, so that the caller can lay out the text again
+    /// with a smaller scale or a wrapping width. This is given by
+    /// [FormattedTextBlock::overflows_rect()]. With
+    /// [VerticalAlignment::Baseline], the descent of the first line is
+    /// allowed to extend below the rectangle, and isn't cropped.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::font::{
+    /// #     Font,
+    /// #     TextAlignment,
+    /// #     TextLayout,
+    /// #     TextOptions,
+    /// #     VerticalAlignment
+    /// # };
+    /// # use speedy2d::shape::Rect;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # let bytes = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+    /// # let font = Font::new(bytes).unwrap();
+    /// let label = font.layout_text("OK", 24.0, TextOptions::new());
+    /// let button = Rect::from_tuples((100.0, 100.0), (200.0, 140.0));
+    ///
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.draw_text_in_rect(
+    ///     button,
+    ///     &label,
+    ///     TextAlignment::Center,
+    ///     VerticalAlignment::Middle,
+    ///     Color::BLACK
+    /// );
+    /// # });
+    /// ```
+    pub fn draw_text_in_rect(
+        &mut self,
+        rect: Rect,
+        text: &FormattedTextBlock,
+        h_align: TextAlignment,
+        v_align: VerticalAlignment,
+        color: Color
+    ) -> bool
+    {
+        let position = text.position_in_rect(&rect, h_align, v_align);
+        let overflows = text.overflows_rect(&rect, h_align, v_align);
+
+        if overflows {
+            let crop_window = text.area_in_rect(&rect, v_align);
+            self.renderer
+                .draw_text_cropped(position, crop_window, color, text);
+        } else {
+            self.renderer.draw_text(position, color, text);
+        }
+
+        overflows
+    }
+
     /// Draws a polygon with a single color, with the specified offset in
     /// pixels.
     pub fn draw_polygon<V: Into<Vec2>>(
@@ -812,7 +1221,21 @@ impl Graphics2D
         color: Color
     )
     {
-        self.renderer.draw_polygon(polygon, offset, color)
+        self.renderer
+            .draw_polygon(polygon, offset, &Paint::Color(color))
+    }
+
+    /// Draws a polygon filled with the specified paint, which may be a color
+    /// or a [paint::Gradient], with the specified offset in pixels. The
+    /// gradient coordinates are not affected by the offset.
+    pub fn draw_polygon_with_paint<V: Into<Vec2>, P: Into<Paint>>(
+        &mut self,
+        polygon: &Polygon,
+        offset: V,
+        paint: P
+    )
+    {
+        self.renderer.draw_polygon(polygon, offset, &paint.into())
     }
 
     /// Draws a triangle with the specified colors (one color for each corner).
@@ -885,9 +1308,10 @@ impl Graphics2D
         let vp = vertex_positions_clockwise;
         let vc = vertex_colors;
 
-        self.draw_triangle_three_color([vp[0], vp[1], vp[2]], [vc[0], vc[1], vc[2]]);
-
-        self.draw_triangle_three_color([vp[2], vp[3], vp[0]], [vc[2], vc[3], vc[0]]);
+        self.renderer.draw_triangles_three_color(&[
+            ([vp[0], vp[1], vp[2]], [vc[0], vc[1], vc[2]]),
+            ([vp[2], vp[3], vp[0]], [vc[2], vc[3], vc[0]])
+        ]);
     }
 
     /// Draws a quadrilateral with the specified color.
@@ -1021,6 +1445,40 @@ impl Graphics2D
         self.draw_rectangle_image_tinted(rect, Color::WHITE, image);
     }
 
+    /// Draws a rectangle using a custom fragment shader, created using
+    /// [Graphics2D::create_fragment_shader()]. No image is bound to
+    /// `in_Texture`.
+    pub fn draw_rectangle_with_shader(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        shader: &ShaderHandle,
+        uniforms: &ShaderUniforms
+    )
+    {
+        self.renderer
+            .draw_rectangle_with_shader(rect.as_ref(), None, shader, uniforms);
+    }
+
+    /// Draws an image using a custom fragment shader, created using
+    /// [Graphics2D::create_fragment_shader()]. The image is bound to
+    /// `in_Texture`, and will be scaled to fill the pixel coordinates in the
+    /// provided rectangle.
+    pub fn draw_rectangle_image_with_shader(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        image: &ImageHandle,
+        shader: &ShaderHandle,
+        uniforms: &ShaderUniforms
+    )
+    {
+        self.renderer.draw_rectangle_with_shader(
+            rect.as_ref(),
+            Some(image),
+            shader,
+            uniforms
+        );
+    }
+
     /// Draws an image at the specified pixel location. The image will be
     /// drawn at its original size with no scaling.
     #[inline]
@@ -1052,6 +1510,54 @@ impl Graphics2D
         );
     }
 
+    /// Draws a rectangle filled with the specified paint, which may be a color
+    /// or a [paint::Gradient]. The coordinates of the rectangle are specified
+    /// in pixels.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::paint::Gradient;
+    /// # use speedy2d::shape::Rectangle;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// let gradient = Gradient::new_linear((100.0, 0.0), (300.0, 0.0))
+    ///     .with_stop(0.0, Color::RED)
+    ///     .with_stop(0.5, Color::YELLOW)
+    ///     .with_stop(1.0, Color::GREEN);
+    ///
+    /// graphics.draw_rectangle_with_paint(
+    ///     Rectangle::from_tuples((100.0, 100.0), (300.0, 200.0)),
+    ///     &gradient
+    /// );
+    /// # });
+    /// ```
+    pub fn draw_rectangle_with_paint<P: Into<Paint>>(
+        &mut self,
+        rect: impl AsRef<Rectangle>,
+        paint: P
+    )
+    {
+        let rect = rect.as_ref();
+
+        let top_left = *rect.top_left();
+        let top_right = rect.top_right();
+        let bottom_right = *rect.bottom_right();
+        let bottom_left = rect.bottom_left();
+
+        self.renderer.draw_triangles_with_paint(
+            [
+                [top_left, top_right, bottom_right],
+                [bottom_right, bottom_left, top_left]
+            ],
+            &paint.into()
+        );
+    }
+
     /// Draws a single-color line between the given points, specified in pixels.
     ///
     /// # Pixel alignment
@@ -1112,6 +1618,101 @@ impl Graphics2D
         );
     }
 
+    /// Fills the interior of the specified path with a single color.
+    ///
+    /// Subpaths which are inside another subpath are treated as holes, using
+    /// the even-odd rule. Open subpaths are closed automatically. Subpaths
+    /// should not intersect each other or themselves.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Path;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// let path = Path::new()
+    ///     .move_to((100.0, 100.0))
+    ///     .cubic_to((150.0, 0.0), (250.0, 200.0), (300.0, 100.0))
+    ///     .line_to((300.0, 300.0))
+    ///     .line_to((100.0, 300.0))
+    ///     .close();
+    ///
+    /// graphics.draw_path_filled(&path, Color::BLUE);
+    /// # });
+    /// ```
+    pub fn draw_path_filled(&mut self, path: &shape::Path, color: Color)
+    {
+        self.renderer.draw_path_filled(path, &Paint::Color(color));
+    }
+
+    /// Fills the interior of the specified path with the specified paint,
+    /// which may be a color or a [paint::Gradient]. See
+    /// [Graphics2D::draw_path_filled] for details.
+    pub fn draw_path_filled_with_paint<P: Into<Paint>>(
+        &mut self,
+        path: &shape::Path,
+        paint: P
+    )
+    {
+        self.renderer.draw_path_filled(path, &paint.into());
+    }
+
+    /// Draws the outline of the specified path with a single color, using the
+    /// width, line joins and line caps in `stroke`.
+    ///
+    /// Note: parts of the stroke may be drawn more than once where segments
+    /// overlap, so a translucent color may appear darker at the joins.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::{LineCap, LineJoin, Path, StrokeStyle};
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// let path = Path::new()
+    ///     .move_to((100.0, 100.0))
+    ///     .line_to((200.0, 150.0))
+    ///     .quad_to((300.0, 100.0), (300.0, 200.0));
+    ///
+    /// let stroke = StrokeStyle::new(10.0)
+    ///     .with_join(LineJoin::Round)
+    ///     .with_cap(LineCap::Square);
+    ///
+    /// graphics.draw_path_stroked(&path, &stroke, Color::RED);
+    /// # });
+    /// ```
+    pub fn draw_path_stroked(
+        &mut self,
+        path: &shape::Path,
+        stroke: &StrokeStyle,
+        color: Color
+    )
+    {
+        self.renderer
+            .draw_path_stroked(path, stroke, &Paint::Color(color));
+    }
+
+    /// Draws the outline of the specified path with the specified paint,
+    /// which may be a color or a [paint::Gradient]. See
+    /// [Graphics2D::draw_path_stroked] for details.
+    pub fn draw_path_stroked_with_paint<P: Into<Paint>>(
+        &mut self,
+        path: &shape::Path,
+        stroke: &StrokeStyle,
+        paint: P
+    )
+    {
+        self.renderer.draw_path_stroked(path, stroke, &paint.into());
+    }
+
     /// Draws a circle, filled with a single color, at the specified pixel
     /// location.
     pub fn draw_circle<V: Into<Vec2>>(
@@ -1120,32 +1721,63 @@ impl Graphics2D
         radius: f32,
         color: Color
     )
+    {
+        self.draw_circle_with_paint(center_position, radius, color);
+    }
+
+    /// Draws a circle, filled with the specified paint, at the specified pixel
+    /// location. The paint may be a color or a [paint::Gradient].
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::paint::Gradient;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// let glow = Gradient::new_radial((200.0, 200.0), 100.0)
+    ///     .with_stop(0.0, Color::WHITE)
+    ///     .with_stop(1.0, Color::from_rgba(1.0, 1.0, 0.0, 0.0));
+    ///
+    /// graphics.draw_circle_with_paint((200.0, 200.0), 100.0, glow);
+    /// # });
+    /// ```
+    pub fn draw_circle_with_paint<V: Into<Vec2>, P: Into<Paint>>(
+        &mut self,
+        center_position: V,
+        radius: f32,
+        paint: P
+    )
     {
         let center_position = center_position.into();
+        let paint = paint.into();
 
         let top_left = center_position + Vec2::new(-radius, -radius);
         let top_right = center_position + Vec2::new(radius, -radius);
         let bottom_right = center_position + Vec2::new(radius, radius);
         let bottom_left = center_position + Vec2::new(-radius, radius);
 
-        self.renderer.draw_circle_section(
+        self.renderer.draw_circle_section_with_paint(
             [top_left, top_right, bottom_right],
-            [color, color, color],
             [
                 Vec2::new(-1.0, -1.0),
                 Vec2::new(1.0, -1.0),
                 Vec2::new(1.0, 1.0)
-            ]
+            ],
+            &paint
         );
 
-        self.renderer.draw_circle_section(
+        self.renderer.draw_circle_section_with_paint(
             [bottom_right, bottom_left, top_left],
-            [color, color, color],
             [
                 Vec2::new(1.0, 1.0),
                 Vec2::new(-1.0, 1.0),
                 Vec2::new(-1.0, -1.0)
-            ]
+            ],
+            &paint
         );
     }
 
@@ -1200,11 +1832,397 @@ impl Graphics2D
     /// Sets the current clip to the rectangle specified by the given
     /// coordinates. Rendering operations have no effect outside of the
     /// clipping area.
+    ///
+    /// This replaces the current clip rectangle, including one set using
+    /// [Graphics2D::push_clip()], until the next call to
+    /// [Graphics2D::pop_clip()].
     pub fn set_clip(&mut self, rect: Option<Rectangle<i32>>)
     {
         self.renderer.set_clip(rect);
     }
 
+    /// Pushes a clip rectangle onto the clip stack. Until the matching call
+    /// to [Graphics2D::pop_clip()], rendering operations only have an effect
+    /// inside the intersection of `rect` and the existing clip rectangle.
+    /// This allows clip areas to be nested, for example in scrollable UI
+    /// panels.
+    ///
+    /// As with [Graphics2D::set_clip()], the rectangle is specified in window
+    /// coordinates, and is not transformed.
+    ///
+    /// The clip stack is reset at the start of every frame.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Rectangle;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.push_clip(Rectangle::from_tuples((0, 0), (300, 300)));
+    /// graphics.push_clip(Rectangle::from_tuples((200, 200), (400, 400)));
+    ///
+    /// // Only the area from (200, 200) to (300, 300) is drawn
+    /// graphics.clear_screen(Color::BLUE);
+    ///
+    /// graphics.pop_clip();
+    /// graphics.pop_clip();
+    /// # });
+    /// ```
+    #[inline]
+    pub fn push_clip(&mut self, rect: Rectangle<i32>)
+    {
+        self.renderer.push_clip(rect);
+    }
+
+    /// Pushes a circular clip area onto the clip stack. Until the matching
+    /// call to [Graphics2D::pop_clip()], rendering operations only have an
+    /// effect inside both the circle and any existing clip areas.
+    ///
+    /// Unlike clip rectangles, the circle is transformed by the current
+    /// transform (see [Graphics2D::push_transform()]). Clip shapes are
+    /// implemented using the stencil buffer, so the edges of the circle are
+    /// not antialiased.
+    ///
+    /// Note: if you are managing the GL context yourself, it must be created
+    /// with a stencil buffer for clip shapes to have any effect.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::shape::Rectangle;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.push_clip_circle((320.0, 240.0), 100.0);
+    ///
+    /// // Draws a circle with a red left half and a blue right half
+    /// graphics.draw_rectangle(
+    ///     Rectangle::from_tuples((0.0, 0.0), (320.0, 480.0)),
+    ///     Color::RED
+    /// );
+    /// graphics.draw_rectangle(
+    ///     Rectangle::from_tuples((320.0, 0.0), (640.0, 480.0)),
+    ///     Color::BLUE
+    /// );
+    ///
+    /// graphics.pop_clip();
+    /// # });
+    /// ```
+    #[inline]
+    pub fn push_clip_circle<V: Into<Vec2>>(&mut self, center: V, radius: f32)
+    {
+        self.renderer.push_clip_circle(center.into(), radius);
+    }
+
+    /// Pushes a polygonal clip area, offset by `offset`, onto the clip stack.
+    /// See [Graphics2D::push_clip_circle()] for details.
+    #[inline]
+    pub fn push_clip_polygon<V: Into<Vec2>>(&mut self, polygon: &Polygon, offset: V)
+    {
+        let offset = offset.into();
+
+        self.renderer.push_clip_triangles(
+            polygon
+                .triangles
+                .iter()
+                .map(|triangle| triangle.map(|vertex| vertex + offset))
+        );
+    }
+
+    /// Pushes a clip area with the shape of the filled path onto the clip
+    /// stack. See [Graphics2D::push_clip_circle()] for details.
+    #[inline]
+    pub fn push_clip_path(&mut self, path: &shape::Path)
+    {
+        self.renderer.push_clip_path(path);
+    }
+
+    /// Removes the clip area most recently added using
+    /// [Graphics2D::push_clip()], [Graphics2D::push_clip_circle()],
+    /// [Graphics2D::push_clip_polygon()], or [Graphics2D::push_clip_path()],
+    /// restoring the previous clip.
+    ///
+    /// If the clip stack is empty, this call has no effect.
+    #[inline]
+    pub fn pop_clip(&mut self)
+    {
+        self.renderer.pop_clip();
+    }
+
+    /// Sets the blend mode used for all subsequent drawing operations, which
+    /// determines how the colors of shapes, images, and text are combined
+    /// with the existing contents of the screen.
+    ///
+    /// The blend mode is reset to [BlendMode::Normal] at the start of every
+    /// frame, and inside [Graphics2D::draw_to_image()].
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::{BlendMode, Color};
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.set_blend_mode(BlendMode::Additive);
+    /// graphics.draw_circle((300.0, 240.0), 100.0, Color::RED);
+    /// graphics.draw_circle((380.0, 240.0), 100.0, Color::GREEN);
+    /// graphics.set_blend_mode(BlendMode::Normal);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode)
+    {
+        self.renderer.set_blend_mode(blend_mode);
+    }
+
+    /// Returns the blend mode set using [Graphics2D::set_blend_mode()].
+    #[inline]
+    #[must_use]
+    pub fn blend_mode(&self) -> BlendMode
+    {
+        self.renderer.blend_mode()
+    }
+
+    /// Enables or disables anti-aliasing of the edges of subsequently drawn
+    /// polygons, paths, lines, rectangles, quads, and triangles filled with a
+    /// color or a [paint::Gradient]. This works on all targets without
+    /// multisampling, including when drawing to an image.
+    ///
+    /// Each shape is anti-aliased by adding a one-pixel-wide fringe along its
+    /// outer edges, in which the shape fades out. Edges shared by two
+    /// triangles of the same shape are not affected, so a shape made of
+    /// several triangles should be drawn with a single call (for example
+    /// using [Graphics2D::draw_polygon()]) rather than one triangle at a time.
+    ///
+    /// Images, text, custom shaders, and circles are not affected.
+    ///
+    /// Edge anti-aliasing is disabled at the start of every frame.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.set_edge_antialiasing(true);
+    /// graphics.draw_line((100.0, 100.0), (540.0, 180.0), 3.0, Color::BLUE);
+    /// # });
+    /// ```
+    #[inline]
+    pub fn set_edge_antialiasing(&mut self, enabled: bool)
+    {
+        self.renderer.set_edge_antialiasing(enabled);
+    }
+
+    /// Returns whether edge anti-aliasing was enabled using
+    /// [Graphics2D::set_edge_antialiasing()].
+    #[inline]
+    #[must_use]
+    pub fn edge_antialiasing(&self) -> bool
+    {
+        self.renderer.edge_antialiasing()
+    }
+
+    /// Sets the way in which the glyphs of subsequently drawn text are
+    /// rasterized. See [TextRenderingMode] for the available modes.
+    ///
+    /// In [TextRenderingMode::SignedDistanceField] mode, each glyph is
+    /// rasterized only once, and then drawn at any size, so text which is
+    /// zoomed, scaled, or rotated stays sharp without filling the glyph cache
+    /// with a bitmap for every size.
+    ///
+    /// The text rendering mode is reset to [TextRenderingMode::Bitmap] at the
+    /// start of every frame.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::dimen::Affine2;
+    /// # use speedy2d::font::{Font, TextLayout, TextOptions, TextRenderingMode};
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # let bytes = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+    /// # let font = Font::new(bytes).unwrap();
+    /// let text = font.layout_text("Hello", 32.0, TextOptions::new());
+    ///
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.set_text_rendering_mode(TextRenderingMode::SignedDistanceField);
+    /// graphics.push_transform(Affine2::rotation(0.5).then(&Affine2::scale((3.0, 3.0))));
+    /// graphics.draw_text((100.0, 100.0), Color::BLACK, &text);
+    /// graphics.pop_transform();
+    /// # });
+    /// ```
+    #[inline]
+    pub fn set_text_rendering_mode(&mut self, mode: TextRenderingMode)
+    {
+        self.renderer.set_text_rendering_mode(mode);
+    }
+
+    /// Returns the text rendering mode set using
+    /// [Graphics2D::set_text_rendering_mode()].
+    #[inline]
+    #[must_use]
+    pub fn text_rendering_mode(&self) -> TextRenderingMode
+    {
+        self.renderer.text_rendering_mode()
+    }
+
+    /// Sets the options for the cache in which rasterized glyphs are stored
+    /// between frames. This empties the cache, and resets its statistics.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::font::GlyphCacheOptions;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.set_glyph_cache_options(
+    ///     GlyphCacheOptions::new().with_max_pages(2).with_eviction_frames(600)
+    /// );
+    /// # });
+    /// ```
+    pub fn set_glyph_cache_options(&mut self, options: GlyphCacheOptions)
+    {
+        self.renderer.set_glyph_cache_options(options);
+    }
+
+    /// Returns the options set using [Graphics2D::set_glyph_cache_options()].
+    #[inline]
+    #[must_use]
+    pub fn glyph_cache_options(&self) -> &GlyphCacheOptions
+    {
+        self.renderer.glyph_cache_options()
+    }
+
+    /// Returns statistics about the glyph cache, such as the number of glyphs
+    /// which had to be rasterized. Text is added to the cache when the render
+    /// queue is flushed, which happens at the latest when the frame ends.
+    #[inline]
+    #[must_use]
+    pub fn glyph_cache_stats(&self) -> GlyphCacheStats
+    {
+        self.renderer.glyph_cache_stats()
+    }
+
+    /// Rasterizes the glyphs for the specified characters ahead of time, so
+    /// that drawing them later doesn't cause a delay. The glyphs are
+    /// rasterized in the current [Graphics2D::set_text_rendering_mode()].
+    ///
+    /// In [TextRenderingMode::Bitmap] mode, glyphs are rasterized separately
+    /// for each scale and subpixel offset, and this only covers glyphs drawn
+    /// at the specified scale and positioned on whole pixels. In
+    /// [TextRenderingMode::SignedDistanceField] mode, each glyph is
+    /// rasterized once for all scales and positions.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::font::{Font, TextRenderingMode};
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # let bytes = include_bytes!("../assets/fonts/NotoSans-Regular.ttf");
+    /// # let font = Font::new(bytes).unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.set_text_rendering_mode(TextRenderingMode::SignedDistanceField);
+    /// graphics.prewarm_glyph_cache(&font, 32.0, "0123456789");
+    /// # });
+    /// ```
+    pub fn prewarm_glyph_cache<T: TextLayout + ?Sized>(
+        &mut self,
+        font: &T,
+        scale: f32,
+        chars: &str
+    )
+    {
+        let blocks: Vec<_> = chars
+            .chars()
+            .map(|c| {
+                font.layout_text_from_unindexed_codepoints(
+                    &[c],
+                    scale,
+                    TextOptions::new()
+                )
+            })
+            .collect();
+
+        self.renderer.prewarm_glyph_cache(&blocks);
+    }
+
+    /// Pushes the specified transformation onto the transform stack. Until
+    /// the matching call to [Graphics2D::pop_transform()], the coordinates
+    /// of all shapes, images, and text drawn are transformed by `transform`,
+    /// followed by any transformations which were already on the stack.
+    ///
+    /// The transform stack is reset at the start of every frame.
+    ///
+    /// Note: the clipping area set using [Graphics2D::set_clip()] is always
+    /// specified in window coordinates, and is not transformed.
+    ///
+    /// ```rust,no_run
+    /// # use speedy2d::GLRenderer;
+    /// # use speedy2d::color::Color;
+    /// # use speedy2d::dimen::Affine2;
+    /// # use speedy2d::shape::Rectangle;
+    /// # let mut renderer = unsafe {
+    /// #     GLRenderer::new_for_gl_context((640, 480), |fn_name| {
+    /// #         std::ptr::null() as *const _
+    /// #     })
+    /// # }.unwrap();
+    /// # renderer.draw_frame(|graphics| {
+    /// graphics.push_transform(Affine2::translation((320.0, 240.0)));
+    /// graphics.push_transform(Affine2::rotation(std::f32::consts::FRAC_PI_4));
+    ///
+    /// // Draws a square rotated by 45 degrees in the center of the window
+    /// graphics.draw_rectangle(
+    ///     Rectangle::from_tuples((-50.0, -50.0), (50.0, 50.0)),
+    ///     Color::BLUE
+    /// );
+    ///
+    /// graphics.pop_transform();
+    /// graphics.pop_transform();
+    /// # });
+    /// ```
+    pub fn push_transform(&mut self, transform: Affine2)
+    {
+        self.renderer.push_transform(&transform);
+    }
+
+    /// Removes the most recently pushed transformation from the transform
+    /// stack, restoring the transformation which was active before the
+    /// corresponding call to [Graphics2D::push_transform()].
+    pub fn pop_transform(&mut self)
+    {
+        self.renderer.pop_transform();
+    }
+
+    /// Returns the combined transformation which is currently being applied
+    /// to all drawing operations.
+    #[must_use]
+    pub fn current_transform(&self) -> Affine2
+    {
+        self.renderer.current_transform()
+    }
+
     /// Captures a screenshot of the render window. The returned data contains
     /// the color of each pixel. Pixels are represented using a `u8` for each
     /// component (red, green, blue, and alpha). Use the `format` parameter to
@@ -0,0 +1,414 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use crate::color::Color;
+use crate::dimen::Vec2;
+
+/// The number of pixels in the lookup texture generated for each gradient.
+/// The r2d fragment shaders assume this value when sampling the texture.
+pub(crate) const GRADIENT_RAMP_SIZE: usize = 256;
+
+/// Describes how the colors of a shape are determined.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Paint
+{
+    /// The shape is filled with a single color.
+    Color(Color),
+    /// The shape is filled with a gradient.
+    Gradient(Gradient)
+}
+
+impl From<Color> for Paint
+{
+    #[inline]
+    fn from(color: Color) -> Self
+    {
+        Paint::Color(color)
+    }
+}
+
+impl From<Gradient> for Paint
+{
+    #[inline]
+    fn from(gradient: Gradient) -> Self
+    {
+        Paint::Gradient(gradient)
+    }
+}
+
+impl From<&Gradient> for Paint
+{
+    #[inline]
+    fn from(gradient: &Gradient) -> Self
+    {
+        Paint::Gradient(gradient.clone())
+    }
+}
+
+/// The geometry of a gradient, in the same coordinate space as the shape
+/// being drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientKind
+{
+    /// The color varies along the line from `start` (offset `0.0`) to `end`
+    /// (offset `1.0`), and is constant along lines perpendicular to it.
+    Linear
+    {
+        /// The point at which the gradient has offset `0.0`.
+        start: Vec2,
+        /// The point at which the gradient has offset `1.0`.
+        end: Vec2
+    },
+
+    /// The color varies with the distance from `center`, from offset `0.0`
+    /// at the center to offset `1.0` at `radius`.
+    Radial
+    {
+        /// The point at which the gradient has offset `0.0`.
+        center: Vec2,
+        /// The distance from the center at which the gradient has offset
+        /// `1.0`.
+        radius: f32
+    }
+}
+
+/// A color at a specific offset along a gradient.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GradientStop
+{
+    offset: f32,
+    color: Color
+}
+
+impl GradientStop
+{
+    /// The offset of this stop, between `0.0` and `1.0`.
+    #[inline]
+    #[must_use]
+    pub fn offset(&self) -> f32
+    {
+        self.offset
+    }
+
+    /// The color of the gradient at this stop.
+    #[inline]
+    #[must_use]
+    pub fn color(&self) -> Color
+    {
+        self.color
+    }
+}
+
+/// A linear or radial gradient between any number of colors.
+///
+/// Before the first stop, the gradient has the color of the first stop, and
+/// after the last stop, it has the color of the last stop. A gradient with
+/// no stops is fully transparent.
+///
+/// ```rust
+/// # use speedy2d::color::Color;
+/// # use speedy2d::paint::Gradient;
+/// let sunset = Gradient::new_linear((0.0, 0.0), (0.0, 480.0))
+///     .with_stop(0.0, Color::BLUE)
+///     .with_stop(0.6, Color::MAGENTA)
+///     .with_stop(1.0, Color::YELLOW);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient
+{
+    kind: GradientKind,
+    stops: Vec<GradientStop>
+}
+
+impl Gradient
+{
+    /// Creates a new gradient, with no stops, which varies along the line
+    /// from `start` to `end`.
+    #[inline]
+    #[must_use]
+    pub fn new_linear<V1: Into<Vec2>, V2: Into<Vec2>>(start: V1, end: V2) -> Self
+    {
+        Gradient {
+            kind: GradientKind::Linear {
+                start: start.into(),
+                end: end.into()
+            },
+            stops: Vec::new()
+        }
+    }
+
+    /// Creates a new gradient, with no stops, which varies with the distance
+    /// from `center`, reaching offset `1.0` at `radius`.
+    #[inline]
+    #[must_use]
+    pub fn new_radial<V: Into<Vec2>>(center: V, radius: f32) -> Self
+    {
+        Gradient {
+            kind: GradientKind::Radial {
+                center: center.into(),
+                radius
+            },
+            stops: Vec::new()
+        }
+    }
+
+    /// Adds a color stop at the specified offset, which is clamped to the
+    /// range `0.0` to `1.0`. Stops may be added in any order. If several
+    /// stops have the same offset, the color changes abruptly at that offset.
+    #[must_use]
+    pub fn with_stop(mut self, offset: f32, color: Color) -> Self
+    {
+        let offset = offset.clamp(0.0, 1.0);
+
+        let index = self
+            .stops
+            .iter()
+            .position(|stop| stop.offset > offset)
+            .unwrap_or(self.stops.len());
+
+        self.stops.insert(index, GradientStop { offset, color });
+        self
+    }
+
+    /// Returns the geometry of this gradient.
+    #[inline]
+    #[must_use]
+    pub fn kind(&self) -> &GradientKind
+    {
+        &self.kind
+    }
+
+    /// Returns the color stops of this gradient, sorted by offset.
+    #[inline]
+    #[must_use]
+    pub fn stops(&self) -> &[GradientStop]
+    {
+        &self.stops
+    }
+
+    /// Returns the coordinates passed to the shader for the specified point.
+    /// For linear gradients, the first component is the offset. For radial
+    /// gradients, the length of the vector is the offset.
+    pub(crate) fn shader_coord(&self, point: Vec2) -> Vec2
+    {
+        match self.kind {
+            GradientKind::Linear { start, end } => {
+                let direction = end - start;
+                let length_squared = direction.magnitude_squared();
+
+                if length_squared == 0.0 {
+                    return Vec2::ZERO;
+                }
+
+                let relative = point - start;
+
+                Vec2::new(
+                    (relative.x * direction.x + relative.y * direction.y)
+                        / length_squared,
+                    0.0
+                )
+            }
+
+            GradientKind::Radial { center, radius } => {
+                if radius <= 0.0 {
+                    return Vec2::new(1.0, 0.0);
+                }
+
+                (point - center) / radius
+            }
+        }
+    }
+
+    /// Returns the color of the gradient at the specified offset.
+    pub(crate) fn color_at(&self, offset: f32) -> Color
+    {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::TRANSPARENT
+        };
+
+        if offset < first.offset {
+            return first.color;
+        }
+
+        for pair in self.stops.windows(2) {
+            let (before, after) = (&pair[0], &pair[1]);
+
+            if offset < after.offset {
+                let amount = (offset - before.offset) / (after.offset - before.offset);
+                return interpolate_premultiplied(&before.color, &after.color, amount);
+            }
+        }
+
+        last.color
+    }
+
+    /// Returns the RGBA pixel data of a texture of width
+    /// `GRADIENT_RAMP_SIZE` and height 1, containing the colors of the
+    /// gradient from offset `0.0` to `1.0`.
+    pub(crate) fn ramp_pixels(&self) -> Vec<u8>
+    {
+        let mut result = Vec::with_capacity(GRADIENT_RAMP_SIZE * 4);
+
+        for i in 0..GRADIENT_RAMP_SIZE {
+            let color = self.color_at(i as f32 / (GRADIENT_RAMP_SIZE - 1) as f32);
+
+            for value in [color.r(), color.g(), color.b(), color.a()] {
+                result.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            }
+        }
+
+        result
+    }
+
+    /// A key which uniquely identifies the colors of this gradient, ignoring
+    /// the geometry, for use when caching the ramp texture.
+    pub(crate) fn ramp_key(&self) -> Vec<u32>
+    {
+        self.stops
+            .iter()
+            .flat_map(|stop| {
+                [
+                    stop.offset,
+                    stop.color.r(),
+                    stop.color.g(),
+                    stop.color.b(),
+                    stop.color.a()
+                ]
+            })
+            .map(f32::to_bits)
+            .collect()
+    }
+}
+
+/// Interpolates between the colors with premultiplied alpha, so that a
+/// transparent stop does not darken the neighbouring colors.
+fn interpolate_premultiplied(from: &Color, to: &Color, amount: f32) -> Color
+{
+    let lerp = |a: f32, b: f32| a + (b - a) * amount;
+
+    let alpha = lerp(from.a(), to.a());
+
+    if alpha <= 0.0 {
+        return Color::TRANSPARENT;
+    }
+
+    Color::from_rgba(
+        lerp(from.r() * from.a(), to.r() * to.a()) / alpha,
+        lerp(from.g() * from.a(), to.g() * to.a()) / alpha,
+        lerp(from.b() * from.a(), to.b() * to.a()) / alpha,
+        alpha
+    )
+}
+
+#[cfg(test)]
+mod test
+{
+    use crate::color::Color;
+    use crate::dimen::Vec2;
+    use crate::paint::{Gradient, GRADIENT_RAMP_SIZE};
+
+    #[test]
+    pub fn test_stops_sorted()
+    {
+        let gradient = Gradient::new_linear((0.0, 0.0), (1.0, 0.0))
+            .with_stop(1.0, Color::BLUE)
+            .with_stop(0.0, Color::RED)
+            .with_stop(0.5, Color::GREEN)
+            .with_stop(2.0, Color::BLACK);
+
+        let offsets: Vec<f32> =
+            gradient.stops().iter().map(|stop| stop.offset()).collect();
+
+        assert_eq!(vec![0.0, 0.5, 1.0, 1.0], offsets);
+        assert_eq!(Color::BLACK, gradient.stops()[3].color());
+    }
+
+    #[test]
+    pub fn test_color_at()
+    {
+        let gradient = Gradient::new_linear((0.0, 0.0), (1.0, 0.0))
+            .with_stop(0.25, Color::RED)
+            .with_stop(0.75, Color::BLUE);
+
+        assert_eq!(Color::RED, gradient.color_at(0.0));
+        assert_eq!(Color::RED, gradient.color_at(0.25));
+        assert_eq!(Color::from_rgb(0.5, 0.0, 0.5), gradient.color_at(0.5));
+        assert_eq!(Color::BLUE, gradient.color_at(0.75));
+        assert_eq!(Color::BLUE, gradient.color_at(1.0));
+
+        assert_eq!(
+            Color::TRANSPARENT,
+            Gradient::new_radial((0.0, 0.0), 1.0).color_at(0.5)
+        );
+
+        let fade = Gradient::new_linear((0.0, 0.0), (1.0, 0.0))
+            .with_stop(0.0, Color::RED)
+            .with_stop(1.0, Color::TRANSPARENT);
+
+        assert_eq!(Color::from_rgba(1.0, 0.0, 0.0, 0.5), fade.color_at(0.5));
+    }
+
+    #[test]
+    pub fn test_hard_stop()
+    {
+        let gradient = Gradient::new_linear((0.0, 0.0), (1.0, 0.0))
+            .with_stop(0.5, Color::RED)
+            .with_stop(0.5, Color::BLUE);
+
+        assert_eq!(Color::RED, gradient.color_at(0.49));
+        assert_eq!(Color::BLUE, gradient.color_at(0.5));
+    }
+
+    #[test]
+    pub fn test_shader_coord()
+    {
+        let linear = Gradient::new_linear((10.0, 10.0), (10.0, 30.0));
+
+        assert_eq!(
+            Vec2::new(0.0, 0.0),
+            linear.shader_coord(Vec2::new(50.0, 10.0))
+        );
+        assert_eq!(
+            Vec2::new(0.5, 0.0),
+            linear.shader_coord(Vec2::new(0.0, 20.0))
+        );
+        assert_eq!(
+            Vec2::new(-1.0, 0.0),
+            linear.shader_coord(Vec2::new(0.0, -10.0))
+        );
+
+        let radial = Gradient::new_radial((10.0, 10.0), 20.0);
+
+        assert_eq!(
+            Vec2::new(0.5, -1.0),
+            radial.shader_coord(Vec2::new(20.0, -10.0))
+        );
+    }
+
+    #[test]
+    pub fn test_ramp_pixels()
+    {
+        let ramp = Gradient::new_linear((0.0, 0.0), (1.0, 0.0))
+            .with_stop(0.0, Color::BLACK)
+            .with_stop(1.0, Color::WHITE)
+            .ramp_pixels();
+
+        assert_eq!(GRADIENT_RAMP_SIZE * 4, ramp.len());
+        assert_eq!([0, 0, 0, 255], ramp[0..4]);
+        assert_eq!([255, 255, 255, 255], ramp[ramp.len() - 4..]);
+    }
+}
@@ -0,0 +1,679 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Converts a `Path` into triangles, for either filling or stroking.
+
+use crate::dimen::Vec2;
+use crate::shape::{LineCap, LineJoin, Path, PathSegment, StrokeStyle};
+
+/// The maximum number of line segments generated for a single curve.
+const MAX_CURVE_SEGMENTS: f32 = 1024.0;
+
+/// Points closer together than this are merged, as the direction of the
+/// segment between them can't be reliably calculated.
+const MIN_SEGMENT_LENGTH: f32 = 1.0e-4;
+
+/// A subpath, after curves have been converted into line segments.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct FlattenedSubpath
+{
+    pub points: Vec<Vec2>,
+    pub closed: bool
+}
+
+impl FlattenedSubpath
+{
+    fn new(start: Vec2) -> Self
+    {
+        FlattenedSubpath {
+            points: vec![start],
+            closed: false
+        }
+    }
+
+    fn push(&mut self, point: Vec2)
+    {
+        if let Some(last) = self.points.last() {
+            if coincident(*last, point) {
+                return;
+            }
+        }
+
+        self.points.push(point);
+    }
+
+    /// Removes the final point if it duplicates the first, as the connection
+    /// back to the start is implied for closed subpaths.
+    fn finish(mut self, closed: bool) -> Self
+    {
+        self.closed = closed;
+
+        if self.closed
+            && self.points.len() > 1
+            && coincident(self.points[0], *self.points.last().unwrap())
+        {
+            self.points.pop();
+        }
+
+        self
+    }
+}
+
+#[inline]
+fn coincident(a: Vec2, b: Vec2) -> bool
+{
+    (a - b).magnitude_squared() < MIN_SEGMENT_LENGTH * MIN_SEGMENT_LENGTH
+}
+
+#[inline]
+fn dot(a: Vec2, b: Vec2) -> f32
+{
+    a.x * b.x + a.y * b.y
+}
+
+#[inline]
+fn cross(a: Vec2, b: Vec2) -> f32
+{
+    a.x * b.y - a.y * b.x
+}
+
+#[inline]
+fn rotate(vector: Vec2, radians: f32) -> Vec2
+{
+    let (sin, cos) = radians.sin_cos();
+    Vec2::new(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos
+    )
+}
+
+/// The number of line segments needed to approximate a curve, given the
+/// largest second difference of its control points. See "Piecewise Linear
+/// Approximation of Bezier Curves" (Roger Willcocks, 2000).
+fn curve_segment_count(second_difference: f32, factor: f32, tolerance: f32) -> usize
+{
+    (second_difference * factor / tolerance)
+        .sqrt()
+        .ceil()
+        .clamp(1.0, MAX_CURVE_SEGMENTS) as usize
+}
+
+/// Converts the path into a list of subpaths containing only straight lines.
+/// No point on the approximated curves will be more than `tolerance` away
+/// from the exact curve.
+pub(crate) fn flatten(path: &Path, tolerance: f32) -> Vec<FlattenedSubpath>
+{
+    let mut result = Vec::new();
+    let mut current: Option<FlattenedSubpath> = None;
+
+    // Subpaths consisting of only a `MoveTo` are ignored, but zero-length
+    // segments are kept, as they may be drawn with caps
+    let mut has_segments = false;
+
+    for segment in &path.segments {
+        if !matches!(segment, PathSegment::MoveTo(_) | PathSegment::Close) {
+            has_segments |= current.is_some();
+        }
+
+        match segment {
+            PathSegment::MoveTo(point) => {
+                if let Some(subpath) = current.take() {
+                    if has_segments {
+                        result.push(subpath.finish(false));
+                    }
+                }
+                current = Some(FlattenedSubpath::new(*point));
+                has_segments = false;
+            }
+
+            PathSegment::LineTo(point) => {
+                if let Some(subpath) = &mut current {
+                    subpath.push(*point);
+                }
+            }
+
+            PathSegment::QuadTo(control, end) => {
+                if let Some(subpath) = &mut current {
+                    let start = *subpath.points.last().unwrap();
+
+                    let second_difference =
+                        (start - *control - *control + *end).magnitude();
+
+                    let count = curve_segment_count(second_difference, 0.25, tolerance);
+
+                    for i in 1..=count {
+                        let t = i as f32 / count as f32;
+                        let mt = 1.0 - t;
+
+                        subpath.push(
+                            start * (mt * mt)
+                                + *control * (2.0 * mt * t)
+                                + *end * (t * t)
+                        );
+                    }
+                }
+            }
+
+            PathSegment::CubicTo(control1, control2, end) => {
+                if let Some(subpath) = &mut current {
+                    let start = *subpath.points.last().unwrap();
+
+                    let second_difference = (start - *control1 - *control1 + *control2)
+                        .magnitude()
+                        .max((*control1 - *control2 - *control2 + *end).magnitude());
+
+                    let count = curve_segment_count(second_difference, 0.75, tolerance);
+
+                    for i in 1..=count {
+                        let t = i as f32 / count as f32;
+                        let mt = 1.0 - t;
+
+                        subpath.push(
+                            start * (mt * mt * mt)
+                                + *control1 * (3.0 * mt * mt * t)
+                                + *control2 * (3.0 * mt * t * t)
+                                + *end * (t * t * t)
+                        );
+                    }
+                }
+            }
+
+            PathSegment::Close => {
+                if let Some(subpath) = current.take() {
+                    let start = subpath.points[0];
+
+                    if has_segments {
+                        result.push(subpath.finish(true));
+                    }
+
+                    // Any further segments continue from the start point
+                    current = Some(FlattenedSubpath::new(start));
+                    has_segments = false;
+                }
+            }
+        }
+    }
+
+    if let Some(subpath) = current.take() {
+        if has_segments {
+            result.push(subpath.finish(false));
+        }
+    }
+
+    result
+}
+
+/// Returns true if the point is inside the polygon, using the even-odd rule.
+fn polygon_contains(polygon: &[Vec2], point: Vec2) -> bool
+{
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+
+    for current in polygon {
+        if (current.y > point.y) != (previous.y > point.y) {
+            let intersect_x = previous.x
+                + (point.y - previous.y) * (current.x - previous.x)
+                    / (current.y - previous.y);
+
+            if point.x < intersect_x {
+                inside = !inside;
+            }
+        }
+
+        previous = *current;
+    }
+
+    inside
+}
+
+/// Triangulates the interior of the path. Subpaths which lie inside an odd
+/// number of other subpaths are treated as holes, which matches the even-odd
+/// fill rule as long as the subpaths do not intersect.
+pub(crate) fn fill(path: &Path, tolerance: f32) -> Vec<[Vec2; 3]>
+{
+    let subpaths: Vec<Vec<Vec2>> = flatten(path, tolerance)
+        .into_iter()
+        .map(|subpath| subpath.finish(true).points)
+        .filter(|points| points.len() >= 3)
+        .collect();
+
+    let containers: Vec<Vec<usize>> = subpaths
+        .iter()
+        .enumerate()
+        .map(|(index, subpath)| {
+            (0..subpaths.len())
+                .filter(|other| {
+                    *other != index && polygon_contains(&subpaths[*other], subpath[0])
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut triangles = Vec::new();
+
+    for (outer_index, outer) in subpaths.iter().enumerate() {
+        let depth = containers[outer_index].len();
+
+        if depth % 2 == 1 {
+            continue;
+        }
+
+        let mut vertices: Vec<Vec2> = outer.clone();
+        let mut hole_indices = Vec::new();
+
+        for (hole_index, hole) in subpaths.iter().enumerate() {
+            let hole_containers = &containers[hole_index];
+
+            if hole_containers.len() == depth + 1
+                && hole_containers.contains(&outer_index)
+            {
+                hole_indices.push(vertices.len());
+                vertices.extend_from_slice(hole);
+            }
+        }
+
+        let mut flattened = Vec::with_capacity(vertices.len() * 2);
+
+        for vertex in &vertices {
+            flattened.push(vertex.x);
+            flattened.push(vertex.y);
+        }
+
+        let triangulation = earcutr::earcut(&flattened, &hole_indices, 2);
+
+        for triangle in triangulation.chunks_exact(3) {
+            triangles.push([
+                vertices[triangle[0]],
+                vertices[triangle[1]],
+                vertices[triangle[2]]
+            ]);
+        }
+    }
+
+    triangles
+}
+
+struct Stroker<'a>
+{
+    style: &'a StrokeStyle,
+    half_width: f32,
+    tolerance: f32,
+    triangles: Vec<[Vec2; 3]>
+}
+
+impl<'a> Stroker<'a>
+{
+    fn push_quad(&mut self, quad: [Vec2; 4])
+    {
+        self.triangles.push([quad[0], quad[1], quad[2]]);
+        self.triangles.push([quad[2], quad[3], quad[0]]);
+    }
+
+    /// Adds a triangle fan around `center`, starting at `center + offset` and
+    /// rotating by `radians`.
+    fn push_arc(&mut self, center: Vec2, offset: Vec2, radians: f32)
+    {
+        let radius = offset.magnitude();
+
+        let max_step = if self.tolerance < radius {
+            2.0 * (1.0 - self.tolerance / radius).acos()
+        } else {
+            std::f32::consts::FRAC_PI_2
+        };
+
+        let steps = (radians.abs() / max_step)
+            .ceil()
+            .clamp(1.0, MAX_CURVE_SEGMENTS) as usize;
+
+        let mut previous = center + offset;
+
+        for step in 1..=steps {
+            let next = center + rotate(offset, radians * step as f32 / steps as f32);
+            self.triangles.push([center, previous, next]);
+            previous = next;
+        }
+    }
+
+    fn push_cap(&mut self, point: Vec2, direction: Vec2)
+    {
+        let normal = direction.rotate_90_degrees_clockwise() * self.half_width;
+
+        match self.style.cap {
+            LineCap::Butt => {}
+            LineCap::Round => self.push_arc(point, normal, -std::f32::consts::PI),
+            LineCap::Square => {
+                let extension = direction * self.half_width;
+                self.push_quad([
+                    point + normal,
+                    point + normal + extension,
+                    point - normal + extension,
+                    point - normal
+                ]);
+            }
+        }
+    }
+
+    fn push_join(&mut self, point: Vec2, incoming: Vec2, outgoing: Vec2)
+    {
+        let turn = cross(incoming, outgoing);
+        let alignment = dot(incoming, outgoing);
+
+        if turn.abs() < 1.0e-6 && alignment > 0.0 {
+            // The segments continue in a straight line
+            return;
+        }
+
+        // The join is added on the outside of the turn
+        let side = if turn > 0.0 { -1.0 } else { 1.0 };
+
+        let outer_incoming =
+            incoming.rotate_90_degrees_clockwise() * (self.half_width * side);
+        let outer_outgoing =
+            outgoing.rotate_90_degrees_clockwise() * (self.half_width * side);
+
+        match self.style.join {
+            LineJoin::Bevel => {
+                self.triangles.push([
+                    point,
+                    point + outer_incoming,
+                    point + outer_outgoing
+                ]);
+            }
+
+            LineJoin::Round => {
+                let angle = cross(outer_incoming, outer_outgoing)
+                    .atan2(dot(outer_incoming, outer_outgoing));
+
+                self.push_arc(point, outer_incoming, angle);
+            }
+
+            LineJoin::Miter => {
+                // The cosine of half the angle between the two normals
+                let cos_half_angle = ((1.0 + alignment) / 2.0).max(0.0).sqrt();
+
+                let miter_direction = (outer_incoming + outer_outgoing).normalize();
+
+                match miter_direction {
+                    Some(miter_direction)
+                        if cos_half_angle * self.style.miter_limit >= 1.0 =>
+                    {
+                        let tip =
+                            point + miter_direction * (self.half_width / cos_half_angle);
+
+                        self.triangles.push([point, point + outer_incoming, tip]);
+                        self.triangles.push([point, tip, point + outer_outgoing]);
+                    }
+
+                    _ => {
+                        self.triangles.push([
+                            point,
+                            point + outer_incoming,
+                            point + outer_outgoing
+                        ]);
+                    }
+                }
+            }
+        }
+    }
+
+    fn push_subpath(&mut self, subpath: &FlattenedSubpath)
+    {
+        let points = &subpath.points;
+
+        if points.len() == 1 {
+            // A zero-length subpath is only visible if it has caps
+            let point = points[0];
+            self.push_cap(point, Vec2::new(1.0, 0.0));
+            self.push_cap(point, Vec2::new(-1.0, 0.0));
+            return;
+        }
+
+        let segment_count = if subpath.closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+
+        let directions: Vec<Vec2> = (0..segment_count)
+            .map(|i| {
+                (points[(i + 1) % points.len()] - points[i])
+                    .normalize()
+                    .unwrap()
+            })
+            .collect();
+
+        for (i, direction) in directions.iter().enumerate() {
+            let start = points[i];
+            let end = points[(i + 1) % points.len()];
+            let normal = direction.rotate_90_degrees_clockwise() * self.half_width;
+
+            self.push_quad([start + normal, end + normal, end - normal, start - normal]);
+        }
+
+        if subpath.closed {
+            for i in 0..segment_count {
+                let incoming = directions[(i + segment_count - 1) % segment_count];
+                self.push_join(points[i], incoming, directions[i]);
+            }
+        } else {
+            for i in 1..segment_count {
+                self.push_join(points[i], directions[i - 1], directions[i]);
+            }
+
+            self.push_cap(points[0], directions[0] * -1.0);
+            self.push_cap(points[segment_count], directions[segment_count - 1]);
+        }
+    }
+}
+
+/// Triangulates the outline of the path, using the specified stroke style.
+/// Triangles may overlap at joins.
+pub(crate) fn stroke(path: &Path, style: &StrokeStyle, tolerance: f32) -> Vec<[Vec2; 3]>
+{
+    let mut stroker = Stroker {
+        style,
+        half_width: style.width / 2.0,
+        tolerance,
+        triangles: Vec::new()
+    };
+
+    if stroker.half_width <= 0.0 {
+        return Vec::new();
+    }
+
+    for subpath in flatten(path, tolerance) {
+        stroker.push_subpath(&subpath);
+    }
+
+    stroker.triangles
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    fn total_area(triangles: &[[Vec2; 3]]) -> f32
+    {
+        triangles
+            .iter()
+            .map(|[a, b, c]| cross(*b - *a, *c - *a).abs() / 2.0)
+            .sum()
+    }
+
+    fn assert_near(expected: f32, actual: f32)
+    {
+        assert!(
+            (expected - actual).abs() < 0.01,
+            "Expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn square(path: Path, x: f32, y: f32, size: f32) -> Path
+    {
+        path.move_to((x, y))
+            .line_to((x + size, y))
+            .line_to((x + size, y + size))
+            .line_to((x, y + size))
+            .close()
+    }
+
+    #[test]
+    fn test_flatten()
+    {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((10.0, 0.0))
+            .line_to((10.0, 0.0))
+            .close()
+            .line_to((0.0, 10.0))
+            .quad_to((5.0, 20.0), (10.0, 10.0));
+
+        let subpaths = flatten(&path, 0.1);
+
+        assert_eq!(2, subpaths.len());
+
+        assert_eq!(
+            FlattenedSubpath {
+                points: vec![Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)],
+                closed: true
+            },
+            subpaths[0]
+        );
+
+        let curve = &subpaths[1];
+
+        assert!(!curve.closed);
+        assert!(curve.points.len() > 4);
+        assert_eq!(Some(&Vec2::new(10.0, 10.0)), curve.points.last());
+
+        for point in &curve.points[2..] {
+            assert!(point.y > 10.0 || *point == Vec2::new(10.0, 10.0));
+        }
+    }
+
+    #[test]
+    fn test_arc_to()
+    {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .arc_to((10.0, 0.0), (10.0, 10.0), 4.0);
+
+        let subpaths = flatten(&path, 0.01);
+        let points = &subpaths[0].points;
+
+        assert_eq!(Vec2::new(6.0, 0.0), points[1]);
+
+        let end = *points.last().unwrap();
+        assert_near(10.0, end.x);
+        assert_near(4.0, end.y);
+
+        for point in &points[1..] {
+            assert_near(4.0, (*point - Vec2::new(6.0, 4.0)).magnitude());
+        }
+    }
+
+    #[test]
+    fn test_fill()
+    {
+        let path = square(Path::new(), 0.0, 0.0, 10.0);
+        assert_near(100.0, total_area(&fill(&path, 0.1)));
+
+        let with_hole = square(square(path, 2.0, 2.0, 4.0), 3.0, 3.0, 2.0);
+        assert_near(100.0 - 16.0 + 4.0, total_area(&fill(&with_hole, 0.1)));
+
+        let separate = square(square(Path::new(), 0.0, 0.0, 1.0), 5.0, 5.0, 2.0);
+        assert_near(5.0, total_area(&fill(&separate, 0.1)));
+    }
+
+    #[test]
+    fn test_stroke_caps()
+    {
+        let line = Path::new().move_to((0.0, 0.0)).line_to((10.0, 0.0));
+
+        let butt = StrokeStyle::new(2.0);
+        assert_near(20.0, total_area(&stroke(&line, &butt, 0.1)));
+
+        let square = butt.clone().with_cap(LineCap::Square);
+        assert_near(24.0, total_area(&stroke(&line, &square, 0.1)));
+
+        let round = butt.with_cap(LineCap::Round);
+        let area = total_area(&stroke(&line, &round, 0.001));
+        assert!((area - (20.0 + std::f32::consts::PI)).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_stroke_joins()
+    {
+        let corner = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((10.0, 0.0))
+            .line_to((10.0, 10.0));
+
+        let segments = 2.0 * 20.0;
+
+        let miter = StrokeStyle::new(2.0);
+        assert_near(segments + 1.0, total_area(&stroke(&corner, &miter, 0.1)));
+
+        let bevel = miter.clone().with_join(LineJoin::Bevel);
+        assert_near(segments + 0.5, total_area(&stroke(&corner, &bevel, 0.1)));
+
+        let round = miter.clone().with_join(LineJoin::Round);
+        let area = total_area(&stroke(&corner, &round, 0.001));
+        assert!((area - (segments + std::f32::consts::FRAC_PI_4)).abs() < 0.05);
+
+        // The miter of a 45 degree corner is 2.6 times the stroke width
+        let sharp = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((10.0, 0.0))
+            .line_to((0.0, 10.0));
+
+        let limited = miter.with_miter_limit(2.0);
+        let unlimited = limited.clone().with_miter_limit(3.0);
+
+        assert!(
+            total_area(&stroke(&sharp, &unlimited, 0.1))
+                > total_area(&stroke(&sharp, &limited, 0.1)) + 0.5
+        );
+    }
+
+    #[test]
+    fn test_stroke_tiny_segments()
+    {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((1.0e-23, 0.0))
+            .line_to((40.0, 40.0));
+
+        assert_eq!(2, flatten(&path, 0.1)[0].points.len());
+
+        let style = StrokeStyle::new(2.0);
+        let length = 40.0 * std::f32::consts::SQRT_2;
+        assert_near(2.0 * length, total_area(&stroke(&path, &style, 0.1)));
+
+        let closed = Path::new()
+            .move_to((0.0, 0.0))
+            .line_to((10.0, 0.0))
+            .line_to((10.0, 10.0))
+            .line_to((1.0e-23, 0.0))
+            .close();
+
+        assert_eq!(3, flatten(&closed, 0.1)[0].points.len());
+        assert!(!stroke(&closed, &style, 0.1).is_empty());
+    }
+}
@@ -14,6 +14,7 @@
  *  limitations under the License.
  */
 
+use std::collections::HashMap;
 use std::rc::Rc;
 
 #[cfg(any(feature = "image-loading", doc, doctest))]
@@ -25,14 +26,26 @@ use {
     std::path::Path
 };
 
-use crate::color::Color;
-use crate::dimen::{UVec2, Vec2};
+use crate::color::{BlendMode, Color};
+use crate::dimen::{Affine2, UVec2, Vec2};
+use crate::edge_antialiasing::{self, FeatherMode};
 use crate::error::{BacktraceError, Context, ErrorMessage};
-use crate::font::{FormattedGlyph, FormattedTextBlock};
-use crate::font_cache::GlyphCache;
+use crate::font::{
+    BitmapFont,
+    FormattedGlyph,
+    FormattedTextBlock,
+    GlyphCacheOptions,
+    GlyphCacheStats,
+    TextRenderingMode,
+    TextStyle
+};
+use crate::font_cache::{GlyphCache, GlyphRendering};
 use crate::glwrapper::*;
 use crate::image::{ImageDataType, ImageHandle, ImageSmoothingMode};
-use crate::{Polygon, RawBitmapData, Rect, Rectangle};
+use crate::paint::{Gradient, GradientKind, Paint, GRADIENT_RAMP_SIZE};
+use crate::shader::{ShaderHandle, ShaderUniforms};
+use crate::shape::{self, StrokeStyle};
+use crate::{path_tessellation, Polygon, RawBitmapData, Rect, Rectangle};
 
 struct AttributeBuffers
 {
@@ -41,12 +54,24 @@ struct AttributeBuffers
     texture_coord: Vec<f32>,
     texture_mix: Vec<f32>,
     circle_mix: Vec<f32>,
+    gradient_coord: Vec<f32>,
+    linear_gradient_mix: Vec<f32>,
+    radial_gradient_mix: Vec<f32>,
+    sdf_mix: Vec<f32>,
+    sdf_edge: Vec<f32>,
+    coverage_mix: Vec<f32>,
 
     glbuf_position: GLBuffer,
     glbuf_color: GLBuffer,
     glbuf_texture_coord: GLBuffer,
     glbuf_texture_mix: GLBuffer,
-    glbuf_circle_mix: GLBuffer
+    glbuf_circle_mix: GLBuffer,
+    glbuf_gradient_coord: GLBuffer,
+    glbuf_linear_gradient_mix: GLBuffer,
+    glbuf_radial_gradient_mix: GLBuffer,
+    glbuf_sdf_mix: GLBuffer,
+    glbuf_sdf_edge: GLBuffer,
+    glbuf_coverage_mix: GLBuffer
 }
 
 impl AttributeBuffers
@@ -62,6 +87,12 @@ impl AttributeBuffers
             texture_coord: Vec::new(),
             texture_mix: Vec::new(),
             circle_mix: Vec::new(),
+            gradient_coord: Vec::new(),
+            linear_gradient_mix: Vec::new(),
+            radial_gradient_mix: Vec::new(),
+            sdf_mix: Vec::new(),
+            sdf_edge: Vec::new(),
+            coverage_mix: Vec::new(),
 
             glbuf_position: context
                 .new_buffer(
@@ -111,7 +142,67 @@ impl AttributeBuffers
                         .get_attribute_handle(Renderer2D::ATTR_NAME_CIRCLE_MIX)
                         .context("Failed to get attribute CIRCLE_MIX")?
                 )
-                .context("Failed to create buffer for attribute CIRCLE_MIX")?
+                .context("Failed to create buffer for attribute CIRCLE_MIX")?,
+
+            glbuf_gradient_coord: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    2,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_GRADIENT_COORD)
+                        .context("Failed to get attribute GRADIENT_COORD")?
+                )
+                .context("Failed to create buffer for attribute GRADIENT_COORD")?,
+
+            glbuf_linear_gradient_mix: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    1,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_LINEAR_GRADIENT_MIX)
+                        .context("Failed to get attribute LINEAR_GRADIENT_MIX")?
+                )
+                .context("Failed to create buffer for attribute LINEAR_GRADIENT_MIX")?,
+
+            glbuf_radial_gradient_mix: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    1,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_RADIAL_GRADIENT_MIX)
+                        .context("Failed to get attribute RADIAL_GRADIENT_MIX")?
+                )
+                .context("Failed to create buffer for attribute RADIAL_GRADIENT_MIX")?,
+
+            glbuf_sdf_mix: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    1,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_SDF_MIX)
+                        .context("Failed to get attribute SDF_MIX")?
+                )
+                .context("Failed to create buffer for attribute SDF_MIX")?,
+
+            glbuf_sdf_edge: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    2,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_SDF_EDGE)
+                        .context("Failed to get attribute SDF_EDGE")?
+                )
+                .context("Failed to create buffer for attribute SDF_EDGE")?,
+
+            glbuf_coverage_mix: context
+                .new_buffer(
+                    GLBufferTarget::Array,
+                    1,
+                    program
+                        .get_attribute_handle(Renderer2D::ATTR_NAME_COVERAGE_MIX)
+                        .context("Failed to get attribute COVERAGE_MIX")?
+                )
+                .context("Failed to create buffer for attribute COVERAGE_MIX")?
         })
     }
 
@@ -129,6 +220,16 @@ impl AttributeBuffers
             .set_data(context, &self.texture_coord);
         self.glbuf_texture_mix.set_data(context, &self.texture_mix);
         self.glbuf_circle_mix.set_data(context, &self.circle_mix);
+        self.glbuf_gradient_coord
+            .set_data(context, &self.gradient_coord);
+        self.glbuf_linear_gradient_mix
+            .set_data(context, &self.linear_gradient_mix);
+        self.glbuf_radial_gradient_mix
+            .set_data(context, &self.radial_gradient_mix);
+        self.glbuf_sdf_mix.set_data(context, &self.sdf_mix);
+        self.glbuf_sdf_edge.set_data(context, &self.sdf_edge);
+        self.glbuf_coverage_mix
+            .set_data(context, &self.coverage_mix);
         self.clear();
     }
 
@@ -139,23 +240,28 @@ impl AttributeBuffers
         self.texture_coord.clear();
         self.texture_mix.clear();
         self.circle_mix.clear();
+        self.gradient_coord.clear();
+        self.linear_gradient_mix.clear();
+        self.radial_gradient_mix.clear();
+        self.sdf_mix.clear();
+        self.sdf_edge.clear();
+        self.coverage_mix.clear();
     }
 
     #[inline]
-    pub fn append(
-        &mut self,
-        position: &Vec2,
-        color: &Color,
-        texture_coord: &Vec2,
-        texture_mix: f32,
-        circle_mix: f32
-    )
+    pub fn append(&mut self, vertex: &Renderer2DVertex)
     {
-        AttributeBuffers::push_vec2(&mut self.position, position);
-        AttributeBuffers::push_color(&mut self.color, color);
-        AttributeBuffers::push_vec2(&mut self.texture_coord, texture_coord);
-        self.texture_mix.push(texture_mix);
-        self.circle_mix.push(circle_mix);
+        AttributeBuffers::push_vec2(&mut self.position, &vertex.position);
+        AttributeBuffers::push_color(&mut self.color, &vertex.color);
+        AttributeBuffers::push_vec2(&mut self.texture_coord, &vertex.texture_coord);
+        self.texture_mix.push(vertex.texture_mix);
+        self.circle_mix.push(vertex.circle_mix);
+        AttributeBuffers::push_vec2(&mut self.gradient_coord, &vertex.gradient_coord);
+        self.linear_gradient_mix.push(vertex.linear_gradient_mix);
+        self.radial_gradient_mix.push(vertex.radial_gradient_mix);
+        self.sdf_mix.push(vertex.sdf_mix);
+        AttributeBuffers::push_vec2(&mut self.sdf_edge, &vertex.sdf_edge);
+        self.coverage_mix.push(vertex.coverage_mix);
     }
 
     #[inline]
@@ -179,7 +285,10 @@ struct Uniforms
 {
     scale_x: GLUniformHandle,
     scale_y: GLUniformHandle,
-    texture: GLUniformHandle
+    texture: GLUniformHandle,
+    premultiply_alpha: GLUniformHandle,
+    premultiply_coverage: GLUniformHandle,
+    coverage_threshold: GLUniformHandle
 }
 
 impl Uniforms
@@ -198,7 +307,19 @@ impl Uniforms
                 .context("Failed to find SCALE_Y uniform")?,
             texture: program
                 .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_TEXTURE)
-                .context("Failed to find TEXTURE uniform")?
+                .context("Failed to find TEXTURE uniform")?,
+            premultiply_alpha: program
+                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_PREMULTIPLY_ALPHA)
+                .context("Failed to find PREMULTIPLY_ALPHA uniform")?,
+            premultiply_coverage: program
+                .get_uniform_handle(
+                    context,
+                    Renderer2D::UNIFORM_NAME_PREMULTIPLY_COVERAGE
+                )
+                .context("Failed to find PREMULTIPLY_COVERAGE uniform")?,
+            coverage_threshold: program
+                .get_uniform_handle(context, Renderer2D::UNIFORM_NAME_COVERAGE_THRESHOLD)
+                .context("Failed to find COVERAGE_THRESHOLD uniform")?
         })
     }
 
@@ -218,6 +339,16 @@ impl Uniforms
     {
         self.texture.set_value_int(context, texture_unit);
     }
+
+    fn set_blend_mode(&self, context: &GLContextManager, blend_mode: BlendMode)
+    {
+        self.premultiply_alpha
+            .set_value_float(context, Renderer2D::premultiply_alpha(blend_mode));
+        self.premultiply_coverage
+            .set_value_float(context, Renderer2D::premultiply_coverage(blend_mode));
+        self.coverage_threshold
+            .set_value_float(context, Renderer2D::coverage_threshold(blend_mode));
+    }
 }
 
 pub(crate) struct Renderer2DVertex
@@ -226,7 +357,15 @@ pub(crate) struct Renderer2DVertex
     pub texture_coord: Vec2,
     pub color: Color,
     pub texture_mix: f32,
-    pub circle_mix: f32
+    pub circle_mix: f32,
+    pub gradient_coord: Vec2,
+    pub linear_gradient_mix: f32,
+    pub radial_gradient_mix: f32,
+    pub sdf_mix: f32,
+    pub sdf_edge: Vec2,
+    /// The extent to which the alpha of the texture is the coverage of a
+    /// glyph, rather than part of the color of an image.
+    pub coverage_mix: f32
 }
 
 impl Renderer2DVertex
@@ -234,24 +373,40 @@ impl Renderer2DVertex
     #[inline]
     fn append_to_attribute_buffers(&self, attribute_buffers: &mut AttributeBuffers)
     {
-        attribute_buffers.append(
-            &self.position,
-            &self.color,
-            &self.texture_coord,
-            self.texture_mix,
-            self.circle_mix
-        );
+        attribute_buffers.append(self);
     }
 }
 
+/// A custom shader, along with the uniform values for a single draw call.
+pub(crate) struct ShaderInvocation
+{
+    shader: ShaderHandle,
+    uniforms: ShaderUniforms
+}
+
 pub(crate) struct Renderer2DAction
 {
     pub texture: Option<GLTexture>,
+    pub shader: Option<Rc<ShaderInvocation>>,
     pub vertices_clockwise: [Renderer2DVertex; 3]
 }
 
 impl Renderer2DAction
 {
+    /// Actions can only be drawn in the same batch if they use the same
+    /// shader invocation.
+    #[inline]
+    fn has_same_shader(&self, current_shader: &Option<Rc<ShaderInvocation>>) -> bool
+    {
+        match (&self.shader, current_shader) {
+            (None, None) => true,
+            (Some(own_shader), Some(current_shader)) => {
+                Rc::ptr_eq(own_shader, current_shader)
+            }
+            _ => false
+        }
+    }
+
     #[inline]
     fn update_current_texture_if_empty(
         &self,
@@ -280,13 +435,27 @@ impl Renderer2DAction
     }
 }
 
+/// An outline, shadow or glow drawn behind text, using the signed distance
+/// fields of the glyphs. Distances are in pixels at the scale of the text.
+#[derive(Debug, Clone, PartialEq)]
+struct TextEffect
+{
+    dilation: f32,
+    blur: f32
+}
+
 enum RenderQueueItem
 {
+    /// If `effect` is set, the glyphs are drawn as distance fields in `color`,
+    /// ignoring both `rendering_mode` and the colors of the glyphs.
     FormattedTextBlock
     {
         position: Vec2,
         color: Color,
-        block: FormattedTextBlock
+        block: FormattedTextBlock,
+        transform: Affine2,
+        rendering_mode: TextRenderingMode,
+        effect: Option<TextEffect>
     },
 
     FormattedTextGlyph
@@ -294,7 +463,9 @@ enum RenderQueueItem
         position: Vec2,
         color: Color,
         glyph: FormattedGlyph,
-        crop_window: Rect
+        crop_window: Rect,
+        transform: Affine2,
+        rendering_mode: TextRenderingMode
     },
 
     CircleSectionColored
@@ -316,11 +487,87 @@ enum RenderQueueItem
         vertex_colors_clockwise: [Color; 3],
         vertex_texture_coords_clockwise: [Vec2; 3],
         texture: GLTexture
+    },
+
+    TriangleCustomShader
+    {
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_texture_coords_clockwise: [Vec2; 3],
+        texture: Option<GLTexture>,
+        shader: Rc<ShaderInvocation>
+    },
+
+    TriangleGradient
+    {
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_gradient_coords_clockwise: [Vec2; 3],
+        vertex_colors_clockwise: [Color; 3],
+        vertex_normalized_circle_coords_clockwise: Option<[Vec2; 3]>,
+        radial: bool,
+        texture: GLTexture
     }
 }
 
 impl RenderQueueItem
 {
+    /// Wraps the specified runner, so that the vertex positions of each
+    /// action are transformed before being passed on.
+    #[inline]
+    fn transformed_runner<'a>(
+        transform: &'a Affine2,
+        runner: &'a mut impl FnMut(Renderer2DAction)
+    ) -> impl FnMut(Renderer2DAction) + 'a
+    {
+        move |mut action| {
+            if *transform != Affine2::IDENTITY {
+                for vertex in action.vertices_clockwise.iter_mut() {
+                    vertex.position = transform.transform_point(vertex.position);
+                }
+            }
+
+            runner(action)
+        }
+    }
+
+    /// Text effects are drawn in their own color, rather than the color of
+    /// each span.
+    #[inline]
+    fn glyph_color(
+        glyph: &FormattedGlyph,
+        color: Color,
+        effect: Option<&TextEffect>
+    ) -> Color
+    {
+        match effect {
+            None => glyph.color().unwrap_or(color),
+            Some(_) => color
+        }
+    }
+
+    #[inline]
+    fn glyph_rendering(
+        rendering_mode: TextRenderingMode,
+        effect: Option<&TextEffect>,
+        transform: &Affine2
+    ) -> GlyphRendering
+    {
+        if effect.is_none() && rendering_mode == TextRenderingMode::Bitmap {
+            return GlyphRendering::Bitmap;
+        }
+
+        // The factor by which the transform scales areas, expressed as a length
+        let matrix = transform.matrix();
+        let pixel_scale = (matrix[0][0] * matrix[1][1] - matrix[0][1] * matrix[1][0])
+            .abs()
+            .sqrt();
+
+        GlyphRendering::DistanceField {
+            pixel_scale,
+            dilation: effect.map(|effect| effect.dilation).unwrap_or(0.0),
+            blur: effect.map(|effect| effect.blur).unwrap_or(0.0)
+        }
+    }
+
     #[inline]
     fn generate_actions(
         &self,
@@ -332,12 +579,26 @@ impl RenderQueueItem
             RenderQueueItem::FormattedTextBlock {
                 position,
                 color,
-                block
+                block,
+                transform,
+                rendering_mode,
+                effect
             } => {
+                let rendering = RenderQueueItem::glyph_rendering(
+                    *rendering_mode,
+                    effect.as_ref(),
+                    transform
+                );
+
+                let runner = &mut RenderQueueItem::transformed_runner(transform, runner);
+
                 for line in block.iter_lines() {
                     for glyph in line.iter_glyphs() {
+                        let color =
+                            RenderQueueItem::glyph_color(glyph, *color, effect.as_ref());
+
                         glyph_cache.get_renderer2d_actions(
-                            glyph, *position, *color, None, runner
+                            glyph, *position, color, None, &rendering, runner
                         );
                     }
                 }
@@ -347,14 +608,17 @@ impl RenderQueueItem
                 glyph,
                 position,
                 color,
-                crop_window
+                crop_window,
+                transform,
+                rendering_mode
             } => {
                 glyph_cache.get_renderer2d_actions(
                     glyph,
                     *position,
                     *color,
                     Some(crop_window),
-                    runner
+                    &RenderQueueItem::glyph_rendering(*rendering_mode, None, transform),
+                    &mut RenderQueueItem::transformed_runner(transform, runner)
                 );
             }
 
@@ -364,27 +628,46 @@ impl RenderQueueItem
                 vertex_normalized_circle_coords_clockwise
             } => runner(Renderer2DAction {
                 texture: None,
+                shader: None,
                 vertices_clockwise: [
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[0],
                         texture_coord: vertex_normalized_circle_coords_clockwise[0],
                         color: vertex_colors_clockwise[0],
                         texture_mix: 0.0,
-                        circle_mix: 1.0
+                        circle_mix: 1.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[1],
                         texture_coord: vertex_normalized_circle_coords_clockwise[1],
                         color: vertex_colors_clockwise[1],
                         texture_mix: 0.0,
-                        circle_mix: 1.0
+                        circle_mix: 1.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[2],
                         texture_coord: vertex_normalized_circle_coords_clockwise[2],
                         color: vertex_colors_clockwise[2],
                         texture_mix: 0.0,
-                        circle_mix: 1.0
+                        circle_mix: 1.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     }
                 ]
             }),
@@ -394,27 +677,46 @@ impl RenderQueueItem
                 vertex_colors_clockwise
             } => runner(Renderer2DAction {
                 texture: None,
+                shader: None,
                 vertices_clockwise: [
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[0],
                         texture_coord: Vec2::ZERO,
                         color: vertex_colors_clockwise[0],
                         texture_mix: 0.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[1],
                         texture_coord: Vec2::ZERO,
                         color: vertex_colors_clockwise[1],
                         texture_mix: 0.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[2],
                         texture_coord: Vec2::ZERO,
                         color: vertex_colors_clockwise[2],
                         texture_mix: 0.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     }
                 ]
             }),
@@ -426,47 +728,241 @@ impl RenderQueueItem
                 texture
             } => runner(Renderer2DAction {
                 texture: Some(texture.clone()),
+                shader: None,
                 vertices_clockwise: [
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[0],
                         texture_coord: vertex_texture_coords_clockwise[0],
                         color: vertex_colors_clockwise[0],
                         texture_mix: 1.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[1],
                         texture_coord: vertex_texture_coords_clockwise[1],
                         color: vertex_colors_clockwise[1],
                         texture_mix: 1.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     },
                     Renderer2DVertex {
                         position: vertex_positions_clockwise[2],
                         texture_coord: vertex_texture_coords_clockwise[2],
                         color: vertex_colors_clockwise[2],
                         texture_mix: 1.0,
-                        circle_mix: 0.0
+                        circle_mix: 0.0,
+                        gradient_coord: Vec2::ZERO,
+                        linear_gradient_mix: 0.0,
+                        radial_gradient_mix: 0.0,
+                        sdf_mix: 0.0,
+                        sdf_edge: Vec2::ZERO,
+                        coverage_mix: 0.0
                     }
                 ]
-            })
+            }),
+
+            RenderQueueItem::TriangleCustomShader {
+                vertex_positions_clockwise,
+                vertex_texture_coords_clockwise,
+                texture,
+                shader
+            } => {
+                let texture_mix = if texture.is_some() { 1.0 } else { 0.0 };
+
+                let vertex = |i: usize| Renderer2DVertex {
+                    position: vertex_positions_clockwise[i],
+                    texture_coord: vertex_texture_coords_clockwise[i],
+                    color: Color::WHITE,
+                    texture_mix,
+                    circle_mix: 0.0,
+                    gradient_coord: Vec2::ZERO,
+                    linear_gradient_mix: 0.0,
+                    radial_gradient_mix: 0.0,
+                    sdf_mix: 0.0,
+                    sdf_edge: Vec2::ZERO,
+                    coverage_mix: 0.0
+                };
+
+                runner(Renderer2DAction {
+                    texture: texture.clone(),
+                    shader: Some(shader.clone()),
+                    vertices_clockwise: [vertex(0), vertex(1), vertex(2)]
+                })
+            }
+
+            RenderQueueItem::TriangleGradient {
+                vertex_positions_clockwise,
+                vertex_gradient_coords_clockwise,
+                vertex_colors_clockwise,
+                vertex_normalized_circle_coords_clockwise,
+                radial,
+                texture
+            } => {
+                let (circle_coords, circle_mix) =
+                    match vertex_normalized_circle_coords_clockwise {
+                        None => ([Vec2::ZERO; 3], 0.0),
+                        Some(circle_coords) => (*circle_coords, 1.0)
+                    };
+
+                let (linear_gradient_mix, radial_gradient_mix) =
+                    if *radial { (0.0, 1.0) } else { (1.0, 0.0) };
+
+                let vertex = |i: usize| Renderer2DVertex {
+                    position: vertex_positions_clockwise[i],
+                    texture_coord: circle_coords[i],
+                    color: vertex_colors_clockwise[i],
+                    texture_mix: 0.0,
+                    circle_mix,
+                    gradient_coord: vertex_gradient_coords_clockwise[i],
+                    linear_gradient_mix,
+                    radial_gradient_mix,
+                    sdf_mix: 0.0,
+                    sdf_edge: Vec2::ZERO,
+                    coverage_mix: 0.0
+                };
+
+                runner(Renderer2DAction {
+                    texture: Some(texture.clone()),
+                    shader: None,
+                    vertices_clockwise: [vertex(0), vertex(1), vertex(2)]
+                })
+            }
+        }
+    }
+}
+
+/// The lookup textures of the gradients drawn recently, keyed by the colors
+/// of the gradient. Textures which are not used during a frame are deleted
+/// at the end of that frame.
+struct GradientTextureCache
+{
+    textures: HashMap<Vec<u32>, (GLTexture, bool)>
+}
+
+impl GradientTextureCache
+{
+    fn new() -> Self
+    {
+        GradientTextureCache {
+            textures: HashMap::new()
+        }
+    }
+
+    fn get_texture(
+        &mut self,
+        context: &GLContextManager,
+        gradient: &Gradient
+    ) -> Result<GLTexture, BacktraceError<ErrorMessage>>
+    {
+        let key = gradient.ramp_key();
+
+        if let Some((texture, used)) = self.textures.get_mut(&key) {
+            *used = true;
+            return Ok(texture.clone());
         }
+
+        let texture = context
+            .new_texture()
+            .context("Failed to create gradient texture")?;
+
+        texture
+            .set_image_data(
+                context,
+                GLTextureImageFormatU8::RGBA,
+                GLTextureSmoothing::Linear,
+                &UVec2::new(GRADIENT_RAMP_SIZE as u32, 1),
+                &gradient.ramp_pixels()
+            )
+            .context("Failed to upload gradient texture")?;
+
+        self.textures.insert(key, (texture.clone(), true));
+
+        Ok(texture)
+    }
+
+    fn on_new_frame_start(&mut self)
+    {
+        self.textures.retain(|_, (_, used)| std::mem::take(used));
+    }
+}
+
+/// An entry on the clip stack, containing what is needed to undo it in
+/// `Renderer2D::pop_clip`.
+enum ClipStackEntry
+{
+    /// The clip rectangle was replaced by its intersection with another
+    /// rectangle.
+    Rectangle
+    {
+        previous: Option<Rectangle<i32>>
+    },
+
+    /// The triangles, in render target coordinates, were added to the
+    /// stencil buffer.
+    Stencil
+    {
+        triangles: Vec<[Vec2; 3]>
     }
 }
 
+/// The state which is saved by `Renderer2D::begin_render_to_image`, and
+/// restored by `Renderer2D::end_render_to_image`.
+pub(crate) struct RenderToImageState
+{
+    image: ImageHandle,
+    framebuffer: GLFramebuffer,
+    stencil_renderbuffer: Option<GLRenderbuffer>,
+    previous_framebuffer: Option<GLFramebuffer>,
+    previous_viewport_size_pixels: UVec2,
+    previous_clip: Option<Rectangle<i32>>,
+    previous_clip_stack: Vec<ClipStackEntry>,
+    previous_stencil_depth: u8,
+    previous_transform: Affine2,
+    previous_transform_stack: Vec<Affine2>,
+    previous_render_target_transform: Affine2,
+    previous_blend_mode: BlendMode
+}
+
 pub struct Renderer2D
 {
     context: GLContextManager,
+    viewport_size_pixels: UVec2,
 
+    vertex_shader: GLShader,
     program: Rc<GLProgram>,
 
-    render_queue: Vec<RenderQueueItem>,
+    /// Each item is drawn using the blend mode which was set when it was
+    /// added to the queue.
+    render_queue: Vec<(RenderQueueItem, BlendMode)>,
 
     glyph_cache: GlyphCache,
+    gradient_texture_cache: GradientTextureCache,
     attribute_buffers: AttributeBuffers,
     current_texture: Option<GLTexture>,
+    current_shader: Option<Rc<ShaderInvocation>>,
+    current_blend_mode: BlendMode,
+
+    transform: Affine2,
+    transform_stack: Vec<Affine2>,
+    render_target_transform: Affine2,
+    clip: Option<Rectangle<i32>>,
+    clip_stack: Vec<ClipStackEntry>,
+    stencil_depth: u8,
+    blend_mode: BlendMode,
+    edge_antialiasing: bool,
+    text_rendering_mode: TextRenderingMode,
 
-    #[allow(dead_code)]
     uniforms: Uniforms
 }
 
@@ -477,17 +973,32 @@ impl Renderer2D
     const ATTR_NAME_TEXTURE_COORD: &'static str = "in_TextureCoord";
     const ATTR_NAME_TEXTURE_MIX: &'static str = "in_TextureMix";
     const ATTR_NAME_CIRCLE_MIX: &'static str = "in_CircleMix";
+    const ATTR_NAME_GRADIENT_COORD: &'static str = "in_GradientCoord";
+    const ATTR_NAME_LINEAR_GRADIENT_MIX: &'static str = "in_LinearGradientMix";
+    const ATTR_NAME_RADIAL_GRADIENT_MIX: &'static str = "in_RadialGradientMix";
+    const ATTR_NAME_SDF_MIX: &'static str = "in_SdfMix";
+    const ATTR_NAME_SDF_EDGE: &'static str = "in_SdfEdge";
+    const ATTR_NAME_COVERAGE_MIX: &'static str = "in_CoverageMix";
 
     const UNIFORM_NAME_SCALE_X: &'static str = "in_ScaleX";
     const UNIFORM_NAME_SCALE_Y: &'static str = "in_ScaleY";
     const UNIFORM_NAME_TEXTURE: &'static str = "in_Texture";
+    const UNIFORM_NAME_PREMULTIPLY_ALPHA: &'static str = "in_PremultiplyAlpha";
+    const UNIFORM_NAME_PREMULTIPLY_COVERAGE: &'static str = "in_PremultiplyCoverage";
+    const UNIFORM_NAME_COVERAGE_THRESHOLD: &'static str = "in_CoverageThreshold";
 
-    const ALL_ATTRIBUTES: [&'static str; 5] = [
+    const ALL_ATTRIBUTES: [&'static str; 11] = [
         Renderer2D::ATTR_NAME_POSITION,
         Renderer2D::ATTR_NAME_COLOR,
         Renderer2D::ATTR_NAME_TEXTURE_COORD,
         Renderer2D::ATTR_NAME_TEXTURE_MIX,
-        Renderer2D::ATTR_NAME_CIRCLE_MIX
+        Renderer2D::ATTR_NAME_CIRCLE_MIX,
+        Renderer2D::ATTR_NAME_GRADIENT_COORD,
+        Renderer2D::ATTR_NAME_LINEAR_GRADIENT_MIX,
+        Renderer2D::ATTR_NAME_RADIAL_GRADIENT_MIX,
+        Renderer2D::ATTR_NAME_SDF_MIX,
+        Renderer2D::ATTR_NAME_SDF_EDGE,
+        Renderer2D::ATTR_NAME_COVERAGE_MIX
     ];
 
     pub fn new(
@@ -547,17 +1058,36 @@ impl Renderer2D
 
         Ok(Renderer2D {
             context: context.clone(),
+            viewport_size_pixels,
+            vertex_shader,
             program,
             render_queue: Vec::new(),
             glyph_cache: GlyphCache::new(),
+            gradient_texture_cache: GradientTextureCache::new(),
             attribute_buffers,
             current_texture: None,
+            current_shader: None,
+            current_blend_mode: BlendMode::Normal,
+            transform: Affine2::IDENTITY,
+            transform_stack: Vec::new(),
+            render_target_transform: Affine2::IDENTITY,
+            clip: None,
+            clip_stack: Vec::new(),
+            stencil_depth: 0,
+            blend_mode: BlendMode::Normal,
+            edge_antialiasing: false,
+            text_rendering_mode: TextRenderingMode::Bitmap,
             uniforms
         })
     }
 
-    pub fn set_viewport_size_pixels(&self, viewport_size_pixels: UVec2)
+    pub fn set_viewport_size_pixels(&mut self, viewport_size_pixels: UVec2)
     {
+        self.viewport_size_pixels = viewport_size_pixels;
+
+        // Uniforms are set on the active program
+        self.context.use_program(&self.program);
+
         self.uniforms
             .set_viewport_size_pixels(&self.context, viewport_size_pixels);
 
@@ -568,6 +1098,7 @@ impl Renderer2D
     {
         self.flush_render_queue();
         self.glyph_cache.on_new_frame_start();
+        self.gradient_texture_cache.on_new_frame_start();
     }
 
     fn flush_render_queue(&mut self)
@@ -580,17 +1111,33 @@ impl Renderer2D
 
         let mut has_text = false;
 
-        for item in &self.render_queue {
+        for (item, _) in &self.render_queue {
             match item {
                 RenderQueueItem::FormattedTextBlock {
-                    block, position, ..
+                    block,
+                    position,
+                    color,
+                    rendering_mode,
+                    effect,
+                    ..
                 } => {
+                    let rendering_mode = match effect {
+                        None => *rendering_mode,
+                        Some(_) => TextRenderingMode::SignedDistanceField
+                    };
+
                     for line in block.iter_lines() {
                         for glyph in line.iter_glyphs() {
                             self.glyph_cache.add_to_cache(
                                 &self.context,
                                 glyph,
-                                *position
+                                *position,
+                                RenderQueueItem::glyph_color(
+                                    glyph,
+                                    *color,
+                                    effect.as_ref()
+                                ),
+                                rendering_mode
                             );
                         }
                     }
@@ -598,15 +1145,26 @@ impl Renderer2D
                     has_text = true;
                 }
                 RenderQueueItem::FormattedTextGlyph {
-                    glyph, position, ..
+                    glyph,
+                    position,
+                    color,
+                    rendering_mode,
+                    ..
                 } => {
-                    self.glyph_cache
-                        .add_to_cache(&self.context, glyph, *position);
+                    self.glyph_cache.add_to_cache(
+                        &self.context,
+                        glyph,
+                        *position,
+                        *color,
+                        *rendering_mode
+                    );
                     has_text = true;
                 }
                 RenderQueueItem::CircleSectionColored { .. }
                 | RenderQueueItem::TriangleColored { .. }
-                | RenderQueueItem::TriangleTextured { .. } => {}
+                | RenderQueueItem::TriangleTextured { .. }
+                | RenderQueueItem::TriangleCustomShader { .. }
+                | RenderQueueItem::TriangleGradient { .. } => {}
             }
         }
 
@@ -618,21 +1176,37 @@ impl Renderer2D
 
         {
             let current_texture = &mut self.current_texture;
+            let current_shader = &mut self.current_shader;
             let context = &self.context;
             let program = &self.program;
             let attribute_buffers = &mut self.attribute_buffers;
+            let uniforms = &self.uniforms;
+            let viewport_size_pixels = self.viewport_size_pixels;
+            let current_blend_mode = &mut self.current_blend_mode;
 
-            for item in &self.render_queue {
+            for (item, blend_mode) in &self.render_queue {
                 item.generate_actions(&self.glyph_cache, &mut |action| {
-                    if !action.update_current_texture_if_empty(current_texture) {
+                    // The blend mode and shader are checked first, so that the
+                    // texture of a batch which can't be extended is left
+                    // unchanged
+                    if *blend_mode != *current_blend_mode
+                        || !action.has_same_shader(current_shader)
+                        || !action.update_current_texture_if_empty(current_texture)
+                    {
                         Renderer2D::draw_buffers(
                             context,
                             program,
+                            uniforms,
                             attribute_buffers,
-                            current_texture
+                            current_texture,
+                            current_shader,
+                            viewport_size_pixels,
+                            *current_blend_mode
                         );
 
                         *current_texture = action.texture.clone();
+                        *current_shader = action.shader.clone();
+                        *current_blend_mode = *blend_mode;
                     }
 
                     action.append_to_attribute_buffers(attribute_buffers);
@@ -645,16 +1219,25 @@ impl Renderer2D
         Renderer2D::draw_buffers(
             &self.context,
             &self.program,
+            &self.uniforms,
             &mut self.attribute_buffers,
-            &mut self.current_texture
+            &mut self.current_texture,
+            &mut self.current_shader,
+            self.viewport_size_pixels,
+            self.current_blend_mode
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn draw_buffers(
         context: &GLContextManager,
         program: &Rc<GLProgram>,
+        uniforms: &Uniforms,
         attribute_buffers: &mut AttributeBuffers,
-        current_texture: &mut Option<GLTexture>
+        current_texture: &mut Option<GLTexture>,
+        current_shader: &mut Option<Rc<ShaderInvocation>>,
+        viewport_size_pixels: UVec2,
+        blend_mode: BlendMode
     )
     {
         let vertex_count = attribute_buffers.get_vertex_count();
@@ -663,7 +1246,18 @@ impl Renderer2D
             return;
         }
 
-        context.use_program(program);
+        match current_shader.take() {
+            None => {
+                context.use_program(program);
+                uniforms.set_blend_mode(context, blend_mode);
+            }
+            Some(invocation) => Renderer2D::use_custom_shader(
+                context,
+                &invocation,
+                viewport_size_pixels,
+                blend_mode
+            )
+        }
 
         attribute_buffers.upload_and_clear(context);
 
@@ -674,10 +1268,116 @@ impl Renderer2D
             Some(texture) => context.bind_texture(texture)
         }
 
-        context.draw_triangles(
-            GLBlendEnabled::Enabled(GLBlendMode::OneMinusSrcAlpha),
-            vertex_count
-        );
+        context.draw_triangles(blend_mode.into(), vertex_count);
+    }
+
+    /// Activates the program of a custom shader, and sets the built-in
+    /// uniforms as well as those specified by the user.
+    fn use_custom_shader(
+        context: &GLContextManager,
+        invocation: &ShaderInvocation,
+        viewport_size_pixels: UVec2,
+        blend_mode: BlendMode
+    )
+    {
+        let shader = &invocation.shader;
+
+        context.use_program(&shader.program);
+
+        if let Some(handle) =
+            shader.uniform_handle(context, Renderer2D::UNIFORM_NAME_SCALE_X)
+        {
+            handle.set_value_float(context, 2.0 / viewport_size_pixels.x as f32);
+        }
+
+        if let Some(handle) =
+            shader.uniform_handle(context, Renderer2D::UNIFORM_NAME_SCALE_Y)
+        {
+            handle.set_value_float(context, -2.0 / viewport_size_pixels.y as f32);
+        }
+
+        if let Some(handle) =
+            shader.uniform_handle(context, Renderer2D::UNIFORM_NAME_TEXTURE)
+        {
+            handle.set_value_int(context, 0);
+        }
+
+        if let Some(handle) =
+            shader.uniform_handle(context, Renderer2D::UNIFORM_NAME_PREMULTIPLY_ALPHA)
+        {
+            handle.set_value_float(context, Renderer2D::premultiply_alpha(blend_mode));
+        }
+
+        invocation.uniforms.apply(context, shader);
+    }
+
+    /// The value of the `in_PremultiplyAlpha` uniform for the specified blend
+    /// mode. Modes which don't scale the source color by its alpha require
+    /// the shader output to be premultiplied.
+    fn premultiply_alpha(blend_mode: BlendMode) -> f32
+    {
+        match blend_mode {
+            BlendMode::Multiply | BlendMode::Screen => 1.0,
+            BlendMode::Normal
+            | BlendMode::Additive
+            | BlendMode::Premultiplied
+            | BlendMode::Replace => 0.0
+        }
+    }
+
+    /// The value of the `in_PremultiplyCoverage` uniform for the specified
+    /// blend mode. The color is already premultiplied in `Premultiplied`
+    /// mode, so the coverage of glyphs and circles must scale all of its
+    /// components rather than just the alpha.
+    fn premultiply_coverage(blend_mode: BlendMode) -> f32
+    {
+        match blend_mode {
+            BlendMode::Premultiplied => 1.0,
+            BlendMode::Normal
+            | BlendMode::Additive
+            | BlendMode::Multiply
+            | BlendMode::Screen
+            | BlendMode::Replace => 0.0
+        }
+    }
+
+    /// The value of the `in_CoverageThreshold` uniform for the specified
+    /// blend mode. Without blending, partial coverage can't be drawn, so in
+    /// `Replace` mode pixels which are less than half covered are discarded.
+    fn coverage_threshold(blend_mode: BlendMode) -> f32
+    {
+        match blend_mode {
+            BlendMode::Replace => 0.5,
+            BlendMode::Normal
+            | BlendMode::Additive
+            | BlendMode::Multiply
+            | BlendMode::Screen
+            | BlendMode::Premultiplied => 0.0
+        }
+    }
+
+    /// Compiles a custom fragment shader, and links it with the built-in
+    /// vertex shader.
+    pub(crate) fn create_fragment_shader(
+        &self,
+        fragment_shader_source: &str
+    ) -> Result<ShaderHandle, BacktraceError<ErrorMessage>>
+    {
+        let fragment_shader = self
+            .context
+            .new_shader(GLShaderType::Fragment, fragment_shader_source)
+            .context("Failed to compile custom fragment shader")?;
+
+        let program = self
+            .context
+            .new_program(
+                &self.vertex_shader,
+                &fragment_shader,
+                &Renderer2D::ALL_ATTRIBUTES
+            )
+            .context("Failed to link custom fragment shader")?;
+
+        Ok(ShaderHandle::new(program))
     }
 
     pub(crate) fn create_image_from_raw_pixels<S: Into<UVec2>>(
@@ -729,6 +1429,168 @@ impl Renderer2D
         Ok(ImageHandle { size, texture })
     }
 
+    pub(crate) fn create_bitmap_font(
+        &mut self,
+        bytes: &[u8],
+        pages: Vec<ImageHandle>
+    ) -> Result<BitmapFont, BacktraceError<ErrorMessage>>
+    {
+        let font = BitmapFont::new(bytes, pages)?;
+        self.glyph_cache.add_bitmap_font(&font);
+        Ok(font)
+    }
+
+    /// Redirects all subsequent drawing operations into a new image of the
+    /// specified size, until `end_render_to_image` is called.
+    ///
+    /// GL textures are stored bottom row first, so the image is rendered
+    /// upside down to make the top row of the drawing become the first row
+    /// of the texture.
+    pub(crate) fn begin_render_to_image(
+        &mut self,
+        size: UVec2,
+        smoothing_mode: ImageSmoothingMode
+    ) -> Result<RenderToImageState, BacktraceError<ErrorMessage>>
+    {
+        if size.x == 0 || size.y == 0 {
+            return Err(ErrorMessage::msg(format!(
+                "Invalid image size {}x{}",
+                size.x, size.y
+            )));
+        }
+
+        self.flush_render_queue();
+
+        let gl_smoothing = match smoothing_mode {
+            ImageSmoothingMode::NearestNeighbor => GLTextureSmoothing::NearestNeighbour,
+            ImageSmoothingMode::Linear => GLTextureSmoothing::Linear
+        };
+
+        let texture = self
+            .context
+            .new_texture()
+            .context("Failed to create GPU texture")?;
+
+        texture
+            .allocate_image_data(
+                &self.context,
+                GLTextureImageFormatU8::RGBA,
+                gl_smoothing,
+                &size
+            )
+            .context("Failed to allocate image data")?;
+
+        // Sampling from a texture while rendering into it is not allowed
+        self.context.unbind_texture();
+
+        let framebuffer = self
+            .context
+            .new_framebuffer()
+            .context("Failed to create framebuffer")?;
+
+        let previous_framebuffer = self.context.active_framebuffer();
+
+        self.context.bind_framebuffer(Some(&framebuffer));
+
+        if let Err(err) = framebuffer.attach_texture(&self.context, &texture) {
+            self.context.bind_framebuffer(previous_framebuffer.as_ref());
+            return Err(err).context("Failed to attach texture to framebuffer");
+        }
+
+        // Without a stencil buffer, the image can still be drawn, but clip
+        // shapes have no effect
+        let stencil_renderbuffer =
+            match self.create_stencil_renderbuffer(&framebuffer, size) {
+                Ok(renderbuffer) => Some(renderbuffer),
+                Err(err) => {
+                    log::warn!("Failed to create stencil buffer for image: {:?}", err);
+                    None
+                }
+            };
+
+        let state = RenderToImageState {
+            image: ImageHandle { size, texture },
+            framebuffer,
+            stencil_renderbuffer,
+            previous_framebuffer,
+            previous_viewport_size_pixels: self.viewport_size_pixels,
+            previous_clip: self.clip.take(),
+            previous_clip_stack: std::mem::take(&mut self.clip_stack),
+            previous_stencil_depth: std::mem::take(&mut self.stencil_depth),
+            previous_transform: self.transform,
+            previous_transform_stack: std::mem::take(&mut self.transform_stack),
+            previous_render_target_transform: self.render_target_transform,
+            previous_blend_mode: self.blend_mode
+        };
+
+        self.set_viewport_size_pixels(size);
+        self.context.set_enable_scissor(false);
+        self.context.set_stencil_mode(GLStencilMode::Disabled);
+        self.set_blend_mode(BlendMode::Normal);
+
+        self.transform = Affine2::IDENTITY;
+        self.render_target_transform =
+            Affine2::new([[1.0, 0.0, 0.0], [0.0, -1.0, size.y as f32]]);
+
+        self.context.clear_screen(Color::TRANSPARENT);
+
+        Ok(state)
+    }
+
+    /// Finishes drawing into the image, and restores the render target which
+    /// was active before the corresponding call to `begin_render_to_image`.
+    pub(crate) fn end_render_to_image(&mut self, state: RenderToImageState)
+        -> ImageHandle
+    {
+        self.flush_render_queue();
+
+        self.context
+            .bind_framebuffer(state.previous_framebuffer.as_ref());
+
+        self.set_viewport_size_pixels(state.previous_viewport_size_pixels);
+
+        self.transform = state.previous_transform;
+        self.transform_stack = state.previous_transform_stack;
+        self.render_target_transform = state.previous_render_target_transform;
+        self.set_blend_mode(state.previous_blend_mode);
+
+        self.set_clip(state.previous_clip);
+
+        self.clip_stack = state.previous_clip_stack;
+        self.stencil_depth = state.previous_stencil_depth;
+        self.context
+            .set_stencil_mode(Renderer2D::stencil_test_mode(self.stencil_depth));
+
+        // The framebuffer is no longer needed, but the texture lives on in
+        // the image
+        drop(state.framebuffer);
+        drop(state.stencil_renderbuffer);
+
+        state.image
+    }
+
+    fn create_stencil_renderbuffer(
+        &self,
+        framebuffer: &GLFramebuffer,
+        size: UVec2
+    ) -> Result<GLRenderbuffer, BacktraceError<ErrorMessage>>
+    {
+        let renderbuffer = self
+            .context
+            .new_renderbuffer()
+            .context("Failed to create renderbuffer")?;
+
+        renderbuffer
+            .allocate_stencil_storage(&self.context, &size)
+            .context("Failed to allocate stencil buffer")?;
+
+        framebuffer
+            .attach_stencil_renderbuffer(&self.context, &renderbuffer)
+            .context("Failed to attach stencil buffer to framebuffer")?;
+
+        Ok(renderbuffer)
+    }
+
     #[cfg(any(feature = "image-loading", doc, doctest))]
     pub fn create_image_from_file_path<P: AsRef<Path>>(
         &mut self,
@@ -794,8 +1656,13 @@ impl Renderer2D
     #[inline]
     pub(crate) fn clear_screen(&mut self, color: Color)
     {
-        if color.a() < 1.0 {
-            self.flush_render_queue();
+        if self.stencil_depth > 0 {
+            self.clear_screen_inside_clip_shapes(color);
+            return;
+        }
+
+        if color.a() < 1.0 {
+            self.flush_render_queue();
         } else {
             self.render_queue.clear();
         }
@@ -803,10 +1670,38 @@ impl Renderer2D
         self.context.clear_screen(color);
     }
 
+    /// Clearing the screen ignores the stencil buffer, so instead a rectangle
+    /// covering the render target is drawn without blending.
+    fn clear_screen_inside_clip_shapes(&mut self, color: Color)
+    {
+        let previous_blend_mode = self.blend_mode;
+
+        self.set_blend_mode(BlendMode::Replace);
+
+        let size = self.viewport_size_pixels.into_f32();
+
+        let top_left = Vec2::ZERO;
+        let top_right = Vec2::new(size.x, 0.0);
+        let bottom_right = size;
+        let bottom_left = Vec2::new(0.0, size.y);
+
+        for vertex_positions_clockwise in [
+            [top_left, top_right, bottom_right],
+            [bottom_right, bottom_left, top_left]
+        ] {
+            self.add_to_render_queue(RenderQueueItem::TriangleColored {
+                vertex_positions_clockwise,
+                vertex_colors_clockwise: [color; 3]
+            });
+        }
+
+        self.set_blend_mode(previous_blend_mode);
+    }
+
     #[inline]
     fn add_to_render_queue(&mut self, item: RenderQueueItem)
     {
-        self.render_queue.push(item);
+        self.render_queue.push((item, self.blend_mode));
 
         if self.render_queue.len() > 100000 {
             self.flush_render_queue();
@@ -818,16 +1713,374 @@ impl Renderer2D
         &mut self,
         polygon: &Polygon,
         offset: V,
-        color: Color
+        paint: &Paint
     )
     {
-        let color = [color; 3];
         let offset = offset.into();
 
-        for triangle in polygon.triangles.iter() {
-            let triangle = triangle.map(|vertex| vertex + offset);
+        self.draw_triangles_with_paint(
+            polygon
+                .triangles
+                .iter()
+                .map(|triangle| triangle.map(|vertex| vertex + offset)),
+            paint
+        );
+    }
+
+    pub(crate) fn draw_path_filled(&mut self, path: &shape::Path, paint: &Paint)
+    {
+        let tolerance = self.path_tolerance();
+
+        self.draw_triangles_with_paint(path_tessellation::fill(path, tolerance), paint);
+    }
+
+    pub(crate) fn draw_path_stroked(
+        &mut self,
+        path: &shape::Path,
+        stroke: &StrokeStyle,
+        paint: &Paint
+    )
+    {
+        let tolerance = self.path_tolerance();
+
+        // The stroke triangles overlap, so the shape can't be shrunk
+        self.draw_shape_with_paint(
+            path_tessellation::stroke(path, stroke, tolerance),
+            paint,
+            FeatherMode::Outside
+        );
+    }
+
+    /// Draws the triangles as a single shape. If edge anti-aliasing is
+    /// enabled, only the outer edges of the shape are anti-aliased.
+    #[inline]
+    pub(crate) fn draw_triangles_with_paint(
+        &mut self,
+        triangles: impl IntoIterator<Item = [Vec2; 3]>,
+        paint: &Paint
+    )
+    {
+        self.draw_shape_with_paint(triangles, paint, FeatherMode::Centered);
+    }
+
+    fn draw_shape_with_paint(
+        &mut self,
+        triangles: impl IntoIterator<Item = [Vec2; 3]>,
+        paint: &Paint,
+        feather_mode: FeatherMode
+    )
+    {
+        match paint {
+            Paint::Color(color) => {
+                let triangles: Vec<([Vec2; 3], [Color; 3])> = triangles
+                    .into_iter()
+                    .map(|triangle| (triangle, [*color; 3]))
+                    .collect();
+
+                self.draw_shape_three_color(&triangles, feather_mode);
+            }
+
+            Paint::Gradient(gradient) => {
+                let texture = match self.gradient_texture(gradient) {
+                    None => return,
+                    Some(texture) => texture
+                };
+
+                let triangles: Vec<[Vec2; 3]> = triangles.into_iter().collect();
+
+                self.draw_shape_gradient(&triangles, gradient, &texture, feather_mode);
+            }
+        }
+    }
+
+    /// Draws the triangles as a single shape, with the specified colors for
+    /// each vertex. If edge anti-aliasing is enabled, only the outer edges of
+    /// the shape are anti-aliased.
+    #[inline]
+    pub(crate) fn draw_triangles_three_color(
+        &mut self,
+        triangles: &[([Vec2; 3], [Color; 3])]
+    )
+    {
+        self.draw_shape_three_color(triangles, FeatherMode::Centered);
+    }
+
+    fn draw_shape_three_color(
+        &mut self,
+        triangles: &[([Vec2; 3], [Color; 3])],
+        feather_mode: FeatherMode
+    )
+    {
+        let positions: Vec<[Vec2; 3]> = triangles
+            .iter()
+            .map(|(positions, _)| self.transform_vertices(*positions))
+            .collect();
+
+        if !self.edge_antialiasing_active() {
+            for (positions, (_, colors)) in positions.into_iter().zip(triangles) {
+                self.add_to_render_queue(RenderQueueItem::TriangleColored {
+                    vertex_positions_clockwise: positions,
+                    vertex_colors_clockwise: *colors
+                });
+            }
+            return;
+        }
+
+        let feathered = edge_antialiasing::feather(&positions, feather_mode);
+
+        for (positions, (_, colors)) in feathered.interior.into_iter().zip(triangles) {
+            self.add_to_render_queue(RenderQueueItem::TriangleColored {
+                vertex_positions_clockwise: positions,
+                vertex_colors_clockwise: *colors
+            });
+        }
+
+        for fringe in feathered.fringe {
+            let colors = [0, 1, 2].map(|i| {
+                let (triangle, vertex) = fringe.sources[i];
+                self.fringe_color(triangles[triangle].1[vertex], fringe.opacity[i])
+            });
+
+            self.add_to_render_queue(RenderQueueItem::TriangleColored {
+                vertex_positions_clockwise: fringe.positions,
+                vertex_colors_clockwise: colors
+            });
+        }
+    }
+
+    /// Anti-aliased fringes are blended with the existing contents, so they
+    /// aren't drawn in `Replace` mode.
+    #[inline]
+    fn edge_antialiasing_active(&self) -> bool
+    {
+        self.edge_antialiasing && self.blend_mode != BlendMode::Replace
+    }
+
+    /// The color of a fringe vertex with the specified opacity. In
+    /// `Premultiplied` mode, the opacity scales every component of the color.
+    #[inline]
+    fn fringe_color(&self, color: Color, opacity: f32) -> Color
+    {
+        match self.blend_mode {
+            BlendMode::Premultiplied => Color::from_rgba(
+                color.r() * opacity,
+                color.g() * opacity,
+                color.b() * opacity,
+                color.a() * opacity
+            ),
+            _ => Color::from_rgba(color.r(), color.g(), color.b(), color.a() * opacity)
+        }
+    }
+
+    /// The fringe is generated in pixel coordinates, so its gradient
+    /// coordinates are found by reversing the transform.
+    fn draw_shape_gradient(
+        &mut self,
+        triangles: &[[Vec2; 3]],
+        gradient: &Gradient,
+        texture: &GLTexture,
+        feather_mode: FeatherMode
+    )
+    {
+        let inverse_transform = match self.effective_transform().inverse() {
+            Some(inverse_transform) if self.edge_antialiasing_active() => {
+                inverse_transform
+            }
+            _ => {
+                for triangle in triangles {
+                    self.draw_gradient_section(*triangle, None, gradient, texture);
+                }
+                return;
+            }
+        };
+
+        let positions: Vec<[Vec2; 3]> = triangles
+            .iter()
+            .map(|triangle| self.transform_vertices(*triangle))
+            .collect();
+
+        let feathered = edge_antialiasing::feather(&positions, feather_mode);
+
+        let radial = matches!(gradient.kind(), GradientKind::Radial { .. });
+
+        let gradient_coords = |positions: [Vec2; 3]| {
+            positions.map(|position| {
+                gradient.shader_coord(inverse_transform.transform_point(position))
+            })
+        };
+
+        for positions in feathered.interior {
+            self.add_to_render_queue(RenderQueueItem::TriangleGradient {
+                vertex_positions_clockwise: positions,
+                vertex_gradient_coords_clockwise: gradient_coords(positions),
+                vertex_colors_clockwise: [Color::WHITE; 3],
+                vertex_normalized_circle_coords_clockwise: None,
+                radial,
+                texture: texture.clone()
+            });
+        }
+
+        for fringe in feathered.fringe {
+            self.add_to_render_queue(RenderQueueItem::TriangleGradient {
+                vertex_positions_clockwise: fringe.positions,
+                vertex_gradient_coords_clockwise: gradient_coords(fringe.positions),
+                vertex_colors_clockwise: fringe
+                    .opacity
+                    .map(|opacity| self.fringe_color(Color::WHITE, opacity)),
+                vertex_normalized_circle_coords_clockwise: None,
+                radial,
+                texture: texture.clone()
+            });
+        }
+    }
+
+    /// Draws a circle section with the specified paint. See
+    /// `draw_circle_section` for the meaning of the circle coordinates.
+    pub(crate) fn draw_circle_section_with_paint(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_normalized_circle_coords_clockwise: [Vec2; 3],
+        paint: &Paint
+    )
+    {
+        match paint {
+            Paint::Color(color) => self.draw_circle_section(
+                vertex_positions_clockwise,
+                [*color; 3],
+                vertex_normalized_circle_coords_clockwise
+            ),
+
+            Paint::Gradient(gradient) => {
+                if let Some(texture) = self.gradient_texture(gradient) {
+                    self.draw_gradient_section(
+                        vertex_positions_clockwise,
+                        Some(vertex_normalized_circle_coords_clockwise),
+                        gradient,
+                        &texture
+                    );
+                }
+            }
+        }
+    }
+
+    fn gradient_texture(&mut self, gradient: &Gradient) -> Option<GLTexture>
+    {
+        match self
+            .gradient_texture_cache
+            .get_texture(&self.context, gradient)
+        {
+            Ok(texture) => Some(texture),
+            Err(err) => {
+                log::error!("Failed to create gradient texture, skipping: {:?}", err);
+                None
+            }
+        }
+    }
+
+    /// The gradient coordinates are calculated before the vertices are
+    /// transformed, so that the gradient is transformed along with the shape.
+    #[inline]
+    fn draw_gradient_section(
+        &mut self,
+        vertex_positions_clockwise: [Vec2; 3],
+        vertex_normalized_circle_coords_clockwise: Option<[Vec2; 3]>,
+        gradient: &Gradient,
+        texture: &GLTexture
+    )
+    {
+        self.add_to_render_queue(RenderQueueItem::TriangleGradient {
+            vertex_positions_clockwise: self
+                .transform_vertices(vertex_positions_clockwise),
+            vertex_gradient_coords_clockwise: vertex_positions_clockwise
+                .map(|vertex| gradient.shader_coord(vertex)),
+            vertex_colors_clockwise: [Color::WHITE; 3],
+            vertex_normalized_circle_coords_clockwise,
+            radial: matches!(gradient.kind(), GradientKind::Radial { .. }),
+            texture: texture.clone()
+        })
+    }
+
+    /// The maximum distance between a curve and its approximation, in path
+    /// coordinates, such that the error on screen is at most a tenth of a
+    /// pixel.
+    #[inline]
+    fn path_tolerance(&self) -> f32
+    {
+        const TOLERANCE_PIXELS: f32 = 0.1;
+
+        let scale = self.effective_transform().max_scale_factor();
 
-            self.draw_triangle_three_color(triangle, color);
+        if scale > 0.0 {
+            TOLERANCE_PIXELS / scale
+        } else {
+            TOLERANCE_PIXELS
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push_transform(&mut self, transform: &Affine2)
+    {
+        self.transform_stack.push(self.transform);
+        self.transform = &self.transform * transform;
+    }
+
+    #[inline]
+    pub(crate) fn pop_transform(&mut self)
+    {
+        match self.transform_stack.pop() {
+            None => log::warn!("Ignoring pop_transform: the transform stack is empty"),
+            Some(transform) => self.transform = transform
+        }
+    }
+
+    #[inline]
+    pub(crate) fn current_transform(&self) -> Affine2
+    {
+        self.transform
+    }
+
+    #[inline]
+    pub(crate) fn reset_transform(&mut self)
+    {
+        self.transform = Affine2::IDENTITY;
+        self.transform_stack.clear();
+    }
+
+    /// The user transform, followed by the transform required by the current
+    /// render target.
+    #[inline]
+    fn effective_transform(&self) -> Affine2
+    {
+        self.render_target_transform * self.transform
+    }
+
+    #[inline]
+    fn transform_vertices(&self, vertices: [Vec2; 3]) -> [Vec2; 3]
+    {
+        let transform = self.effective_transform();
+
+        if transform == Affine2::IDENTITY {
+            vertices
+        } else {
+            vertices.map(|vertex| transform.transform_point(vertex))
+        }
+    }
+
+    /// Text is rasterized at integer pixel positions, so translations are
+    /// applied to the position directly to keep the text sharp. Any other
+    /// transformation is applied to the glyph vertices.
+    #[inline]
+    fn split_text_transform(&self, position: Vec2) -> (Vec2, Affine2)
+    {
+        let transform = self.effective_transform();
+
+        if transform.is_translation_only() {
+            (
+                position + transform.translation_component(),
+                Affine2::IDENTITY
+            )
+        } else {
+            (position, transform)
         }
     }
 
@@ -838,10 +2091,55 @@ impl Renderer2D
         vertex_colors_clockwise: [Color; 3]
     )
     {
-        self.add_to_render_queue(RenderQueueItem::TriangleColored {
+        self.draw_triangles_three_color(&[(
             vertex_positions_clockwise,
             vertex_colors_clockwise
-        })
+        )]);
+    }
+
+    pub(crate) fn draw_rectangle_with_shader(
+        &mut self,
+        rect: &Rectangle,
+        image: Option<&ImageHandle>,
+        shader: &ShaderHandle,
+        uniforms: &ShaderUniforms
+    )
+    {
+        let invocation = Rc::new(ShaderInvocation {
+            shader: shader.clone(),
+            uniforms: uniforms.clone()
+        });
+
+        let positions = [
+            *rect.top_left(),
+            rect.top_right(),
+            *rect.bottom_right(),
+            rect.bottom_left()
+        ];
+
+        let texture_coords = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0)
+        ];
+
+        for [a, b, c] in [[0, 1, 2], [2, 3, 0]] {
+            self.add_to_render_queue(RenderQueueItem::TriangleCustomShader {
+                vertex_positions_clockwise: self.transform_vertices([
+                    positions[a],
+                    positions[b],
+                    positions[c]
+                ]),
+                vertex_texture_coords_clockwise: [
+                    texture_coords[a],
+                    texture_coords[b],
+                    texture_coords[c]
+                ],
+                texture: image.map(|image| image.texture.clone()),
+                shader: invocation.clone()
+            })
+        }
     }
 
     #[inline]
@@ -854,13 +2152,57 @@ impl Renderer2D
     )
     {
         self.add_to_render_queue(RenderQueueItem::TriangleTextured {
-            vertex_positions_clockwise,
+            vertex_positions_clockwise: self
+                .transform_vertices(vertex_positions_clockwise),
             vertex_colors_clockwise,
             vertex_texture_coords_clockwise,
             texture: image.texture.clone()
         })
     }
 
+    /// Draws the underlines and strikethroughs of the text, as solid
+    /// rectangles. Unlike the glyphs, these are not snapped to the pixel grid.
+    fn draw_text_decorations(
+        &mut self,
+        position: Vec2,
+        crop_window: Option<&Rect>,
+        color: Color,
+        text: &FormattedTextBlock
+    )
+    {
+        let mut triangles = Vec::new();
+
+        for line in text.iter_lines() {
+            for decoration in line.iter_decorations() {
+                let rect = decoration.rect().with_offset(position);
+
+                let rect = match crop_window {
+                    None => rect,
+                    Some(crop_window) => match rect.intersect(crop_window) {
+                        None => continue,
+                        Some(rect) => rect
+                    }
+                };
+
+                let colors = [decoration.color().unwrap_or(color); 3];
+
+                triangles.push((
+                    [*rect.top_left(), rect.top_right(), *rect.bottom_right()],
+                    colors
+                ));
+
+                triangles.push((
+                    [*rect.bottom_right(), rect.bottom_left(), *rect.top_left()],
+                    colors
+                ));
+            }
+        }
+
+        if !triangles.is_empty() {
+            self.draw_triangles_three_color(&triangles);
+        }
+    }
+
     #[inline]
     pub(crate) fn draw_text<V: Into<Vec2>>(
         &mut self,
@@ -869,11 +2211,78 @@ impl Renderer2D
         text: &FormattedTextBlock
     )
     {
+        let original_position = position.into();
+        let (position, transform) = self.split_text_transform(original_position);
+
         self.add_to_render_queue(RenderQueueItem::FormattedTextBlock {
-            position: position.into(),
+            position,
             color,
-            block: text.clone()
-        })
+            block: text.clone(),
+            transform,
+            rendering_mode: self.text_rendering_mode,
+            effect: None
+        });
+
+        self.draw_text_decorations(original_position, None, color, text);
+    }
+
+    /// Draws the effects of the style from back to front (shadow, glow, then
+    /// outline), followed by the text itself.
+    pub(crate) fn draw_text_with_style<V: Into<Vec2>>(
+        &mut self,
+        position: V,
+        color: Color,
+        style: &TextStyle,
+        text: &FormattedTextBlock
+    )
+    {
+        let position = position.into();
+
+        let outline_width = style.outline().map(|(width, _)| width).unwrap_or(0.0);
+
+        let mut effects = Vec::new();
+
+        if let Some((offset, blur, color)) = style.shadow() {
+            let effect = TextEffect {
+                dilation: outline_width,
+                blur
+            };
+
+            effects.push((position + offset, color, effect));
+        }
+
+        if let Some((radius, color)) = style.glow() {
+            let effect = TextEffect {
+                dilation: outline_width + radius / 2.0,
+                blur: radius
+            };
+
+            effects.push((position, color, effect));
+        }
+
+        if let Some((width, color)) = style.outline() {
+            let effect = TextEffect {
+                dilation: width,
+                blur: 0.0
+            };
+
+            effects.push((position, color, effect));
+        }
+
+        for (position, color, effect) in effects {
+            let (position, transform) = self.split_text_transform(position);
+
+            self.add_to_render_queue(RenderQueueItem::FormattedTextBlock {
+                position,
+                color,
+                block: text.clone(),
+                transform,
+                rendering_mode: TextRenderingMode::SignedDistanceField,
+                effect: Some(effect)
+            });
+        }
+
+        self.draw_text(position, color, text);
     }
 
     #[inline]
@@ -885,23 +2294,41 @@ impl Renderer2D
         text: &FormattedTextBlock
     )
     {
-        let position = position.into();
+        let original_position = position.into();
+        let (transformed_position, transform) =
+            self.split_text_transform(original_position);
+
+        let glyph_crop_window =
+            crop_window.with_offset(transformed_position - original_position);
+        let position = transformed_position;
 
         for line in text.iter_lines() {
             for glyph in line.iter_glyphs() {
-                if let Some(glyph_outline) = glyph.pixel_bounding_box() {
-                    let glyph_outline = glyph_outline.with_offset(position);
-                    if glyph_outline.intersect(&crop_window).is_some() {
-                        self.add_to_render_queue(RenderQueueItem::FormattedTextGlyph {
-                            position,
-                            color,
-                            glyph: glyph.clone(),
-                            crop_window: crop_window.clone()
-                        })
-                    }
+                let visible = match glyph.pixel_bounding_box() {
+                    Some(glyph_outline) => glyph_outline
+                        .with_offset(position)
+                        .intersect(&glyph_crop_window)
+                        .is_some(),
+
+                    // Color glyph images may have no outline, and are cropped
+                    // when drawn instead
+                    None => glyph.color_glyphs().is_some()
+                };
+
+                if visible {
+                    self.add_to_render_queue(RenderQueueItem::FormattedTextGlyph {
+                        position,
+                        color: glyph.color().unwrap_or(color),
+                        glyph: glyph.clone(),
+                        crop_window: glyph_crop_window.clone(),
+                        transform,
+                        rendering_mode: self.text_rendering_mode
+                    })
                 }
             }
         }
+
+        self.draw_text_decorations(original_position, Some(&crop_window), color, text);
     }
 
     #[inline]
@@ -913,7 +2340,8 @@ impl Renderer2D
     )
     {
         self.add_to_render_queue(RenderQueueItem::CircleSectionColored {
-            vertex_positions_clockwise,
+            vertex_positions_clockwise: self
+                .transform_vertices(vertex_positions_clockwise),
             vertex_colors_clockwise,
             vertex_normalized_circle_coords_clockwise
         })
@@ -925,6 +2353,7 @@ impl Renderer2D
         // If we change the clip area, we need to draw everything in a queue
         // through the current clip before setting new one.
         self.flush_render_queue();
+        self.clip = rect.clone();
         match rect {
             None => self.context.set_enable_scissor(false),
             Some(rect) => {
@@ -939,6 +2368,267 @@ impl Renderer2D
         }
     }
 
+    /// Intersects the clip rectangle with the specified rectangle, until the
+    /// matching call to `pop_clip`.
+    pub(crate) fn push_clip(&mut self, rect: Rectangle<i32>)
+    {
+        let previous = self.clip.clone();
+
+        let clip = match &previous {
+            None => rect,
+            Some(previous) => previous
+                .intersect(&rect)
+                .unwrap_or_else(|| Rectangle::new(*rect.top_left(), *rect.top_left()))
+        };
+
+        self.clip_stack.push(ClipStackEntry::Rectangle { previous });
+        self.set_clip(Some(clip));
+    }
+
+    /// Restricts drawing to the inside of the specified triangles, in
+    /// addition to any existing clip areas, until the matching call to
+    /// `pop_clip`. The triangles are transformed by the current transform.
+    pub(crate) fn push_clip_triangles(
+        &mut self,
+        triangles: impl IntoIterator<Item = [Vec2; 3]>
+    )
+    {
+        if self.stencil_depth == u8::MAX {
+            log::error!(
+                "Ignoring clip shape: at most {} clip shapes may be nested",
+                u8::MAX
+            );
+
+            // Popping this entry leaves the clip unchanged
+            self.clip_stack.push(ClipStackEntry::Rectangle {
+                previous: self.clip.clone()
+            });
+            return;
+        }
+
+        let triangles: Vec<[Vec2; 3]> = triangles
+            .into_iter()
+            .map(|triangle| self.transform_vertices(triangle))
+            .collect();
+
+        if self.stencil_depth == 0 {
+            // The contents of the stencil buffer are undefined at the start
+            // of a frame, and after the last clip shape is popped
+            self.flush_render_queue();
+            self.context.clear_stencil();
+        }
+
+        self.write_stencil(
+            &triangles,
+            GLStencilMode::Increment {
+                reference: self.stencil_depth
+            }
+        );
+
+        self.stencil_depth += 1;
+
+        self.context
+            .set_stencil_mode(Renderer2D::stencil_test_mode(self.stencil_depth));
+
+        self.clip_stack.push(ClipStackEntry::Stencil { triangles });
+    }
+
+    /// Restricts drawing to the inside of the specified circle, in addition
+    /// to any existing clip areas, until the matching call to `pop_clip`.
+    pub(crate) fn push_clip_circle(&mut self, center: Vec2, radius: f32)
+    {
+        let radius = radius.max(0.0);
+
+        // Each edge deviates from the circle by at most the path tolerance
+        let max_angle = 2.0 * (1.0 - self.path_tolerance() / radius).max(0.0).acos();
+
+        let segments = if max_angle > 0.0 {
+            (std::f32::consts::TAU / max_angle)
+                .ceil()
+                .clamp(8.0, 1024.0) as usize
+        } else {
+            8
+        };
+
+        let points: Vec<Vec2> = (0..segments)
+            .map(|i| {
+                let (sin, cos) =
+                    (std::f32::consts::TAU * i as f32 / segments as f32).sin_cos();
+                center + Vec2::new(cos, sin) * radius
+            })
+            .collect();
+
+        self.push_clip_triangles(
+            (1..segments - 1).map(|i| [points[0], points[i], points[i + 1]])
+        );
+    }
+
+    pub(crate) fn push_clip_path(&mut self, path: &shape::Path)
+    {
+        let tolerance = self.path_tolerance();
+
+        self.push_clip_triangles(path_tessellation::fill(path, tolerance));
+    }
+
+    /// Undoes the most recent call to `push_clip`, `push_clip_triangles`,
+    /// `push_clip_circle`, or `push_clip_path`.
+    pub(crate) fn pop_clip(&mut self)
+    {
+        match self.clip_stack.pop() {
+            None => log::warn!("Ignoring pop_clip: the clip stack is empty"),
+
+            Some(ClipStackEntry::Rectangle { previous }) => self.set_clip(previous),
+
+            Some(ClipStackEntry::Stencil { triangles }) => {
+                if self.stencil_depth == 1 {
+                    // The stencil buffer will be cleared when the next clip
+                    // shape is pushed, so there is no need to undo this one
+                    self.flush_render_queue();
+                } else {
+                    self.write_stencil(
+                        &triangles,
+                        GLStencilMode::Decrement {
+                            reference: self.stencil_depth
+                        }
+                    );
+                }
+
+                self.stencil_depth -= 1;
+
+                self.context
+                    .set_stencil_mode(Renderer2D::stencil_test_mode(self.stencil_depth));
+            }
+        }
+    }
+
+    /// Removes all clip areas, including the clip rectangle.
+    pub(crate) fn reset_clip(&mut self)
+    {
+        self.set_clip(None);
+        self.clip_stack.clear();
+        self.stencil_depth = 0;
+        self.context.set_stencil_mode(GLStencilMode::Disabled);
+    }
+
+    /// Draws the triangles into the stencil buffer only. The scissor test is
+    /// disabled, so that the same pixels are affected when the shape is
+    /// pushed and popped, even if the clip rectangle has changed.
+    fn write_stencil(&mut self, triangles: &[[Vec2; 3]], stencil_mode: GLStencilMode)
+    {
+        self.flush_render_queue();
+
+        self.context.set_enable_scissor(false);
+        self.context.set_stencil_mode(stencil_mode);
+
+        for triangle in triangles {
+            self.add_to_render_queue(RenderQueueItem::TriangleColored {
+                vertex_positions_clockwise: *triangle,
+                vertex_colors_clockwise: [Color::WHITE; 3]
+            });
+        }
+
+        self.flush_render_queue();
+
+        self.context.set_enable_scissor(self.clip.is_some());
+    }
+
+    fn stencil_test_mode(stencil_depth: u8) -> GLStencilMode
+    {
+        match stencil_depth {
+            0 => GLStencilMode::Disabled,
+            reference => GLStencilMode::Test { reference }
+        }
+    }
+
+    /// The blend mode is stored with each item in the render queue, so no
+    /// flush is required.
+    pub(crate) fn set_blend_mode(&mut self, blend_mode: BlendMode)
+    {
+        self.blend_mode = blend_mode;
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn blend_mode(&self) -> BlendMode
+    {
+        self.blend_mode
+    }
+
+    /// The fringe geometry is generated when shapes are drawn, so no flush is
+    /// required.
+    #[inline]
+    pub(crate) fn set_edge_antialiasing(&mut self, enabled: bool)
+    {
+        self.edge_antialiasing = enabled;
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn edge_antialiasing(&self) -> bool
+    {
+        self.edge_antialiasing
+    }
+
+    /// The glyphs are rasterized when the render queue is flushed, so no
+    /// flush is required.
+    #[inline]
+    pub(crate) fn set_text_rendering_mode(&mut self, mode: TextRenderingMode)
+    {
+        self.text_rendering_mode = mode;
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn text_rendering_mode(&self) -> TextRenderingMode
+    {
+        self.text_rendering_mode
+    }
+
+    /// Empties the glyph cache, so that the new options apply to all glyphs.
+    pub(crate) fn set_glyph_cache_options(&mut self, options: GlyphCacheOptions)
+    {
+        // The queued text must be drawn using the glyphs in the old cache
+        self.flush_render_queue();
+        self.glyph_cache.set_options(options);
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn glyph_cache_options(&self) -> &GlyphCacheOptions
+    {
+        self.glyph_cache.options()
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn glyph_cache_stats(&self) -> GlyphCacheStats
+    {
+        self.glyph_cache.stats()
+    }
+
+    /// Rasterizes the glyphs of the text blocks using the current text
+    /// rendering mode, as if they were drawn at the origin, and uploads them.
+    pub(crate) fn prewarm_glyph_cache(&mut self, blocks: &[FormattedTextBlock])
+    {
+        for block in blocks {
+            for line in block.iter_lines() {
+                for glyph in line.iter_glyphs() {
+                    self.glyph_cache.add_to_cache(
+                        &self.context,
+                        glyph,
+                        Vec2::ZERO,
+                        glyph.color().unwrap_or(Color::BLACK),
+                        self.text_rendering_mode
+                    );
+                }
+            }
+        }
+
+        if let Err(err) = self.glyph_cache.prepare_for_draw(&self.context) {
+            log::error!("Error updating font texture, continuing anyway: {:?}", err);
+        }
+    }
+
     pub(crate) fn capture(&mut self, format: ImageDataType) -> RawBitmapData
     {
         self.flush_render_queue();
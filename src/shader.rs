@@ -0,0 +1,298 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::color::Color;
+use crate::dimen::Vec2;
+use crate::glwrapper::{GLContextManager, GLProgram, GLUniformHandle};
+use crate::image::ImageHandle;
+
+/// The maximum number of images which may be passed to a single draw call
+/// using [ShaderUniforms::with_image]. Texture unit 0 is reserved for the
+/// image being drawn.
+pub const MAX_SHADER_IMAGES: usize = 7;
+
+/// Represents a handle for a custom fragment shader, created using
+/// [crate::GLRenderer::create_fragment_shader] or
+/// [crate::Graphics2D::create_fragment_shader].
+///
+/// Note: this handle can only be used in the graphics context in which it was
+/// created.
+#[derive(Debug, Clone)]
+pub struct ShaderHandle
+{
+    pub(crate) program: Rc<GLProgram>,
+    uniform_handles: Rc<RefCell<HashMap<String, Option<GLUniformHandle>>>>
+}
+
+impl PartialEq for ShaderHandle
+{
+    fn eq(&self, other: &Self) -> bool
+    {
+        Rc::ptr_eq(&self.program, &other.program)
+    }
+}
+
+impl Eq for ShaderHandle {}
+
+impl ShaderHandle
+{
+    pub(crate) fn new(program: Rc<GLProgram>) -> Self
+    {
+        ShaderHandle {
+            program,
+            uniform_handles: Rc::new(RefCell::new(HashMap::new()))
+        }
+    }
+
+    /// Returns the handle of the uniform with the specified name, or `None`
+    /// if the shader has no such uniform. The GLSL compiler removes uniforms
+    /// which don't affect the output, so a missing uniform is not an error.
+    pub(crate) fn uniform_handle(
+        &self,
+        context: &GLContextManager,
+        name: &str
+    ) -> Option<GLUniformHandle>
+    {
+        if let Some(handle) = self.uniform_handles.borrow().get(name) {
+            return handle.clone();
+        }
+
+        let handle = match self.program.get_uniform_handle(context, name) {
+            Ok(handle) => Some(handle),
+            Err(_) => {
+                log::info!("Uniform '{name}' not found in custom shader");
+                None
+            }
+        };
+
+        self.uniform_handles
+            .borrow_mut()
+            .insert(name.to_string(), handle.clone());
+
+        handle
+    }
+}
+
+/// The value of a uniform variable in a custom fragment shader.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderUniformValue
+{
+    /// A `float` uniform.
+    Float(f32),
+    /// A `vec2` uniform.
+    Vec2(Vec2),
+    /// A `vec3` uniform.
+    Vec3([f32; 3]),
+    /// A `vec4` uniform.
+    Vec4([f32; 4]),
+    /// A `vec4` uniform, containing the red, green, blue, and alpha
+    /// components of the color.
+    Color(Color),
+    /// A `sampler2D` uniform.
+    Image(ImageHandle)
+}
+
+/// The values of the uniform variables used by a custom fragment shader for a
+/// single draw call.
+///
+/// ```rust
+/// # use speedy2d::color::Color;
+/// # use speedy2d::shader::ShaderUniforms;
+/// let uniforms = ShaderUniforms::new()
+///     .with_float("u_Amount", 0.75)
+///     .with_vec2("u_Direction", (1.0, 0.0))
+///     .with_color("u_Tint", Color::from_rgb(1.0, 0.8, 0.6));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ShaderUniforms
+{
+    values: Vec<(String, ShaderUniformValue)>
+}
+
+impl ShaderUniforms
+{
+    /// Creates an empty set of uniform values.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self
+    {
+        ShaderUniforms { values: Vec::new() }
+    }
+
+    /// Sets the value of the uniform with the specified name, replacing any
+    /// previous value.
+    #[must_use]
+    pub fn with_value<S: Into<String>>(
+        mut self,
+        name: S,
+        value: ShaderUniformValue
+    ) -> Self
+    {
+        let name = name.into();
+
+        match self
+            .values
+            .iter_mut()
+            .find(|(existing, _)| *existing == name)
+        {
+            None => self.values.push((name, value)),
+            Some((_, existing)) => *existing = value
+        }
+
+        self
+    }
+
+    /// Sets the value of a `float` uniform.
+    #[inline]
+    #[must_use]
+    pub fn with_float<S: Into<String>>(self, name: S, value: f32) -> Self
+    {
+        self.with_value(name, ShaderUniformValue::Float(value))
+    }
+
+    /// Sets the value of a `vec2` uniform.
+    #[inline]
+    #[must_use]
+    pub fn with_vec2<S: Into<String>, V: Into<Vec2>>(self, name: S, value: V) -> Self
+    {
+        self.with_value(name, ShaderUniformValue::Vec2(value.into()))
+    }
+
+    /// Sets the value of a `vec3` uniform.
+    #[inline]
+    #[must_use]
+    pub fn with_vec3<S: Into<String>>(self, name: S, value: [f32; 3]) -> Self
+    {
+        self.with_value(name, ShaderUniformValue::Vec3(value))
+    }
+
+    /// Sets the value of a `vec4` uniform.
+    #[inline]
+    #[must_use]
+    pub fn with_vec4<S: Into<String>>(self, name: S, value: [f32; 4]) -> Self
+    {
+        self.with_value(name, ShaderUniformValue::Vec4(value))
+    }
+
+    /// Sets the value of a `vec4` uniform to the specified color.
+    #[inline]
+    #[must_use]
+    pub fn with_color<S: Into<String>>(self, name: S, value: Color) -> Self
+    {
+        self.with_value(name, ShaderUniformValue::Color(value))
+    }
+
+    /// Sets the image sampled by a `sampler2D` uniform. At most
+    /// [MAX_SHADER_IMAGES] images may be used in a single draw call.
+    #[inline]
+    #[must_use]
+    pub fn with_image<S: Into<String>>(self, name: S, image: &ImageHandle) -> Self
+    {
+        self.with_value(name, ShaderUniformValue::Image(image.clone()))
+    }
+
+    /// Returns the uniform values, in the order in which they were first set.
+    #[inline]
+    #[must_use]
+    pub fn values(&self) -> &[(String, ShaderUniformValue)]
+    {
+        &self.values
+    }
+
+    /// Uploads the values to the specified shader, which must be the active
+    /// program. Images are bound to texture units starting at 1.
+    pub(crate) fn apply(&self, context: &GLContextManager, shader: &ShaderHandle)
+    {
+        let mut next_texture_unit = 1;
+
+        for (name, value) in &self.values {
+            let handle = match shader.uniform_handle(context, name) {
+                None => continue,
+                Some(handle) => handle
+            };
+
+            match value {
+                ShaderUniformValue::Float(value) => {
+                    handle.set_value_float(context, *value)
+                }
+                ShaderUniformValue::Vec2(value) => {
+                    handle.set_value_vec2(context, value.x, value.y)
+                }
+                ShaderUniformValue::Vec3([x, y, z]) => {
+                    handle.set_value_vec3(context, *x, *y, *z)
+                }
+                ShaderUniformValue::Vec4([x, y, z, w]) => {
+                    handle.set_value_vec4(context, *x, *y, *z, *w)
+                }
+                ShaderUniformValue::Color(color) => handle.set_value_vec4(
+                    context,
+                    color.r(),
+                    color.g(),
+                    color.b(),
+                    color.a()
+                ),
+                ShaderUniformValue::Image(image) => {
+                    if next_texture_unit > MAX_SHADER_IMAGES as u32 {
+                        log::error!(
+                            "Ignoring image uniform '{name}': at most \
+                             {MAX_SHADER_IMAGES} images are supported"
+                        );
+                        continue;
+                    }
+
+                    context.bind_texture_to_unit(next_texture_unit, &image.texture);
+                    handle.set_value_int(context, next_texture_unit as i32);
+                    next_texture_unit += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test
+{
+    use crate::color::Color;
+    use crate::dimen::Vec2;
+    use crate::shader::{ShaderUniformValue, ShaderUniforms};
+
+    #[test]
+    pub fn test_uniforms_replace_existing()
+    {
+        let uniforms = ShaderUniforms::new()
+            .with_float("u_Amount", 0.5)
+            .with_vec2("u_Offset", (1.0, 2.0))
+            .with_color("u_Amount", Color::RED);
+
+        assert_eq!(
+            &[
+                (
+                    String::from("u_Amount"),
+                    ShaderUniformValue::Color(Color::RED)
+                ),
+                (
+                    String::from("u_Offset"),
+                    ShaderUniformValue::Vec2(Vec2::new(1.0, 2.0))
+                )
+            ],
+            uniforms.values()
+        );
+    }
+}
@@ -17,23 +17,73 @@
  */
 
 uniform sampler2D in_Texture;
+uniform float in_PremultiplyAlpha;
+uniform float in_PremultiplyCoverage;
+uniform float in_CoverageThreshold;
 
 varying vec4 pass_Color;
 varying vec2 pass_TextureCoord;
 varying float pass_TextureMix;
 varying float pass_CircleMix;
+varying vec2 pass_GradientCoord;
+varying float pass_LinearGradientMix;
+varying float pass_RadialGradientMix;
+varying float pass_SdfMix;
+varying vec2 pass_SdfEdge;
+varying float pass_CoverageMix;
 
 void main(void) {
 
     vec4 texCol = texture2D(in_Texture, pass_TextureCoord);
 
+    // The alpha channel of a signed distance field texture holds the distance
+    // to the glyph outline, which is converted to coverage using the edge.
+    float sdfAlpha = clamp(
+            (texCol.a - pass_SdfEdge.x) / max(pass_SdfEdge.y - pass_SdfEdge.x, 0.0001),
+            0.0,
+            1.0);
+
+    texCol.a = mix(texCol.a, sdfAlpha, pass_SdfMix);
+
     float texCoordMagSquared = pass_TextureCoord.x * pass_TextureCoord.x
             + pass_TextureCoord.y * pass_TextureCoord.y;
 
     float circleAlpha = 1.0 - step(1.0, texCoordMagSquared);
 
-    gl_FragColor = pass_Color * (
+    // The coverage of glyphs and circles is normally applied through the
+    // alpha channel, but some blend modes must apply it separately.
+    float coverage = mix(1.0, texCol.a, pass_CoverageMix)
+            * mix(1.0, circleAlpha, pass_CircleMix);
+
+    if (coverage < in_CoverageThreshold) {
+        discard;
+    }
+
+    // The gradient texture contains 256 pixels, from offset 0.0 at the
+    // center of the first pixel to offset 1.0 at the center of the last.
+    float gradientOffset = clamp(
+            pass_GradientCoord.x * pass_LinearGradientMix
+                    + length(pass_GradientCoord) * pass_RadialGradientMix,
+            0.0,
+            1.0);
+
+    vec4 gradientCol = texture2D(
+            in_Texture,
+            vec2((gradientOffset * 255.0 + 0.5) / 256.0, 0.5));
+
+    float gradientMix = pass_LinearGradientMix + pass_RadialGradientMix;
+
+    vec4 color = pass_Color * (
             vec4(1.0 - pass_TextureMix - pass_CircleMix)
                     + (texCol * pass_TextureMix)
-                    + (vec4(vec3(1.0), circleAlpha)) * pass_CircleMix);
+                    + (vec4(vec3(1.0), circleAlpha)) * pass_CircleMix)
+            * (vec4(1.0 - gradientMix) + gradientCol * gradientMix);
+
+    // Some blend modes require the output to be premultiplied by alpha, or
+    // by coverage when the color is already premultiplied
+    gl_FragColor = vec4(
+            color.rgb
+                    * mix(1.0, color.a, in_PremultiplyAlpha)
+                    * mix(1.0, coverage, in_PremultiplyCoverage),
+            color.a);
 }
\ No newline at end of file
@@ -21,6 +21,12 @@ attribute vec4 in_Color;
 attribute vec2 in_TextureCoord;
 attribute float in_TextureMix;
 attribute float in_CircleMix;
+attribute vec2 in_GradientCoord;
+attribute float in_LinearGradientMix;
+attribute float in_RadialGradientMix;
+attribute float in_SdfMix;
+attribute vec2 in_SdfEdge;
+attribute float in_CoverageMix;
 
 uniform float in_ScaleX;
 uniform float in_ScaleY;
@@ -29,6 +35,12 @@ varying vec4 pass_Color;
 varying vec2 pass_TextureCoord;
 varying float pass_TextureMix;
 varying float pass_CircleMix;
+varying vec2 pass_GradientCoord;
+varying float pass_LinearGradientMix;
+varying float pass_RadialGradientMix;
+varying float pass_SdfMix;
+varying vec2 pass_SdfEdge;
+varying float pass_CoverageMix;
 
 void main(void) {
 
@@ -42,4 +54,10 @@ void main(void) {
     pass_TextureCoord = in_TextureCoord;
     pass_TextureMix = in_TextureMix;
     pass_CircleMix = in_CircleMix;
+    pass_GradientCoord = in_GradientCoord;
+    pass_LinearGradientMix = in_LinearGradientMix;
+    pass_RadialGradientMix = in_RadialGradientMix;
+    pass_SdfMix = in_SdfMix;
+    pass_SdfEdge = in_SdfEdge;
+    pass_CoverageMix = in_CoverageMix;
 }
\ No newline at end of file
@@ -21,6 +21,12 @@ in vec4 in_Color;
 in vec2 in_TextureCoord;
 in float in_TextureMix;
 in float in_CircleMix;
+in vec2 in_GradientCoord;
+in float in_LinearGradientMix;
+in float in_RadialGradientMix;
+in float in_SdfMix;
+in vec2 in_SdfEdge;
+in float in_CoverageMix;
 
 uniform float in_ScaleX;
 uniform float in_ScaleY;
@@ -29,6 +35,12 @@ out vec4 pass_Color;
 out vec2 pass_TextureCoord;
 out float pass_TextureMix;
 out float pass_CircleMix;
+out vec2 pass_GradientCoord;
+out float pass_LinearGradientMix;
+out float pass_RadialGradientMix;
+out float pass_SdfMix;
+out vec2 pass_SdfEdge;
+out float pass_CoverageMix;
 
 void main(void) {
 
@@ -42,4 +54,10 @@ void main(void) {
     pass_TextureCoord = in_TextureCoord;
     pass_TextureMix = in_TextureMix;
     pass_CircleMix = in_CircleMix;
+    pass_GradientCoord = in_GradientCoord;
+    pass_LinearGradientMix = in_LinearGradientMix;
+    pass_RadialGradientMix = in_RadialGradientMix;
+    pass_SdfMix = in_SdfMix;
+    pass_SdfEdge = in_SdfEdge;
+    pass_CoverageMix = in_CoverageMix;
 }
\ No newline at end of file
@@ -310,6 +310,352 @@ impl Polygon
     }
 }
 
+/// A single segment of a [Path].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum PathSegment
+{
+    MoveTo(Vec2),
+    LineTo(Vec2),
+    QuadTo(Vec2, Vec2),
+    CubicTo(Vec2, Vec2, Vec2),
+    Close
+}
+
+/// A shape made up of straight lines and curves, which may be filled using
+/// [crate::Graphics2D::draw_path_filled], or stroked using
+/// [crate::Graphics2D::draw_path_stroked].
+///
+/// A path consists of one or more subpaths. Each subpath begins with a call to
+/// [Path::move_to], and may optionally be closed using [Path::close].
+///
+/// ```rust
+/// use speedy2d::shape::Path;
+///
+/// let path = Path::new()
+///     .move_to((10.0, 10.0))
+///     .line_to((100.0, 10.0))
+///     .quad_to((150.0, 50.0), (100.0, 90.0))
+///     .arc_to((10.0, 90.0), (10.0, 10.0), 20.0)
+///     .close();
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Path
+{
+    pub(crate) segments: Vec<PathSegment>,
+    current_point: Option<Vec2>,
+    subpath_start: Option<Vec2>
+}
+
+impl Path
+{
+    /// Creates a new, empty path.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self
+    {
+        Self::default()
+    }
+
+    /// Returns true if no segments have been added to this path.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool
+    {
+        self.segments.is_empty()
+    }
+
+    /// Begins a new subpath at the specified point.
+    #[must_use]
+    pub fn move_to<V: Into<Vec2>>(mut self, point: V) -> Self
+    {
+        let point = point.into();
+
+        self.segments.push(PathSegment::MoveTo(point));
+        self.current_point = Some(point);
+        self.subpath_start = Some(point);
+        self
+    }
+
+    /// Adds a straight line from the current point to the specified point.
+    ///
+    /// If the path is empty, this is equivalent to [Path::move_to].
+    #[must_use]
+    pub fn line_to<V: Into<Vec2>>(self, point: V) -> Self
+    {
+        let point = point.into();
+        let mut path = self.ensure_current_point(point);
+
+        path.segments.push(PathSegment::LineTo(point));
+        path.current_point = Some(point);
+        path
+    }
+
+    /// Adds a quadratic Bezier curve from the current point to `end`, using
+    /// the specified control point.
+    #[must_use]
+    pub fn quad_to<V1: Into<Vec2>, V2: Into<Vec2>>(self, control: V1, end: V2) -> Self
+    {
+        let control = control.into();
+        let end = end.into();
+        let mut path = self.ensure_current_point(control);
+
+        path.segments.push(PathSegment::QuadTo(control, end));
+        path.current_point = Some(end);
+        path
+    }
+
+    /// Adds a cubic Bezier curve from the current point to `end`, using the
+    /// two specified control points.
+    #[must_use]
+    pub fn cubic_to<V1: Into<Vec2>, V2: Into<Vec2>, V3: Into<Vec2>>(
+        self,
+        control1: V1,
+        control2: V2,
+        end: V3
+    ) -> Self
+    {
+        let control1 = control1.into();
+        let control2 = control2.into();
+        let end = end.into();
+        let mut path = self.ensure_current_point(control1);
+
+        path.segments
+            .push(PathSegment::CubicTo(control1, control2, end));
+        path.current_point = Some(end);
+        path
+    }
+
+    /// Adds a circular arc with the specified radius, which is tangent to both
+    /// the line from the current point to `corner`, and the line from `corner`
+    /// to `end`. A straight line is added from the current point to the start
+    /// of the arc.
+    ///
+    /// As with the `arcTo()` function of the HTML canvas API, the path ends
+    /// at the point where the arc touches the second line, rather than at
+    /// `end`. This is useful for drawing rounded corners.
+    #[must_use]
+    pub fn arc_to<V1: Into<Vec2>, V2: Into<Vec2>>(
+        self,
+        corner: V1,
+        end: V2,
+        radius: f32
+    ) -> Self
+    {
+        let corner = corner.into();
+        let end = end.into();
+
+        let start = match self.current_point {
+            None => return self.move_to(corner),
+            Some(start) => start
+        };
+
+        let (to_start, to_end) =
+            match ((start - corner).normalize(), (end - corner).normalize()) {
+                (Some(to_start), Some(to_end)) => (to_start, to_end),
+                _ => return self.line_to(corner)
+            };
+
+        let cos_angle = to_start.x * to_end.x + to_start.y * to_end.y;
+        let sin_angle = to_start.x * to_end.y - to_start.y * to_end.x;
+
+        if radius <= 0.0 || sin_angle.abs() < 1.0e-6 {
+            return self.line_to(corner);
+        }
+
+        // The half angle between the two lines at the corner
+        let half_angle = sin_angle.abs().atan2(cos_angle) / 2.0;
+
+        let tangent_distance = radius / half_angle.tan();
+
+        let arc_start = corner + to_start * tangent_distance;
+        let arc_end = corner + to_end * tangent_distance;
+
+        let bisector = match (to_start + to_end).normalize() {
+            None => return self.line_to(corner),
+            Some(bisector) => bisector
+        };
+
+        let center = corner + bisector * (radius / half_angle.sin());
+
+        let start_angle = (arc_start.y - center.y).atan2(arc_start.x - center.x);
+        let end_angle = (arc_end.y - center.y).atan2(arc_end.x - center.x);
+
+        let mut sweep = end_angle - start_angle;
+
+        if sweep > std::f32::consts::PI {
+            sweep -= 2.0 * std::f32::consts::PI;
+        } else if sweep < -std::f32::consts::PI {
+            sweep += 2.0 * std::f32::consts::PI;
+        }
+
+        let mut path = self.line_to(arc_start);
+
+        // Each cubic Bezier approximates at most a quarter of a circle
+        let parts = (sweep.abs() / std::f32::consts::FRAC_PI_2).ceil().max(1.0);
+        let part_sweep = sweep / parts;
+        let handle_length = radius * 4.0 / 3.0 * (part_sweep / 4.0).tan();
+
+        for part in 0..(parts as usize) {
+            let angle1 = start_angle + part_sweep * part as f32;
+            let angle2 = angle1 + part_sweep;
+
+            let (sin1, cos1) = angle1.sin_cos();
+            let (sin2, cos2) = angle2.sin_cos();
+
+            let point1 = center + Vec2::new(cos1, sin1) * radius;
+            let point2 = center + Vec2::new(cos2, sin2) * radius;
+
+            path = path.cubic_to(
+                point1 + Vec2::new(-sin1, cos1) * handle_length,
+                point2 - Vec2::new(-sin2, cos2) * handle_length,
+                point2
+            );
+        }
+
+        path
+    }
+
+    /// Closes the current subpath, by adding a straight line back to the
+    /// point where it began.
+    #[must_use]
+    pub fn close(mut self) -> Self
+    {
+        if self.current_point.is_some() {
+            self.segments.push(PathSegment::Close);
+            self.current_point = self.subpath_start;
+        }
+
+        self
+    }
+
+    fn ensure_current_point(self, point: Vec2) -> Self
+    {
+        if self.current_point.is_none() {
+            self.move_to(point)
+        } else {
+            self
+        }
+    }
+}
+
+/// The shape used to join two segments of a stroked [Path].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineJoin
+{
+    /// The outer edges of the two segments are extended until they meet. If
+    /// this would extend further than the miter limit (see
+    /// [StrokeStyle::with_miter_limit]), a bevel join is used instead.
+    Miter,
+    /// The segments are joined with a circular arc.
+    Round,
+    /// The outer corners of the two segments are connected with a straight
+    /// line.
+    Bevel
+}
+
+/// The shape used at the start and end of an open subpath when it is stroked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineCap
+{
+    /// The stroke ends exactly at the end point.
+    Butt,
+    /// The stroke ends with a semicircle centered on the end point.
+    Round,
+    /// The stroke is extended past the end point by half the stroke width.
+    Square
+}
+
+/// The settings used when stroking a [Path], using
+/// [crate::Graphics2D::draw_path_stroked].
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle
+{
+    pub(crate) width: f32,
+    pub(crate) join: LineJoin,
+    pub(crate) cap: LineCap,
+    pub(crate) miter_limit: f32
+}
+
+impl StrokeStyle
+{
+    /// Creates a new stroke style with the specified width, using miter joins
+    /// and butt caps.
+    #[inline]
+    #[must_use]
+    pub fn new(width: f32) -> Self
+    {
+        StrokeStyle {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0
+        }
+    }
+
+    /// Sets the shape used to join segments. The default is
+    /// [LineJoin::Miter].
+    #[inline]
+    #[must_use]
+    pub fn with_join(mut self, join: LineJoin) -> Self
+    {
+        self.join = join;
+        self
+    }
+
+    /// Sets the shape used at the ends of open subpaths. The default is
+    /// [LineCap::Butt].
+    #[inline]
+    #[must_use]
+    pub fn with_cap(mut self, cap: LineCap) -> Self
+    {
+        self.cap = cap;
+        self
+    }
+
+    /// Sets the maximum ratio between the length of a miter join and the
+    /// stroke width. Sharper corners fall back to a bevel join. The default
+    /// is `4.0`.
+    #[inline]
+    #[must_use]
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self
+    {
+        self.miter_limit = miter_limit;
+        self
+    }
+
+    /// Returns the width of the stroke.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> f32
+    {
+        self.width
+    }
+
+    /// Returns the shape used to join segments.
+    #[inline]
+    #[must_use]
+    pub fn join(&self) -> LineJoin
+    {
+        self.join
+    }
+
+    /// Returns the shape used at the ends of open subpaths.
+    #[inline]
+    #[must_use]
+    pub fn cap(&self) -> LineCap
+    {
+        self.cap
+    }
+
+    /// Returns the miter limit.
+    #[inline]
+    #[must_use]
+    pub fn miter_limit(&self) -> f32
+    {
+        self.miter_limit
+    }
+}
+
 #[cfg(test)]
 mod test
 {
@@ -0,0 +1,684 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::error::{BacktraceError, ErrorMessage};
+use crate::font::{Font, FontFamily};
+
+/// The file extensions of the fonts which are loaded when scanning a
+/// directory.
+const FONT_FILE_EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "otc"];
+
+/// The Windows language ID for US English, whose names are preferred.
+const LANGUAGE_ENGLISH_US: u16 = 0x0409;
+
+/// Whether a font is upright or slanted.
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum FontStyle
+{
+    /// An upright font.
+    Normal,
+    /// A slanted font, designed with cursive letterforms.
+    Italic,
+    /// A slanted version of an upright font.
+    Oblique
+}
+
+impl FontStyle
+{
+    /// How far this style is from the requested style, with zero being an
+    /// exact match. Italic and oblique fonts are preferred over upright fonts
+    /// when a slanted font is requested, and vice versa.
+    fn distance_from(self, requested: FontStyle) -> u8
+    {
+        match (requested, self) {
+            (requested, style) if requested == style => 0,
+            (FontStyle::Italic, FontStyle::Oblique) => 1,
+            (FontStyle::Oblique, FontStyle::Italic) => 1,
+            (FontStyle::Normal, FontStyle::Oblique) => 1,
+            _ => 2
+        }
+    }
+}
+
+/// A font installed on the system, found using [SystemFonts::scan()].
+///
+/// The font file is only read again when the font is loaded using
+/// [SystemFont::load()].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SystemFont
+{
+    path: PathBuf,
+    index: u32,
+    family_name: String,
+    weight: u16,
+    style: FontStyle,
+    /// The Unicode codepoints in the font, as sorted, non-overlapping,
+    /// inclusive ranges.
+    coverage: Vec<(u32, u32)>
+}
+
+impl SystemFont
+{
+    /// Reads the name, style and coverage of the font at the specified index
+    /// of the file. Returns `None` if the font can't be parsed, or has no
+    /// family name.
+    fn parse(path: &Path, data: &[u8], index: u32) -> Option<Self>
+    {
+        let face = ttf_parser::Face::from_slice(data, index).ok()?;
+
+        let style = match face.style() {
+            ttf_parser::Style::Normal => FontStyle::Normal,
+            ttf_parser::Style::Italic => FontStyle::Italic,
+            ttf_parser::Style::Oblique => FontStyle::Oblique
+        };
+
+        let mut codepoints = Vec::new();
+
+        if let Some(cmap) = face.tables().cmap {
+            for subtable in cmap.subtables {
+                if subtable.is_unicode() {
+                    subtable.codepoints(|codepoint| codepoints.push(codepoint));
+                }
+            }
+        }
+
+        Some(SystemFont {
+            path: path.to_path_buf(),
+            index,
+            family_name: family_name(&face)?,
+            weight: face.weight().to_number(),
+            style,
+            coverage: coverage_ranges(codepoints)
+        })
+    }
+
+    /// The path of the font file.
+    #[inline]
+    #[must_use]
+    pub fn path(&self) -> &Path
+    {
+        &self.path
+    }
+
+    /// The index of the font within the file, which is non-zero only for
+    /// font collections.
+    #[inline]
+    #[must_use]
+    pub fn index(&self) -> u32
+    {
+        self.index
+    }
+
+    /// The name of the family which the font belongs to, for example
+    /// `"Noto Sans"`.
+    #[inline]
+    #[must_use]
+    pub fn family_name(&self) -> &str
+    {
+        &self.family_name
+    }
+
+    /// The weight of the font, from `100` (thin) to `900` (black). Regular
+    /// fonts have a weight of `400`, and bold fonts have a weight of `700`.
+    #[inline]
+    #[must_use]
+    pub fn weight(&self) -> u16
+    {
+        self.weight
+    }
+
+    /// Whether the font is upright or slanted.
+    #[inline]
+    #[must_use]
+    pub fn style(&self) -> FontStyle
+    {
+        self.style
+    }
+
+    /// Returns true if the font contains a glyph for the specified codepoint.
+    #[must_use]
+    pub fn contains_codepoint(&self, codepoint: char) -> bool
+    {
+        let codepoint = codepoint as u32;
+
+        let index = self
+            .coverage
+            .partition_point(|(start, _)| *start <= codepoint);
+
+        index > 0 && codepoint <= self.coverage[index - 1].1
+    }
+
+    /// Reads the font file, and loads the font.
+    pub fn load(&self) -> Result<Font, BacktraceError<ErrorMessage>>
+    {
+        let bytes = std::fs::read(&self.path).map_err(|err| {
+            ErrorMessage::msg_with_cause(
+                format!("Failed to read font file {:?}", self.path),
+                err
+            )
+        })?;
+
+        Font::new_from_collection(&bytes, self.index)
+    }
+
+    /// How well this font matches the requested weight and style, with lower
+    /// values being better. The style is more important than the weight.
+    ///
+    /// As in CSS, if the requested weight is below `400`, lighter fonts are
+    /// preferred over heavier fonts at the same distance, and otherwise
+    /// heavier fonts are preferred.
+    fn match_distance(&self, weight: u16, style: FontStyle) -> (u8, u16, bool)
+    {
+        let prefer_lighter = weight < 400;
+
+        (
+            self.style.distance_from(style),
+            self.weight.abs_diff(weight),
+            (self.weight < weight) != prefer_lighter && self.weight != weight
+        )
+    }
+}
+
+/// Returns the family name of the font, preferring the typographic family
+/// name which groups all the weights of a family together, and names in US
+/// English.
+fn family_name(face: &ttf_parser::Face) -> Option<String>
+{
+    [
+        ttf_parser::name_id::TYPOGRAPHIC_FAMILY,
+        ttf_parser::name_id::FAMILY
+    ]
+    .iter()
+    .find_map(|name_id| {
+        let names: Vec<_> = face
+            .names()
+            .into_iter()
+            .filter(|name| name.name_id == *name_id)
+            .filter_map(|name| Some((name.language_id, name.to_string()?)))
+            .filter(|(_, value)| !value.is_empty())
+            .collect();
+
+        names
+            .iter()
+            .find(|(language_id, _)| *language_id == LANGUAGE_ENGLISH_US)
+            .or_else(|| names.first())
+            .map(|(_, value)| value.clone())
+    })
+}
+
+/// The font directories used on Linux, following the XDG Base Directory
+/// Specification.
+fn standard_font_directories() -> Vec<PathBuf>
+{
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".local/share")));
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut directories: Vec<PathBuf> = data_home.into_iter().collect();
+
+    directories.extend(data_dirs.split(':').map(PathBuf::from));
+
+    let mut directories: Vec<PathBuf> = directories
+        .into_iter()
+        .map(|directory| directory.join("fonts"))
+        .collect();
+
+    directories.extend(home.map(|home| home.join(".fonts")));
+
+    directories
+}
+
+/// Finds the font files in the directory and its subdirectories, skipping
+/// directories which have already been visited through another path.
+fn find_font_files(
+    directory: &Path,
+    visited: &mut HashSet<PathBuf>,
+    output: &mut Vec<PathBuf>
+)
+{
+    match directory.canonicalize() {
+        Ok(canonical) => {
+            if !visited.insert(canonical) {
+                return;
+            }
+        }
+        Err(_) => return
+    }
+
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            log::debug!("Failed to read font directory {:?}: {:?}", directory, err);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            find_font_files(&path, visited, output);
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| {
+                FONT_FILE_EXTENSIONS
+                    .iter()
+                    .any(|known| known.eq_ignore_ascii_case(extension))
+            })
+            .unwrap_or(false)
+        {
+            output.push(path);
+        }
+    }
+}
+
+/// A list of the fonts installed on the system, which can be used to find
+/// fonts by family name, weight, style, and the codepoints they contain.
+///
+/// ```rust,no_run
+/// use speedy2d::system_fonts::{FontQuery, FontStyle, SystemFonts};
+///
+/// let system_fonts = SystemFonts::scan();
+///
+/// let family = system_fonts
+///     .font_family(
+///         &FontQuery::new()
+///             .with_family("Noto Sans")
+///             .with_family("DejaVu Sans")
+///             .with_weight(700)
+///             .with_coverage("Ελληνικά 日本語")
+///     )
+///     .unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SystemFonts
+{
+    fonts: Vec<SystemFont>
+}
+
+impl SystemFonts
+{
+    /// Scans the standard font directories on Linux: `fonts` within
+    /// `$XDG_DATA_HOME` (`~/.local/share` by default) and each of
+    /// `$XDG_DATA_DIRS` (`/usr/local/share` and `/usr/share` by default),
+    /// and `~/.fonts`.
+    ///
+    /// Every font file is read, so this may take some time on systems with
+    /// many fonts installed.
+    #[must_use]
+    pub fn scan() -> Self
+    {
+        SystemFonts::scan_directories(&standard_font_directories())
+    }
+
+    /// Scans the specified directories and their subdirectories for
+    /// TrueType and OpenType fonts and font collections. Files which can't
+    /// be read or parsed are skipped.
+    #[must_use]
+    pub fn scan_directories<P: AsRef<Path>>(directories: &[P]) -> Self
+    {
+        let mut visited = HashSet::new();
+        let mut paths = Vec::new();
+
+        for directory in directories {
+            find_font_files(directory.as_ref(), &mut visited, &mut paths);
+        }
+
+        // Sorted so that the results don't depend on the order of the files
+        // in the directory
+        paths.sort();
+
+        let mut fonts = Vec::new();
+
+        for path in paths {
+            let data = match std::fs::read(&path) {
+                Ok(data) => data,
+                Err(err) => {
+                    log::debug!("Failed to read font file {:?}: {:?}", path, err);
+                    continue;
+                }
+            };
+
+            for index in 0..Font::collection_size(&data) {
+                match SystemFont::parse(&path, &data, index) {
+                    Some(font) => fonts.push(font),
+                    None => log::debug!("Failed to parse font {:?} ({})", path, index)
+                }
+            }
+        }
+
+        SystemFonts { fonts }
+    }
+
+    /// All the fonts which were found.
+    #[inline]
+    #[must_use]
+    pub fn fonts(&self) -> &[SystemFont]
+    {
+        &self.fonts
+    }
+
+    /// Returns the font of the specified family which best matches the
+    /// weight and style, or `None` if no font of the family is installed.
+    /// Family names are compared case-insensitively.
+    #[must_use]
+    pub fn find(
+        &self,
+        family_name: &str,
+        weight: u16,
+        style: FontStyle
+    ) -> Option<&SystemFont>
+    {
+        self.fonts
+            .iter()
+            .filter(|font| font.family_name.eq_ignore_ascii_case(family_name))
+            .min_by_key(|font| font.match_distance(weight, style))
+    }
+
+    /// Returns the font containing the specified codepoint which best
+    /// matches the weight and style, or `None` if no installed font contains
+    /// the codepoint.
+    #[must_use]
+    pub fn find_for_codepoint(
+        &self,
+        codepoint: char,
+        weight: u16,
+        style: FontStyle
+    ) -> Option<&SystemFont>
+    {
+        self.fonts
+            .iter()
+            .filter(|font| font.contains_codepoint(codepoint))
+            .min_by_key(|font| font.match_distance(weight, style))
+    }
+
+    /// Loads a [FontFamily] containing the fonts which best match the query.
+    ///
+    /// The family contains the best match for each of the family names in
+    /// the query which is installed, in order, followed by fallback fonts for
+    /// any codepoints in the query's coverage which those fonts don't
+    /// contain. Codepoints which no installed font contains are drawn as set
+    /// using [crate::font::TextOptions::with_missing_glyph()].
+    ///
+    /// An error is returned if no fonts match, or if a font can't be loaded.
+    pub fn font_family(
+        &self,
+        query: &FontQuery
+    ) -> Result<FontFamily, BacktraceError<ErrorMessage>>
+    {
+        let mut chosen: Vec<&SystemFont> = Vec::new();
+
+        for family_name in &query.families {
+            if let Some(font) = self.find(family_name, query.weight, query.style) {
+                if !chosen.contains(&font) {
+                    chosen.push(font);
+                }
+            }
+        }
+
+        for codepoint in &query.coverage {
+            if chosen
+                .iter()
+                .any(|font| font.contains_codepoint(*codepoint))
+            {
+                continue;
+            }
+
+            if let Some(font) =
+                self.find_for_codepoint(*codepoint, query.weight, query.style)
+            {
+                chosen.push(font);
+            }
+        }
+
+        if chosen.is_empty() {
+            return Err(ErrorMessage::msg(format!(
+                "No installed fonts match the families {:?}",
+                query.families
+            )));
+        }
+
+        let fonts = chosen
+            .iter()
+            .map(|font| font.load())
+            .collect::<Result<Vec<Font>, _>>()?;
+
+        Ok(FontFamily::new(fonts))
+    }
+}
+
+/// A description of the fonts to load from the system using
+/// [SystemFonts::font_family()].
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+pub struct FontQuery
+{
+    families: Vec<String>,
+    weight: u16,
+    style: FontStyle,
+    coverage: Vec<char>
+}
+
+impl FontQuery
+{
+    /// Instantiates a new `FontQuery` with the default settings.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self
+    {
+        FontQuery {
+            families: Vec::new(),
+            weight: 400,
+            style: FontStyle::Normal,
+            coverage: Vec::new()
+        }
+    }
+
+    /// Adds a family name to the query. Families added earlier take
+    /// priority, and families which aren't installed are ignored.
+    ///
+    /// The default is no families, in which case the fonts are chosen by
+    /// their coverage only.
+    #[inline]
+    #[must_use]
+    pub fn with_family<S: Into<String>>(mut self, family_name: S) -> Self
+    {
+        self.families.push(family_name.into());
+        self
+    }
+
+    /// Sets the weight of the fonts, from `100` (thin) to `900` (black).
+    ///
+    /// The default is `400`, the weight of regular fonts.
+    #[inline]
+    #[must_use]
+    pub fn with_weight(mut self, weight: u16) -> Self
+    {
+        self.weight = weight;
+        self
+    }
+
+    /// Sets whether the fonts should be upright or slanted.
+    ///
+    /// The default is [FontStyle::Normal].
+    #[inline]
+    #[must_use]
+    pub fn with_style(mut self, style: FontStyle) -> Self
+    {
+        self.style = style;
+        self
+    }
+
+    /// Adds the codepoints of the text to the coverage of the query. Fallback
+    /// fonts are added to the family for any of these codepoints which the
+    /// requested families don't contain.
+    ///
+    /// The default is no coverage.
+    #[inline]
+    #[must_use]
+    pub fn with_coverage(mut self, text: &str) -> Self
+    {
+        self.coverage.extend(text.chars());
+        self
+    }
+}
+
+impl Default for FontQuery
+{
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+/// Merges the codepoints into sorted, non-overlapping inclusive ranges.
+/// Codepoints which aren't valid characters are skipped, as a malformed
+/// cmap table may contain them.
+fn coverage_ranges(mut codepoints: Vec<u32>) -> Vec<(u32, u32)>
+{
+    codepoints.sort_unstable();
+
+    let mut coverage: Vec<(u32, u32)> = Vec::new();
+
+    for codepoint in codepoints {
+        if codepoint > char::MAX as u32 {
+            break;
+        }
+
+        match coverage.last_mut() {
+            Some((_, end)) if codepoint <= end.saturating_add(1) => {
+                *end = codepoint.max(*end)
+            }
+            _ => coverage.push((codepoint, codepoint))
+        }
+    }
+
+    coverage
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+    use crate::font::TextLayout;
+
+    fn system_font(family_name: &str, weight: u16, style: FontStyle) -> SystemFont
+    {
+        SystemFont {
+            path: PathBuf::from(format!("{}-{}.ttf", family_name, weight)),
+            index: 0,
+            family_name: family_name.to_string(),
+            weight,
+            style,
+            coverage: vec![(0x41, 0x5A), (0x61, 0x7A)]
+        }
+    }
+
+    #[test]
+    fn test_scan_directories()
+    {
+        let system_fonts = SystemFonts::scan_directories(&["assets/fonts"]);
+
+        assert_eq!(1, system_fonts.fonts().len());
+
+        let font = &system_fonts.fonts()[0];
+
+        assert_eq!("Noto Sans", font.family_name());
+        assert_eq!(400, font.weight());
+        assert_eq!(FontStyle::Normal, font.style());
+        assert!(font.contains_codepoint('a'));
+        assert!(font.contains_codepoint('Ж'));
+        assert!(!font.contains_codepoint('\u{65E5}'));
+
+        assert_eq!(
+            Some(font),
+            system_fonts.find("noto sans", 700, FontStyle::Italic)
+        );
+        assert_eq!(
+            None,
+            system_fonts.find("Noto Serif", 400, FontStyle::Normal)
+        );
+
+        let family = system_fonts
+            .font_family(
+                &FontQuery::new()
+                    .with_family("Noto Serif")
+                    .with_coverage("a\u{65E5}")
+            )
+            .unwrap();
+
+        assert!(family.lookup_glyph_for_codepoint('a').is_some());
+
+        assert!(system_fonts
+            .font_family(&FontQuery::new().with_family("Noto Serif"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_find_best_match()
+    {
+        let system_fonts = SystemFonts {
+            fonts: vec![
+                system_font("Sans", 300, FontStyle::Normal),
+                system_font("Sans", 500, FontStyle::Normal),
+                system_font("Sans", 700, FontStyle::Normal),
+                system_font("Sans", 400, FontStyle::Oblique),
+                system_font("Serif", 400, FontStyle::Normal),
+            ]
+        };
+
+        let find = |weight: u16, style: FontStyle| {
+            let font = system_fonts.find("Sans", weight, style).unwrap();
+            (font.weight(), font.style())
+        };
+
+        assert_eq!((500, FontStyle::Normal), find(400, FontStyle::Normal));
+        assert_eq!((300, FontStyle::Normal), find(200, FontStyle::Normal));
+        assert_eq!((700, FontStyle::Normal), find(900, FontStyle::Normal));
+        assert_eq!((400, FontStyle::Oblique), find(700, FontStyle::Italic));
+
+        assert!(system_fonts.fonts()[0].contains_codepoint('Z'));
+        assert!(!system_fonts.fonts()[0].contains_codepoint('['));
+        assert!(system_fonts
+            .find_for_codepoint('\u{65E5}', 400, FontStyle::Normal)
+            .is_none());
+    }
+
+    #[test]
+    fn test_coverage_ranges()
+    {
+        assert_eq!(
+            vec![(0x20, 0x22), (0x41, 0x41)],
+            coverage_ranges(vec![0x22, 0x41, 0x20, 0x21, 0x21])
+        );
+
+        // A group in a malformed cmap table may extend to u32::MAX
+        assert_eq!(
+            vec![(0x10FFFE, 0x10FFFF)],
+            coverage_ranges(vec![u32::MAX, 0x10FFFE, 0x110000, 0x10FFFF])
+        );
+    }
+}
@@ -0,0 +1,169 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Applies the Unicode Bidirectional Algorithm, so that right-to-left scripts
+//! and mixed-direction text are displayed in the correct order.
+
+use unicode_bidi::BidiInfo;
+
+/// Codepoints below this value never have a right-to-left bidi class, so the
+/// algorithm can be skipped for text which only contains these.
+const FIRST_RIGHT_TO_LEFT_CODEPOINT: char = '\u{0590}';
+
+/// The resolved embedding levels of a sequence of codepoints. Odd levels are
+/// right-to-left, and even levels are left-to-right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BidiLevels
+{
+    /// The embedding level of each codepoint.
+    pub levels: Vec<u8>,
+    /// The embedding level of the paragraph containing each codepoint.
+    pub paragraph_levels: Vec<u8>
+}
+
+#[inline]
+#[must_use]
+pub(crate) fn is_right_to_left(level: u8) -> bool
+{
+    level % 2 == 1
+}
+
+/// Returns true for the explicit directional formatting characters, which
+/// affect the embedding levels but are not displayed.
+#[inline]
+#[must_use]
+pub(crate) fn is_formatting_character(codepoint: char) -> bool
+{
+    matches!(
+        codepoint,
+        '\u{061C}' | '\u{200E}' | '\u{200F}' | '\u{202A}'..='\u{202E}'
+            | '\u{2066}'..='\u{2069}'
+    )
+}
+
+/// Resolves the embedding level of each codepoint. The direction of each
+/// paragraph is taken from its first strong character, and is left-to-right
+/// if there is none.
+pub(crate) fn resolve_levels(codepoints: &[char]) -> BidiLevels
+{
+    if codepoints
+        .iter()
+        .all(|codepoint| *codepoint < FIRST_RIGHT_TO_LEFT_CODEPOINT)
+    {
+        return BidiLevels {
+            levels: vec![0; codepoints.len()],
+            paragraph_levels: vec![0; codepoints.len()]
+        };
+    }
+
+    let text: String = codepoints.iter().collect();
+    let info = BidiInfo::new(&text, None);
+
+    let mut levels = Vec::with_capacity(codepoints.len());
+    let mut paragraph_levels = Vec::with_capacity(codepoints.len());
+    let mut paragraphs = info.paragraphs.iter().peekable();
+
+    // Both are indexed by byte offset into the string
+    for (byte_offset, _) in text.char_indices() {
+        while let Some(paragraph) = paragraphs.peek() {
+            if paragraph.range.end <= byte_offset {
+                paragraphs.next();
+            } else {
+                break;
+            }
+        }
+
+        levels.push(info.levels[byte_offset].number());
+
+        paragraph_levels.push(
+            paragraphs
+                .peek()
+                .map(|paragraph| paragraph.level.number())
+                .unwrap_or(0)
+        );
+    }
+
+    BidiLevels {
+        levels,
+        paragraph_levels
+    }
+}
+
+/// Returns the indices of the items in visual order from left to right, given
+/// the embedding level of each item in logical order (rule L2).
+pub(crate) fn visual_order(levels: &[u8]) -> Vec<usize>
+{
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+
+    let highest_level = match levels.iter().max() {
+        None => return order,
+        Some(level) => *level
+    };
+
+    let lowest_odd_level = levels.iter().min().map(|level| level | 1).unwrap_or(1);
+
+    for level in (lowest_odd_level..=highest_level).rev() {
+        let mut index = 0;
+
+        while index < order.len() {
+            if levels[order[index]] < level {
+                index += 1;
+                continue;
+            }
+
+            let run_start = index;
+
+            while index < order.len() && levels[order[index]] >= level {
+                index += 1;
+            }
+
+            order[run_start..index].reverse();
+        }
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    pub fn test_resolve_levels()
+    {
+        let codepoints: Vec<char> = "abc אבג\nאב".chars().collect();
+
+        assert_eq!(
+            BidiLevels {
+                levels: vec![0, 0, 0, 0, 1, 1, 1, 0, 1, 1],
+                paragraph_levels: vec![0, 0, 0, 0, 0, 0, 0, 0, 1, 1]
+            },
+            resolve_levels(&codepoints)
+        );
+    }
+
+    #[test]
+    pub fn test_visual_order()
+    {
+        assert_eq!(vec![0, 1, 3, 2, 4], visual_order(&[0, 0, 1, 1, 0]));
+
+        // A left-to-right number inside right-to-left text
+        assert_eq!(vec![4, 2, 3, 1, 0], visual_order(&[1, 1, 2, 2, 1]));
+
+        assert_eq!(Vec::<usize>::new(), visual_order(&[]));
+    }
+}
@@ -0,0 +1,63 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Finds the places where a line of text may be wrapped, using the Unicode
+//! Line Breaking Algorithm.
+
+use unicode_linebreak::BreakOpportunity;
+
+/// Returns true for each codepoint which a line may be wrapped before.
+pub(crate) fn break_opportunities(codepoints: &[char]) -> Vec<bool>
+{
+    let text: String = codepoints.iter().collect();
+
+    let mut result = vec![false; codepoints.len()];
+    let mut byte_offsets = text.char_indices().map(|(offset, _)| offset).enumerate();
+
+    // Both are in order of byte offset into the string
+    for (byte_offset, opportunity) in unicode_linebreak::linebreaks(&text) {
+        if opportunity != BreakOpportunity::Allowed {
+            continue;
+        }
+
+        for (index, codepoint_offset) in byte_offsets.by_ref() {
+            if codepoint_offset == byte_offset {
+                result[index] = true;
+                break;
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test
+{
+    use super::*;
+
+    #[test]
+    pub fn test_break_opportunities()
+    {
+        let codepoints: Vec<char> = "ab cd-ef 日本".chars().collect();
+
+        // Before "cd", "ef", and each of the Japanese characters
+        assert_eq!(
+            vec![false, false, false, true, false, false, true, false, false, true, true],
+            break_opportunities(&codepoints)
+        );
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ *  Copyright 2021 QuantumBadger
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ */
+
+//! Converts runs of codepoints into positioned glyphs using `rustybuzz`, so
+//! that ligatures, contextual forms, and mark positioning are applied.
+
+use crate::font::Font;
+
+/// A glyph produced by shaping a run of text. All distances are in pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ShapedGlyph
+{
+    pub glyph_id: u16,
+    /// The index of the first codepoint in the run which produced this glyph.
+    pub cluster: usize,
+    pub x_advance: f32,
+    pub x_offset: f32,
+    /// The vertical offset from the baseline. Positive values are downwards.
+    pub y_offset: f32
+}
+
+/// Shapes the codepoints using the specified font and direction, returning
+/// the glyphs in logical order, so that the clusters are increasing. The
+/// glyphs of right-to-left text must be reversed to get the visual order. The
+/// script is guessed from the text.
+///
+/// If the font cannot be parsed by the shaper, each codepoint is mapped to a
+/// glyph directly, without kerning.
+pub(crate) fn shape(
+    font: &Font,
+    codepoints: &[char],
+    scale: f32,
+    right_to_left: bool
+) -> Vec<ShapedGlyph>
+{
+    let mut face = match rustybuzz::Face::from_slice(font.data(), font.index()) {
+        None => {
+            log::warn!("Font not supported by shaper, falling back to simple layout");
+            return shape_unsupported(font, codepoints, scale);
+        }
+        Some(face) => face
+    };
+
+    let variations: Vec<rustybuzz::Variation> = font
+        .variations()
+        .iter()
+        .map(|variation| rustybuzz::Variation {
+            tag: rustybuzz::Tag::from_bytes(variation.tag()),
+            value: variation.value()
+        })
+        .collect();
+
+    face.set_variations(&variations);
+
+    let v_metrics = font.font().v_metrics_unscaled();
+    let pixels_per_unit = scale / (v_metrics.ascent - v_metrics.descent);
+
+    let text: String = codepoints.iter().collect();
+
+    // The shaper reports clusters as byte offsets into the string
+    let mut codepoint_indices = vec![0; text.len()];
+
+    for (index, (byte_offset, _)) in text.char_indices().enumerate() {
+        codepoint_indices[byte_offset] = index;
+    }
+
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(&text);
+
+    buffer.set_direction(
+        if right_to_left {
+            rustybuzz::Direction::RightToLeft
+        } else {
+            rustybuzz::Direction::LeftToRight
+        }
+    );
+
+    buffer.guess_segment_properties();
+
+    let output = rustybuzz::shape(&face, &[], buffer);
+
+    let mut result: Vec<ShapedGlyph> = output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            glyph_id: info.glyph_id as u16,
+            cluster: codepoint_indices[info.cluster as usize],
+            x_advance: position.x_advance as f32 * pixels_per_unit,
+            x_offset: position.x_offset as f32 * pixels_per_unit,
+            y_offset: -position.y_offset as f32 * pixels_per_unit
+        })
+        .collect();
+
+    // The shaper outputs right-to-left text in visual order
+    if right_to_left {
+        result.reverse();
+    }
+
+    result
+}
+
+fn shape_unsupported(font: &Font, codepoints: &[char], scale: f32) -> Vec<ShapedGlyph>
+{
+    codepoints
+        .iter()
+        .enumerate()
+        .map(|(cluster, codepoint)| {
+            let glyph = font
+                .font()
+                .glyph(*codepoint)
+                .scaled(rusttype::Scale::uniform(scale));
+
+            ShapedGlyph {
+                glyph_id: glyph.id().0,
+                cluster,
+                x_advance: glyph.h_metrics().advance_width,
+                x_offset: 0.0,
+                y_offset: 0.0
+            }
+        })
+        .collect()
+}
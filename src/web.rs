@@ -30,7 +30,15 @@ use web_sys::{
     MediaQueryListEvent,
     MouseEvent
 };
-use web_sys::{Document, Element, HtmlCanvasElement, HtmlElement, Performance, Window};
+use web_sys::{
+    Document,
+    Element,
+    HtmlCanvasElement,
+    HtmlElement,
+    Performance,
+    WebGlContextAttributes,
+    Window
+};
 
 use crate::dimen::UVec2;
 #[cfg(feature = "windowing")]
@@ -472,9 +480,13 @@ impl WebCanvasElement
             viewport_size_pixels
         );
 
+        // The stencil buffer is used for clipping to arbitrary shapes
+        let attributes = WebGlContextAttributes::new();
+        attributes.set_stencil(true);
+
         let context = self
             .canvas
-            .get_context("webgl2")
+            .get_context_with_context_options("webgl2", &attributes)
             .map_err(|err| {
                 GLRendererCreationError::msg(format!(
                     "Failed to get WebGL2 context: '{err:?}'"
@@ -640,6 +640,7 @@ fn create_best_context<UserEventType>(
 
             let mut windowed_context = glutin::ContextBuilder::new()
                 .with_vsync(*vsync)
+                .with_stencil_buffer(8)
                 .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (2, 0)));
 
             if *multisampling > 1 {
@@ -25,11 +25,26 @@ use std::sync::mpsc::channel;
 use glutin::dpi::PhysicalSize;
 use glutin::event_loop::EventLoop;
 use image::{ColorType, GenericImageView, ImageFormat};
-use speedy2d::color::Color;
-use speedy2d::dimen::{Vec2, Vector2};
-use speedy2d::font::{Font, TextAlignment, TextLayout, TextOptions};
+use speedy2d::color::{BlendMode, Color};
+use speedy2d::dimen::{Affine2, Vec2, Vector2};
+use speedy2d::font::{
+    Font,
+    GlyphCacheOptions,
+    LineBreaking,
+    RichText,
+    TabStops,
+    TextAlignment,
+    TextLayout,
+    TextOptions,
+    TextRenderingMode,
+    TextSpan,
+    TextStyle,
+    VerticalAlignment
+};
 use speedy2d::image::{ImageDataType, ImageSmoothingMode};
-use speedy2d::shape::{Polygon, Rect, Rectangle};
+use speedy2d::paint::Gradient;
+use speedy2d::shader::ShaderUniforms;
+use speedy2d::shape::{LineCap, LineJoin, Path, Polygon, Rect, Rectangle, StrokeStyle};
 use speedy2d::GLRenderer;
 
 const NOTO_SANS_REGULAR_BYTES: &[u8] =
@@ -78,6 +93,7 @@ where
     let context_builder = glutin::ContextBuilder::new()
         .with_gl_debug_flag(true)
         .with_multisampling(0)
+        .with_stencil_buffer(8)
         .with_gl(glutin::GlRequest::Specific(glutin::Api::OpenGl, (2, 0)));
 
     #[cfg(not(target_os = "linux"))]
@@ -740,6 +756,106 @@ fn main()
         })
     });
 
+    #[cfg(feature = "image-loading")]
+    tests.push(GLTest {
+        width: 320,
+        height: 200,
+        name: "text_bitmap_font".to_string(),
+        action: Box::new(|renderer| {
+            let page = renderer
+                .create_image_from_file_path(
+                    None,
+                    ImageSmoothingMode::NearestNeighbor,
+                    "test/assets/test_images/pixel_font.png"
+                )
+                .unwrap();
+
+            let font = renderer
+                .create_bitmap_font(
+                    include_bytes!("assets/test_images/pixel_font.fnt"),
+                    vec![page]
+                )
+                .unwrap();
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                graphics.draw_text(
+                    (10.0, 10.0),
+                    Color::BLACK,
+                    &font.layout_text(
+                        "HELLO WORLD!",
+                        font.line_height() * 2.0,
+                        TextOptions::new()
+                    )
+                );
+
+                graphics.draw_text(
+                    (10.0, 40.0),
+                    Color::from_rgb(0.8, 0.1, 0.1),
+                    &font.layout_text(
+                        "AVA LAVA",
+                        font.line_height() * 4.0,
+                        TextOptions::new()
+                    )
+                );
+
+                graphics.draw_text(
+                    (10.0, 90.5),
+                    Color::BLUE,
+                    &font.layout_text(
+                        "HELLO WORLD",
+                        font.line_height() * 3.0,
+                        TextOptions::new()
+                            .with_wrap_to_width(150.0, TextAlignment::Right)
+                    )
+                );
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 640,
+        height: 400,
+        name: "text_justify".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((10.0, 10.0), (410.0, 400.0)),
+                    Color::from_rgb(0.9, 0.9, 1.0)
+                );
+
+                graphics.draw_text(
+                    (10.0, 10.0),
+                    Color::BLACK,
+                    &typeface.layout_text(
+                        "The quick brown föx jumped over the lazy dog! Pack my box with \
+                         five dozen liquor jugs.",
+                        32.0,
+                        TextOptions::new()
+                            .with_wrap_to_width(400.0, TextAlignment::Justify)
+                    )
+                );
+
+                graphics.draw_text(
+                    (10.0, 210.0),
+                    Color::BLACK,
+                    &typeface.layout_text(
+                        "See https://example.com/a-long/path for details",
+                        40.0,
+                        TextOptions::new()
+                            .with_wrap_to_width(400.0, TextAlignment::Left)
+                            .with_line_breaking(LineBreaking::Unicode)
+                    )
+                );
+            });
+        })
+    });
+
     tests.push(GLTest {
         width: 640,
         height: 640,
@@ -1162,6 +1278,768 @@ fn main()
         })
     });
 
+    tests.push(GLTest {
+        width: 200,
+        height: 200,
+        name: "transform_stack".to_string(),
+        action: Box::new(|renderer| {
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                graphics.push_transform(Affine2::translation((100.0, 100.0)));
+
+                graphics.push_transform(Affine2::rotation(std::f32::consts::FRAC_PI_4));
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((-40.0, -40.0), (40.0, 40.0)),
+                    Color::BLUE
+                );
+                graphics.pop_transform();
+
+                graphics.push_transform(Affine2::scale((2.0, 1.0)));
+                graphics.draw_circle((0.0, 0.0), 20.0, Color::RED);
+                graphics.pop_transform();
+
+                graphics.pop_transform();
+
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (20.0, 20.0)),
+                    Color::GREEN
+                );
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 200,
+        height: 200,
+        name: "draw_to_image".to_string(),
+        action: Box::new(|renderer| {
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                let image = graphics
+                    .draw_to_image((80, 60), ImageSmoothingMode::Linear, |graphics| {
+                        graphics.clear_screen(Color::YELLOW);
+                        graphics.draw_rectangle(
+                            Rectangle::from_tuples((0.0, 0.0), (40.0, 20.0)),
+                            Color::BLUE
+                        );
+                        graphics.draw_circle((50.0, 35.0), 20.0, Color::RED);
+                    })
+                    .unwrap();
+
+                graphics.draw_image((10.0, 10.0), &image);
+
+                graphics.push_transform(Affine2::translation((100.0, 190.0)));
+                graphics.push_transform(Affine2::rotation(-std::f32::consts::FRAC_PI_2));
+                graphics.draw_image((0.0, 0.0), &image);
+                graphics.pop_transform();
+                graphics.pop_transform();
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 200,
+        height: 200,
+        name: "paths".to_string(),
+        action: Box::new(|renderer| {
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                let ring = Path::new()
+                    .move_to((10.0, 10.0))
+                    .line_to((90.0, 10.0))
+                    .quad_to((110.0, 50.0), (90.0, 90.0))
+                    .line_to((10.0, 90.0))
+                    .close()
+                    .move_to((30.0, 30.0))
+                    .line_to((70.0, 30.0))
+                    .cubic_to((80.0, 40.0), (60.0, 60.0), (70.0, 70.0))
+                    .line_to((30.0, 70.0))
+                    .close();
+
+                graphics.draw_path_filled(&ring, Color::BLUE);
+
+                let rounded = Path::new()
+                    .move_to((120.0, 10.0))
+                    .arc_to((190.0, 10.0), (190.0, 90.0), 20.0)
+                    .arc_to((190.0, 90.0), (120.0, 90.0), 20.0)
+                    .line_to((120.0, 90.0))
+                    .close();
+
+                graphics.draw_path_filled(&rounded, Color::GREEN);
+
+                let zigzag = |y: f32| {
+                    Path::new()
+                        .move_to((20.0, y + 20.0))
+                        .line_to((50.0, y))
+                        .line_to((80.0, y + 20.0))
+                        .line_to((110.0, y))
+                };
+
+                graphics.draw_path_stroked(
+                    &zigzag(110.0),
+                    &StrokeStyle::new(8.0)
+                        .with_join(LineJoin::Miter)
+                        .with_cap(LineCap::Butt),
+                    Color::RED
+                );
+
+                graphics.draw_path_stroked(
+                    &zigzag(140.0),
+                    &StrokeStyle::new(8.0)
+                        .with_join(LineJoin::Round)
+                        .with_cap(LineCap::Round),
+                    Color::BLACK
+                );
+
+                graphics.draw_path_stroked(
+                    &zigzag(170.0),
+                    &StrokeStyle::new(8.0)
+                        .with_join(LineJoin::Bevel)
+                        .with_cap(LineCap::Square),
+                    Color::MAGENTA
+                );
+
+                graphics.draw_path_stroked(
+                    &rounded,
+                    &StrokeStyle::new(3.0),
+                    Color::BLACK
+                );
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 200,
+        height: 200,
+        name: "gradients".to_string(),
+        action: Box::new(|renderer| {
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                let rainbow = Gradient::new_linear((10.0, 0.0), (190.0, 0.0))
+                    .with_stop(0.0, Color::RED)
+                    .with_stop(0.25, Color::YELLOW)
+                    .with_stop(0.5, Color::GREEN)
+                    .with_stop(0.75, Color::BLUE)
+                    .with_stop(1.0, Color::MAGENTA);
+
+                graphics.draw_rectangle_with_paint(
+                    Rectangle::from_tuples((10.0, 10.0), (190.0, 40.0)),
+                    &rainbow
+                );
+
+                let glow = Gradient::new_radial((50.0, 90.0), 40.0)
+                    .with_stop(0.0, Color::WHITE)
+                    .with_stop(0.5, Color::YELLOW)
+                    .with_stop(1.0, Color::from_rgba(1.0, 0.0, 0.0, 0.0));
+
+                graphics.draw_circle_with_paint((50.0, 90.0), 40.0, glow);
+
+                let hard_stop = Gradient::new_linear((110.0, 50.0), (190.0, 130.0))
+                    .with_stop(0.5, Color::BLACK)
+                    .with_stop(0.5, Color::CYAN);
+
+                graphics.draw_polygon_with_paint(
+                    &Polygon::new(&[(150.0, 50.0), (190.0, 130.0), (110.0, 130.0)]),
+                    (0.0, 0.0),
+                    hard_stop
+                );
+
+                let path = Path::new().move_to((20.0, 150.0)).cubic_to(
+                    (60.0, 110.0),
+                    (140.0, 190.0),
+                    (180.0, 150.0)
+                );
+
+                let fade = Gradient::new_linear((20.0, 0.0), (180.0, 0.0))
+                    .with_stop(0.0, Color::BLUE)
+                    .with_stop(1.0, Color::from_rgba(0.0, 0.0, 1.0, 0.0));
+
+                graphics.draw_path_stroked_with_paint(
+                    &path,
+                    &StrokeStyle::new(10.0).with_cap(LineCap::Round),
+                    fade
+                );
+
+                graphics.push_transform(Affine2::translation((100.0, 185.0)));
+                graphics.push_transform(Affine2::scale((2.0, 0.5)));
+                graphics.draw_circle_with_paint(
+                    (0.0, 0.0),
+                    20.0,
+                    Gradient::new_radial((0.0, 0.0), 20.0)
+                        .with_stop(0.0, Color::RED)
+                        .with_stop(1.0, Color::BLUE)
+                );
+                graphics.pop_transform();
+                graphics.pop_transform();
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 200,
+        height: 100,
+        name: "custom_shaders".to_string(),
+        action: Box::new(|renderer| {
+            let error = renderer
+                .create_fragment_shader(
+                    "#version 110\nvoid main(void) { gl_FragColor = u_Undeclared; }"
+                )
+                .unwrap_err();
+
+            assert!(
+                format!("{:?}", error).contains("u_Undeclared"),
+                "Compile log missing from error: {:?}",
+                error
+            );
+
+            let pattern = renderer
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGBA,
+                    ImageSmoothingMode::NearestNeighbor,
+                    Vector2::new(2, 2),
+                    &[
+                        255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 0, 255
+                    ]
+                )
+                .unwrap();
+
+            let image = renderer
+                .create_image_from_raw_pixels(
+                    ImageDataType::RGBA,
+                    ImageSmoothingMode::NearestNeighbor,
+                    Vector2::new(2, 1),
+                    &[255, 128, 0, 255, 0, 128, 255, 255]
+                )
+                .unwrap();
+
+            let stripes = renderer
+                .create_fragment_shader(
+                    r#"
+                    #version 110
+
+                    uniform sampler2D u_Pattern;
+                    uniform float u_Amount;
+                    uniform vec2 u_Offset;
+                    uniform vec4 u_Tint;
+
+                    varying vec2 pass_TextureCoord;
+
+                    void main(void) {
+                        vec4 pattern = texture2D(u_Pattern, pass_TextureCoord + u_Offset);
+                        gl_FragColor = mix(pattern, u_Tint, u_Amount);
+                    }
+                    "#
+                )
+                .unwrap();
+
+            let grayscale = renderer
+                .create_fragment_shader(
+                    r#"
+                    #version 110
+
+                    uniform sampler2D in_Texture;
+                    uniform float u_Amount;
+
+                    varying vec2 pass_TextureCoord;
+
+                    void main(void) {
+                        vec4 color = texture2D(in_Texture, pass_TextureCoord);
+                        float gray = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+                        vec3 mixed = mix(color.rgb, vec3(gray), u_Amount);
+                        gl_FragColor = vec4(mixed, color.a);
+                    }
+                    "#
+                )
+                .unwrap();
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                graphics.draw_rectangle_with_shader(
+                    Rectangle::from_tuples((10.0, 10.0), (50.0, 50.0)),
+                    &stripes,
+                    &ShaderUniforms::new()
+                        .with_image("u_Pattern", &pattern)
+                        .with_float("u_Amount", 0.0)
+                        .with_vec2("u_Offset", (0.0, 0.0))
+                        .with_color("u_Tint", Color::BLACK)
+                );
+
+                graphics.draw_rectangle_with_shader(
+                    Rectangle::from_tuples((60.0, 10.0), (100.0, 50.0)),
+                    &stripes,
+                    &ShaderUniforms::new()
+                        .with_image("u_Pattern", &pattern)
+                        .with_float("u_Amount", 0.5)
+                        .with_vec2("u_Offset", (0.5, 0.0))
+                        .with_vec4("u_Tint", [1.0, 0.0, 1.0, 1.0])
+                );
+
+                graphics.draw_rectangle_image_with_shader(
+                    Rectangle::from_tuples((110.0, 10.0), (150.0, 50.0)),
+                    &image,
+                    &grayscale,
+                    &ShaderUniforms::new().with_float("u_Amount", 0.0)
+                );
+
+                graphics.draw_rectangle_image_with_shader(
+                    Rectangle::from_tuples((150.0, 10.0), (190.0, 50.0)),
+                    &image,
+                    &grayscale,
+                    &ShaderUniforms::new().with_float("u_Amount", 1.0)
+                );
+
+                // Built-in drawing still works after the custom shaders
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((10.0, 60.0), (190.0, 90.0)),
+                    Color::from_rgb(0.2, 0.4, 0.8)
+                );
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 240,
+        height: 110,
+        name: "blend_modes".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+            let text = typeface.layout_text("Ab", 24.0, TextOptions::new());
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::from_rgb(0.5, 0.5, 0.5));
+
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 40.0), (240.0, 80.0)),
+                    Color::from_rgb(0.2, 0.4, 0.8)
+                );
+
+                let modes = [
+                    BlendMode::Normal,
+                    BlendMode::Additive,
+                    BlendMode::Multiply,
+                    BlendMode::Screen,
+                    BlendMode::Premultiplied,
+                    BlendMode::Replace
+                ];
+
+                for (i, mode) in modes.iter().enumerate() {
+                    let x = i as f32 * 40.0 + 20.0;
+
+                    graphics.set_blend_mode(*mode);
+                    assert_eq!(*mode, graphics.blend_mode());
+
+                    graphics.draw_circle((x, 25.0), 15.0, Color::from_rgb(1.0, 0.5, 0.0));
+                    graphics.draw_circle(
+                        (x, 55.0),
+                        15.0,
+                        Color::from_rgba(1.0, 0.5, 0.0, 0.5)
+                    );
+                    graphics.draw_text(
+                        (x - 14.0, 78.0),
+                        Color::from_rgb(1.0, 0.5, 0.0),
+                        &text
+                    );
+                }
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 200,
+        height: 200,
+        name: "clipping".to_string(),
+        action: Box::new(|renderer| {
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                let full = Rectangle::from_tuples((0.0, 0.0), (200.0, 200.0));
+
+                graphics.push_clip(Rectangle::from_tuples((10, 10), (80, 80)));
+                graphics.push_clip(Rectangle::from_tuples((40, 40), (95, 95)));
+                graphics.draw_rectangle(full.clone(), Color::RED);
+                graphics.pop_clip();
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((0.0, 0.0), (200.0, 20.0)),
+                    Color::GREEN
+                );
+                graphics.pop_clip();
+
+                graphics.push_clip_circle((150.0, 50.0), 40.0);
+                graphics.draw_rectangle(full.clone(), Color::BLUE);
+                graphics.push_clip_polygon(
+                    &Polygon::new(&[(0.0, -30.0), (30.0, 30.0), (-30.0, 30.0)]),
+                    (150.0, 50.0)
+                );
+                graphics.draw_rectangle(full.clone(), Color::YELLOW);
+                graphics.pop_clip();
+                graphics.draw_rectangle(
+                    Rectangle::from_tuples((100.0, 45.0), (200.0, 55.0)),
+                    Color::MAGENTA
+                );
+                graphics.pop_clip();
+
+                graphics.push_transform(Affine2::translation((50.0, 150.0)));
+                graphics.push_transform(Affine2::scale((1.5, 1.0)));
+                graphics.push_clip_path(
+                    &Path::new()
+                        .move_to((-30.0, -40.0))
+                        .line_to((30.0, -40.0))
+                        .line_to((0.0, 0.0))
+                        .line_to((30.0, 40.0))
+                        .line_to((-30.0, 40.0))
+                        .close()
+                );
+                graphics.pop_transform();
+                graphics.pop_transform();
+                graphics.push_clip(Rectangle::from_tuples((0, 100), (60, 200)));
+                graphics.draw_rectangle(full.clone(), Color::CYAN);
+                graphics.pop_clip();
+                graphics.pop_clip();
+
+                graphics.push_clip_circle((150.0, 150.0), 45.0);
+
+                let image = graphics
+                    .draw_to_image((60, 60), ImageSmoothingMode::Linear, |graphics| {
+                        graphics.clear_screen(Color::BLACK);
+                        graphics.push_clip_circle((30.0, 30.0), 25.0);
+                        graphics.clear_screen(Color::from_rgb(1.0, 0.5, 0.0));
+                        graphics.pop_clip();
+                    })
+                    .unwrap();
+
+                graphics.draw_image((100.0, 100.0), &image);
+                graphics.pop_clip();
+
+                graphics.draw_line((0.0, 198.0), (200.0, 198.0), 2.0, Color::BLACK);
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 240,
+        height: 120,
+        name: "edge_antialiasing".to_string(),
+        action: Box::new(|renderer| {
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                // The top row is aliased, and the bottom row is anti-aliased
+                for row in 0..2 {
+                    graphics.set_edge_antialiasing(row == 1);
+                    assert_eq!(row == 1, graphics.edge_antialiasing());
+
+                    let y = row as f32 * 60.0;
+
+                    graphics.draw_polygon(
+                        &Polygon::new(&[(0.0, 0.0), (40.0, 10.0), (10.0, 45.0)]),
+                        (5.0, y + 5.0),
+                        Color::BLUE
+                    );
+
+                    graphics.push_transform(
+                        Affine2::translation((75.0, y + 30.0))
+                            * Affine2::rotation(20.0_f32.to_radians())
+                    );
+                    graphics.draw_rectangle(
+                        Rectangle::from_tuples((-20.0, -15.0), (20.0, 15.0)),
+                        Color::RED
+                    );
+                    graphics.pop_transform();
+
+                    graphics.draw_line(
+                        (105.0, y + 5.0),
+                        (145.0, y + 20.0),
+                        1.0,
+                        Color::BLACK
+                    );
+                    graphics.draw_line(
+                        (105.0, y + 20.0),
+                        (145.0, y + 50.0),
+                        4.0,
+                        Color::BLACK
+                    );
+
+                    graphics.draw_polygon_with_paint(
+                        &Polygon::new(&[(0.0, 0.0), (35.0, 20.0), (5.0, 50.0)]),
+                        (150.0, y + 5.0),
+                        Gradient::new_linear((150.0, 0.0), (190.0, 0.0))
+                            .with_stop(0.0, Color::GREEN)
+                            .with_stop(1.0, Color::MAGENTA)
+                    );
+
+                    graphics.draw_path_stroked(
+                        &Path::new()
+                            .move_to((200.0, y + 50.0))
+                            .quad_to((210.0, y), (235.0, y + 40.0)),
+                        &StrokeStyle::new(3.0),
+                        Color::from_rgb(0.0, 0.5, 0.0)
+                    );
+                }
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 320,
+        height: 120,
+        name: "rich_text".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            let text = RichText::new()
+                .with_span(TextSpan::new("Small and ", &typeface, 18.0))
+                .with_span(
+                    TextSpan::new("large", &typeface, 36.0)
+                        .with_color(Color::BLUE)
+                        .with_underline(true)
+                )
+                .with_span(TextSpan::new(" text, ", &typeface, 18.0))
+                .with_span(
+                    TextSpan::new("struck through", &typeface, 24.0)
+                        .with_color(Color::RED)
+                        .with_strikethrough(true)
+                )
+                .with_span(TextSpan::new(" and wrapped.", &typeface, 18.0))
+                .layout(
+                    TextOptions::new().with_wrap_to_width(300.0, TextAlignment::Center)
+                );
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+                graphics.draw_text((10.0, 10.0), Color::BLACK, &text);
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 320,
+        height: 160,
+        name: "text_decorations".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            let text = typeface.layout_text(
+                "Underlined and overlined text, wrapped over lines",
+                24.0,
+                TextOptions::new()
+                    .with_wrap_to_width(300.0, TextAlignment::Left)
+                    .with_underline(true)
+                    .with_overline(true)
+            );
+
+            let struck = typeface.layout_text(
+                "Struck through",
+                24.0,
+                TextOptions::new().with_strikethrough(true)
+            );
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+                graphics.draw_text((10.0, 10.0), Color::BLACK, &text);
+                graphics.draw_text((10.0, 110.0), Color::RED, &struck);
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 400,
+        height: 300,
+        name: "text_tab_stops_and_paragraphs".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            let table = typeface.layout_text(
+                "Name\tSize\tTime\nfont.rs\t120\t3ms\n\tlib.rs\t45\t12ms",
+                18.0,
+                TextOptions::new()
+                    .with_tab_stops(TabStops::Positions(vec![20.0, 120.0, 200.0]))
+            );
+
+            let paragraphs = typeface.layout_text(
+                "The first paragraph, wrapped over two lines.\nThe second one.",
+                18.0,
+                TextOptions::new()
+                    .with_wrap_to_width(300.0, TextAlignment::Left)
+                    .with_first_line_indent(30.0)
+                    .with_paragraph_spacing(15.0)
+            );
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+                graphics.draw_text((10.0, 10.0), Color::BLACK, &table);
+                graphics.draw_text((10.0, 120.0), Color::BLACK, &paragraphs);
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 420,
+        height: 200,
+        name: "text_in_rect".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            let label = typeface.layout_text("Button", 20.0, TextOptions::new());
+            let long =
+                typeface.layout_text("Overflowing label", 20.0, TextOptions::new());
+
+            let alignments = [
+                (TextAlignment::Left, VerticalAlignment::Top),
+                (TextAlignment::Center, VerticalAlignment::Middle),
+                (TextAlignment::Right, VerticalAlignment::Bottom),
+                (TextAlignment::Center, VerticalAlignment::Baseline)
+            ];
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                for (i, (h_align, v_align)) in alignments.iter().enumerate() {
+                    let left = 10.0 + i as f32 * 100.0;
+                    let rect = Rect::from_tuples((left, 10.0), (left + 90.0, 80.0));
+
+                    graphics.draw_rectangle(rect.clone(), Color::from_rgb(0.9, 0.9, 1.0));
+
+                    let overflows = graphics.draw_text_in_rect(
+                        rect,
+                        &label,
+                        *h_align,
+                        *v_align,
+                        Color::BLACK
+                    );
+
+                    assert!(!overflows);
+                }
+
+                let rect = Rect::from_tuples((10.0, 110.0), (110.0, 150.0));
+                graphics.draw_rectangle(rect.clone(), Color::from_rgb(1.0, 0.9, 0.9));
+
+                assert!(graphics.draw_text_in_rect(
+                    rect,
+                    &long,
+                    TextAlignment::Left,
+                    VerticalAlignment::Middle,
+                    Color::BLACK
+                ));
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 320,
+        height: 240,
+        name: "text_signed_distance_field".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            let small = typeface.layout_text("Small SDF text", 12.0, TextOptions::new());
+            let large = typeface.layout_text("Zoomed", 24.0, TextOptions::new());
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+                graphics.set_text_rendering_mode(TextRenderingMode::SignedDistanceField);
+
+                graphics.draw_text((10.0, 10.0), Color::BLACK, &small);
+
+                graphics.push_transform(
+                    Affine2::scale((3.0, 3.0)).then(&Affine2::translation((10.0, 40.0)))
+                );
+                graphics.draw_text((0.0, 0.0), Color::BLUE, &large);
+                graphics.pop_transform();
+
+                graphics.push_transform(
+                    Affine2::rotation(-0.4).then(&Affine2::translation((40.0, 220.0)))
+                );
+                graphics.draw_text((0.0, 0.0), Color::RED, &large);
+                graphics.pop_transform();
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 320,
+        height: 200,
+        name: "text_style".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            let text = typeface.layout_text("Effects", 40.0, TextOptions::new());
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::from_rgb(0.6, 0.8, 0.6));
+
+                graphics.draw_text_with_style(
+                    (10.0, 0.0),
+                    Color::WHITE,
+                    &TextStyle::new().with_outline(3.0, Color::BLACK),
+                    &text
+                );
+
+                graphics.draw_text_with_style(
+                    (10.0, 60.0),
+                    Color::WHITE,
+                    &TextStyle::new().with_shadow(
+                        (3.0, 3.0),
+                        2.0,
+                        Color::from_rgba(0.0, 0.0, 0.0, 0.6)
+                    ),
+                    &text
+                );
+
+                graphics.draw_text_with_style(
+                    (10.0, 120.0),
+                    Color::BLACK,
+                    &TextStyle::new()
+                        .with_outline(1.0, Color::WHITE)
+                        .with_glow(6.0, Color::YELLOW),
+                    &text
+                );
+
+                graphics.push_transform(
+                    Affine2::rotation(0.3).then(&Affine2::translation((200.0, 40.0)))
+                );
+                graphics.draw_text_with_style(
+                    (0.0, 0.0),
+                    Color::RED,
+                    &TextStyle::new().with_outline(2.0, Color::WHITE),
+                    &text
+                );
+                graphics.pop_transform();
+            });
+        })
+    });
+
+    tests.push(GLTest {
+        width: 200,
+        height: 120,
+        name: "text_color_glyphs".to_string(),
+        action: Box::new(|renderer| {
+            // Glyph "A" has a red layer, and a layer in the foreground color
+            let typeface =
+                Font::new(include_bytes!("assets/test_fonts/colr_layers.ttf")).unwrap();
+
+            let text = typeface.layout_text("AA", 40.0, TextOptions::new());
+
+            renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                graphics.draw_text((10.0, 10.0), Color::BLUE, &text);
+                graphics.draw_text((10.0, 60.0), Color::GREEN, &text);
+
+                graphics.set_text_rendering_mode(TextRenderingMode::SignedDistanceField);
+                graphics.draw_text(
+                    (110.0, 10.0),
+                    Color::from_rgba(0.0, 0.0, 1.0, 0.5),
+                    &text
+                );
+            });
+        })
+    });
+
     tests.push(GLTest {
         width: 640,
         height: 479,
@@ -1199,6 +2077,108 @@ fn main()
         })
     });
 
+    tests.push(GLTest {
+        width: 640,
+        height: 260,
+        name: "text_glyph_cache_eviction".to_string(),
+        action: Box::new(|renderer| {
+            let typeface = Font::new(NOTO_SANS_REGULAR_BYTES).unwrap();
+
+            // Too small for the glyphs of all three sizes
+            renderer.set_glyph_cache_options(
+                GlyphCacheOptions::new()
+                    .with_page_size(256)
+                    .with_max_pages(1)
+            );
+
+            for scale in [32.0, 64.0, 128.0] {
+                renderer.draw_frame(|graphics| {
+                    graphics.clear_screen(Color::WHITE);
+
+                    graphics.draw_text(
+                        (10.0, 10.0),
+                        Color::BLACK,
+                        &typeface.layout_text(
+                            "The quick brown fox",
+                            scale,
+                            TextOptions::new()
+                        )
+                    );
+                });
+            }
+
+            let stats = renderer.glyph_cache_stats();
+
+            // The glyphs of the first frame were used least recently, and some
+            // of those of the second frame had to be evicted as well
+            assert_eq!(48, stats.misses());
+            assert!(stats.evictions() > 16);
+            assert_eq!(48 - stats.evictions(), stats.cached_glyphs() as u64);
+            assert_eq!(1, stats.pages_in_use());
+
+            let prewarmed_misses = renderer.draw_frame(|graphics| {
+                graphics.clear_screen(Color::WHITE);
+
+                graphics.draw_text(
+                    (10.0, 10.0),
+                    Color::BLACK,
+                    &typeface.layout_text(
+                        "The quick brown fox",
+                        128.0,
+                        TextOptions::new()
+                    )
+                );
+
+                graphics.set_text_rendering_mode(TextRenderingMode::SignedDistanceField);
+                graphics.prewarm_glyph_cache(&typeface, 48.0, "0123456789");
+
+                graphics.draw_text(
+                    (10.0, 190.0),
+                    Color::BLUE,
+                    &typeface.layout_text("9876543210", 48.0, TextOptions::new())
+                );
+
+                graphics.glyph_cache_stats().misses()
+            });
+
+            // Drawing the text didn't rasterize any more glyphs
+            assert_eq!(58, prewarmed_misses);
+            assert_eq!(58, renderer.glyph_cache_stats().misses());
+
+            // Replacing the options empties the cache, and glyphs are then
+            // evicted once they haven't been drawn during a frame
+            renderer.set_glyph_cache_options(
+                GlyphCacheOptions::new()
+                    .with_page_size(256)
+                    .with_eviction_frames(1)
+            );
+
+            renderer.draw_frame(|graphics| {
+                graphics.prewarm_glyph_cache(&typeface, 32.0, "0123456789");
+            });
+
+            let stats = renderer.glyph_cache_stats();
+            assert_eq!(10, stats.cached_glyphs());
+            assert_eq!(0, stats.evictions());
+
+            // The screen isn't cleared, so that the previous frame is captured
+            renderer.draw_frame(|_graphics| {});
+
+            let stats = renderer.glyph_cache_stats();
+            assert_eq!(0, stats.cached_glyphs());
+            assert_eq!(10, stats.evictions());
+            assert_eq!(0, stats.pages_in_use());
+
+            // Glyphs are only counted once when they're evicted
+            renderer.draw_frame(|_graphics| {});
+
+            let stats = renderer.glyph_cache_stats();
+            assert_eq!(0, stats.cached_glyphs());
+            assert_eq!(10, stats.evictions());
+            assert_eq!(10, stats.misses());
+        })
+    });
+
     for test in tests {
         log::info!("Running test {}", test.name);
 